@@ -1,26 +1,223 @@
 use anyhow::{Result, anyhow};
-use reqwest::Client;
-use serde::Deserialize;
+use filetime::FileTime;
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, LOCATION, RETRY_AFTER};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tracing::{info, error};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, error, warn, Instrument};
 
 use crate::auth::AuthManager;
+use crate::budget;
+use crate::clock;
+use crate::pacing::UploadPacer;
+use crate::quickxor;
 
-#[derive(Debug, Clone, Deserialize)]
+/// Appended to a file's name while it is being downloaded
+pub const PARTIAL_DOWNLOAD_SUFFIX: &str = ".partial";
+
+/// Files below this size are uploaded in a single request
+pub const SIMPLE_UPLOAD_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// Largest file OneDrive accepts
+pub const MAX_FILE_SIZE: u64 = 250 * 1024 * 1024 * 1024;
+
+/// Upload session chunks must be a multiple of this size
+pub const UPLOAD_CHUNK_UNIT: u64 = 320 * 1024;
+
+/// Upload session chunk size unless configured otherwise
+const DEFAULT_UPLOAD_CHUNK_SIZE: u64 = 32 * UPLOAD_CHUNK_UNIT;
+
+/// Throttled requests are retried this many times before the error reaches
+/// the caller
+const MAX_THROTTLE_RETRIES: u32 = 5;
+
+/// Longest `Retry-After` wait honoured for a single retry
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Most requests Graph takes in one JSON batch
+const MAX_BATCH_REQUESTS: usize = 20;
+
+/// Wait between checks on a copy OneDrive runs in the background
+const COPY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A Graph request answered slower than this is logged as a warning
+const SLOW_REQUEST: Duration = Duration::from_secs(10);
+
+/// The modified time of a local file as Graph expects it in `fileSystemInfo`.
+async fn local_modified_time(local_path: &Path) -> Option<String> {
+    let modified = fs::metadata(local_path).await.ok()?.modified().ok()?;
+    Some(chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+/// Where an in-progress download of `local_path` is written.
+pub fn partial_download_path(local_path: &Path) -> std::path::PathBuf {
+    let mut partial_name = local_path.as_os_str().to_owned();
+    partial_name.push(PARTIAL_DOWNLOAD_SUFFIX);
+    std::path::PathBuf::from(partial_name)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DriveItem {
     pub id: String,
+    /// Missing, like the modified time, on items a delta query reports as deleted
+    #[serde(default)]
     pub name: String,
-    #[serde(rename = "lastModifiedDateTime")]
+    #[serde(rename = "lastModifiedDateTime", default)]
     pub last_modified: String,
     pub size: Option<u64>,
-    pub file: Option<serde_json::Value>,
+    pub file: Option<FileFacet>,
     pub folder: Option<serde_json::Value>,
     #[serde(rename = "@microsoft.graph.downloadUrl")]
     pub download_url: Option<String>,
+    #[serde(rename = "fileSystemInfo")]
+    pub file_system_info: Option<FileSystemInfo>,
+    /// Present when the item is shared with anyone else
+    pub shared: Option<serde_json::Value>,
+    /// Who last changed the item
+    #[serde(rename = "lastModifiedBy")]
+    pub last_modified_by: Option<IdentitySet>,
+    /// The folder holding the item; `None` for the drive's root
+    #[serde(rename = "parentReference")]
+    pub parent_reference: Option<ItemReference>,
+    /// Present when a delta query reports the item as deleted
+    pub deleted: Option<serde_json::Value>,
+}
+
+/// Another item, by ID. Delta queries leave out the path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemReference {
+    pub id: Option<String>,
+    pub path: Option<String>,
+}
+
+/// The user and application behind a change; only the user is of interest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdentitySet {
+    pub user: Option<Identity>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Identity {
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+}
+
+/// Times reported by the client that uploaded the file, as opposed to when
+/// OneDrive itself last changed the item.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileSystemInfo {
+    #[serde(rename = "lastModifiedDateTime")]
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileFacet {
+    pub hashes: Option<FileHashes>,
+    #[serde(rename = "mimeType")]
+    pub mime_type: Option<String>,
+}
+
+/// Content hashes Graph reports for a file. `quickXorHash` is available on every
+/// drive type; the others only on some.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileHashes {
+    #[serde(rename = "quickXorHash")]
+    pub quick_xor_hash: Option<String>,
+    #[serde(rename = "sha1Hash")]
+    pub sha1_hash: Option<String>,
+    #[serde(rename = "sha256Hash")]
+    pub sha256_hash: Option<String>,
+}
+
+impl DriveItem {
+    pub fn quick_xor_hash(&self) -> Option<&str> {
+        self.file.as_ref()?.hashes.as_ref()?.quick_xor_hash.as_deref()
+    }
+
+    /// Content type OneDrive detected for the file, e.g. `image/jpeg`
+    pub fn mime_type(&self) -> Option<&str> {
+        self.file.as_ref()?.mime_type.as_deref()
+    }
+
+    /// Name of the person who last changed the item, when OneDrive knows it.
+    pub fn modified_by(&self) -> Option<&str> {
+        self.last_modified_by.as_ref()?.user.as_ref()?.display_name.as_deref()
+    }
+
+    /// When the file was last changed on its author's computer, or when
+    /// OneDrive last changed it if no client reported that.
+    pub fn modified_time(&self) -> &str {
+        self.file_system_info
+            .as_ref()
+            .and_then(|info| info.last_modified.as_deref())
+            .unwrap_or(&self.last_modified)
+    }
+}
+
+/// Access to an item granted to someone else, directly or through a link.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Permission {
+    pub id: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Set when access is through a sharing link
+    pub link: Option<SharingLink>,
+    #[serde(rename = "expirationDateTime")]
+    pub expiration: Option<String>,
+    /// Set when the permission comes from a parent folder rather than the item
+    #[serde(rename = "inheritedFrom")]
+    pub inherited_from: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SharingLink {
+    /// `view`, `edit` or `embed`
+    #[serde(rename = "type")]
+    pub link_type: Option<String>,
+    /// `anonymous` (anyone with the link), `organization` or `users`
+    pub scope: Option<String>,
+    #[serde(rename = "webUrl")]
+    pub web_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PermissionsResponse {
+    value: Vec<Permission>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+/// A Graph change subscription, which posts to a notification URL whenever
+/// something in the drive changes, until it expires.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    #[serde(rename = "expirationDateTime")]
+    pub expiration: String,
+}
+
+/// What the monitor URL of a background copy reports.
+#[derive(Debug, Clone, Deserialize)]
+struct CopyStatus {
+    /// `notStarted`, `inProgress`, `completed` or `failed`
+    status: String,
+    #[serde(rename = "percentageComplete")]
+    percentage_complete: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadSession {
+    #[serde(rename = "uploadUrl")]
+    pub upload_url: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -30,6 +227,17 @@ pub struct DriveResponse {
     pub next_link: Option<String>,
 }
 
+/// A page of a delta query. Removed items carry little more than their ID.
+#[derive(Debug, Clone, Deserialize)]
+struct DeltaResponse {
+    #[serde(default)]
+    value: Vec<DriveItem>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct UserInfo {
     pub id: String,
@@ -43,11 +251,21 @@ pub struct UserInfo {
 #[derive(Debug, Clone, Deserialize)]
 pub struct DriveInfo {
     pub id: String,
+    /// Shown to the user, e.g. "OneDrive" or a document library's title
+    #[serde(default)]
+    pub name: Option<String>,
     #[serde(rename = "driveType")]
     pub drive_type: String,
     pub quota: Option<DriveQuota>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct DriveList {
+    value: Vec<DriveInfo>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct DriveQuota {
     pub total: u64,
@@ -55,10 +273,254 @@ pub struct DriveQuota {
     pub remaining: u64,
 }
 
+#[derive(Debug, Deserialize)]
+struct GraphErrorEnvelope {
+    error: GraphErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphErrorBody {
+    code: String,
+    message: String,
+    #[serde(rename = "innerError")]
+    inner_error: Option<serde_json::Value>,
+}
+
+/// An error response from Microsoft Graph, parsed from its JSON error envelope.
+/// Displays as an actionable message for the user; the raw Graph message and
+/// request ID stay available for diagnostics.
+#[derive(Debug, Clone, Error)]
+#[error("{context}: {user_message}")]
+pub struct GraphError {
+    pub context: String,
+    pub status: u16,
+    pub code: String,
+    pub message: String,
+    pub request_id: Option<String>,
+    pub user_message: String,
+}
+
+impl GraphError {
+    /// Reads Graph's JSON error envelope from `body`, falling back to the
+    /// status alone when there is none.
+    fn from_body(context: &str, status: u16, body: &serde_json::Value) -> Self {
+        match serde_json::from_value::<GraphErrorEnvelope>(body.clone()) {
+            Ok(envelope) => {
+                let request_id = envelope
+                    .error
+                    .inner_error
+                    .as_ref()
+                    .and_then(|inner| inner.get("request-id"))
+                    .and_then(|id| id.as_str())
+                    .map(|id| id.to_string());
+                GraphError::new(context, status, envelope.error.code, envelope.error.message, request_id)
+            }
+            Err(_) => GraphError::new(context, status, String::new(), String::new(), None),
+        }
+    }
+
+    fn new(context: &str, status: u16, code: String, message: String, request_id: Option<String>) -> Self {
+        let user_message = match code.as_str() {
+            "itemNotFound" => "The item no longer exists on OneDrive. It may have been moved or deleted elsewhere.".to_string(),
+            "nameAlreadyExists" => "An item with the same name already exists in the destination folder.".to_string(),
+            "quotaLimitReached" => "Your OneDrive storage is full. Free up space online or upgrade your plan to continue syncing.".to_string(),
+            "accessDenied" => "You don't have permission to change this item. It may be in a folder shared with you as read-only.".to_string(),
+            "InvalidAuthenticationToken" | "unauthenticated" => "Your Microsoft sign-in has expired. Please sign in again.".to_string(),
+            "activityLimitReached" | "throttledRequest" => "OneDrive is limiting requests right now. Sync will retry automatically.".to_string(),
+            "resyncRequired" => "OneDrive asked for a full resynchronization of the folder.".to_string(),
+            _ if message.is_empty() => format!("OneDrive returned HTTP {}", status),
+            _ => message.clone(),
+        };
+
+        Self {
+            context: context.to_string(),
+            status,
+            code,
+            message,
+            request_id,
+            user_message,
+        }
+    }
+}
+
+/// Returned by a transfer stopped through its cancellation token. Whatever was
+/// already transferred is kept so the transfer can resume later.
+#[derive(Debug, Clone, Copy, Error)]
+#[error("Transfer cancelled")]
+pub struct Cancelled;
+
+/// A download whose content does not match the QuickXorHash OneDrive reported.
+/// The partial file is deleted, so the next attempt starts over.
+#[derive(Debug, Clone, Error)]
+#[error("Downloaded content of {0} does not match the hash reported by OneDrive")]
+pub struct HashMismatch(pub String);
+
+/// Turns an unsuccessful Graph response into a `GraphError`, logging the raw body.
+async fn graph_error(context: &str, response: reqwest::Response) -> anyhow::Error {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    error!("{} (HTTP {}): {}", context, status, body);
+
+    let body = serde_json::from_str(&body).unwrap_or_default();
+    GraphError::from_body(context, status, &body).into()
+}
+
+/// One request of a JSON batch (`OneDriveAPI::batch`), addressed relative to
+/// the Graph version root.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    method: &'static str,
+    url: String,
+    body: Option<serde_json::Value>,
+    /// What failed, when Graph answers with an error
+    context: &'static str,
+}
+
+impl BatchRequest {
+    pub fn get_item(drive: &str, path: &str) -> Self {
+        let url = if path == "/" {
+            format!("{}/root", drive)
+        } else {
+            format!("{}/root:{}", drive, encode_drive_path(path))
+        };
+        Self { method: "GET", url, body: None, context: "Failed to get item" }
+    }
+
+    pub fn create_folder(drive: &str, folder_name: &str, parent_path: &str) -> Self {
+        let url = if parent_path == "/" {
+            format!("{}/root/children", drive)
+        } else {
+            format!("{}/root:{}:/children", drive, encode_drive_path(parent_path))
+        };
+        let body = serde_json::json!({
+            "name": folder_name,
+            "folder": {}
+        });
+        Self { method: "POST", url, body: Some(body), context: "Failed to create folder" }
+    }
+
+    pub fn delete_item(drive: &str, item_id: &str) -> Self {
+        Self {
+            method: "DELETE",
+            url: format!("{}/items/{}", drive, item_id),
+            body: None,
+            context: "Failed to delete item",
+        }
+    }
+
+    fn to_json(&self, id: usize) -> serde_json::Value {
+        let mut request = serde_json::json!({
+            "id": id.to_string(),
+            "method": self.method,
+            "url": self.url,
+        });
+        if let Some(body) = &self.body {
+            request["body"] = body.clone();
+            request["headers"] = serde_json::json!({ "Content-Type": "application/json" });
+        }
+        request
+    }
+}
+
+/// Percent-encodes each segment of a drive path for a batched request's URL,
+/// which, unlike a request of its own, reqwest does not encode.
+fn encode_drive_path(path: &str) -> String {
+    path.split('/').map(|segment| urlencoding::encode(segment).into_owned()).collect::<Vec<_>>().join("/")
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    responses: Vec<BatchItemResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchItemResponse {
+    id: String,
+    status: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: serde_json::Value,
+}
+
+trait SendThrottled {
+    /// Sends the request, waiting out and retrying responses that say Graph is
+    /// throttling us (HTTP 429, or 503 while the service is overloaded) for as
+    /// long as their `Retry-After` header asks. Every response also updates the
+    /// clock skew.
+    async fn send_throttled(self) -> reqwest::Result<Response>;
+}
+
+impl SendThrottled for RequestBuilder {
+    async fn send_throttled(self) -> reqwest::Result<Response> {
+        let (client, request) = self.build_split();
+        let request = request?;
+        let span = tracing::debug_span!("graph_request", method = %request.method(), path = %request.url().path());
+        async move {
+            let mut attempt = 0;
+            loop {
+                budget::record_request();
+
+                // A streamed body cannot be sent twice
+                let Some(attempt_request) = request.try_clone() else {
+                    let response = client.execute(request).await?;
+                    clock::observe_server_date(response.headers());
+                    return Ok(response);
+                };
+
+                let started = std::time::Instant::now();
+                let response = client.execute(attempt_request).await?;
+                clock::observe_server_date(response.headers());
+                let status = response.status();
+                let elapsed = started.elapsed();
+                if elapsed >= SLOW_REQUEST {
+                    warn!("OneDrive took {}s to answer {} {}", elapsed.as_secs(), request.method(), request.url().path());
+                } else {
+                    debug!(status = status.as_u16(), elapsed_ms = elapsed.as_millis() as u64, "Graph responded");
+                }
+                let throttled = status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE;
+                if !throttled || attempt == MAX_THROTTLE_RETRIES {
+                    return Ok(response);
+                }
+
+                attempt += 1;
+                let delay = retry_after(response.headers())
+                    .unwrap_or(Duration::from_secs(1 << attempt))
+                    .min(MAX_RETRY_AFTER);
+                warn!(
+                    "OneDrive is throttling requests (HTTP {}); retrying in {}s ({}/{})",
+                    status.as_u16(),
+                    delay.as_secs(),
+                    attempt,
+                    MAX_THROTTLE_RETRIES
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// The wait a throttled response asks for, given in seconds or as an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let retry_at = chrono::DateTime::parse_from_rfc2822(value).ok()?.timestamp();
+    Some(Duration::from_secs((retry_at - clock::server_now_secs() as i64).max(0) as u64))
+}
+
 pub struct OneDriveAPI {
     client: Client,
     auth: Arc<Mutex<AuthManager>>,
     base_url: String,
+    /// Drive the item requests go to; the signed-in user's OneDrive when `None`
+    drive_id: Option<String>,
+    upload_chunk_size: u64,
+    /// Holds uploads back while a camera or microphone is in use
+    upload_pacer: Option<Arc<UploadPacer>>,
 }
 
 impl OneDriveAPI {
@@ -67,9 +529,48 @@ impl OneDriveAPI {
             client: Client::new(),
             auth,
             base_url: "https://graph.microsoft.com/v1.0".to_string(),
+            drive_id: None,
+            upload_chunk_size: DEFAULT_UPLOAD_CHUNK_SIZE,
+            upload_pacer: None,
+        }
+    }
+
+    /// Sends upload sessions in chunks of about `bytes`, rounded down to the
+    /// multiple of 320 KiB that Graph requires.
+    pub fn with_upload_chunk_size(mut self, bytes: u64) -> Self {
+        self.upload_chunk_size = (bytes / UPLOAD_CHUNK_UNIT).max(1) * UPLOAD_CHUNK_UNIT;
+        self
+    }
+
+    /// Limits uploads to `bytes_per_second` while a call is going on, or never
+    /// when `None`.
+    pub fn with_call_upload_limit(mut self, bytes_per_second: Option<u64>) -> Self {
+        self.upload_pacer = bytes_per_second.map(|limit| Arc::new(UploadPacer::new(limit)));
+        self
+    }
+
+    /// Sends item requests to the drive `drive_id`, one of `list_drives`, or
+    /// to the user's OneDrive when `None`.
+    pub fn with_drive(mut self, drive_id: Option<&str>) -> Self {
+        self.drive_id = drive_id.map(str::to_string);
+        self
+    }
+
+    /// The drive's path under the Graph version root, e.g. `/drives/{id}`.
+    fn drive(&self) -> String {
+        match &self.drive_id {
+            Some(drive_id) => format!("/drives/{}", drive_id),
+            None => "/me/drive".to_string(),
         }
     }
 
+    /// Talks to another Graph endpoint, such as the fake backend the tests run.
+    #[cfg(test)]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
     async fn get_auth_header(&self) -> Result<String> {
         let mut auth = self.auth.lock().await;
         let token = auth.get_access_token().await?;
@@ -83,13 +584,11 @@ impl OneDriveAPI {
             .client
             .get(&format!("{}/me", self.base_url))
             .header("Authorization", auth_header)
-            .send()
+            .send_throttled()
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("Failed to get user info: {}", error_text);
-            return Err(anyhow!("Failed to get user info: {}", error_text));
+            return Err(graph_error("Failed to get user info", response).await);
         }
 
         let user_info: UserInfo = response.json().await?;
@@ -102,15 +601,13 @@ impl OneDriveAPI {
         
         let response = self
             .client
-            .get(&format!("{}/me/drive", self.base_url))
+            .get(&format!("{}{}", self.base_url, self.drive()))
             .header("Authorization", auth_header)
-            .send()
+            .send_throttled()
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("Failed to get drive info: {}", error_text);
-            return Err(anyhow!("Failed to get drive info: {}", error_text));
+            return Err(graph_error("Failed to get drive info", response).await);
         }
 
         let drive_info: DriveInfo = response.json().await?;
@@ -118,6 +615,58 @@ impl OneDriveAPI {
         Ok(drive_info)
     }
 
+    /// Every drive the user can sync: their OneDrive and, for work accounts,
+    /// any other document libraries they own.
+    pub async fn list_drives(&self) -> Result<Vec<DriveInfo>> {
+        let auth_header = self.get_auth_header().await?;
+
+        let mut drives = Vec::new();
+        let mut next_url = Some(format!("{}/me/drives", self.base_url));
+        while let Some(url) = next_url {
+            let response = self
+                .client
+                .get(&url)
+                .header("Authorization", auth_header.clone())
+                .send_throttled()
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(graph_error("Failed to list drives", response).await);
+            }
+
+            let page: DriveList = response.json().await?;
+            drives.extend(page.value);
+            next_url = page.next_link;
+        }
+
+        info!("Found {} drives", drives.len());
+        Ok(drives)
+    }
+
+    pub async fn get_item(&self, path: &str) -> Result<DriveItem> {
+        let auth_header = self.get_auth_header().await?;
+
+        let url = if path == "/" {
+            format!("{}{}/root", self.base_url, self.drive())
+        } else {
+            format!("{}{}/root:{}", self.base_url, self.drive(), path)
+        };
+
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", auth_header)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to get item", response).await);
+        }
+
+        let item: DriveItem = response.json().await?;
+        Ok(item)
+    }
+
     pub async fn list_root_items(&self) -> Result<Vec<DriveItem>> {
         self.list_items("/").await
     }
@@ -126,9 +675,9 @@ impl OneDriveAPI {
         let auth_header = self.get_auth_header().await?;
         
         let url = if path == "/" {
-            format!("{}/me/drive/root/children", self.base_url)
+            format!("{}{}/root/children", self.base_url, self.drive())
         } else {
-            format!("{}/me/drive/root:{}:/children", self.base_url, path)
+            format!("{}{}/root:{}:/children", self.base_url, self.drive(), path)
         };
 
         let mut all_items = Vec::new();
@@ -139,13 +688,11 @@ impl OneDriveAPI {
                 .client
                 .get(&url)
                 .header("Authorization", auth_header.clone())
-                .send()
+                .send_throttled()
                 .await?;
 
             if !response.status().is_success() {
-                let error_text = response.text().await?;
-                error!("Failed to list items: {}", error_text);
-                return Err(anyhow!("Failed to list items: {}", error_text));
+                return Err(graph_error("Failed to list items", response).await);
             }
 
             let drive_response: DriveResponse = response.json().await?;
@@ -157,33 +704,85 @@ impl OneDriveAPI {
         Ok(all_items)
     }
 
-    pub async fn download_file(&self, item: &DriveItem, local_path: &Path) -> Result<()> {
-        let download_url = if let Some(url) = &item.download_url {
-            url.clone()
-        } else {
-            // Get download URL from item ID
-            let auth_header = self.get_auth_header().await?;
+    /// A delta link that reports changes made from now on, without listing the
+    /// drive's current contents.
+    pub async fn latest_delta_link(&self) -> Result<String> {
+        let url = format!("{}{}/root/delta?token=latest", self.base_url, self.drive());
+        let (_, delta_link) = self.delta_changes(&url).await?;
+        Ok(delta_link)
+    }
+
+    /// The items changed or deleted since `delta_link` was issued, and the delta
+    /// link to ask next time. Fails with a `GraphError` of status 410 when
+    /// OneDrive no longer accepts the link and the drive has to be scanned in full.
+    pub async fn delta_changes(&self, delta_link: &str) -> Result<(Vec<DriveItem>, String)> {
+        let auth_header = self.get_auth_header().await?;
+
+        let mut changed = Vec::new();
+        let mut next_url = delta_link.to_string();
+        loop {
             let response = self
                 .client
-                .get(&format!("{}/me/drive/items/{}/content", self.base_url, item.id))
-                .header("Authorization", auth_header)
-                .send()
+                .get(&next_url)
+                .header("Authorization", auth_header.clone())
+                .send_throttled()
                 .await?;
 
             if !response.status().is_success() {
-                let error_text = response.text().await?;
-                error!("Failed to get download URL: {}", error_text);
-                return Err(anyhow!("Failed to get download URL: {}", error_text));
+                return Err(graph_error("Failed to check for changes", response).await);
             }
 
-            response.url().to_string()
-        };
+            let page: DeltaResponse = response.json().await?;
+            changed.extend(page.value);
+            match (page.next_link, page.delta_link) {
+                (Some(next_link), _) => next_url = next_link,
+                (None, Some(delta_link)) => return Ok((changed, delta_link)),
+                (None, None) => return Err(anyhow!("Delta response has neither a next nor a delta link")),
+            }
+        }
+    }
 
-        // Download the file
-        let response = self.client.get(&download_url).send().await?;
+    pub async fn download_file(&self, item: &DriveItem, local_path: &Path) -> Result<()> {
+        let result = self.download_file_from(item, local_path, 0, &CancellationToken::new(), |_| {}).await;
+        if result.is_err() {
+            let _ = fs::remove_file(partial_download_path(local_path)).await;
+        }
+        result
+    }
+
+    /// Downloads into `partial_download_path(local_path)`, continuing after the
+    /// first `offset` bytes already in it when the server honours the `Range`
+    /// request, and renames it into place once complete with the item's modified
+    /// time. The partial file is kept on failure or cancellation so a later call
+    /// can resume it, except when its content does not match the item's
+    /// QuickXorHash: then it is deleted and the call fails with `HashMismatch`.
+    /// `on_progress` gets the bytes in the partial file after each chunk written.
+    pub async fn download_file_from(
+        &self,
+        item: &DriveItem,
+        local_path: &Path,
+        offset: u64,
+        cancel: &CancellationToken,
+        on_progress: impl Fn(u64),
+    ) -> Result<()> {
+        let mut request = match &item.download_url {
+            Some(url) => self.client.get(url),
+            None => {
+                // Graph redirects to a fresh download link, and the body that
+                // comes back from it is the file itself
+                let auth_header = self.get_auth_header().await?;
+                self.client
+                    .get(format!("{}{}/items/{}/content", self.base_url, self.drive(), item.id))
+                    .header("Authorization", auth_header)
+            }
+        };
+        if offset > 0 {
+            request = request.header("Range", format!("bytes={}-", offset));
+        }
+        let response = request.send_throttled().await?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("Failed to download file: HTTP {}", response.status()));
+            return Err(graph_error("Failed to download file", response).await);
         }
 
         // Create parent directories
@@ -191,150 +790,611 @@ impl OneDriveAPI {
             fs::create_dir_all(parent).await?;
         }
 
-        // Write file content
-        let content = response.bytes().await?;
-        let mut file = fs::File::create(local_path).await?;
-        file.write_all(&content).await?;
+        // Stream into a .partial file and only rename it into place once complete,
+        // so an interrupted download never leaves a truncated file behind
+        let partial_path = partial_download_path(local_path);
+        let (mut file, mut written) = if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            info!("Resuming download of {} at byte {}", item.name, offset);
+            (fs::OpenOptions::new().append(true).open(&partial_path).await?, offset)
+        } else {
+            (fs::File::create(&partial_path).await?, 0)
+        };
+        on_progress(written);
+
+        let mut body = response.bytes_stream();
+        loop {
+            let chunk = tokio::select! {
+                chunk = body.next() => chunk,
+                _ = cancel.cancelled() => {
+                    file.flush().await?;
+                    info!("Download of {} cancelled", item.name);
+                    return Err(Cancelled.into());
+                }
+            };
+            match chunk {
+                Some(chunk) => {
+                    let chunk = chunk?;
+                    file.write_all(&chunk).await?;
+                    written += chunk.len() as u64;
+                    on_progress(written);
+                }
+                None => break,
+            }
+        }
+        file.sync_all().await?;
+        drop(file);
+
+        // Checked before the rename, so a corrupt download never replaces the local file
+        if let Some(expected) = item.quick_xor_hash() {
+            if quickxor::hash_file(&partial_path).await? != expected {
+                if let Err(e) = fs::remove_file(&partial_path).await {
+                    warn!("Failed to remove corrupt download of {}: {}", item.name, e);
+                }
+                return Err(HashMismatch(item.name.clone()).into());
+            }
+        }
+
+        // Keep the file's own modified time rather than the time of the download
+        match chrono::DateTime::parse_from_rfc3339(item.modified_time()) {
+            Ok(modified) => {
+                let mtime = FileTime::from_unix_time(modified.timestamp(), modified.timestamp_subsec_nanos());
+                if let Err(e) = filetime::set_file_mtime(&partial_path, mtime) {
+                    warn!("Failed to set the modified time of {}: {}", local_path.display(), e);
+                }
+            }
+            Err(e) => warn!("Invalid lastModifiedDateTime for {}: {}", item.name, e),
+        }
+        fs::rename(&partial_path, local_path).await?;
 
         info!("Downloaded file: {} -> {}", item.name, local_path.display());
         Ok(())
     }
 
     pub async fn upload_file(&self, local_path: &Path, remote_name: &str) -> Result<DriveItem> {
-        let auth_header = self.get_auth_header().await?;
-        
-        // Read file content
-        let content = fs::read(local_path).await?;
-        let file_size = content.len();
+        let file_size = fs::metadata(local_path).await?.len();
 
         info!("Uploading file: {} ({} bytes)", remote_name, file_size);
 
         // For files smaller than 4MB, use simple upload
-        if file_size < 4 * 1024 * 1024 {
-            let url = format!("{}/me/drive/root:/{remote_name}:/content", self.base_url);
+        if file_size < SIMPLE_UPLOAD_LIMIT {
+            let auth_header = self.get_auth_header().await?;
+            let content = fs::read(local_path).await?;
+            let url = format!("{}{}/root:/{remote_name}:/content", self.base_url, self.drive());
             
             let response = self
                 .client
                 .put(&url)
                 .header("Authorization", auth_header)
                 .header("Content-Type", "application/octet-stream")
-                .body(content)
-                .send()
+                .header("Content-Length", content.len().to_string())
+                .body(self.upload_body(content).await)
+                .send_throttled()
                 .await?;
 
             if !response.status().is_success() {
-                let error_text = response.text().await?;
-                error!("Failed to upload file: {}", error_text);
-                return Err(anyhow!("Failed to upload file: {}", error_text));
+                return Err(graph_error("Failed to upload file", response).await);
             }
 
             let item: DriveItem = response.json().await?;
             info!("Successfully uploaded file: {}", remote_name);
-            Ok(item)
+
+            // A simple upload carries no metadata, so the modified time follows
+            match local_modified_time(local_path).await {
+                Some(modified) => match self.set_modified_time(&item.id, &modified).await {
+                    Ok(item) => Ok(item),
+                    Err(e) => {
+                        warn!("Failed to set the modified time of {} on OneDrive: {}", remote_name, e);
+                        Ok(item)
+                    }
+                },
+                None => Ok(item),
+            }
         } else {
             // Use resumable upload for larger files
-            self.upload_large_file(local_path, remote_name, content).await
+            self.upload_large_file(local_path, remote_name, file_size).await
         }
     }
 
-    async fn upload_large_file(&self, _local_path: &Path, remote_name: &str, content: Vec<u8>) -> Result<DriveItem> {
+    /// The body for uploading `content`, paced while a call is going on.
+    async fn upload_body(&self, content: Vec<u8>) -> reqwest::Body {
+        match &self.upload_pacer {
+            Some(pacer) if pacer.in_call().await => pacer.paced_body(content),
+            _ => content.into(),
+        }
+    }
+
+    /// Uploads through an upload session, reading one chunk at a time from disk so
+    /// memory use stays at one chunk regardless of the file size.
+    async fn upload_large_file(&self, local_path: &Path, remote_name: &str, total_size: u64) -> Result<DriveItem> {
+        let session = self.create_upload_session(local_path, remote_name).await?;
+        self.upload_to_session(&session.upload_url, local_path, 0, total_size, &CancellationToken::new(), |_| {}).await
+    }
+
+    /// Opens an upload session for `local_path`, carrying its modified time so
+    /// OneDrive shows when the file was changed rather than when it was uploaded.
+    pub async fn create_upload_session(&self, local_path: &Path, remote_name: &str) -> Result<UploadSession> {
         let auth_header = self.get_auth_header().await?;
         
-        // Create upload session
-        let session_url = format!("{}/me/drive/root:/{remote_name}:/createUploadSession", self.base_url);
-        let session_body = serde_json::json!({
+        let session_url = format!("{}{}/root:/{remote_name}:/createUploadSession", self.base_url, self.drive());
+        let mut session_body = serde_json::json!({
             "item": {
                 "@microsoft.graph.conflictBehavior": "replace"
             }
         });
+        if let Some(modified) = local_modified_time(local_path).await {
+            session_body["item"]["fileSystemInfo"] = serde_json::json!({ "lastModifiedDateTime": modified });
+        }
 
         let response = self
             .client
             .post(&session_url)
-            .header("Authorization", auth_header.clone())
+            .header("Authorization", auth_header)
             .header("Content-Type", "application/json")
             .json(&session_body)
-            .send()
+            .send_throttled()
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Failed to create upload session: {}", error_text));
+            return Err(graph_error("Failed to create upload session", response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// The byte offset an existing upload session expects next, or `None` when
+    /// the session has expired or was cancelled and a new one is needed.
+    pub async fn upload_session_offset(&self, upload_url: &str) -> Result<Option<u64>> {
+        // Upload URLs are pre-authenticated; sending a token is an error
+        let response = self.client.get(upload_url).send_throttled().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to query upload session", response).await);
         }
 
         #[derive(Deserialize)]
-        struct UploadSession {
-            #[serde(rename = "uploadUrl")]
-            upload_url: String,
+        struct SessionStatus {
+            #[serde(rename = "nextExpectedRanges", default)]
+            next_expected_ranges: Vec<String>,
         }
 
-        let session: UploadSession = response.json().await?;
-        
-        // Upload file in chunks
-        let chunk_size = 320 * 1024; // 320KB chunks
-        let total_size = content.len();
-        let mut offset = 0;
+        let status: SessionStatus = response.json().await?;
+        Ok(status
+            .next_expected_ranges
+            .first()
+            .and_then(|range| range.split('-').next())
+            .and_then(|start| start.parse().ok()))
+    }
+
+    /// Sends the file from `offset` onwards to an upload session in chunks,
+    /// calling `on_progress` with the confirmed offset after each one. A
+    /// cancellation abandons the chunk in flight; the session stays open, so
+    /// Graph's reported offset tells a later call where to continue.
+    pub async fn upload_to_session(
+        &self,
+        upload_url: &str,
+        local_path: &Path,
+        mut offset: u64,
+        total_size: u64,
+        cancel: &CancellationToken,
+        on_progress: impl Fn(u64),
+    ) -> Result<DriveItem> {
+        let mut file = fs::File::open(local_path).await?;
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
 
         while offset < total_size {
-            let end = std::cmp::min(offset + chunk_size, total_size);
-            let chunk = &content[offset..end];
+            let end = std::cmp::min(offset + self.upload_chunk_size, total_size);
+            let mut chunk = vec![0u8; (end - offset) as usize];
+            file.read_exact(&mut chunk).await?;
             
             let content_range = format!("bytes {}-{}/{}", offset, end - 1, total_size);
             
-            let response = self
+            let request = self
                 .client
-                .put(&session.upload_url)
+                .put(upload_url)
                 .header("Content-Range", content_range)
                 .header("Content-Length", chunk.len().to_string())
-                .body(chunk.to_vec())
-                .send()
-                .await?;
+                .body(self.upload_body(chunk).await)
+                .send_throttled();
+            let response = tokio::select! {
+                response = request => response?,
+                _ = cancel.cancelled() => {
+                    info!("Upload of {} cancelled at {}/{} bytes", local_path.display(), offset, total_size);
+                    return Err(Cancelled.into());
+                }
+            };
 
             if response.status().as_u16() == 202 {
                 // Chunk uploaded successfully, continue
                 offset = end;
+                on_progress(offset);
                 info!("Uploaded chunk: {}/{} bytes", end, total_size);
             } else if response.status().as_u16() == 201 || response.status().as_u16() == 200 {
                 // Upload complete
                 let item: DriveItem = response.json().await?;
-                info!("Successfully uploaded large file: {}", remote_name);
+                info!("Successfully uploaded large file: {}", local_path.display());
                 return Ok(item);
             } else {
-                let error_text = response.text().await?;
-                return Err(anyhow!("Upload chunk failed: {}", error_text));
+                return Err(graph_error("Upload chunk failed", response).await);
             }
         }
 
         Err(anyhow!("Upload completed but no final response received"))
     }
 
+    /// Moves and/or renames an item in place on OneDrive, without transferring content.
+    pub async fn move_item(&self, item_id: &str, new_parent_path: &str, new_name: &str) -> Result<DriveItem> {
+        let auth_header = self.get_auth_header().await?;
+
+        // Graph writes the signed-in user's own drive as `/drive` here
+        let drive = match &self.drive_id {
+            Some(_) => self.drive(),
+            None => "/drive".to_string(),
+        };
+        let parent_reference = if new_parent_path == "/" {
+            format!("{}/root:", drive)
+        } else {
+            format!("{}/root:{}", drive, new_parent_path)
+        };
+
+        let body = serde_json::json!({
+            "parentReference": { "path": parent_reference },
+            "name": new_name
+        });
+
+        let response = self
+            .client
+            .patch(format!("{}{}/items/{}", self.base_url, self.drive(), item_id))
+            .header("Authorization", auth_header)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to move item", response).await);
+        }
+
+        let item: DriveItem = response.json().await?;
+        info!("Successfully moved item {} to {}/{}", item_id, new_parent_path.trim_end_matches('/'), new_name);
+        Ok(item)
+    }
+
+    /// Copies an item, a folder with everything in it, into `new_parent_path`
+    /// as `new_name` without the content passing through this computer.
+    /// OneDrive copies in the background and hands out a monitor URL, which
+    /// is polled until the copy is done. Cancelling stops the wait, not the
+    /// copy.
+    pub async fn copy_item(
+        &self,
+        item_id: &str,
+        new_parent_path: &str,
+        new_name: &str,
+        cancel: &CancellationToken,
+    ) -> Result<DriveItem> {
+        let parent = self.get_item(new_parent_path).await?;
+        let drive_id = match &self.drive_id {
+            Some(drive_id) => drive_id.clone(),
+            None => self.get_drive_info().await?.id,
+        };
+        let auth_header = self.get_auth_header().await?;
+
+        let body = serde_json::json!({
+            "parentReference": { "driveId": drive_id, "id": parent.id },
+            "name": new_name
+        });
+
+        let response = self
+            .client
+            .post(format!("{}{}/items/{}/copy", self.base_url, self.drive(), item_id))
+            .header("Authorization", auth_header)
+            .json(&body)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to copy item", response).await);
+        }
+        let monitor_url = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| anyhow!("OneDrive did not say where to follow the copy"))?
+            .to_string();
+
+        loop {
+            // The monitor URL is pre-authenticated
+            let response = self.client.get(&monitor_url).send_throttled().await?;
+            if !response.status().is_success() {
+                return Err(graph_error("Failed to check on a copy", response).await);
+            }
+            let copy: CopyStatus = response.json().await?;
+            match copy.status.as_str() {
+                "completed" => break,
+                "failed" => return Err(anyhow!("OneDrive could not copy the item to {}/{}", new_parent_path.trim_end_matches('/'), new_name)),
+                status => debug!("Copy of {} is {} ({:.0}% done)", item_id, status, copy.percentage_complete.unwrap_or(0.0)),
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => return Err(Cancelled.into()),
+                _ = tokio::time::sleep(COPY_POLL_INTERVAL) => {}
+            }
+        }
+
+        let item = self.get_item(&format!("{}/{}", new_parent_path.trim_end_matches('/'), new_name)).await?;
+        info!("Successfully copied item {} to {}/{}", item_id, new_parent_path.trim_end_matches('/'), new_name);
+        Ok(item)
+    }
+
+    /// Sets the modified time OneDrive shows for an item, given in RFC 3339.
+    pub async fn set_modified_time(&self, item_id: &str, modified: &str) -> Result<DriveItem> {
+        let auth_header = self.get_auth_header().await?;
+
+        let body = serde_json::json!({
+            "fileSystemInfo": { "lastModifiedDateTime": modified }
+        });
+
+        let response = self
+            .client
+            .patch(format!("{}{}/items/{}", self.base_url, self.drive(), item_id))
+            .header("Authorization", auth_header)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to set modified time", response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Everyone and every link an item is shared with.
+    pub async fn list_permissions(&self, item_id: &str) -> Result<Vec<Permission>> {
+        let auth_header = self.get_auth_header().await?;
+
+        let mut permissions = Vec::new();
+        let mut next_url = Some(format!("{}{}/items/{}/permissions", self.base_url, self.drive(), item_id));
+        while let Some(url) = next_url {
+            let response = self
+                .client
+                .get(&url)
+                .header("Authorization", auth_header.clone())
+                .send_throttled()
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(graph_error("Failed to list sharing permissions", response).await);
+            }
+
+            let page: PermissionsResponse = response.json().await?;
+            permissions.extend(page.value);
+            next_url = page.next_link;
+        }
+        Ok(permissions)
+    }
+
+    /// Revokes one permission of an item, such as a sharing link.
+    pub async fn delete_permission(&self, item_id: &str, permission_id: &str) -> Result<()> {
+        let auth_header = self.get_auth_header().await?;
+
+        let response = self
+            .client
+            .delete(format!("{}{}/items/{}/permissions/{}", self.base_url, self.drive(), item_id, permission_id))
+            .header("Authorization", auth_header)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to remove sharing permission", response).await);
+        }
+
+        info!("Removed permission {} from item {}", permission_id, item_id);
+        Ok(())
+    }
+
+    /// Subscribes `notification_url` to changes anywhere in the drive until
+    /// `expires`. Graph first posts a validation token to the URL, which has to
+    /// be echoed back before this returns. `client_state` comes back with every
+    /// notification, so the receiver can tell genuine ones apart.
+    pub async fn create_subscription(
+        &self,
+        notification_url: &str,
+        client_state: &str,
+        expires: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Subscription> {
+        let auth_header = self.get_auth_header().await?;
+
+        let body = serde_json::json!({
+            "changeType": "updated",
+            "notificationUrl": notification_url,
+            "resource": format!("{}/root", self.drive()),
+            "expirationDateTime": expires.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "clientState": client_state,
+        });
+        let response = self
+            .client
+            .post(format!("{}/subscriptions", self.base_url))
+            .header("Authorization", auth_header)
+            .json(&body)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to subscribe to drive changes", response).await);
+        }
+
+        let subscription: Subscription = response.json().await?;
+        info!("Subscribed to drive changes until {}", subscription.expiration);
+        Ok(subscription)
+    }
+
+    /// Moves a subscription's expiry to `expires`.
+    pub async fn renew_subscription(&self, subscription_id: &str, expires: chrono::DateTime<chrono::Utc>) -> Result<Subscription> {
+        let auth_header = self.get_auth_header().await?;
+
+        let body = serde_json::json!({
+            "expirationDateTime": expires.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        });
+        let response = self
+            .client
+            .patch(format!("{}/subscriptions/{}", self.base_url, subscription_id))
+            .header("Authorization", auth_header)
+            .json(&body)
+            .send_throttled()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(graph_error("Failed to renew the drive change subscription", response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
     pub async fn delete_item(&self, item_id: &str) -> Result<()> {
         let auth_header = self.get_auth_header().await?;
         
         let response = self
             .client
-            .delete(&format!("{}/me/drive/items/{}", self.base_url, item_id))
+            .delete(&format!("{}{}/items/{}", self.base_url, self.drive(), item_id))
             .header("Authorization", auth_header)
-            .send()
+            .send_throttled()
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("Failed to delete item: {}", error_text);
-            return Err(anyhow!("Failed to delete item: {}", error_text));
+            return Err(graph_error("Failed to delete item", response).await);
         }
 
         info!("Successfully deleted item: {}", item_id);
         Ok(())
     }
 
+    /// Sends `requests` packed into JSON batches of up to 20, so a burst of
+    /// small operations costs a round trip per batch rather than per request.
+    /// Each request gets its own result, in order: the body of its response,
+    /// or the error Graph answered it with. Requests throttled inside a batch
+    /// go again in a later one, once their `Retry-After` has passed.
+    pub async fn batch(&self, requests: &[BatchRequest]) -> Result<Vec<Result<serde_json::Value>>> {
+        let mut results: Vec<Option<Result<serde_json::Value>>> = requests.iter().map(|_| None).collect();
+        let mut pending: Vec<usize> = (0..requests.len()).collect();
+        let mut attempt = 0;
+
+        while !pending.is_empty() {
+            let mut throttled = Vec::new();
+            let mut delay = Duration::ZERO;
+            for chunk in pending.chunks(MAX_BATCH_REQUESTS) {
+                let auth_header = self.get_auth_header().await?;
+                let body = serde_json::json!({
+                    "requests": chunk.iter().map(|&index| requests[index].to_json(index)).collect::<Vec<_>>(),
+                });
+
+                let response = self
+                    .client
+                    .post(format!("{}/$batch", self.base_url))
+                    .header("Authorization", auth_header)
+                    .json(&body)
+                    .send_throttled()
+                    .await?;
+
+                if !response.status().is_success() {
+                    return Err(graph_error("Failed to send batch request", response).await);
+                }
+
+                let batch: BatchResponse = response.json().await?;
+                for response in batch.responses {
+                    let Some(index) = response.id.parse::<usize>().ok().filter(|index| chunk.contains(index)) else {
+                        continue;
+                    };
+                    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                    let is_throttled = status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE;
+                    if is_throttled && attempt < MAX_THROTTLE_RETRIES {
+                        let wait = response
+                            .headers
+                            .iter()
+                            .find(|(name, _)| name.eq_ignore_ascii_case("Retry-After"))
+                            .and_then(|(_, value)| value.trim().parse().ok())
+                            .map(Duration::from_secs)
+                            .unwrap_or(Duration::from_secs(1 << (attempt + 1)));
+                        delay = delay.max(wait.min(MAX_RETRY_AFTER));
+                        throttled.push(index);
+                    } else if status.is_success() {
+                        results[index] = Some(Ok(response.body));
+                    } else {
+                        let context = requests[index].context;
+                        error!("{} (HTTP {}, batched): {}", context, response.status, response.body);
+                        results[index] = Some(Err(GraphError::from_body(context, response.status, &response.body).into()));
+                    }
+                }
+            }
+
+            if !throttled.is_empty() {
+                attempt += 1;
+                warn!(
+                    "OneDrive is throttling {} batched requests; retrying in {}s ({}/{})",
+                    throttled.len(),
+                    delay.as_secs(),
+                    attempt,
+                    MAX_THROTTLE_RETRIES
+                );
+                tokio::time::sleep(delay).await;
+            }
+            pending = throttled;
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| Err(anyhow!("OneDrive did not answer a batched request"))))
+            .collect())
+    }
+
+    /// Looks up several items by path in batched requests.
+    pub async fn get_items(&self, paths: &[String]) -> Result<Vec<Result<DriveItem>>> {
+        let drive = self.drive();
+        let requests: Vec<BatchRequest> = paths.iter().map(|path| BatchRequest::get_item(&drive, path)).collect();
+        Ok(self
+            .batch(&requests)
+            .await?
+            .into_iter()
+            .map(|result| Ok(serde_json::from_value(result?)?))
+            .collect())
+    }
+
+    /// Deletes several items in batched requests.
+    pub async fn delete_items(&self, item_ids: &[String]) -> Result<Vec<Result<()>>> {
+        let drive = self.drive();
+        let requests: Vec<BatchRequest> = item_ids.iter().map(|id| BatchRequest::delete_item(&drive, id)).collect();
+        let results: Vec<Result<()>> = self.batch(&requests).await?.into_iter().map(|result| result.map(|_| ())).collect();
+        info!("Deleted {} of {} items", results.iter().filter(|result| result.is_ok()).count(), item_ids.len());
+        Ok(results)
+    }
+
+    /// Creates each `(folder_name, parent_path)` folder in batched requests.
+    /// The parents must exist already: batched requests run in no set order.
+    pub async fn create_folders(&self, folders: &[(String, String)]) -> Result<Vec<Result<DriveItem>>> {
+        let drive = self.drive();
+        let requests: Vec<BatchRequest> = folders
+            .iter()
+            .map(|(folder_name, parent_path)| BatchRequest::create_folder(&drive, folder_name, parent_path))
+            .collect();
+        let results: Vec<Result<DriveItem>> = self
+            .batch(&requests)
+            .await?
+            .into_iter()
+            .map(|result| Ok(serde_json::from_value(result?)?))
+            .collect();
+        info!("Created {} of {} folders", results.iter().filter(|result| result.is_ok()).count(), folders.len());
+        Ok(results)
+    }
+
     pub async fn create_folder(&self, folder_name: &str, parent_path: &str) -> Result<DriveItem> {
         let auth_header = self.get_auth_header().await?;
         
         let url = if parent_path == "/" {
-            format!("{}/me/drive/root/children", self.base_url)
+            format!("{}{}/root/children", self.base_url, self.drive())
         } else {
-            format!("{}/me/drive/root:{}:/children", self.base_url, parent_path)
+            format!("{}{}/root:{}:/children", self.base_url, self.drive(), parent_path)
         };
 
         let folder_data = serde_json::json!({
@@ -348,13 +1408,11 @@ impl OneDriveAPI {
             .header("Authorization", auth_header)
             .header("Content-Type", "application/json")
             .json(&folder_data)
-            .send()
+            .send_throttled()
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("Failed to create folder: {}", error_text);
-            return Err(anyhow!("Failed to create folder: {}", error_text));
+            return Err(graph_error("Failed to create folder", response).await);
         }
 
         let item: DriveItem = response.json().await?;
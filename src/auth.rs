@@ -8,14 +8,21 @@ use oauth2::reqwest::async_http_client;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::SystemTime;
 use tokio::net::TcpListener;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tracing::{info, warn, error};
 use url::Url;
 
+use crate::clock;
 use crate::config::Config;
 
+/// Returned when there is no valid access token and none could be refreshed,
+/// so the user has to sign in again.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("Not authenticated and cannot refresh token")]
+pub struct NotAuthenticated;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenData {
     pub access_token: String,
@@ -24,6 +31,21 @@ pub struct TokenData {
     pub token_type: String,
 }
 
+/// A pending device code sign-in: the user enters `user_code` at
+/// `verification_uri` while the client polls for the result.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+const DEVICE_CODE_URL: &str = "https://login.microsoftonline.com/common/oauth2/v2.0/devicecode";
+const TOKEN_URL: &str = "https://login.microsoftonline.com/common/oauth2/v2.0/token";
+const SCOPES: &str = "https://graph.microsoft.com/Files.ReadWrite.All https://graph.microsoft.com/User.Read offline_access";
+
 pub struct AuthManager {
     config: Arc<Config>,
     oauth_client: BasicClient,
@@ -32,13 +54,7 @@ pub struct AuthManager {
 
 impl AuthManager {
     pub fn new(config: Arc<Config>) -> Result<Self> {
-        let client = BasicClient::new(
-            ClientId::new(config.client_id.clone()),
-            None, // No client secret for public clients
-            AuthUrl::new("https://login.microsoftonline.com/common/oauth2/v2.0/authorize".to_string())?,
-            Some(TokenUrl::new("https://login.microsoftonline.com/common/oauth2/v2.0/token".to_string())?),
-        )
-        .set_redirect_uri(RedirectUrl::new(config.redirect_uri.clone())?);
+        let client = Self::build_oauth_client(&config)?;
 
         let mut auth_manager = Self {
             config: config.clone(),
@@ -52,6 +68,26 @@ impl AuthManager {
         Ok(auth_manager)
     }
 
+    fn build_oauth_client(config: &Config) -> Result<BasicClient> {
+        Ok(BasicClient::new(
+            ClientId::new(config.client_id.clone()),
+            None, // No client secret for public clients
+            AuthUrl::new("https://login.microsoftonline.com/common/oauth2/v2.0/authorize".to_string())?,
+            Some(TokenUrl::new(TOKEN_URL.to_string())?),
+        )
+        .set_redirect_uri(RedirectUrl::new(config.redirect_uri.clone())?))
+    }
+
+    /// Switches to another app registration and saves it to the config, so token
+    /// refreshes keep using the client the tokens were issued to.
+    pub fn use_client_id(&mut self, client_id: &str) -> Result<()> {
+        let mut config = (*self.config).clone();
+        config.update_azure_config(client_id.to_string(), config.redirect_uri.clone())?;
+        self.oauth_client = Self::build_oauth_client(&config)?;
+        self.config = Arc::new(config);
+        Ok(())
+    }
+
     fn load_tokens(&mut self) -> Result<()> {
         if self.config.token_file.exists() {
             match fs::read_to_string(&self.config.token_file) {
@@ -85,10 +121,7 @@ impl AuthManager {
 
     pub fn is_authenticated(&self) -> bool {
         if let Some(ref tokens) = self.tokens {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            let now = clock::server_now_secs();
             
             // Check if token is still valid (with 5 minute buffer)
             tokens.expires_at > now + 300
@@ -97,6 +130,11 @@ impl AuthManager {
         }
     }
 
+    /// Whether tokens were loaded, even if the access token needs a refresh.
+    pub fn has_tokens(&self) -> bool {
+        self.tokens.is_some()
+    }
+
     pub async fn get_access_token(&mut self) -> Result<String> {
         if self.is_authenticated() {
             return Ok(self.tokens.as_ref().unwrap().access_token.clone());
@@ -111,7 +149,7 @@ impl AuthManager {
             }
         }
 
-        Err(anyhow!("Not authenticated and cannot refresh token"))
+        Err(NotAuthenticated.into())
     }
 
     pub async fn authenticate(&mut self) -> Result<()> {
@@ -221,6 +259,8 @@ impl AuthManager {
                 error!("HTTP request failed: {:?}", e);
                 anyhow!("Failed to send token request: {}", e)
             })?;
+        // The token's lifetime counts from the server's clock
+        clock::observe_server_date(response.headers());
         
         let status = response.status();
         let response_text = response.text().await.map_err(|e| {
@@ -228,8 +268,8 @@ impl AuthManager {
             anyhow!("Failed to read response: {}", e)
         })?;
         
+        // Never the body: it holds the tokens, and the log ends up in crash reports
         info!("Token response status: {}", status);
-        info!("Token response body: {}", response_text);
         
         if !status.is_success() {
             error!("Token exchange failed with status {}: {}", status, response_text);
@@ -252,11 +292,9 @@ impl AuthManager {
             .as_u64()
             .unwrap_or(3600);
         
-        let expires_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + expires_in;
+        // Expiry is kept in server time so a skewed local clock cannot make a
+        // valid token look expired, or an expired one valid
+        let expires_at = clock::server_now_secs() + expires_in;
 
         // Store tokens
         self.tokens = Some(TokenData {
@@ -272,20 +310,112 @@ impl AuthManager {
         Ok(())
     }
 
+    /// Starts a device code sign-in, which needs no redirect URI or local
+    /// callback server and so works with the built-in app registration.
+    pub async fn request_device_code(&self) -> Result<DeviceCode> {
+        info!("Requesting device code");
+        let response = reqwest::Client::new()
+            .post(DEVICE_CODE_URL)
+            .form(&[("client_id", self.config.client_id.as_str()), ("scope", SCOPES)])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            error!("Device code request failed with status {}: {}", status, body);
+            return Err(anyhow!("Device code request failed with status {}", status));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Polls until the user finishes signing in with `device_code`. Takes no
+    /// `self` so the auth lock is not held while waiting on the user.
+    pub async fn poll_device_code(client_id: &str, device_code: &DeviceCode) -> Result<TokenData> {
+        let client = reqwest::Client::new();
+        let deadline = SystemTime::now() + std::time::Duration::from_secs(device_code.expires_in);
+        let mut interval = device_code.interval.max(1);
+
+        while SystemTime::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+
+            let response = client
+                .post(TOKEN_URL)
+                .form(&[
+                    ("client_id", client_id),
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                    ("device_code", device_code.device_code.as_str()),
+                ])
+                .send()
+                .await?;
+            clock::observe_server_date(response.headers());
+
+            let status = response.status();
+            let token_response: serde_json::Value = response.json().await?;
+
+            if status.is_success() {
+                let access_token = token_response["access_token"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("No access_token in response"))?;
+                let expires_at = clock::server_now_secs() + token_response["expires_in"].as_u64().unwrap_or(3600);
+
+                info!("Device code authentication successful");
+                return Ok(TokenData {
+                    access_token: access_token.to_string(),
+                    refresh_token: token_response["refresh_token"].as_str().map(|t| t.to_string()),
+                    expires_at,
+                    token_type: "Bearer".to_string(),
+                });
+            }
+
+            match token_response["error"].as_str() {
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += 5,
+                Some(error) => {
+                    let description = token_response["error_description"].as_str().unwrap_or(error);
+                    return Err(anyhow!("Sign-in failed: {}", description));
+                }
+                None => return Err(anyhow!("Sign-in failed with status {}", status)),
+            }
+        }
+
+        Err(anyhow!("Sign-in code expired before it was used"))
+    }
+
+    /// Signs in with a device code instead of the localhost callback of
+    /// `authenticate`: `show` tells the user which code to enter where, on
+    /// any device, and this waits until they have. Works over SSH, on
+    /// servers without a browser, and where a firewall blocks the callback.
+    pub async fn authenticate_with_device_code(&mut self, show: impl FnOnce(&DeviceCode)) -> Result<()> {
+        let device_code = self.request_device_code().await?;
+        show(&device_code);
+        let tokens = Self::poll_device_code(&self.config.client_id, &device_code).await?;
+        self.store_tokens(tokens)?;
+        info!("Authentication successful");
+        Ok(())
+    }
+
+    pub fn store_tokens(&mut self, tokens: TokenData) -> Result<()> {
+        self.tokens = Some(tokens);
+        self.save_tokens()
+    }
+
     async fn refresh_access_token(&mut self, refresh_token: String) -> Result<()> {
         info!("Refreshing access token");
 
         let token_result = self
             .oauth_client
             .exchange_refresh_token(&RefreshToken::new(refresh_token))
-            .request_async(async_http_client)
+            .request_async(|request| async move {
+                async_http_client(request).await.map(|response| {
+                    clock::observe_server_date(&response.headers);
+                    response
+                })
+            })
             .await?;
 
-        let expires_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + token_result.expires_in().map(|d| d.as_secs()).unwrap_or(3600);
+        let expires_at = clock::server_now_secs() + token_result.expires_in().map(|d| d.as_secs()).unwrap_or(3600);
 
         // Update tokens
         if let Some(ref mut tokens) = self.tokens {
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::api::{OneDriveAPI, UPLOAD_CHUNK_UNIT};
+use crate::auth::AuthManager;
+use crate::config::Config;
+
+/// Sizes of the synthetic files timed one at a time
+const SAMPLE_SIZES: &[u64] = &[1024 * 1024, 8 * 1024 * 1024, 32 * 1024 * 1024];
+
+/// Upload session chunk sizes tried, in multiples of `UPLOAD_CHUNK_UNIT`
+const CHUNK_MULTIPLES: &[u64] = &[8, 16, 32, 64];
+
+/// Parallel transfer counts tried
+const CONCURRENCY_LEVELS: &[usize] = &[1, 2, 4, 8];
+
+/// Small files moved at every concurrency level; per-request latency dominates
+/// small files, which is where parallel transfers pay off
+const CONCURRENCY_FILES: usize = 8;
+const CONCURRENCY_FILE_SIZE: u64 = 1024 * 1024;
+
+/// A smaller setting is preferred when it reaches this share of the best speed
+const GOOD_ENOUGH: f64 = 0.9;
+
+/// Settings suggested by a benchmark run.
+pub struct Recommendation {
+    pub upload_chunk_size_kib: u64,
+    pub max_concurrent_transfers: usize,
+}
+
+/// Times uploads and downloads of synthetic files in a temporary OneDrive
+/// folder, prints the results and returns the settings that suit this
+/// connection. The temporary files are removed on both sides afterwards.
+pub async fn run(config: &Config, auth: Arc<Mutex<AuthManager>>) -> Result<Recommendation> {
+    let api = OneDriveAPI::new(auth.clone()).with_drive(config.selected_drive());
+    let folder_name = format!("onedrive-ubuntu-bench-{}", std::process::id());
+    let local_dir = std::env::temp_dir().join(&folder_name);
+    fs::create_dir_all(&local_dir).await?;
+
+    let result = match api.create_folder(&folder_name, "/").await {
+        Ok(remote_folder) => {
+            let result = measure(config, &auth, &api, &local_dir, &folder_name).await;
+            if let Err(e) = api.delete_item(&remote_folder.id).await {
+                warn!("Could not remove the benchmark folder /{}: {}", folder_name, e);
+            }
+            result
+        }
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = fs::remove_dir_all(&local_dir).await {
+        warn!("Could not remove {}: {}", local_dir.display(), e);
+    }
+    result
+}
+
+async fn measure(
+    config: &Config,
+    auth: &Arc<Mutex<AuthManager>>,
+    api: &OneDriveAPI,
+    local_dir: &Path,
+    folder_name: &str,
+) -> Result<Recommendation> {
+    let largest = *SAMPLE_SIZES.last().unwrap_or(&0);
+    let total: u64 = SAMPLE_SIZES.iter().sum::<u64>() * 2
+        + largest * CHUNK_MULTIPLES.len() as u64
+        + CONCURRENCY_FILE_SIZE * (CONCURRENCY_FILES * CONCURRENCY_LEVELS.len()) as u64;
+    println!("Measuring transfers to OneDrive in /{} (about {} MB)...", folder_name, total / (1024 * 1024));
+    println!();
+
+    // Single files of several sizes, each uploaded and downloaded once
+    println!("{:>10}  {:>12}  {:>12}", "File size", "Upload", "Download");
+    let mut largest_path = PathBuf::new();
+    for &size in SAMPLE_SIZES {
+        let name = format!("sample-{}", size);
+        let local_path = write_sample(local_dir, &name, size).await?;
+        let remote_name = format!("{}/{}", folder_name, name);
+
+        let start = Instant::now();
+        let item = api.upload_file(&local_path, &remote_name).await?;
+        let upload = mib_per_sec(size, start.elapsed());
+
+        let start = Instant::now();
+        api.download_file(&item, &local_dir.join(format!("{}.downloaded", name))).await?;
+        let download = mib_per_sec(size, start.elapsed());
+
+        println!("{:>7} MB  {:>7.1} MB/s  {:>7.1} MB/s", size / (1024 * 1024), upload, download);
+        largest_path = local_path;
+    }
+    println!();
+
+    // The largest file again, through upload sessions with different chunk sizes
+    println!("{:>10}  {:>12}", "Chunk size", "Upload");
+    let mut chunk_results = Vec::new();
+    for &multiple in CHUNK_MULTIPLES {
+        let chunk_size = multiple * UPLOAD_CHUNK_UNIT;
+        let chunk_api = OneDriveAPI::new(auth.clone())
+            .with_drive(config.selected_drive())
+            .with_upload_chunk_size(chunk_size);
+        let remote_name = format!("{}/chunks-{}", folder_name, multiple);
+
+        let start = Instant::now();
+        chunk_api.upload_file(&largest_path, &remote_name).await?;
+        let upload = mib_per_sec(largest, start.elapsed());
+
+        println!("{:>7} KB  {:>7.1} MB/s", chunk_size / 1024, upload);
+        chunk_results.push((chunk_size / 1024, upload));
+    }
+    println!();
+
+    // Batches of small files, uploaded with different numbers in parallel
+    let mut small_files = Vec::new();
+    for index in 0..CONCURRENCY_FILES {
+        let name = format!("small-{}", index);
+        let local_path = write_sample(local_dir, &name, CONCURRENCY_FILE_SIZE).await?;
+        small_files.push((local_path, name));
+    }
+
+    println!("{:>10}  {:>12}", "Parallel", "Upload");
+    let mut concurrency_results = Vec::new();
+    for &level in CONCURRENCY_LEVELS {
+        let start = Instant::now();
+        let uploads: Vec<Result<_>> = stream::iter(&small_files)
+            .map(|(local_path, name)| {
+                let remote_name = format!("{}/parallel-{}-{}", folder_name, level, name);
+                async move { api.upload_file(local_path, &remote_name).await }
+            })
+            .buffer_unordered(level)
+            .collect()
+            .await;
+        for upload in uploads {
+            upload?;
+        }
+        let upload = mib_per_sec(CONCURRENCY_FILE_SIZE * CONCURRENCY_FILES as u64, start.elapsed());
+
+        println!("{:>10}  {:>7.1} MB/s", level, upload);
+        concurrency_results.push((level, upload));
+    }
+    println!();
+
+    let recommendation = Recommendation {
+        upload_chunk_size_kib: smallest_good_enough(&chunk_results).unwrap_or(config.upload_chunk_size_kib),
+        max_concurrent_transfers: smallest_good_enough(&concurrency_results).unwrap_or(config.max_concurrent_transfers),
+    };
+
+    println!("Recommended settings for this connection:");
+    println!(
+        "  Upload chunk size:     {} KB (currently {} KB)",
+        recommendation.upload_chunk_size_kib, config.upload_chunk_size_kib
+    );
+    println!(
+        "  Concurrent transfers:  {} (currently {})",
+        recommendation.max_concurrent_transfers, config.max_concurrent_transfers
+    );
+
+    Ok(recommendation)
+}
+
+/// Writes `size` bytes of incompressible data, so compression along the way
+/// does not flatter the results.
+async fn write_sample(dir: &Path, name: &str, size: u64) -> Result<PathBuf> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15 ^ size;
+    let mut data = Vec::with_capacity(size as usize + 8);
+    while (data.len() as u64) < size {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.extend_from_slice(&state.to_le_bytes());
+    }
+    data.truncate(size as usize);
+
+    let path = dir.join(name);
+    fs::write(&path, data).await?;
+    Ok(path)
+}
+
+fn mib_per_sec(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64().max(0.001)
+}
+
+/// The smallest setting whose speed is close to the best one measured, given
+/// results ordered from the smallest setting up.
+fn smallest_good_enough<T: Copy>(results: &[(T, f64)]) -> Option<T> {
+    let best = results.iter().map(|(_, speed)| *speed).fold(0.0, f64::max);
+    results
+        .iter()
+        .find(|(_, speed)| *speed >= best * GOOD_ENOUGH)
+        .map(|(setting, _)| *setting)
+}
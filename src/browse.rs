@@ -0,0 +1,88 @@
+//! Folder listings the Files tab has shown from OneDrive, kept on disk so the
+//! browser opens instantly and still works offline. A cached listing is shown
+//! as stale until it has been fetched again this session.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tracing::warn;
+
+use crate::api::DriveItem;
+
+/// Most folder listings kept; the least recently fetched are dropped first
+const MAX_CACHED_FOLDERS: usize = 500;
+
+/// The contents of one OneDrive folder as last fetched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderListing {
+    /// Folders first, then files, each by name
+    pub items: Vec<DriveItem>,
+    /// Unix time the listing was fetched
+    pub fetched: u64,
+}
+
+/// Cached folder listings keyed by path relative to the drive root ("" for
+/// the root), saved to a JSON file next to the sync database.
+#[derive(Debug, Default)]
+pub struct BrowseCache {
+    file: PathBuf,
+    folders: HashMap<String, FolderListing>,
+}
+
+impl BrowseCache {
+    /// Loads the cache from `file`; a missing or unreadable file starts empty.
+    pub fn load(file: PathBuf) -> Self {
+        let folders = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self { file, folders }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&FolderListing> {
+        self.folders.get(path)
+    }
+
+    /// Records a fresh listing of `path` and saves the cache.
+    pub fn insert(&mut self, path: &str, mut items: Vec<DriveItem>) {
+        for item in &mut items {
+            // Download links expire within the hour
+            item.download_url = None;
+        }
+        items.sort_by_key(|item| (item.folder.is_none(), item.name.to_lowercase()));
+
+        let fetched = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.folders.insert(path.to_string(), FolderListing { items, fetched });
+
+        while self.folders.len() > MAX_CACHED_FOLDERS {
+            let Some(oldest) = self.folders.iter().min_by_key(|(_, listing)| listing.fetched).map(|(path, _)| path.clone()) else {
+                break;
+            };
+            self.folders.remove(&oldest);
+        }
+        self.save();
+    }
+
+    /// Forgets every listing, as when the user signs out.
+    pub fn clear(&mut self) {
+        self.folders.clear();
+        if let Err(e) = std::fs::remove_file(&self.file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove the OneDrive folder cache {}: {}", self.file.display(), e);
+            }
+        }
+    }
+
+    fn save(&self) {
+        let result = serde_json::to_vec(&self.folders)
+            .map_err(std::io::Error::from)
+            .and_then(|bytes| std::fs::write(&self.file, bytes));
+        if let Err(e) = result {
+            warn!("Failed to save the OneDrive folder cache to {}: {}", self.file.display(), e);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+
+/// Automatic syncs wait once this share of a request budget is used, leaving
+/// the rest for syncs the user starts
+pub const LOW_BUDGET_FRACTION: f64 = 0.9;
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+/// Graph requests sent by this process as `(unix minute, count)`, oldest
+/// first, covering the last 24 hours
+static REQUESTS: Mutex<VecDeque<(u64, u64)>> = Mutex::new(VecDeque::new());
+
+/// Graph requests sent over the last hour and the last 24 hours.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub last_hour: u64,
+    pub last_day: u64,
+}
+
+/// Counts one request to Microsoft Graph.
+pub fn record_request() {
+    let minute = current_minute();
+    let mut requests = REQUESTS.lock().unwrap();
+    match requests.back_mut() {
+        Some((last, count)) if *last == minute => *count += 1,
+        _ => requests.push_back((minute, 1)),
+    }
+    while requests.front().is_some_and(|(oldest, _)| oldest + MINUTES_PER_DAY <= minute) {
+        requests.pop_front();
+    }
+}
+
+/// Requests sent since the client started, over the last hour and day.
+pub fn usage() -> Usage {
+    let minute = current_minute();
+    let requests = REQUESTS.lock().unwrap();
+    let sent_within = |minutes: u64| -> u64 {
+        requests
+            .iter()
+            .filter(|(sent, _)| sent + minutes > minute)
+            .map(|(_, count)| count)
+            .sum()
+    };
+    Usage {
+        last_hour: sent_within(60),
+        last_day: sent_within(MINUTES_PER_DAY),
+    }
+}
+
+/// Whether `usage` has reached `LOW_BUDGET_FRACTION` of either budget set in
+/// the config. A budget of 0 is no limit.
+pub fn is_low(config: &Config, usage: Usage) -> bool {
+    let low = |used: u64, budget: u64| budget > 0 && used as f64 >= budget as f64 * LOW_BUDGET_FRACTION;
+    low(usage.last_hour, config.request_budget_per_hour) || low(usage.last_day, config.request_budget_per_day)
+}
+
+fn current_minute() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 60)
+        .unwrap_or(0)
+}
@@ -0,0 +1,218 @@
+//! Operations on many OneDrive items at once, chosen in the remote browser:
+//! downloading them to a local folder, deleting them, or moving or copying
+//! them into another OneDrive folder. They run in the background like a
+//! restore, and touch only OneDrive and the chosen folder; the next sync
+//! picks up what changed in the synced tree.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::api::{DriveItem, OneDriveAPI};
+use crate::restore::{self, Restored};
+
+/// Items deleted per batched request, so cancelling stops between them
+const DELETE_CHUNK: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkOperation {
+    /// Copies the items, folders with all they hold, into a local folder
+    Download { destination: PathBuf },
+    Delete,
+    /// Moves the items into another OneDrive folder, relative to the drive root
+    Move { folder: String },
+    /// Copies the items into another OneDrive folder, on OneDrive's side
+    Copy { folder: String },
+}
+
+impl BulkOperation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BulkOperation::Download { .. } => "Downloading",
+            BulkOperation::Delete => "Deleting",
+            BulkOperation::Move { .. } => "Moving",
+            BulkOperation::Copy { .. } => "Copying",
+        }
+    }
+}
+
+/// How far a bulk operation has got, shared with the GUI while it runs.
+#[derive(Debug, Clone, Default)]
+pub struct BulkProgress {
+    /// Still listing the chosen folders to download; the total is not known yet
+    pub listing: bool,
+    pub total: usize,
+    pub done: usize,
+    pub current: String,
+    /// Downloads left alone because a different file was already there
+    pub kept: Vec<String>,
+    pub errors: Vec<String>,
+    pub cancelled: bool,
+    pub finished: bool,
+}
+
+/// Starts `run` on the current Tokio runtime and returns the progress it
+/// reports and a token that stops it.
+pub fn start(
+    api: Arc<OneDriveAPI>,
+    operation: BulkOperation,
+    items: Vec<(String, DriveItem)>,
+) -> (Arc<Mutex<BulkProgress>>, CancellationToken) {
+    let progress = Arc::new(Mutex::new(BulkProgress::default()));
+    let cancel = CancellationToken::new();
+
+    let (task_progress, task_cancel) = (progress.clone(), cancel.clone());
+    tokio::spawn(async move {
+        run(&api, &operation, &items, &task_progress, &task_cancel).await;
+    });
+
+    (progress, cancel)
+}
+
+/// Applies `operation` to `items`, each `(path relative to the drive root,
+/// item)`. One item failing does not stop the others; its error is reported
+/// in `progress`.
+pub async fn run(
+    api: &OneDriveAPI,
+    operation: &BulkOperation,
+    items: &[(String, DriveItem)],
+    progress: &Mutex<BulkProgress>,
+    cancel: &CancellationToken,
+) {
+    info!("{} {} OneDrive items", operation.label(), items.len());
+    update(progress, |progress| {
+        *progress = BulkProgress {
+            total: items.len(),
+            ..BulkProgress::default()
+        }
+    });
+
+    match operation {
+        BulkOperation::Download { destination } => download(api, items, destination, progress, cancel).await,
+        BulkOperation::Delete => delete(api, items, progress, cancel).await,
+        BulkOperation::Move { folder } => place_in(api, items, folder, false, progress, cancel).await,
+        BulkOperation::Copy { folder } => place_in(api, items, folder, true, progress, cancel).await,
+    }
+
+    update(progress, |progress| {
+        progress.current.clear();
+        progress.cancelled = cancel.is_cancelled();
+        progress.finished = true;
+    });
+    info!("{} {} OneDrive items finished", operation.label(), items.len());
+}
+
+/// Downloads each item to `destination/<its name>`, the files in a folder
+/// keeping their place under it. Like a restore, no existing file is
+/// overwritten.
+async fn download(
+    api: &OneDriveAPI,
+    items: &[(String, DriveItem)],
+    destination: &std::path::Path,
+    progress: &Mutex<BulkProgress>,
+    cancel: &CancellationToken,
+) {
+    update(progress, |progress| progress.listing = true);
+    let mut files = Vec::new();
+    for (path, item) in items {
+        let parent = path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("");
+        if item.folder.is_none() {
+            files.push((item.name.clone(), item.clone()));
+            continue;
+        }
+        let mut folder_files = Vec::new();
+        if let Err(e) = restore::list_files(api, path, &mut folder_files, cancel).await {
+            warn!("Failed to list {} for download: {}", path, e);
+            update(progress, |progress| progress.errors.push(format!("{}: {}", path, e)));
+        }
+        for (file_path, file) in folder_files {
+            let relative = file_path.strip_prefix(parent).unwrap_or(&file_path).trim_start_matches('/').to_string();
+            files.push((relative, file));
+        }
+    }
+    update(progress, |progress| {
+        progress.listing = false;
+        progress.total = files.len();
+    });
+
+    for (relative, item) in &files {
+        if cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| progress.current = relative.clone());
+        let result = restore::restore_file(api, item, &destination.join(relative), cancel).await;
+        if result.is_err() && cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| {
+            match result {
+                Ok(Restored::Downloaded) | Ok(Restored::AlreadyThere) => {}
+                Ok(Restored::KeptLocal) => progress.kept.push(relative.clone()),
+                Err(e) => progress.errors.push(format!("{}: {}", relative, e)),
+            }
+            progress.done += 1;
+        });
+    }
+}
+
+async fn delete(api: &OneDriveAPI, items: &[(String, DriveItem)], progress: &Mutex<BulkProgress>, cancel: &CancellationToken) {
+    for chunk in items.chunks(DELETE_CHUNK) {
+        if cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| progress.current = chunk[0].0.clone());
+        let ids: Vec<String> = chunk.iter().map(|(_, item)| item.id.clone()).collect();
+        let results = match api.delete_items(&ids).await {
+            Ok(results) => results,
+            Err(e) => chunk.iter().map(|_| Err(anyhow::anyhow!(e.to_string()))).collect(),
+        };
+        update(progress, |progress| {
+            for ((path, _), result) in chunk.iter().zip(results) {
+                if let Err(e) = result {
+                    progress.errors.push(format!("{}: {}", path, e));
+                }
+            }
+            progress.done += chunk.len();
+        });
+    }
+}
+
+/// Moves each item into `folder`, or copies it there when `copy` is set.
+async fn place_in(
+    api: &OneDriveAPI,
+    items: &[(String, DriveItem)],
+    folder: &str,
+    copy: bool,
+    progress: &Mutex<BulkProgress>,
+    cancel: &CancellationToken,
+) {
+    let folder = folder.trim_matches('/');
+    let parent = format!("/{}", folder);
+    for (path, item) in items {
+        if cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| progress.current = path.clone());
+        let result = if folder == path || folder.strip_prefix(path.as_str()).is_some_and(|rest| rest.starts_with('/')) {
+            Err(anyhow::anyhow!("A folder cannot be put inside itself"))
+        } else if copy {
+            api.copy_item(&item.id, &parent, &item.name, cancel).await.map(|_| ())
+        } else {
+            api.move_item(&item.id, &parent, &item.name).await.map(|_| ())
+        };
+        if result.is_err() && cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| {
+            if let Err(e) = result {
+                progress.errors.push(format!("{}: {}", path, e));
+            }
+            progress.done += 1;
+        });
+    }
+}
+
+fn update(progress: &Mutex<BulkProgress>, change: impl FnOnce(&mut BulkProgress)) {
+    change(&mut progress.lock().unwrap());
+}
@@ -0,0 +1,504 @@
+use anyhow::{Result, anyhow};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::api::{DriveItem, OneDriveAPI};
+use crate::auth::AuthManager;
+use crate::bench;
+use crate::config::{is_valid_client_id, Config, BUILTIN_CLIENT_ID};
+use crate::doctor;
+use crate::format;
+use crate::sync::{SyncHealth, SyncManager, PLACEHOLDER_SUFFIX};
+
+/// Commands that talk to OneDrive directly, without the sync engine.
+pub const COMMANDS: &[&str] = &["login", "ls", "get", "put", "rm", "mv", "cp", "mkdir", "status", "sync", "pause", "resume", "confirm-changes", "download-now", "drives", "mount", "bench", "verify", "doctor"];
+
+pub fn print_usage() {
+    println!("  onedrive-ubuntu login                        # Sign in with a code entered on any device");
+    println!("  onedrive-ubuntu ls [REMOTE_PATH]             # List a remote folder");
+    println!("  onedrive-ubuntu get REMOTE_PATH [LOCAL_PATH] # Download a file");
+    println!("  onedrive-ubuntu put LOCAL_PATH [REMOTE_DIR]  # Upload a file");
+    println!("  onedrive-ubuntu rm REMOTE_PATH...            # Delete remote files or folders");
+    println!("  onedrive-ubuntu mv REMOTE_PATH REMOTE_DIR    # Move a remote file or folder into a folder");
+    println!("  onedrive-ubuntu cp REMOTE_PATH REMOTE_DIR    # Copy a remote file or folder on OneDrive's side");
+    println!("  onedrive-ubuntu mkdir REMOTE_PATH            # Create a remote folder");
+    println!("  onedrive-ubuntu status [--short]             # Show sync health and last sync");
+    println!("  onedrive-ubuntu sync --dry-run               # List what the next sync would change");
+    println!("  onedrive-ubuntu pause                        # Pause syncing until resumed");
+    println!("  onedrive-ubuntu resume                       # Resume syncing");
+    println!("  onedrive-ubuntu confirm-changes              # Upload files held after many changed at once");
+    println!("  onedrive-ubuntu download-now PATH...         # Download online-only files or folders");
+    println!("  onedrive-ubuntu drives [ID|default]          # List the account's drives, or choose the one synced");
+    println!("  onedrive-ubuntu mount PATH                   # Show the drive read-only at PATH until unmounted");
+    println!("  onedrive-ubuntu bench [--apply]              # Measure transfer speed and suggest settings");
+    println!("  onedrive-ubuntu verify                       # Check synced files against OneDrive without syncing");
+    println!("  onedrive-ubuntu doctor                       # Check the sign-in, connection, storage and database");
+}
+
+/// `arg` relative to the sync folder: a path inside it, given from anywhere,
+/// or one already relative to it. A placeholder stands for its file.
+fn sync_relative_path(config: &Config, arg: &str) -> String {
+    let path = arg.strip_suffix(PLACEHOLDER_SUFFIX).unwrap_or(arg);
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+    match absolute.strip_prefix(&config.sync_folder) {
+        Ok(relative) => relative.to_string_lossy().into_owned(),
+        Err(_) => path.trim_matches('/').to_string(),
+    }
+}
+
+#[tokio::main]
+pub async fn run(command: &str, args: &[String]) -> Result<()> {
+    let config = Arc::new(Config::new()?);
+    format::set_size_units(config.size_units);
+    let auth = Arc::new(Mutex::new(AuthManager::new(config.clone())?));
+    let api = OneDriveAPI::new(auth.clone())
+        .with_upload_chunk_size(config.upload_chunk_size_kib * 1024)
+        .with_call_upload_limit(config.call_upload_limit())
+        .with_drive(config.selected_drive());
+    let has_tokens = auth.lock().await.has_tokens();
+
+    if command == "login" {
+        return login(&config, &auth).await;
+    }
+
+    // Status works signed out too; being signed out is part of what it reports
+    if command == "status" {
+        let short = args.iter().any(|arg| arg == "--short");
+        return status(config, api, has_tokens, short).await;
+    }
+
+    // The doctor reports a missing sign-in along with everything else
+    if command == "doctor" {
+        return doctor(config, auth, api).await;
+    }
+
+    // Pausing only touches the sync database, which a running client re-reads
+    // before each sync
+    if command == "pause" || command == "resume" {
+        let sync_manager = SyncManager::new(config, Arc::new(api))?;
+        if command == "pause" {
+            sync_manager.pause().await?;
+            println!("Syncing paused. Run onedrive-ubuntu resume to continue.");
+        } else {
+            sync_manager.resume().await?;
+            println!("Syncing resumed");
+        }
+        return Ok(());
+    }
+
+    if command == "confirm-changes" {
+        let sync_manager = SyncManager::new(config, Arc::new(api))?;
+        if sync_manager.get_status().await.uploads_paused_for_mass_change.is_none() {
+            println!("Uploads are not paused");
+            return Ok(());
+        }
+        sync_manager.control().confirm_mass_change().await?;
+        println!("Changes confirmed; the next sync uploads them.");
+        return Ok(());
+    }
+
+    // Like pausing, a request the running client picks up with its next sync
+    if command == "download-now" {
+        if args.is_empty() {
+            return Err(anyhow!("Usage: onedrive-ubuntu download-now PATH..."));
+        }
+        let paths = args.iter().map(|arg| sync_relative_path(&config, arg)).collect();
+        let sync_manager = SyncManager::new(config, Arc::new(api))?;
+        match sync_manager.control().download_now(paths).await? {
+            0 => println!("No online-only files there"),
+            count => println!("{} files download with the next sync", format::count(count as u64)),
+        }
+        return Ok(());
+    }
+
+    if !has_tokens {
+        return Err(anyhow!("Not signed in. Run onedrive-ubuntu and sign in with Microsoft first."));
+    }
+
+    match command {
+        "ls" => list(&api, args.first().map(String::as_str).unwrap_or("/")).await,
+        "get" => {
+            let remote_path = args.first().ok_or_else(|| anyhow!("Usage: onedrive-ubuntu get REMOTE_PATH [LOCAL_PATH]"))?;
+            get(&api, remote_path, args.get(1).map(PathBuf::from)).await
+        }
+        "put" => {
+            let local_path = args.first().ok_or_else(|| anyhow!("Usage: onedrive-ubuntu put LOCAL_PATH [REMOTE_DIR]"))?;
+            put(&api, Path::new(local_path), args.get(1).map(String::as_str).unwrap_or("/")).await
+        }
+        "rm" => {
+            if args.is_empty() {
+                return Err(anyhow!("Usage: onedrive-ubuntu rm REMOTE_PATH..."));
+            }
+            remove(&api, args).await
+        }
+        "mv" | "cp" => {
+            let (Some(from), Some(to)) = (args.first(), args.get(1)) else {
+                return Err(anyhow!("Usage: onedrive-ubuntu {} REMOTE_PATH REMOTE_DIR", command));
+            };
+            relocate(&api, from, to, command == "cp").await
+        }
+        "drives" => drives(&config, &api, args.first().map(String::as_str)).await,
+        "mount" => {
+            let mount_point = args.first().ok_or_else(|| anyhow!("Usage: onedrive-ubuntu mount PATH"))?;
+            mount(api, PathBuf::from(mount_point)).await
+        }
+        "mkdir" => {
+            let remote_path = args.first().ok_or_else(|| anyhow!("Usage: onedrive-ubuntu mkdir REMOTE_PATH"))?;
+            mkdir(&api, remote_path).await
+        }
+        "sync" => {
+            // Real syncs belong to the running client; the CLI only previews them
+            if !args.iter().any(|arg| arg == "--dry-run") {
+                return Err(anyhow!("Usage: onedrive-ubuntu sync --dry-run"));
+            }
+            preview_sync(config, api).await
+        }
+        "verify" => verify(config, api).await,
+        "bench" => {
+            let recommendation = bench::run(&config, auth).await?;
+            if args.iter().any(|arg| arg == "--apply") {
+                let mut config = (*config).clone();
+                if !config.is_locked("upload_chunk_size_kib") {
+                    config.set_upload_chunk_size_kib(recommendation.upload_chunk_size_kib)?;
+                }
+                if !config.is_locked("max_concurrent_transfers") {
+                    config.set_max_concurrent_transfers(recommendation.max_concurrent_transfers)?;
+                }
+                println!("Settings saved; restart the client to use them.");
+            } else {
+                println!("Run onedrive-ubuntu bench --apply to save these settings.");
+            }
+            Ok(())
+        }
+        _ => Err(anyhow!("Unknown command: {}", command)),
+    }
+}
+
+/// Signs in with a device code, for machines where no browser can finish
+/// the usual sign-in: over SSH, on servers, or behind a strict firewall.
+/// Without an app registration of its own the built-in one is used, as in
+/// the GUI's quick setup.
+async fn login(config: &Config, auth: &Mutex<AuthManager>) -> Result<()> {
+    let mut auth = auth.lock().await;
+    if !is_valid_client_id(&config.client_id) {
+        auth.use_client_id(BUILTIN_CLIENT_ID)?;
+    }
+    auth.authenticate_with_device_code(|device_code| {
+        println!("To sign in, open {} on any device and enter the code {}", device_code.verification_uri, device_code.user_code);
+        println!("Waiting for you to finish signing in...");
+    })
+    .await?;
+    println!("Signed in. Start onedrive-ubuntu to sync.");
+    Ok(())
+}
+
+/// Moves the remote item `from` into the folder `to`, or copies it there
+/// when `copy` is set; either way nothing is downloaded.
+async fn relocate(api: &OneDriveAPI, from: &str, to: &str, copy: bool) -> Result<()> {
+    let item = api.get_item(&normalize_remote_path(from)).await?;
+    let folder = normalize_remote_path(to);
+    if copy {
+        api.copy_item(&item.id, &folder, &item.name, &CancellationToken::new()).await?;
+        println!("Copied {} to {}", normalize_remote_path(from), folder);
+    } else {
+        api.move_item(&item.id, &folder, &item.name).await?;
+        println!("Moved {} to {}", normalize_remote_path(from), folder);
+    }
+    Ok(())
+}
+
+/// Lists the drives the account can sync, marking the one in use, or makes
+/// `choice` (a drive ID, or `default` for the account's OneDrive) the one
+/// synced.
+async fn drives(config: &Config, api: &OneDriveAPI, choice: Option<&str>) -> Result<()> {
+    let drives = api.list_drives().await?;
+    let Some(choice) = choice else {
+        for drive in &drives {
+            let selected = if drive.id == config.drive_id { "*" } else { " " };
+            let name = drive.name.as_deref().unwrap_or("(unnamed)");
+            println!("{} {}  {} ({})", selected, drive.id, name, drive.drive_type);
+        }
+        if config.drive_id.is_empty() {
+            println!("Syncing the account's own OneDrive");
+        }
+        return Ok(());
+    };
+
+    if config.is_locked("drive_id") {
+        return Err(anyhow!("The synced drive is set by your administrator"));
+    }
+    let drive_id = if choice == "default" {
+        String::new()
+    } else if drives.iter().any(|drive| drive.id == choice) {
+        choice.to_string()
+    } else {
+        return Err(anyhow!("No drive with ID {}; run onedrive-ubuntu drives to list them", choice));
+    };
+    let mut config = config.clone();
+    config.set_drive_id(drive_id)?;
+    println!("Drive saved; restart the client to use it. Syncing waits until you re-link the sync folder to it from the Status tab.");
+    Ok(())
+}
+
+/// Normalizes user input like `Documents/` or `/Documents` to `/Documents`.
+fn normalize_remote_path(path: &str) -> String {
+    let trimmed = path.trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+fn split_remote_path(path: &str) -> (String, String) {
+    let normalized = normalize_remote_path(path);
+    match normalized.rsplit_once('/') {
+        Some(("", name)) => ("/".to_string(), name.to_string()),
+        Some((parent, name)) => (parent.to_string(), name.to_string()),
+        None => ("/".to_string(), normalized),
+    }
+}
+
+async fn list(api: &OneDriveAPI, path: &str) -> Result<()> {
+    let mut items = api.list_items(&normalize_remote_path(path)).await?;
+    items.sort_by(|a, b| b.folder.is_some().cmp(&a.folder.is_some()).then(a.name.cmp(&b.name)));
+
+    for item in items {
+        let modified = chrono::DateTime::parse_from_rfc3339(item.modified_time())
+            .map(|dt| format::date_time(&dt))
+            .unwrap_or_else(|_| item.modified_time().to_string());
+
+        if item.folder.is_some() {
+            println!("d {:>10}  {}  {}/", "-", modified, item.name);
+        } else {
+            println!("- {:>10}  {}  {}", format::size(item.size.unwrap_or(0)), modified, item.name);
+        }
+    }
+
+    Ok(())
+}
+
+async fn get(api: &OneDriveAPI, remote_path: &str, local_path: Option<PathBuf>) -> Result<()> {
+    let item = api.get_item(&normalize_remote_path(remote_path)).await?;
+    if item.folder.is_some() {
+        return Err(anyhow!("{} is a folder; only files can be downloaded", remote_path));
+    }
+
+    let local_path = match local_path {
+        Some(path) if path.is_dir() => path.join(&item.name),
+        Some(path) => path,
+        None => PathBuf::from(&item.name),
+    };
+
+    api.download_file(&item, &local_path).await?;
+    println!("Downloaded {} -> {}", remote_path, local_path.display());
+    Ok(())
+}
+
+async fn put(api: &OneDriveAPI, local_path: &Path, remote_dir: &str) -> Result<()> {
+    if !local_path.is_file() {
+        return Err(anyhow!("{} is not a file", local_path.display()));
+    }
+
+    let file_name = local_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file name: {}", local_path.display()))?
+        .to_string_lossy();
+
+    let remote_dir = normalize_remote_path(remote_dir);
+    let remote_name = if remote_dir == "/" {
+        file_name.to_string()
+    } else {
+        format!("{}/{}", remote_dir.trim_start_matches('/'), file_name)
+    };
+
+    let item: DriveItem = api.upload_file(local_path, &remote_name).await?;
+    println!("Uploaded {} -> /{} ({})", local_path.display(), remote_name, format::size(item.size.unwrap_or(0)));
+    Ok(())
+}
+
+/// Deletes every path given, looking them up and deleting them in batched
+/// requests; one that fails does not stop the others.
+async fn remove(api: &OneDriveAPI, remote_paths: &[String]) -> Result<()> {
+    let remote_paths: Vec<String> = remote_paths.iter().map(|path| normalize_remote_path(path)).collect();
+    if remote_paths.iter().any(|path| path == "/") {
+        return Err(anyhow!("Refusing to delete the OneDrive root"));
+    }
+
+    let mut failed = 0;
+    let mut found = Vec::new();
+    for (path, item) in remote_paths.iter().zip(api.get_items(&remote_paths).await?) {
+        match item {
+            Ok(item) => found.push((path, item.id)),
+            Err(e) => {
+                eprintln!("Could not delete {}: {}", path, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let ids: Vec<String> = found.iter().map(|(_, id)| id.clone()).collect();
+    for ((path, _), result) in found.iter().zip(api.delete_items(&ids).await?) {
+        match result {
+            Ok(()) => println!("Deleted {}", path),
+            Err(e) => {
+                eprintln!("Could not delete {}: {}", path, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{} of {} items could not be deleted", failed, remote_paths.len()));
+    }
+    Ok(())
+}
+
+async fn mkdir(api: &OneDriveAPI, remote_path: &str) -> Result<()> {
+    let (parent, name) = split_remote_path(remote_path);
+    if name.is_empty() {
+        return Err(anyhow!("Folder name is required"));
+    }
+
+    api.create_folder(&name, &parent).await?;
+    println!("Created {}", normalize_remote_path(remote_path));
+    Ok(())
+}
+
+/// Reports the status the client last saved, so it works whether or not the
+/// client is running.
+async fn status(config: Arc<Config>, api: OneDriveAPI, has_tokens: bool, short: bool) -> Result<()> {
+    let sync_manager = SyncManager::new(config.clone(), Arc::new(api))?;
+    let mut status = sync_manager.get_status().await;
+    status.auth_failed |= !has_tokens;
+    let health = status.health();
+
+    let last_error = if status.auth_failed {
+        Some("Not signed in".to_string())
+    } else {
+        status.sync_errors.last().cloned()
+    };
+
+    if short {
+        match (health, last_error) {
+            (SyncHealth::Healthy, _) | (_, None) => println!("{}", health.label()),
+            (_, Some(error)) => println!("{}: {}", health.label(), error),
+        }
+        return Ok(());
+    }
+
+    println!("Health:      {}", health.label());
+    if status.paused {
+        println!("Paused:      yes");
+    }
+    println!("Sync folder: {}", config.sync_folder.display());
+    match status.last_sync {
+        Some(last_sync) => {
+            let last_sync: chrono::DateTime<chrono::Local> = last_sync.into();
+            println!("Last sync:   {}", format::date_time(&last_sync));
+        }
+        None => println!("Last sync:   never"),
+    }
+    if let Some(run_id) = &status.run_id {
+        println!("Last run:    {}", run_id);
+    }
+    if status.pending_actions > 0 {
+        println!("Pending:     {} changes", format::count(status.pending_actions));
+    }
+    if !status.file_states.is_empty() {
+        let counts: Vec<String> = status
+            .file_states
+            .iter()
+            .map(|(state, count)| format!("{} {}", count, state.label().to_lowercase()))
+            .collect();
+        println!("Files:       {}", counts.join(", "));
+    }
+    if let Some(error) = last_error {
+        println!("Last error:  {}", error);
+    }
+    if let Some(reason) = &status.uploads_paused_for_mass_change {
+        println!("Uploads:     paused; {}", reason);
+        println!("             Run onedrive-ubuntu confirm-changes once the files are checked.");
+    } else if let Some(reason) = &status.uploads_held_for_quota {
+        println!("Uploads:     waiting; {}", reason);
+    }
+    for issue in &status.issues {
+        println!("Not syncing: {} ({})", issue.path, issue.reason);
+    }
+
+    Ok(())
+}
+
+async fn preview_sync(config: Arc<Config>, api: OneDriveAPI) -> Result<()> {
+    let mut sync_manager = SyncManager::new(config, Arc::new(api))?;
+    let actions = sync_manager.preview_sync().await?;
+
+    if actions.is_empty() {
+        println!("Everything is up to date");
+        return Ok(());
+    }
+
+    for action in &actions {
+        println!("{:<9} {}", action.kind(), action.summary());
+    }
+
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for action in &actions {
+        match counts.iter_mut().find(|(kind, _)| *kind == action.kind()) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((action.kind(), 1)),
+        }
+    }
+    let counts: Vec<String> = counts.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+    println!();
+    println!("{} changes: {}", actions.len(), counts.join(", "));
+
+    Ok(())
+}
+
+/// Prints the health check and fails when a check failed, so scripts can act
+/// on the exit status.
+async fn doctor(config: Arc<Config>, auth: Arc<Mutex<AuthManager>>, api: OneDriveAPI) -> Result<()> {
+    let checks = doctor::run(config, auth, Arc::new(api)).await;
+
+    print!("{}", doctor::render(&checks, std::io::stdout().is_terminal()));
+    let failed = checks.iter().filter(|check| check.outcome == doctor::Outcome::Fail).count();
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(anyhow!("{} checks failed", failed))
+    }
+}
+
+/// Mounts the drive read-only at `mount_point` until it is unmounted.
+#[cfg(feature = "fuse")]
+async fn mount(api: OneDriveAPI, mount_point: PathBuf) -> Result<()> {
+    if !mount_point.is_dir() {
+        return Err(anyhow!("{} is not a folder", mount_point.display()));
+    }
+    println!("OneDrive is mounted read-only at {}. Unmount with: fusermount -u {}", mount_point.display(), mount_point.display());
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || crate::mount::mount(Arc::new(api), runtime, &mount_point)).await?
+}
+
+#[cfg(not(feature = "fuse"))]
+async fn mount(_api: OneDriveAPI, _mount_point: PathBuf) -> Result<()> {
+    Err(anyhow!("This build has no FUSE support; rebuild with: cargo build --release --features fuse"))
+}
+
+/// Prints the integrity audit and fails when it found problems, so scripts
+/// can act on the exit status.
+async fn verify(config: Arc<Config>, api: OneDriveAPI) -> Result<()> {
+    let mut sync_manager = SyncManager::new(config, Arc::new(api))?;
+    let report = sync_manager.verify().await?;
+
+    print!("{}", report.render());
+    if report.is_clean() {
+        Ok(())
+    } else {
+        Err(anyhow!("{} files need attention", report.mismatches.len() + report.unreadable.len()))
+    }
+}
@@ -0,0 +1,64 @@
+use reqwest::header::{HeaderMap, DATE};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Offsets below this are normal network latency and clock jitter
+pub const SKEW_WARNING_SECS: i64 = 120;
+
+/// Server time minus local time, in seconds, from the latest Graph response
+static SKEW_SECS: AtomicI64 = AtomicI64::new(0);
+static SKEW_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Updates the estimated clock skew from a response's `Date` header.
+pub fn observe_server_date(headers: &HeaderMap) {
+    let Some(server_time) = headers
+        .get(DATE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+    else {
+        return;
+    };
+
+    let local_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let skew = server_time.timestamp() - local_time;
+    SKEW_SECS.store(skew, Ordering::Relaxed);
+
+    let skewed = skew.abs() >= SKEW_WARNING_SECS;
+    if skewed && !SKEW_WARNED.swap(true, Ordering::Relaxed) {
+        warn!(
+            "System clock is off by {} seconds compared to Microsoft's servers; compensating, but please enable automatic time sync",
+            skew
+        );
+    } else if !skewed && SKEW_WARNED.swap(false, Ordering::Relaxed) {
+        info!("System clock is back in line with Microsoft's servers");
+    }
+}
+
+/// Server time minus local time, in seconds.
+pub fn skew_secs() -> i64 {
+    SKEW_SECS.load(Ordering::Relaxed)
+}
+
+/// The skew, when it is large enough to tell the user about.
+pub fn significant_skew_secs() -> Option<i64> {
+    let skew = skew_secs();
+    (skew.abs() >= SKEW_WARNING_SECS).then_some(skew)
+}
+
+/// Current Unix time corrected to the server's clock.
+pub fn server_now_secs() -> u64 {
+    let local_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    (local_time + skew_secs()).max(0) as u64
+}
+
+/// Converts a timestamp from the server's clock to the local clock.
+pub fn to_local_secs(server_secs: u64) -> u64 {
+    (server_secs as i64 - skew_secs()).max(0) as u64
+}
@@ -1,19 +1,198 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
+/// Public client ID used when the user has not registered their own Azure app.
+/// It is a shared, publicly known registration, so sign-in shows its name and
+/// some organizations block it.
+pub const BUILTIN_CLIENT_ID: &str = "14d82eec-204b-4c2f-b7e8-296a70dab67e";
+
+/// Whether `client_id` looks like an Azure app registration's ID, a UUID.
+pub fn is_valid_client_id(client_id: &str) -> bool {
+    client_id.len() == 36 &&
+    client_id.chars().enumerate().all(|(i, c)| {
+        match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        }
+    })
+}
+
+/// Settings an administrator sets for everyone on a managed machine. Any key
+/// from the user config found here overrides the user's value, and the names
+/// in its `locked_settings` list cannot be changed in the GUI.
+pub const SYSTEM_CONFIG_FILE: &str = "/etc/onedrive-ubuntu/config.toml";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub client_id: String,
     pub redirect_uri: String,
     pub sync_folder: PathBuf,
+    /// ID of the drive synced, from `/me/drives`; empty for the account's own OneDrive
+    #[serde(default)]
+    pub drive_id: String,
     pub sync_interval_minutes: u64,
     pub auto_start: bool,
     pub minimize_to_tray: bool,
-    pub notifications: bool,
+    /// Which events show a desktop notification; older configs hold a single
+    /// `notifications = true/false` for all of them
+    #[serde(deserialize_with = "deserialize_notifications")]
+    pub notifications: NotificationPrefs,
     pub debug_logging: bool,
+    /// Top-level folders downloaded first during the initial sync
+    #[serde(default = "default_priority_folders")]
+    pub priority_folders: Vec<String>,
+    /// Download the most recently modified files first in later syncs; the
+    /// initial sync follows `priority_folders` instead
+    #[serde(default = "default_true")]
+    pub download_newest_first: bool,
+    /// Files new on OneDrive appear as small placeholders (`name.onedrive`)
+    /// and are only downloaded when asked to; files already here stay
+    #[serde(default)]
+    pub online_only: bool,
+    /// Folder names never synced, wherever they appear in the tree
+    #[serde(default = "default_excluded_folder_names")]
+    pub excluded_folder_names: Vec<String>,
+    /// Skip folders containing another sync client's marker files
+    #[serde(default = "default_true")]
+    pub auto_exclude_sync_folders: bool,
+    /// Glob patterns (e.g. `*.iso`, `target/**`) kept out of sync in both directions
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Bookmark the sync folder in the file manager and give it a cloud icon
+    #[serde(default = "default_true")]
+    pub folder_integration: bool,
+    /// Number of uploads/downloads run in parallel during a sync
+    #[serde(default = "default_max_concurrent_transfers")]
+    pub max_concurrent_transfers: usize,
+    /// Size of each request in a large-file upload, in KiB; rounded down to a
+    /// multiple of 320 KiB
+    #[serde(default = "default_upload_chunk_size_kib")]
+    pub upload_chunk_size_kib: u64,
+    /// Patterns (same syntax as `exclude_patterns`) whose changes sync silently,
+    /// e.g. `.obsidian/` for applications that autosave constantly
+    #[serde(default)]
+    pub notification_exclude_patterns: Vec<String>,
+    /// Folders outside the sync folder mirrored one-way (upload only) to OneDrive
+    #[serde(default)]
+    pub upload_mirrors: Vec<UploadMirror>,
+    /// Content types (e.g. `video/*`, `application/x-iso9660-image`) whose remote
+    /// files are never downloaded; they stay online-only
+    #[serde(default)]
+    pub download_exclude_mime_types: Vec<String>,
+    /// Local files larger than this are never uploaded; 0 is no limit
+    #[serde(default)]
+    pub max_upload_size_mb: u64,
+    /// File extensions (e.g. `iso`, `vdi`, `tmp`) never synced in either direction
+    #[serde(default)]
+    pub excluded_extensions: Vec<String>,
+    /// Extract the text of synced documents (plain text, Markdown, PDF) into a
+    /// local index, so the Files tab can find them by content even offline
+    #[serde(default)]
+    pub index_content: bool,
+    /// Rename local files and folders whose names OneDrive refuses (e.g. with
+    /// `:` or `?`, or ending in a dot) instead of leaving them unsynced
+    #[serde(default)]
+    pub rename_illegal_names: bool,
+    /// Local files modified more recently than this, in seconds, are left for a
+    /// later sync rather than uploaded half-written; 0 uploads them right away
+    #[serde(default = "default_upload_settle_seconds")]
+    pub upload_settle_seconds: u64,
+    /// Sync log entries and run records older than this many days are deleted
+    /// after each sync; 0 keeps them forever
+    #[serde(default = "default_log_retention_days")]
+    pub log_retention_days: u64,
+    /// Most sync log entries kept, the oldest deleted first; 0 means no limit
+    #[serde(default = "default_log_max_rows")]
+    pub log_max_rows: u64,
+    /// Send files deleted on OneDrive to the desktop Trash rather than deleting
+    /// the local copy outright
+    #[serde(default = "default_true")]
+    pub trash_remote_deletions: bool,
+    /// Limit upload speed to `call_upload_limit_kib` while a camera or
+    /// microphone is in use, as during a video call
+    #[serde(default)]
+    pub throttle_uploads_during_calls: bool,
+    /// Upload speed allowed during a call, in KiB per second
+    #[serde(default = "default_call_upload_limit_kib")]
+    pub call_upload_limit_kib: u64,
+    /// Keep the computer from sleeping while a sync moves a lot of data
+    #[serde(default = "default_true")]
+    pub inhibit_sleep_during_transfers: bool,
+    /// Process names (e.g. `kdenlive`, `VirtualBoxVM`) that pause syncing while
+    /// running, so half-written project files are not uploaded
+    #[serde(default)]
+    pub pause_while_running: Vec<String>,
+    /// Times of the week that limit automatic syncing, as set by `sync_window_mode`;
+    /// manual syncs always run
+    #[serde(default)]
+    pub sync_windows: Vec<SyncWindow>,
+    #[serde(default)]
+    pub sync_window_mode: SyncWindowMode,
+    /// Graph requests automatic syncs may use per hour and per day before they
+    /// wait, for business tenants that hit Microsoft's limits; 0 is no limit
+    #[serde(default)]
+    pub request_budget_per_hour: u64,
+    #[serde(default)]
+    pub request_budget_per_day: u64,
+    /// Shell command that receives a plain-text sync summary on standard input
+    /// (e.g. `sendmail`, `ntfy publish`), for machines nobody watches; empty to
+    /// send none
+    #[serde(default)]
+    pub summary_command: String,
+    #[serde(default)]
+    pub summary_frequency: SummaryFrequency,
+    /// URLs sent a JSON payload when a sync finishes or fails and on conflicts,
+    /// e.g. a Home Assistant or Slack incoming webhook
+    #[serde(default)]
+    pub webhook_urls: Vec<String>,
+    /// Shared folders, relative to the sync folder, whose changes downloaded
+    /// from OneDrive are announced with who made them
+    #[serde(default)]
+    pub watched_folders: Vec<String>,
+    /// Public HTTPS URL that forwards to `push_listen_address`, for Graph to
+    /// post change notifications to; empty syncs on the interval only
+    #[serde(default)]
+    pub push_notification_url: String,
+    /// Local address the change notification listener binds to
+    #[serde(default = "default_push_listen_address")]
+    pub push_listen_address: String,
+    /// Between syncs, check OneDrive for changes this often (30 to 60 seconds)
+    /// and download just those; 0 waits for the next sync
+    #[serde(default)]
+    pub remote_poll_seconds: u64,
+    /// Folder in the sync folder (e.g. `Archive`) whose files are removed from
+    /// this computer, staying on OneDrive only, once unopened for
+    /// `archive_after_days`; empty keeps every file
+    #[serde(default)]
+    pub archive_folder: String,
+    /// Days a file in `archive_folder` goes unopened before it is freed
+    #[serde(default = "default_archive_after_days")]
+    pub archive_after_days: u64,
+    /// Whether sizes are shown in GB (powers of 1000, as the file manager does)
+    /// or GiB (powers of 1024)
+    #[serde(default)]
+    pub size_units: SizeUnits,
+    /// White on black with strong outlines, for low vision
+    #[serde(default)]
+    pub high_contrast: bool,
+    /// No animations or spinners, also applied when the desktop asks for
+    /// reduced motion
+    #[serde(default)]
+    pub reduce_motion: bool,
+    
+    /// Settings shown read-only in the GUI, from `SYSTEM_CONFIG_FILE`
+    #[serde(skip)]
+    pub locked_settings: Vec<String>,
+    /// The administrator's values from `SYSTEM_CONFIG_FILE`, applied over the user's
+    #[serde(skip)]
+    pub(crate) system_settings: toml::Table,
+    /// The user's own values of the settings in `system_settings`, which are
+    /// what `save` writes back to config.toml
+    #[serde(skip)]
+    pub(crate) user_settings: toml::Table,
     
     // Internal paths (not serialized)
     #[serde(skip)]
@@ -26,6 +205,118 @@ pub struct Config {
     pub db_file: PathBuf,
 }
 
+/// Desktop notifications the user wants, per kind of event.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationPrefs {
+    /// Files a sync uploaded or downloaded
+    pub sync_complete: bool,
+    /// A sync failed
+    pub errors: bool,
+    /// A file was changed on both sides and a conflict copy kept
+    pub conflicts: bool,
+    /// Uploads are waiting because OneDrive is out of space
+    pub quota: bool,
+    /// Files newly shared with the user
+    pub shared_files: bool,
+    /// Someone else changed a file in one of `Config::watched_folders`
+    pub folder_activity: bool,
+}
+
+impl NotificationPrefs {
+    /// Every notification turned on or off.
+    pub fn all(enabled: bool) -> Self {
+        Self {
+            sync_complete: enabled,
+            errors: enabled,
+            conflicts: enabled,
+            quota: enabled,
+            shared_files: enabled,
+            folder_activity: enabled,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        *self != Self::all(false)
+    }
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self::all(true)
+    }
+}
+
+/// Reads `notifications` as either the per-event table or the single
+/// switch older versions wrote.
+fn deserialize_notifications<'de, D>(deserializer: D) -> std::result::Result<NotificationPrefs, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Notifications {
+        Switch(bool),
+        PerEvent(NotificationPrefs),
+    }
+
+    Ok(match Notifications::deserialize(deserializer)? {
+        Notifications::Switch(enabled) => NotificationPrefs::all(enabled),
+        Notifications::PerEvent(prefs) => prefs,
+    })
+}
+
+/// A watched local folder whose new files are uploaded into `remote_folder`,
+/// without anything ever being downloaded or deleted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UploadMirror {
+    pub local_folder: PathBuf,
+    pub remote_folder: String,
+}
+
+/// A daily time range, `HH:MM` in local time, on the given days (every day
+/// when empty). An `end` at or before `start` runs past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncWindow {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub days: Vec<chrono::Weekday>,
+}
+
+/// How `Config::sync_windows` apply to automatic syncs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncWindowMode {
+    /// Sync automatically only inside the windows
+    #[default]
+    Allow,
+    /// Never sync automatically inside the windows (quiet hours)
+    Quiet,
+}
+
+/// How often `Config::summary_command` is sent a summary.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryFrequency {
+    /// After every sync
+    EverySync,
+    /// At most once a day, covering every sync since the last summary
+    #[default]
+    Daily,
+}
+
+/// Units `format::size` shows sizes in.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeUnits {
+    /// kB, MB, GB: powers of 1000
+    #[default]
+    Decimal,
+    /// KiB, MiB, GiB: powers of 1024
+    Binary,
+}
+
 impl Default for Config {
     fn default() -> Self {
         let config_dir = dirs::config_dir()
@@ -41,11 +332,55 @@ impl Default for Config {
             client_id: "your-client-id-here".to_string(),
             redirect_uri: "http://localhost:8080".to_string(),
             sync_folder,
+            drive_id: String::new(),
             sync_interval_minutes: 5,
             auto_start: true,
             minimize_to_tray: true,
-            notifications: true,
+            notifications: NotificationPrefs::default(),
             debug_logging: false,
+            priority_folders: default_priority_folders(),
+            download_newest_first: true,
+            online_only: false,
+            excluded_folder_names: default_excluded_folder_names(),
+            auto_exclude_sync_folders: true,
+            exclude_patterns: Vec::new(),
+            folder_integration: true,
+            max_concurrent_transfers: default_max_concurrent_transfers(),
+            upload_chunk_size_kib: default_upload_chunk_size_kib(),
+            notification_exclude_patterns: Vec::new(),
+            upload_mirrors: Vec::new(),
+            download_exclude_mime_types: Vec::new(),
+            max_upload_size_mb: 0,
+            excluded_extensions: Vec::new(),
+            index_content: false,
+            rename_illegal_names: false,
+            upload_settle_seconds: default_upload_settle_seconds(),
+            log_retention_days: default_log_retention_days(),
+            log_max_rows: default_log_max_rows(),
+            trash_remote_deletions: true,
+            throttle_uploads_during_calls: false,
+            call_upload_limit_kib: default_call_upload_limit_kib(),
+            inhibit_sleep_during_transfers: true,
+            pause_while_running: Vec::new(),
+            sync_windows: Vec::new(),
+            sync_window_mode: SyncWindowMode::default(),
+            request_budget_per_hour: 0,
+            request_budget_per_day: 0,
+            summary_command: String::new(),
+            summary_frequency: SummaryFrequency::default(),
+            webhook_urls: Vec::new(),
+            watched_folders: Vec::new(),
+            push_notification_url: String::new(),
+            push_listen_address: default_push_listen_address(),
+            remote_poll_seconds: 0,
+            archive_folder: String::new(),
+            archive_after_days: default_archive_after_days(),
+            size_units: SizeUnits::default(),
+            high_contrast: false,
+            reduce_motion: false,
+            locked_settings: Vec::new(),
+            system_settings: toml::Table::new(),
+            user_settings: toml::Table::new(),
             
             config_file: config_dir.join("config.toml"),
             token_file: config_dir.join("tokens.json"),
@@ -55,10 +390,74 @@ impl Default for Config {
     }
 }
 
+fn default_priority_folders() -> Vec<String> {
+    vec!["Desktop".to_string(), "Documents".to_string()]
+}
+
+fn default_excluded_folder_names() -> Vec<String> {
+    [
+        "Dropbox",
+        "Nextcloud",
+        ".git",
+        ".dropbox.cache",
+        "snap",
+        ".var",
+        ".flatpak-builder",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_concurrent_transfers() -> usize {
+    4
+}
+
+fn default_upload_chunk_size_kib() -> u64 {
+    10240
+}
+
+fn default_upload_settle_seconds() -> u64 {
+    15
+}
+
+fn default_archive_after_days() -> u64 {
+    30
+}
+
+fn default_log_retention_days() -> u64 {
+    90
+}
+
+fn default_log_max_rows() -> u64 {
+    100_000
+}
+
+fn default_push_listen_address() -> String {
+    "127.0.0.1:8765".to_string()
+}
+
+fn default_call_upload_limit_kib() -> u64 {
+    128
+}
+
 impl Config {
     pub fn new() -> Result<Self> {
-        let mut config = Self::default();
+        let config = Self::load(Self::default(), Path::new(SYSTEM_CONFIG_FILE))?;
         
+        // Ensure sync folder exists
+        fs::create_dir_all(&config.sync_folder)?;
+        
+        Ok(config)
+    }
+    
+    /// Reads the user's config.toml at `config`'s paths, writing `config` there
+    /// when there is none yet, and overlays the administrator's `system_config`.
+    pub(crate) fn load(mut config: Config, system_config: &Path) -> Result<Self> {
         // Create config directory
         fs::create_dir_all(&config.config_dir)?;
         info!("Config directory: {}", config.config_dir.display());
@@ -81,8 +480,9 @@ impl Config {
             }
         }
         
-        // Ensure sync folder exists
-        fs::create_dir_all(&config.sync_folder)?;
+        if let Err(e) = config.apply_system_config(system_config) {
+            warn!("Failed to apply {}: {}", system_config.display(), e);
+        }
         
         Ok(config)
     }
@@ -100,13 +500,80 @@ impl Config {
         Ok(config)
     }
     
+    /// Overlays the administrator's settings from `path`, when it exists, and
+    /// records which settings are locked.
+    fn apply_system_config(&mut self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let mut system: toml::Table = toml::from_str(&fs::read_to_string(path)?)?;
+        let locked: Vec<String> = match system.remove("locked_settings") {
+            Some(value) => value.try_into()?,
+            None => Vec::new(),
+        };
+
+        if !system.is_empty() {
+            let toml::Value::Table(mut table) = toml::Value::try_from(&*self)? else {
+                return Err(anyhow!("Configuration did not serialize to a table"));
+            };
+            let overridden = system.len();
+            let user_settings: toml::Table = system
+                .keys()
+                .filter_map(|key| Some((key.clone(), table.get(key)?.clone())))
+                .collect();
+            table.extend(system.clone());
+            let mut config: Config = toml::Value::Table(table).try_into()?;
+            config.config_dir = self.config_dir.clone();
+            config.config_file = self.config_file.clone();
+            config.token_file = self.token_file.clone();
+            config.db_file = self.db_file.clone();
+            config.system_settings = system;
+            config.user_settings = user_settings;
+            *self = config;
+            info!("Applied {} settings from {}", overridden, path.display());
+        }
+        if !locked.is_empty() {
+            info!("Settings locked by {}: {}", path.display(), locked.join(", "));
+        }
+        self.locked_settings = locked;
+        Ok(())
+    }
+    
+    /// Whether the administrator has locked `setting`, named as in config.toml.
+    pub fn is_locked(&self, setting: &str) -> bool {
+        self.locked_settings.iter().any(|locked| locked == setting)
+    }
+    
     pub fn save(&self) -> Result<()> {
-        let content = toml::to_string_pretty(self)?;
+        let content = if self.system_settings.is_empty() {
+            toml::to_string_pretty(self)?
+        } else {
+            toml::to_string_pretty(&self.user_table()?)?
+        };
         fs::write(&self.config_file, content)?;
         info!("Configuration saved");
         Ok(())
     }
     
+    /// The settings as the user's config.toml holds them. Where the
+    /// administrator's value is still in effect, the user's own value is
+    /// written instead, so it never becomes the user's setting.
+    fn user_table(&self) -> Result<toml::Table> {
+        let toml::Value::Table(mut table) = toml::Value::try_from(self)? else {
+            return Err(anyhow!("Configuration did not serialize to a table"));
+        };
+        for (key, system_value) in &self.system_settings {
+            if table.get(key) != Some(system_value) {
+                continue;
+            }
+            match self.user_settings.get(key) {
+                Some(user_value) => table.insert(key.clone(), user_value.clone()),
+                None => table.remove(key),
+            };
+        }
+        Ok(table)
+    }
+    
     pub fn update_sync_folder(&mut self, new_path: PathBuf) -> Result<()> {
         fs::create_dir_all(&new_path)?;
         self.sync_folder = new_path;
@@ -126,8 +593,8 @@ impl Config {
         Ok(())
     }
     
-    pub fn set_notifications(&mut self, enabled: bool) -> Result<()> {
-        self.notifications = enabled;
+    pub fn set_notifications(&mut self, notifications: NotificationPrefs) -> Result<()> {
+        self.notifications = notifications;
         self.save()?;
         Ok(())
     }
@@ -138,12 +605,246 @@ impl Config {
         Ok(())
     }
     
+    pub fn set_folder_integration(&mut self, enabled: bool) -> Result<()> {
+        self.folder_integration = enabled;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_trash_remote_deletions(&mut self, enabled: bool) -> Result<()> {
+        self.trash_remote_deletions = enabled;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_inhibit_sleep_during_transfers(&mut self, enabled: bool) -> Result<()> {
+        self.inhibit_sleep_during_transfers = enabled;
+        self.save()?;
+        Ok(())
+    }
+    
     pub fn set_sync_interval(&mut self, minutes: u64) -> Result<()> {
         self.sync_interval_minutes = minutes;
         self.save()?;
         Ok(())
     }
     
+    pub fn set_max_concurrent_transfers(&mut self, transfers: usize) -> Result<()> {
+        self.max_concurrent_transfers = transfers;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_upload_chunk_size_kib(&mut self, kib: u64) -> Result<()> {
+        self.upload_chunk_size_kib = kib;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn add_upload_mirror(&mut self, local_folder: PathBuf, remote_folder: String) -> Result<()> {
+        self.upload_mirrors.push(UploadMirror { local_folder, remote_folder });
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn remove_upload_mirror(&mut self, index: usize) -> Result<()> {
+        if index < self.upload_mirrors.len() {
+            self.upload_mirrors.remove(index);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn add_pause_while_running(&mut self, process_name: String) -> Result<()> {
+        if !self.pause_while_running.contains(&process_name) {
+            self.pause_while_running.push(process_name);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn remove_pause_while_running(&mut self, index: usize) -> Result<()> {
+        if index < self.pause_while_running.len() {
+            self.pause_while_running.remove(index);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn add_sync_window(&mut self, window: SyncWindow) -> Result<()> {
+        if !self.sync_windows.contains(&window) {
+            self.sync_windows.push(window);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn remove_sync_window(&mut self, index: usize) -> Result<()> {
+        if index < self.sync_windows.len() {
+            self.sync_windows.remove(index);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn set_sync_window_mode(&mut self, mode: SyncWindowMode) -> Result<()> {
+        self.sync_window_mode = mode;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_max_upload_size_mb(&mut self, megabytes: u64) -> Result<()> {
+        self.max_upload_size_mb = megabytes;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_index_content(&mut self, index: bool) -> Result<()> {
+        self.index_content = index;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_rename_illegal_names(&mut self, rename: bool) -> Result<()> {
+        self.rename_illegal_names = rename;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_upload_settle_seconds(&mut self, seconds: u64) -> Result<()> {
+        self.upload_settle_seconds = seconds;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_log_retention(&mut self, days: u64, max_rows: u64) -> Result<()> {
+        self.log_retention_days = days;
+        self.log_max_rows = max_rows;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_download_newest_first(&mut self, enabled: bool) -> Result<()> {
+        self.download_newest_first = enabled;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_online_only(&mut self, enabled: bool) -> Result<()> {
+        self.online_only = enabled;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_call_upload_throttle(&mut self, enabled: bool, limit_kib: u64) -> Result<()> {
+        self.throttle_uploads_during_calls = enabled;
+        self.call_upload_limit_kib = limit_kib;
+        self.save()?;
+        Ok(())
+    }
+
+    /// Bytes per second uploads are held to during a call, if they are.
+    pub fn call_upload_limit(&self) -> Option<u64> {
+        self.throttle_uploads_during_calls.then_some(self.call_upload_limit_kib * 1024)
+    }
+    
+    /// The drive synced, when it is not the account's own OneDrive.
+    pub fn selected_drive(&self) -> Option<&str> {
+        (!self.drive_id.is_empty()).then_some(self.drive_id.as_str())
+    }
+    
+    pub fn set_drive_id(&mut self, drive_id: String) -> Result<()> {
+        self.drive_id = drive_id;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_excluded_extensions(&mut self, extensions: Vec<String>) -> Result<()> {
+        self.excluded_extensions = extensions;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_summary_command(&mut self, command: String, frequency: SummaryFrequency) -> Result<()> {
+        self.summary_command = command;
+        self.summary_frequency = frequency;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_size_units(&mut self, units: SizeUnits) -> Result<()> {
+        self.size_units = units;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_appearance(&mut self, high_contrast: bool, reduce_motion: bool) -> Result<()> {
+        self.high_contrast = high_contrast;
+        self.reduce_motion = reduce_motion;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn add_webhook_url(&mut self, url: String) -> Result<()> {
+        if !self.webhook_urls.contains(&url) {
+            self.webhook_urls.push(url);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn remove_webhook_url(&mut self, index: usize) -> Result<()> {
+        if index < self.webhook_urls.len() {
+            self.webhook_urls.remove(index);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn add_watched_folder(&mut self, folder: String) -> Result<()> {
+        let folder = folder.trim_matches('/').to_string();
+        if !folder.is_empty() && !self.watched_folders.contains(&folder) {
+            self.watched_folders.push(folder);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn remove_watched_folder(&mut self, index: usize) -> Result<()> {
+        if index < self.watched_folders.len() {
+            self.watched_folders.remove(index);
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    pub fn set_push_notifications(&mut self, notification_url: String, listen_address: String) -> Result<()> {
+        self.push_notification_url = notification_url;
+        self.push_listen_address = listen_address;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_remote_poll_seconds(&mut self, seconds: u64) -> Result<()> {
+        self.remote_poll_seconds = seconds;
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_archive(&mut self, folder: String, after_days: u64) -> Result<()> {
+        self.archive_folder = folder.trim().trim_matches('/').to_string();
+        self.archive_after_days = after_days.max(1);
+        self.save()?;
+        Ok(())
+    }
+    
+    pub fn set_request_budget(&mut self, per_hour: u64, per_day: u64) -> Result<()> {
+        self.request_budget_per_hour = per_hour;
+        self.request_budget_per_day = per_day;
+        self.save()?;
+        Ok(())
+    }
+    
     pub fn update_azure_config(&mut self, client_id: String, redirect_uri: String) -> Result<()> {
         self.client_id = client_id;
         self.redirect_uri = redirect_uri;
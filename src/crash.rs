@@ -0,0 +1,126 @@
+//! Crash reports: a panic hook that saves what went wrong to a JSON file in
+//! the state directory, so the next start can point the user at it for a bug
+//! report. Nothing is ever sent anywhere.
+
+use chrono::Local;
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::live_log;
+
+/// Log lines from just before the crash included in a report
+const REPORT_LOG_LINES: usize = 50;
+
+/// File touched when the user has seen the reports, so they are offered once
+const REVIEWED_MARKER: &str = "reviewed";
+
+/// What the sync was last doing, as shown in its status
+static SYNC_PHASE: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Debug, Serialize)]
+struct CrashReport {
+    time: String,
+    version: &'static str,
+    thread: String,
+    message: String,
+    location: Option<String>,
+    sync_phase: String,
+    backtrace: String,
+    recent_log: Vec<String>,
+}
+
+/// Directory crash reports are saved in, under `$XDG_STATE_HOME`.
+pub fn crash_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("onedrive-ubuntu")
+        .join("crashes")
+}
+
+/// Remembers what the sync is doing, for the next crash report.
+pub fn record_sync_phase(phase: &str) {
+    if let Ok(mut current) = SYNC_PHASE.try_lock() {
+        if *current != phase {
+            phase.clone_into(&mut current);
+        }
+    }
+}
+
+/// Saves a crash report whenever a thread panics, then carries on with the
+/// usual panic output.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = info.payload().downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Unknown panic".to_string()
+        };
+        match write_report(message, info.location().map(|location| location.to_string())) {
+            Ok(path) => eprintln!("A crash report was saved to {}", path.display()),
+            Err(e) => eprintln!("Could not save a crash report: {}", e),
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_report(message: String, location: Option<String>) -> std::io::Result<PathBuf> {
+    // Left out if the panic came from a thread logging right now
+    let recent_log = live_log::try_recent().unwrap_or_default();
+    let report = CrashReport {
+        time: Local::now().to_rfc3339(),
+        version: env!("CARGO_PKG_VERSION"),
+        thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+        message,
+        location,
+        // Left out if another thread is recording a new phase right now
+        sync_phase: SYNC_PHASE.try_lock().map(|phase| phase.clone()).unwrap_or_default(),
+        backtrace: Backtrace::force_capture().to_string(),
+        recent_log: recent_log
+            .iter()
+            .skip(recent_log.len().saturating_sub(REPORT_LOG_LINES))
+            .map(|line| format!("{} {} {}: {}", line.time.format("%H:%M:%S"), line.level, line.target, line.message))
+            .collect(),
+    };
+
+    let dir = crash_dir();
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("crash-{}.json", Local::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+    Ok(path)
+}
+
+/// Crash reports saved since the user last looked, newest first.
+pub fn unreviewed_reports() -> Vec<PathBuf> {
+    let dir = crash_dir();
+    let reviewed = fs::metadata(dir.join(REVIEWED_MARKER))
+        .and_then(|metadata| metadata.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut reports: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .filter(|(modified, _)| *modified > reviewed)
+        .collect();
+    reports.sort_by(|a, b| b.0.cmp(&a.0));
+    reports.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Stops offering the reports saved so far.
+pub fn mark_reviewed() -> std::io::Result<()> {
+    let dir = crash_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(REVIEWED_MARKER), Local::now().to_rfc3339())
+}
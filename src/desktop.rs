@@ -0,0 +1,206 @@
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use tracing::{info, warn};
+use url::Url;
+
+const BOOKMARK_LABEL: &str = "OneDrive";
+const FOLDER_ICON_NAME: &str = "folder-cloud";
+
+fn bookmarks_file() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir().ok_or_else(|| anyhow!("Could not find config directory"))?;
+    Ok(config_dir.join("gtk-3.0").join("bookmarks"))
+}
+
+fn folder_uri(folder: &Path) -> Result<String> {
+    Url::from_file_path(folder)
+        .map(|url| url.to_string())
+        .map_err(|_| anyhow!("Sync folder is not an absolute path: {}", folder.display()))
+}
+
+/// Whether the desktop has animations turned off (GNOME's Reduce Animation
+/// accessibility setting). False where it cannot be read.
+pub fn prefers_reduced_motion() -> bool {
+    Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "enable-animations"])
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "false")
+        .unwrap_or(false)
+}
+
+/// Adds the sync folder to the file manager sidebar and gives it a cloud icon.
+pub fn install_folder_integration(sync_folder: &Path) -> Result<()> {
+    let uri = folder_uri(sync_folder)?;
+    let bookmarks_file = bookmarks_file()?;
+
+    let content = fs::read_to_string(&bookmarks_file).unwrap_or_default();
+    let already_bookmarked = content
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(uri.as_str()));
+
+    if !already_bookmarked {
+        if let Some(parent) = bookmarks_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut new_content = content;
+        if !new_content.is_empty() && !new_content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        new_content.push_str(&format!("{} {}\n", uri, BOOKMARK_LABEL));
+        fs::write(&bookmarks_file, new_content)?;
+        info!("Added sync folder to file manager bookmarks: {}", uri);
+    }
+
+    // The icon is cosmetic; a missing gio binary must not fail the sync
+    match Command::new("gio")
+        .args(["set", &sync_folder.to_string_lossy(), "metadata::custom-icon-name", FOLDER_ICON_NAME])
+        .output()
+    {
+        Ok(output) if output.status.success() => info!("Set cloud folder icon on {}", sync_folder.display()),
+        Ok(output) => warn!("Failed to set folder icon: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        Err(e) => warn!("Failed to run gio to set folder icon: {}", e),
+    }
+
+    Ok(())
+}
+
+/// Removes the sidebar bookmark and custom icon added by `install_folder_integration`.
+pub fn remove_folder_integration(sync_folder: &Path) -> Result<()> {
+    let uri = folder_uri(sync_folder)?;
+    let bookmarks_file = bookmarks_file()?;
+
+    if let Ok(content) = fs::read_to_string(&bookmarks_file) {
+        let remaining: Vec<&str> = content
+            .lines()
+            .filter(|line| line.split_whitespace().next() != Some(uri.as_str()))
+            .collect();
+
+        if remaining.len() != content.lines().count() {
+            let mut new_content = remaining.join("\n");
+            if !new_content.is_empty() {
+                new_content.push('\n');
+            }
+            fs::write(&bookmarks_file, new_content)?;
+            info!("Removed sync folder from file manager bookmarks");
+        }
+    }
+
+    if sync_folder.exists() {
+        match Command::new("gio")
+            .args(["set", "-t", "unset", &sync_folder.to_string_lossy(), "metadata::custom-icon-name"])
+            .output()
+        {
+            Ok(output) if output.status.success() => info!("Removed cloud folder icon"),
+            Ok(output) => warn!("Failed to remove folder icon: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => warn!("Failed to run gio to remove folder icon: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves a file to the freedesktop Trash, so it can be restored from the file
+/// manager. Waits for `gio`, so async callers run it from `spawn_blocking`.
+pub fn move_to_trash(path: &Path) -> Result<()> {
+    let output = Command::new("gio")
+        .args(["trash", "--"])
+        .arg(path)
+        .output()
+        .map_err(|e| anyhow!("Failed to run gio to trash {}: {}", path.display(), e))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to move {} to the Trash: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Shows `emblem` on a file in the file manager, or removes its emblem when
+/// `None`. Emblems are cosmetic, so failures are only logged.
+pub fn set_emblem(path: &Path, emblem: Option<&str>) {
+    let path = path.to_string_lossy();
+    let args: Vec<&str> = match emblem {
+        Some(emblem) => vec!["set", "-t", "stringv", &path, "metadata::emblems", emblem],
+        None => vec!["set", "-t", "unset", &path, "metadata::emblems"],
+    };
+
+    match Command::new("gio").args(&args).output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => warn!("Failed to set emblem on {}: {}", path, String::from_utf8_lossy(&output.stderr).trim()),
+        Err(e) => warn!("Failed to run gio to set emblem on {}: {}", path, e),
+    }
+}
+
+/// Bytes free for this user on the filesystem holding `path`, as `df` reports them.
+pub fn available_space(path: &Path) -> Result<u64> {
+    let output = Command::new("df")
+        .args(["--output=avail", "-B1", "--"])
+        .arg(path)
+        .output()
+        .map_err(|e| anyhow!("Failed to run df: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("df failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    // A header line, then the number
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)
+        .and_then(|line| line.trim().parse().ok())
+        .ok_or_else(|| anyhow!("Unexpected df output"))
+}
+
+/// Whether an application is recording from a microphone, going by the
+/// source outputs `pactl` lists. PipeWire answers through pipewire-pulse.
+pub fn microphone_in_use() -> Result<bool> {
+    let output = Command::new("pactl")
+        .args(["list", "short", "source-outputs"])
+        .output()
+        .map_err(|e| anyhow!("Failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("pactl failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).lines().any(|line| !line.trim().is_empty()))
+}
+
+/// A systemd-logind inhibitor lock that keeps the computer from suspending,
+/// on request or when idle, until dropped.
+pub struct SleepInhibitor {
+    child: Child,
+}
+
+/// Takes a sleep and idle inhibitor through `systemd-inhibit`, shown to the
+/// user as `why` if they try to suspend. The lock is held by a child process
+/// reading our end of a pipe, so it is released even if this process dies.
+pub fn inhibit_sleep(why: &str) -> Result<SleepInhibitor> {
+    let child = Command::new("systemd-inhibit")
+        .args(["--what=sleep:idle", "--who=OneDrive", &format!("--why={}", why), "--mode=block", "cat"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run systemd-inhibit: {}", e))?;
+
+    info!("Preventing sleep: {}", why);
+    Ok(SleepInhibitor { child })
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        // Closing the pipe ends `cat`, and with it the inhibitor
+        drop(self.child.stdin.take());
+        if let Err(e) = self.child.wait() {
+            warn!("Failed to release sleep inhibitor: {}", e);
+        } else {
+            info!("Allowing sleep again");
+        }
+    }
+}
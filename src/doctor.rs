@@ -0,0 +1,290 @@
+//! `onedrive-ubuntu doctor`: checks what syncing depends on, from the sign-in
+//! to the sync folder, and says how to fix whatever is wrong.
+
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+use crate::api::OneDriveAPI;
+use crate::auth::AuthManager;
+use crate::clock;
+use crate::config::Config;
+use crate::filesystem;
+use crate::format;
+use crate::sync::SyncManager;
+
+/// Share of the drive in use from which storage is reported as running low
+const QUOTA_WARNING_FRACTION: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+    /// Not checked because an earlier check failed
+    Skipped,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Pass => "  OK  ",
+            Outcome::Warn => " WARN ",
+            Outcome::Fail => " FAIL ",
+            Outcome::Skipped => " SKIP ",
+        }
+    }
+
+    /// ANSI color for the label
+    fn color(self) -> &'static str {
+        match self {
+            Outcome::Pass => "\x1b[32m",
+            Outcome::Warn => "\x1b[33m",
+            Outcome::Fail => "\x1b[31m",
+            Outcome::Skipped => "\x1b[90m",
+        }
+    }
+}
+
+/// The result of one check, with what to do about it when it did not pass.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, outcome: Outcome::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, outcome: Outcome::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, outcome: Outcome::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn skipped(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, outcome: Outcome::Skipped, detail: detail.into(), fix: None }
+    }
+}
+
+/// Runs every check in order. Checks that need OneDrive are skipped when
+/// signing in fails; the local ones always run.
+pub async fn run(config: Arc<Config>, auth: Arc<Mutex<AuthManager>>, api: Arc<OneDriveAPI>) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let signed_in = check_sign_in(&auth, &mut checks).await;
+    if signed_in {
+        check_graph(&api, &mut checks).await;
+    } else {
+        checks.push(Check::skipped("OneDrive", "Not signed in"));
+        checks.push(Check::skipped("Storage", "Not signed in"));
+        checks.push(Check::skipped("Clock", "Needs a response from OneDrive"));
+    }
+
+    check_sync_folder(&config, &mut checks);
+
+    match SyncManager::new(config, api) {
+        Ok(sync_manager) => {
+            checks.push(check_database(&sync_manager).await);
+            checks.push(check_issues(&sync_manager).await);
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "Database",
+                format!("Could not open the sync database: {}", e),
+                "Check that the config folder is writable and the disk is not full",
+            ));
+            checks.push(Check::skipped("Sync issues", "Needs the sync database"));
+        }
+    }
+
+    checks
+}
+
+async fn check_sign_in(auth: &Mutex<AuthManager>, checks: &mut Vec<Check>) -> bool {
+    let mut auth = auth.lock().await;
+    if !auth.has_tokens() {
+        checks.push(Check::fail("Sign-in", "Not signed in", "Run onedrive-ubuntu and sign in with Microsoft"));
+        return false;
+    }
+
+    match auth.get_access_token().await {
+        Ok(_) => {
+            let who = auth.get_user_email().unwrap_or_else(|| "your Microsoft account".to_string());
+            checks.push(Check::pass("Sign-in", format!("Signed in as {}", who)));
+            true
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "Sign-in",
+                format!("The saved sign-in no longer works: {}", e),
+                "Sign out and sign in again from the Settings tab",
+            ));
+            false
+        }
+    }
+}
+
+/// Reachability, storage and clock, all from one drive request.
+async fn check_graph(api: &OneDriveAPI, checks: &mut Vec<Check>) {
+    let started = Instant::now();
+    let drive = match api.get_drive_info().await {
+        Ok(drive) => drive,
+        Err(e) => {
+            checks.push(Check::fail(
+                "OneDrive",
+                format!("Could not reach Microsoft Graph: {}", e),
+                "Check the network connection, and any proxy or firewall in the way of graph.microsoft.com",
+            ));
+            checks.push(Check::skipped("Storage", "OneDrive could not be reached"));
+            checks.push(Check::skipped("Clock", "Needs a response from OneDrive"));
+            return;
+        }
+    };
+    checks.push(Check::pass(
+        "OneDrive",
+        format!("Reached Microsoft Graph in {} ms ({} drive)", started.elapsed().as_millis(), drive.drive_type),
+    ));
+
+    checks.push(match drive.quota {
+        None => Check::skipped("Storage", "OneDrive did not report the drive's quota"),
+        Some(quota) => {
+            let usage = format!(
+                "{} of {} used, {} free",
+                format::size(quota.used),
+                format::size(quota.total),
+                format::size(quota.remaining)
+            );
+            if quota.remaining == 0 {
+                Check::fail("Storage", format!("OneDrive is full: {}", usage), "Delete files from OneDrive or add storage to the account")
+            } else if quota.used as f64 >= quota.total as f64 * QUOTA_WARNING_FRACTION {
+                Check::warn("Storage", format!("OneDrive is almost full: {}", usage), "Delete files from OneDrive or add storage to the account")
+            } else {
+                Check::pass("Storage", usage)
+            }
+        }
+    });
+
+    checks.push(match clock::significant_skew_secs() {
+        Some(skew) => Check::warn(
+            "Clock",
+            format!("The system clock is off by {} seconds compared to Microsoft's servers", skew),
+            "Turn on automatic time sync: timedatectl set-ntp true",
+        ),
+        None => Check::pass("Clock", "In line with Microsoft's servers"),
+    });
+}
+
+fn check_sync_folder(config: &Config, checks: &mut Vec<Check>) {
+    let folder = &config.sync_folder;
+    if !folder.exists() {
+        checks.push(Check::warn(
+            "Sync folder",
+            format!("{} does not exist", folder.display()),
+            "It is created by the next sync; choose another folder in the Settings tab if this one is wrong",
+        ));
+        return;
+    }
+    if !folder.is_dir() {
+        checks.push(Check::fail(
+            "Sync folder",
+            format!("{} is not a folder", folder.display()),
+            "Move the file out of the way or choose another sync folder in the Settings tab",
+        ));
+        return;
+    }
+
+    // Hidden at the top of the folder, so a running sync never picks it up
+    let probe = folder.join(format!(".onedrive-doctor-{}", std::process::id()));
+    match std::fs::write(&probe, b"").and_then(|_| std::fs::remove_file(&probe)) {
+        Ok(()) => checks.push(Check::pass("Sync folder", format!("{} is writable", folder.display()))),
+        Err(e) => checks.push(Check::fail(
+            "Sync folder",
+            format!("Cannot write to {}: {}", folder.display(), e),
+            format!("Give your user write access, e.g. sudo chown -R $USER \"{}\"", folder.display()),
+        )),
+    }
+
+    if let Some(limit) = filesystem::name_limit(folder) {
+        checks.push(Check::warn(
+            "File names",
+            format!("The sync folder is on {}, which stores names of at most {} bytes", limit.filesystem, limit.max_bytes),
+            "OneDrive files with longer names are not downloaded; shorten them on OneDrive, or move the sync folder off the encrypted folder",
+        ));
+    }
+}
+
+async fn check_database(sync_manager: &SyncManager) -> Check {
+    match sync_manager.check_database().await {
+        Ok(problems) if problems.is_empty() => Check::pass("Database", "Integrity check passed"),
+        Ok(problems) => Check::fail(
+            "Database",
+            format!("Integrity check found {} problems, the first: {}", problems.len(), problems[0]),
+            "Quit the client and move sync.db aside; the next sync rebuilds it from OneDrive and the sync folder",
+        ),
+        Err(e) => Check::fail("Database", format!("Could not check the sync database: {}", e), "Check that the disk is not full"),
+    }
+}
+
+async fn check_issues(sync_manager: &SyncManager) -> Check {
+    let status = sync_manager.get_status().await;
+    let mut problems = Vec::new();
+    if status.relink_required {
+        problems.push("the sync folder belongs to a different OneDrive account".to_string());
+    }
+    if !status.issues.is_empty() {
+        problems.push(format!("{} files are held out of sync", format::count(status.issues.len() as u64)));
+    }
+    if status.uploads_paused_for_mass_change.is_some() {
+        problems.push("uploads are paused because many files changed at once".to_string());
+    }
+    if let Some(reason) = &status.uploads_held_for_quota {
+        problems.push(format!("uploads are waiting for space ({})", reason));
+    }
+    if let Some(error) = status.sync_errors.last() {
+        problems.push(format!("the last sync reported: {}", error));
+    }
+
+    if problems.is_empty() {
+        let detail = if status.paused { "None; syncing is paused" } else { "None" };
+        return Check::pass("Sync issues", detail);
+    }
+    Check::warn(
+        "Sync issues",
+        problems.join("; "),
+        "Open the Status tab to see each issue; onedrive-ubuntu status lists them too",
+    )
+}
+
+/// The report as printed: one line per check, its fix beneath it, colored
+/// when `color` is set.
+pub fn render(checks: &[Check], color: bool) -> String {
+    let name_width = checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for check in checks {
+        let label = if color {
+            format!("{}[{}]\x1b[0m", check.outcome.color(), check.outcome.label())
+        } else {
+            format!("[{}]", check.outcome.label())
+        };
+        out.push_str(&format!("{} {:<width$}  {}\n", label, check.name, check.detail, width = name_width));
+        if let Some(fix) = &check.fix {
+            out.push_str(&format!("{:indent$}Fix: {}\n", "", fix, indent = 11 + name_width));
+        }
+    }
+
+    let count = |outcome| checks.iter().filter(|check| check.outcome == outcome).count();
+    out.push_str(&format!(
+        "\n{} passed, {} warnings, {} failed\n",
+        count(Outcome::Pass),
+        count(Outcome::Warn),
+        count(Outcome::Fail)
+    ));
+    out
+}
@@ -0,0 +1,96 @@
+//! What the filesystem under the sync folder can store. Encrypted home
+//! folders matter most: eCryptfs keeps names to 143 bytes, and other stacked
+//! filesystems can be shorter than the usual 255, so OneDrive names that are
+//! longer would fail to download with "File name too long".
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Longest file name most Linux filesystems store, in bytes
+const USUAL_NAME_MAX: usize = 255;
+
+/// A name length limit shorter than usual.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameLimit {
+    /// The filesystem as users know it, e.g. "eCryptfs"
+    pub filesystem: String,
+    pub max_bytes: usize,
+}
+
+/// The limit on file names in `folder`, or its nearest existing parent, when
+/// it is shorter than usual.
+pub fn name_limit(folder: &Path) -> Option<NameLimit> {
+    let existing = folder.ancestors().find(|dir| dir.exists())?;
+    let output = Command::new("stat")
+        .args(["--file-system", "--format=%l"])
+        .arg(existing)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut max_bytes: usize = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+
+    let fs_type = mount_type(existing).unwrap_or_default();
+    // Reported as 255 on some kernels, but names are padded when encrypted
+    if fs_type == "ecryptfs" {
+        max_bytes = max_bytes.min(143);
+    }
+    if max_bytes == 0 || max_bytes >= USUAL_NAME_MAX {
+        return None;
+    }
+    Some(NameLimit { filesystem: display_name(&fs_type), max_bytes })
+}
+
+/// The type of the filesystem mounted at or above `path`, as
+/// /proc/self/mountinfo gives it (e.g. "ext4", "ecryptfs", "fuse.gocryptfs").
+fn mount_type(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            // The mount point is the fifth field; the type follows the "-"
+            let fields: Vec<&str> = line.split(' ').collect();
+            let mount_point = unescape_mount_path(fields.get(4)?);
+            let separator = fields.iter().position(|field| *field == "-")?;
+            let fs_type = fields.get(separator + 1)?;
+            path.starts_with(&mount_point).then(|| (mount_point.len(), fs_type.to_string()))
+        })
+        .max_by_key(|(length, _)| *length)
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Undoes the octal escapes (`\040` for a space) of mountinfo paths.
+fn unescape_mount_path(path: &str) -> String {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let code = rest.get(index + 1..index + 4).and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match code {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn display_name(fs_type: &str) -> String {
+    match fs_type {
+        "ecryptfs" => "eCryptfs".to_string(),
+        "fuse.gocryptfs" => "gocryptfs".to_string(),
+        "fuse.cryfs" => "CryFS".to_string(),
+        "fuse.encfs" => "EncFS".to_string(),
+        "" => "the sync folder's filesystem".to_string(),
+        other => other.strip_prefix("fuse.").unwrap_or(other).to_string(),
+    }
+}
@@ -0,0 +1,254 @@
+use std::path::Path;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+/// Name of the per-folder ignore file, read from every directory in the sync folder
+pub const IGNORE_FILE_NAME: &str = ".syncignore";
+
+/// A single gitignore-style rule.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    pattern: glob::Pattern,
+    /// Directory (relative to the sync folder) the rule applies beneath; empty for
+    /// global rules from the config
+    base: String,
+    negated: bool,
+    dir_only: bool,
+    /// Rules containing a `/` match the path relative to `base`, all others match
+    /// the entry name at any depth
+    anchored: bool,
+}
+
+/// Exclusion rules from `Config::exclude_patterns` and any `.syncignore` files,
+/// evaluated the way git evaluates `.gitignore`: rules from deeper directories
+/// come later, and the last matching rule wins.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncFilter {
+    rules: Vec<Rule>,
+}
+
+impl SyncFilter {
+    /// Builds the filter from the global patterns and every `.syncignore` file
+    /// currently present under `sync_folder`.
+    pub fn load(sync_folder: &Path, global_patterns: &[String]) -> Self {
+        let mut filter = Self::default();
+
+        for line in global_patterns {
+            filter.add_rule("", line);
+        }
+
+        let mut ignore_files: Vec<(String, std::path::PathBuf)> = WalkDir::new(sync_folder)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.file_name() == IGNORE_FILE_NAME)
+            .map(|e| {
+                let base = e
+                    .path()
+                    .parent()
+                    .and_then(|parent| parent.strip_prefix(sync_folder).ok())
+                    .map(|parent| parent.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default();
+                (base, e.path().to_path_buf())
+            })
+            .collect();
+
+        // Shallower ignore files first, so deeper rules can override them
+        ignore_files.sort_by_key(|(base, _)| if base.is_empty() { 0 } else { base.matches('/').count() + 1 });
+
+        for (base, path) in ignore_files {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    info!("Loaded ignore rules from {}", path.display());
+                    for line in content.lines() {
+                        filter.add_rule(&base, line);
+                    }
+                }
+                Err(e) => warn!("Failed to read {}: {}", path.display(), e),
+            }
+        }
+
+        filter
+    }
+
+    /// Builds a filter from patterns alone, with no `.syncignore` files.
+    pub fn from_patterns(patterns: &[String]) -> Self {
+        let mut filter = Self::default();
+        for line in patterns {
+            filter.add_rule("", line);
+        }
+        filter
+    }
+
+    fn add_rule(&mut self, base: &str, line: &str) {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        let anchored = line.contains('/');
+        let line = line.trim_start_matches('/');
+
+        match glob::Pattern::new(line) {
+            Ok(pattern) => self.rules.push(Rule {
+                pattern,
+                base: base.to_string(),
+                negated,
+                dir_only,
+                anchored,
+            }),
+            Err(e) => warn!("Ignoring invalid exclude pattern '{}': {}", line, e),
+        }
+    }
+
+    /// Whether `relative_path` (relative to the sync folder, `/`-separated) is
+    /// excluded from sync.
+    pub fn is_excluded(&self, relative_path: &str, is_dir: bool) -> bool {
+        let mut excluded = false;
+
+        for rule in &self.rules {
+            let path_in_base = if rule.base.is_empty() {
+                relative_path
+            } else {
+                match relative_path.strip_prefix(rule.base.as_str()).and_then(|rest| rest.strip_prefix('/')) {
+                    Some(rest) => rest,
+                    None => continue,
+                }
+            };
+
+            if rule.matches(path_in_base, is_dir) {
+                excluded = !rule.negated;
+            }
+        }
+
+        if excluded {
+            debug!("Path excluded by filter rules: {}", relative_path);
+        }
+        excluded
+    }
+}
+
+impl Rule {
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+
+        if !self.anchored {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            return self.pattern.matches_with(name, options);
+        }
+
+        if self.pattern.matches_with(path, options) {
+            return true;
+        }
+
+        // `folder/**` also covers the folder itself, so it is never created empty
+        is_dir
+            && self
+                .pattern
+                .as_str()
+                .strip_suffix("/**")
+                .and_then(|prefix| glob::Pattern::new(prefix).ok())
+                .map(|prefix| prefix.matches_with(path, options))
+                .unwrap_or(false)
+    }
+}
+
+/// Whether `mime_type` matches any of `patterns`, each either an exact type
+/// (`application/pdf`) or a whole category (`video/*`). Case-insensitive, and
+/// parameters such as `; charset=utf-8` are ignored.
+pub fn matches_mime_type(patterns: &[String], mime_type: &str) -> bool {
+    let mime_type = mime_type.split(';').next().unwrap_or(mime_type).trim();
+
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim();
+        match pattern.strip_suffix("/*") {
+            Some(category) => mime_type
+                .split_once('/')
+                .is_some_and(|(mime_category, _)| mime_category.eq_ignore_ascii_case(category)),
+            None => pattern == "*" || mime_type.eq_ignore_ascii_case(pattern),
+        }
+    })
+}
+
+/// Whether the file name at the end of `path` has one of `extensions`, each
+/// written with or without its dot (`iso`, `.vdi`). Case-insensitive.
+pub fn has_extension(extensions: &[String], path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let Some((_, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+
+    extensions
+        .iter()
+        .any(|candidate| candidate.trim().trim_start_matches('.').eq_ignore_ascii_case(extension))
+}
+
+/// Characters OneDrive does not allow anywhere in a file or folder name
+const ILLEGAL_NAME_CHARS: &[char] = &['"', '*', ':', '<', '>', '?', '/', '\\', '|'];
+
+/// Whole names OneDrive refuses, compared case-insensitively
+const RESERVED_NAMES: &[&str] = &[
+    ".lock", "con", "prn", "aux", "nul", "desktop.ini", "com0", "com1", "com2", "com3", "com4", "com5", "com6", "com7",
+    "com8", "com9", "lpt0", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Why OneDrive would refuse `name` (a single file or folder name), if it would.
+pub fn illegal_name_reason(name: &str) -> Option<String> {
+    if let Some(c) = name.chars().find(|c| ILLEGAL_NAME_CHARS.contains(c)) {
+        return Some(format!("OneDrive does not allow the character {} in names", c));
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Some("OneDrive does not allow names ending in a dot or a space".to_string());
+    }
+    if name.starts_with(' ') {
+        return Some("OneDrive does not allow names starting with a space".to_string());
+    }
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(name)) {
+        return Some(format!("{} is a name OneDrive reserves", name));
+    }
+    if name.starts_with("~$") {
+        return Some("OneDrive does not allow names starting with ~$".to_string());
+    }
+    if name.to_ascii_lowercase().contains("_vti_") {
+        return Some("OneDrive does not allow _vti_ in names".to_string());
+    }
+    None
+}
+
+/// `name` changed as little as possible for OneDrive to accept it: illegal
+/// characters become `_`, surrounding spaces and trailing dots go, and
+/// reserved names and prefixes get an `_`.
+pub fn legal_name(name: &str) -> String {
+    let mut legal: String = name
+        .chars()
+        .map(|c| if ILLEGAL_NAME_CHARS.contains(&c) { '_' } else { c })
+        .collect();
+    legal = legal.trim_start_matches(' ').trim_end_matches(['.', ' ']).to_string();
+    if let Some(rest) = legal.strip_prefix("~$") {
+        legal = format!("~_{}", rest);
+    }
+    while let Some(index) = legal.to_ascii_lowercase().find("_vti_") {
+        legal.replace_range(index..index + 5, "_vti-");
+    }
+    if legal.is_empty() || RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(&legal)) {
+        legal.push('_');
+    }
+    legal
+}
@@ -0,0 +1,170 @@
+//! Sizes, counts and times are shown through here so the GUI, tray, CLI,
+//! notifications and summaries all follow the user's locale (`LC_ALL`,
+//! `LC_NUMERIC`/`LC_TIME` or `LANG`) and `Config::size_units` the same way.
+
+use chrono::{DateTime, Local, TimeZone};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::config::SizeUnits;
+
+/// Whether sizes use powers of 1024 (KiB, MiB, GiB); set from
+/// `Config::size_units` at startup and when the setting changes
+static BINARY_UNITS: AtomicBool = AtomicBool::new(false);
+
+/// Number and date conventions of a locale.
+#[derive(Debug, Clone, Copy)]
+struct Locale {
+    thousands_separator: &'static str,
+    decimal_separator: &'static str,
+    /// strftime pattern for a date with hours and minutes
+    date_time: &'static str,
+}
+
+/// Switches every size shown from then on to `units`.
+pub fn set_size_units(units: SizeUnits) {
+    BINARY_UNITS.store(units == SizeUnits::Binary, Ordering::Relaxed);
+}
+
+/// A byte count in the largest unit that keeps it at or above 1, e.g. "3.4 GB"
+/// or "3.2 GiB" depending on `Config::size_units`.
+pub fn size(bytes: u64) -> String {
+    let (base, units): (f64, [&str; 5]) = if BINARY_UNITS.load(Ordering::Relaxed) {
+        (1024.0, ["bytes", "KiB", "MiB", "GiB", "TiB"])
+    } else {
+        (1000.0, ["bytes", "kB", "MB", "GB", "TB"])
+    };
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", count(bytes), units[0])
+    } else {
+        format!("{} {}", decimal(value, 1), units[unit])
+    }
+}
+
+/// A transfer speed, e.g. "3.2 MB/s".
+pub fn rate(bytes_per_second: u64) -> String {
+    format!("{}/s", size(bytes_per_second))
+}
+
+/// A rough length of time, e.g. "45 s", "12 min" or "2 h 5 min".
+pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{} s", secs),
+        60..=3599 => format!("{} min", (secs + 30) / 60),
+        _ => {
+            let minutes = (secs + 30) / 60;
+            format!("{} h {} min", count(minutes / 60), minutes % 60)
+        }
+    }
+}
+
+/// A whole number with the locale's thousands separator, e.g. "12,345".
+pub fn count(value: u64) -> String {
+    group_thousands(&value.to_string(), locale().thousands_separator)
+}
+
+/// `value` rounded to `places` decimals, with the locale's separators.
+pub fn decimal(value: f64, places: usize) -> String {
+    let text = format!("{:.*}", places, value.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let locale = locale();
+
+    let mut formatted = String::new();
+    if value < 0.0 && text.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+        formatted.push('-');
+    }
+    formatted.push_str(&group_thousands(whole, locale.thousands_separator));
+    if !fraction.is_empty() {
+        formatted.push_str(locale.decimal_separator);
+        formatted.push_str(fraction);
+    }
+    formatted
+}
+
+/// A percentage with one decimal, e.g. "42.5%".
+pub fn percent(part: u64, total: u64) -> String {
+    let ratio = if total > 0 { part as f64 / total as f64 * 100.0 } else { 0.0 };
+    format!("{}%", decimal(ratio, 1))
+}
+
+/// A moment in local time, written the way the locale writes dates.
+pub fn date_time<Tz: TimeZone>(time: &DateTime<Tz>) -> String {
+    time.with_timezone(&Local).format(locale().date_time).to_string()
+}
+
+/// Seconds since the Unix epoch, as `date_time` writes them.
+pub fn timestamp(unix_secs: u64) -> String {
+    date_time(&DateTime::<Local>::from(SystemTime::UNIX_EPOCH + Duration::from_secs(unix_secs)))
+}
+
+fn group_thousands(digits: &str, separator: &str) -> String {
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+fn locale() -> Locale {
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    *LOCALE.get_or_init(|| {
+        let variable = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.is_empty())
+                .unwrap_or_default()
+        };
+        let numeric = variable(&["LC_ALL", "LC_NUMERIC", "LANG"]);
+        let time = variable(&["LC_ALL", "LC_TIME", "LANG"]);
+        Locale {
+            date_time: parse_locale(&time).date_time,
+            ..parse_locale(&numeric)
+        }
+    })
+}
+
+/// Conventions for a POSIX locale name such as `de_DE.UTF-8`. Unknown locales
+/// get English separators; `C`, `POSIX` and unknown ones get ISO 8601 dates.
+fn parse_locale(name: &str) -> Locale {
+    let name = name.split(['.', '@']).next().unwrap_or_default();
+    let (language, region) = name.split_once('_').unwrap_or((name, ""));
+
+    let (thousands_separator, decimal_separator) = match (language, region) {
+        ("de" | "fr" | "it" | "rm", "CH" | "LI") => ("’", "."),
+        ("de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl" | "sr", _) => (".", ","),
+        ("fr" | "sv" | "nb" | "nn" | "no" | "fi" | "cs" | "sk" | "pl" | "ru" | "uk" | "hu" | "bg" | "lt" | "lv" | "et", _) => {
+            ("\u{202f}", ",")
+        }
+        _ => (",", "."),
+    };
+
+    let date_time = match (language, region) {
+        ("en", "US" | "PH") => "%m/%d/%Y %-I:%M %p",
+        ("en" | "fr" | "es" | "it" | "pt" | "el" | "ga" | "ca", _) => "%d/%m/%Y %H:%M",
+        ("de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "nn" | "no" | "da" | "tr" | "uk" | "ro" | "bg" | "hr" | "sl" | "sr" | "et" | "lv", _) => {
+            "%d.%m.%Y %H:%M"
+        }
+        ("nl", _) => "%d-%m-%Y %H:%M",
+        _ => "%Y-%m-%d %H:%M",
+    };
+
+    Locale {
+        thousands_separator,
+        decimal_separator,
+        date_time,
+    }
+}
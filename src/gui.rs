@@ -1,18 +1,36 @@
 use eframe::egui;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
-use tracing::{info, error};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, error, debug, warn, Level};
 
-use crate::api::{OneDriveAPI, UserInfo, DriveInfo};
-use crate::auth::AuthManager;
-use crate::config::Config;
-use crate::sync::{SyncManager, SyncStatus, SyncLogEntry};
+use crate::api::{DriveItem, OneDriveAPI, UserInfo, DriveInfo};
+use crate::auth::{AuthManager, DeviceCode};
+use crate::browse::BrowseCache;
+use crate::budget;
+use crate::bulk::{self, BulkOperation, BulkProgress};
+use crate::crash;
+use crate::config::{is_valid_client_id, Config, SizeUnits, SummaryFrequency, SyncWindow, SyncWindowMode, BUILTIN_CLIENT_ID};
+use crate::desktop;
+use crate::format;
+use crate::index::ContentMatch;
+use crate::live_log::{self, LogLine};
+use crate::mirror;
+use crate::restore::{self, RestoreProgress};
+use crate::schedule;
+use crate::sharing::{self, SharedLink};
+use crate::shutdown;
+use crate::sync::{FileState, Placeholder, QueuedAction, SyncControl, SyncHealth, SyncManager, SyncRun, SyncStatus, SyncLogEntry, SyncedFile};
 
 pub struct OneDriveApp {
     config: Arc<Config>,
     auth: Arc<Mutex<AuthManager>>,
     sync_manager: Arc<Mutex<SyncManager>>,
+    /// Pauses and resumes syncing even while a sync holds `sync_manager`
+    sync_control: SyncControl,
     
     // UI state
     current_tab: Tab,
@@ -24,32 +42,189 @@ pub struct OneDriveApp {
     // Logs cache
     sync_history_cache: Vec<SyncLogEntry>,
     last_history_refresh: std::time::Instant,
+    logs_filter: String,
+    /// Filter the cached log entries were fetched with
+    history_cache_filter: Option<String>,
+    logs_view: LogsView,
+    /// "Clear Logs" was clicked and waits for confirmation
+    confirm_clear_logs: bool,
+    /// "These Changes Are Mine" was clicked and waits for confirmation
+    confirm_mass_change: bool,
+    sync_runs_cache: Vec<SyncRun>,
+    last_runs_refresh: Option<std::time::Instant>,
+    queue_cache: Vec<QueuedAction>,
+    queue_counts: HashMap<String, u64>,
+    last_queue_refresh: Option<std::time::Instant>,
+    /// Most verbose level the Live view shows
+    live_log_level: Level,
+    /// Lines on screen, frozen while the Live view is paused
+    live_log_lines: Vec<LogLine>,
+    live_log_paused: bool,
+    /// Keeps the Live view scrolled to the newest line
+    live_log_follow: bool,
+    
+    // Result of the last "Preview changes", filled in by a background task
+    sync_preview: Arc<std::sync::Mutex<Option<SyncPreview>>>,
+    show_sync_preview: bool,
+    
+    // Sharing links found by "Shared Links", filled in by a background task
+    shared_links: Arc<std::sync::Mutex<Option<SharedLinks>>>,
+    show_shared_links: bool,
+    
+    /// Crash reports from earlier runs the user has not seen yet, newest first
+    crash_reports: Vec<PathBuf>,
+    
+    /// The desktop asks for reduced motion, read once at startup
+    desktop_reduces_motion: bool,
+    /// egui's own style, kept to return to when high contrast is turned off
+    base_style: Option<egui::Style>,
+    /// High contrast and reduced motion as last applied to the context
+    applied_appearance: Option<(bool, bool)>,
+    
+    // Files cache
+    files_view: FilesView,
+    synced_files_cache: Vec<SyncedFile>,
+    last_files_refresh: Option<std::time::Instant>,
+    files_filter: String,
+    /// Filter the cached files were fetched with
+    files_cache_filter: Option<String>,
+    placeholders_cache: Vec<Placeholder>,
+    last_placeholders_refresh: Option<std::time::Instant>,
+    placeholders_cache_filter: Option<String>,
+    /// OneDrive folder the browser shows, relative to the drive root
+    browse_path: String,
+    /// Listings shown before, loaded from disk when the browser first opens
+    browse_cache: Option<BrowseCache>,
+    /// Folders fetched this session; the others show their cached listing as stale
+    browse_fresh: HashSet<String>,
+    /// Why the last fetch of each folder failed
+    browse_errors: HashMap<String, String>,
+    /// Folder being fetched in the background, and its listing once it arrives
+    browse_fetching: Option<String>,
+    browse_fetched: Arc<std::sync::Mutex<Option<FolderFetch>>>,
+    /// Drives the account can sync, once listed from the Settings tab
+    available_drives: Option<Result<Vec<DriveInfo>, String>>,
+    /// Whether the drives are being listed in the background, and the listing once it arrives
+    drives_fetching: bool,
+    drives_fetched: Arc<std::sync::Mutex<Option<Result<Vec<DriveInfo>, String>>>>,
+    /// Items ticked in the browser, by path relative to the drive root
+    browse_selected: BTreeSet<String>,
+    /// OneDrive folder the ticked items are moved or copied into
+    bulk_move_folder: String,
+    bulk_confirm_delete: bool,
+    /// The running or last bulk operation, shown in place of the actions
+    bulk_operation: Option<BulkOperation>,
+    bulk_progress: Option<Arc<std::sync::Mutex<BulkProgress>>>,
+    bulk_cancel: Option<CancellationToken>,
+    /// File whose history window is open, with its `sync_log` entries
+    file_history: Option<(String, Result<Vec<SyncLogEntry>, String>)>,
+    /// Words to find in the content index, and the last search's results
+    content_query: String,
+    content_matches: Option<Result<Vec<ContentMatch>, String>>,
     
     // Settings state
     new_sync_folder: String,
+    new_mirror_folder: String,
+    new_mirror_remote: String,
+    new_pause_app: String,
+    /// Comma-separated `Config::excluded_extensions` being edited
+    excluded_extensions_input: String,
+    summary_command_input: String,
+    new_webhook_url: String,
+    new_watched_folder: String,
+    push_url_input: String,
+    push_address_input: String,
+    archive_folder_input: String,
+    archive_days_input: u64,
+    new_window_start: String,
+    new_window_end: String,
+    /// Monday first
+    new_window_days: [bool; 7],
+    upload_mirrors_started: bool,
     
     // Setup wizard state
     show_setup_wizard: bool,
     setup_step: SetupStep,
     client_id_input: String,
+    device_code: Option<DeviceCode>,
+    
+    // Restore flow state
+    /// Signed in only to restore files, so no sync is started
+    restore_only: bool,
+    /// Subfolders of each OneDrive folder listed so far, by path ("" is the root)
+    restore_subfolders: HashMap<String, Result<Vec<DriveItem>, String>>,
+    /// Folders being listed in the background, and their subfolders once they arrive
+    restore_fetching: HashSet<String>,
+    restore_fetched: Arc<std::sync::Mutex<Vec<FolderFetch>>>,
+    restore_selected: BTreeSet<String>,
+    restore_destination: String,
+    restore_progress: Option<Arc<std::sync::Mutex<RestoreProgress>>>,
+    restore_cancel: Option<CancellationToken>,
     
     // Runtime
     rt: tokio::runtime::Runtime,
 }
 
+/// `(kind, summary)` of each planned action, or why planning failed
+type SyncPreview = Result<Vec<(String, String)>, String>;
+
+/// Result of the shared-link audit, filled in by a background task
+type SharedLinks = Result<Vec<SharedLink>, String>;
+
+/// A folder's listing as fetched from OneDrive for the browser or the
+/// restore flow, or why not
+type FolderFetch = (String, Result<Vec<DriveItem>, String>);
+
+/// Most rows the Logs tab shows; filtering happens in the database first
+const LOG_ROW_LIMIT: usize = 50;
+
+/// Most rows the Files tab shows; filtering happens in the database first
+const FILE_ROW_LIMIT: usize = 500;
+
+/// Sync runs the Runs view lists and totals
+const RUN_ROW_LIMIT: usize = 100;
+
+/// Queued actions the Queue view lists
+const QUEUE_ROW_LIMIT: usize = 500;
+
 #[derive(Debug, Clone, PartialEq)]
 enum Tab {
     Status,
+    Files,
     Settings,
     Logs,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilesView {
+    /// Files the sync database tracks
+    Synced,
+    /// Folders browsed live on OneDrive, cached for offline use
+    OneDrive,
+    /// Files shown here only as placeholders (`Config::online_only`)
+    OnlineOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogsView {
+    History,
+    /// One row per sync run, from the `sync_runs` table
+    Runs,
+    /// The actions of the current or last sync, from the `pending_actions` table
+    Queue,
+    Live,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum SetupStep {
     Welcome,
+    BuiltInSignIn,
     AzureInstructions,
     ClientIdInput,
     Complete,
+    RestoreFolders,
+    RestoreDestination,
+    Restoring,
 }
 
 impl OneDriveApp {
@@ -60,12 +235,16 @@ impl OneDriveApp {
     ) -> Self {
         let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
         
-        // Check if we need to show setup wizard (if using default client ID)
-        let needs_setup = config.client_id == "14d82eec-204b-4c2f-b7e8-296a70dab67e";
+        // Show the setup wizard until an app registration is chosen and signed in
+        let has_tokens = rt.block_on(async { auth.lock().await.has_tokens() });
+        let has_own_client_id = config.client_id != BUILTIN_CLIENT_ID && is_valid_client_id(&config.client_id);
+        let needs_setup = !has_own_client_id && !has_tokens;
+        let sync_control = rt.block_on(async { sync_manager.lock().await.control() });
         
         let mut app = Self {
             config: config.clone(),
             auth,
+            sync_control,
             sync_manager,
             current_tab: Tab::Status,
             user_info: None,
@@ -74,10 +253,84 @@ impl OneDriveApp {
             status_message: "Welcome to OneDrive Ubuntu Client".to_string(),
             sync_history_cache: Vec::new(),
             last_history_refresh: std::time::Instant::now(),
+            logs_filter: String::new(),
+            history_cache_filter: None,
+            logs_view: LogsView::History,
+            confirm_clear_logs: false,
+            confirm_mass_change: false,
+            sync_runs_cache: Vec::new(),
+            last_runs_refresh: None,
+            queue_cache: Vec::new(),
+            queue_counts: HashMap::new(),
+            last_queue_refresh: None,
+            live_log_level: Level::INFO,
+            live_log_lines: Vec::new(),
+            live_log_paused: false,
+            live_log_follow: true,
+            sync_preview: Arc::new(std::sync::Mutex::new(None)),
+            show_sync_preview: false,
+            shared_links: Arc::new(std::sync::Mutex::new(None)),
+            show_shared_links: false,
+            crash_reports: crash::unreviewed_reports(),
+            desktop_reduces_motion: desktop::prefers_reduced_motion(),
+            base_style: None,
+            applied_appearance: None,
+            files_view: FilesView::Synced,
+            synced_files_cache: Vec::new(),
+            last_files_refresh: None,
+            files_filter: String::new(),
+            files_cache_filter: None,
+            placeholders_cache: Vec::new(),
+            last_placeholders_refresh: None,
+            placeholders_cache_filter: None,
+            browse_path: String::new(),
+            browse_cache: None,
+            browse_fresh: HashSet::new(),
+            browse_errors: HashMap::new(),
+            browse_fetching: None,
+            browse_fetched: Arc::new(std::sync::Mutex::new(None)),
+            available_drives: None,
+            drives_fetching: false,
+            drives_fetched: Arc::new(std::sync::Mutex::new(None)),
+            browse_selected: BTreeSet::new(),
+            bulk_move_folder: String::new(),
+            bulk_confirm_delete: false,
+            bulk_operation: None,
+            bulk_progress: None,
+            bulk_cancel: None,
+            file_history: None,
+            content_query: String::new(),
+            content_matches: None,
             new_sync_folder: config.sync_folder.to_string_lossy().to_string(),
+            new_mirror_folder: String::new(),
+            new_mirror_remote: String::new(),
+            new_pause_app: String::new(),
+            excluded_extensions_input: config.excluded_extensions.join(", "),
+            summary_command_input: config.summary_command.clone(),
+            new_webhook_url: String::new(),
+            new_watched_folder: String::new(),
+            push_url_input: config.push_notification_url.clone(),
+            push_address_input: config.push_listen_address.clone(),
+            archive_folder_input: config.archive_folder.clone(),
+            archive_days_input: config.archive_after_days,
+            new_window_start: "09:00".to_string(),
+            new_window_end: "18:00".to_string(),
+            new_window_days: [true, true, true, true, true, false, false],
+            upload_mirrors_started: false,
             show_setup_wizard: needs_setup,
             setup_step: SetupStep::Welcome,
             client_id_input: String::new(),
+            device_code: None,
+            restore_only: false,
+            restore_subfolders: HashMap::new(),
+            restore_fetching: HashSet::new(),
+            restore_fetched: Arc::new(std::sync::Mutex::new(Vec::new())),
+            restore_selected: BTreeSet::new(),
+            restore_destination: dirs::home_dir()
+                .map(|home| home.join("OneDrive Restore").to_string_lossy().to_string())
+                .unwrap_or_default(),
+            restore_progress: None,
+            restore_cancel: None,
             rt,
         };
         
@@ -91,7 +344,7 @@ impl OneDriveApp {
     
     fn refresh_data(&mut self) {
         let auth = self.auth.clone();
-        let api = Arc::new(OneDriveAPI::new(auth.clone()));
+        let api = Arc::new(OneDriveAPI::new(auth.clone()).with_drive(self.config.selected_drive()));
         
         // Check authentication status
         let is_authenticated = self.rt.block_on(async {
@@ -118,14 +371,24 @@ impl OneDriveApp {
             
             self.status_message = "✓ Authenticated and ready to sync".to_string();
             
+            if !self.upload_mirrors_started && !self.restore_only {
+                let _guard = self.rt.enter();
+                mirror::start_upload_mirrors(self.config.clone(), api.clone());
+                self.upload_mirrors_started = true;
+            }
+            
             // Trigger initial sync if this is the first time we're authenticated
-            if self.user_info.is_some() {
+            if self.user_info.is_some() && !self.restore_only {
                 let sync_manager = self.sync_manager.clone();
                 let _ = self.rt.spawn(async move {
                     // Wait a moment for everything to initialize
                     tokio::time::sleep(Duration::from_secs(2)).await;
                     
                     let mut sync_guard = sync_manager.lock().await;
+                    if let Err(e) = sync_guard.recover_offline_changes().await {
+                        error!("Startup recovery scan failed: {}", e);
+                    }
+                    
                     info!("Triggering initial sync after authentication");
                     match sync_guard.sync().await {
                         Ok(_) => info!("Initial sync completed"),
@@ -147,6 +410,13 @@ impl OneDriveApp {
 
 impl eframe::App for OneDriveApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // The window is closing; leave the sync where the next start can resume it
+        if ctx.input(|input| input.viewport().close_requested()) {
+            self.rt.block_on(shutdown::stop_sync());
+        }
+        
+        self.apply_appearance(ctx);
+        
         // Check authentication status periodically 
         let is_authenticated = self.rt.block_on(async {
             let auth_guard = self.auth.lock().await;
@@ -165,6 +435,22 @@ impl eframe::App for OneDriveApp {
             self.status_message = "⚠ Please authenticate with Microsoft to enable sync".to_string();
         }
         
+        // The built-in sign-in finishes the wizard as soon as tokens arrive,
+        // unless it was only to restore files
+        if self.show_setup_wizard && self.setup_step == SetupStep::BuiltInSignIn && is_authenticated {
+            self.device_code = None;
+            if self.restore_only {
+                self.setup_step = SetupStep::RestoreFolders;
+            } else {
+                self.show_setup_wizard = false;
+            }
+        }
+        
+        // A code sign-in from the Status tab is done once tokens arrive
+        if is_authenticated && !self.show_setup_wizard {
+            self.device_code = None;
+        }
+        
         // Show setup wizard if needed
         if self.show_setup_wizard {
             self.show_setup_wizard_ui(ctx);
@@ -203,16 +489,18 @@ impl eframe::App for OneDriveApp {
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     // Status indicator
                     let (icon, color) = if self.user_info.is_some() {
-                        if self.sync_status.is_syncing {
-                            ("🔄", egui::Color32::BLUE)
-                        } else {
-                            ("✓", egui::Color32::GREEN)
+                        match self.sync_status.health() {
+                            SyncHealth::Failing => ("✖", egui::Color32::RED),
+                            _ if self.sync_status.is_syncing => ("🔄", egui::Color32::BLUE),
+                            SyncHealth::Degraded => ("⚠", egui::Color32::YELLOW),
+                            SyncHealth::Healthy => ("✓", egui::Color32::GREEN),
                         }
                     } else {
                         ("⚠", egui::Color32::YELLOW)
                     };
                     
-                    ui.colored_label(color, icon);
+                    ui.colored_label(color, icon)
+                        .on_hover_text(format!("Sync health: {}", self.sync_status.health().label()));
                     ui.label(&self.status_message);
                 });
             });
@@ -222,6 +510,7 @@ impl eframe::App for OneDriveApp {
         egui::TopBottomPanel::top("tab_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.selectable_value(&mut self.current_tab, Tab::Status, "Status");
+                ui.selectable_value(&mut self.current_tab, Tab::Files, "Files");
                 ui.selectable_value(&mut self.current_tab, Tab::Settings, "Settings");
                 ui.selectable_value(&mut self.current_tab, Tab::Logs, "Logs");
             });
@@ -231,11 +520,26 @@ impl eframe::App for OneDriveApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.current_tab {
                 Tab::Status => self.show_status_tab(ui, ctx),
+                Tab::Files => self.show_files_tab(ui),
                 Tab::Settings => self.show_settings_tab(ui),
                 Tab::Logs => self.show_logs_tab(ui),
             }
         });
         
+        if self.show_shared_links {
+            self.show_shared_links_window(ctx);
+        }
+        if self.show_sync_preview {
+            self.show_sync_preview_window(ctx);
+        }
+        
+        if self.file_history.is_some() {
+            self.show_file_history_window(ctx);
+        }
+        if !self.crash_reports.is_empty() {
+            self.show_crash_report_window(ctx);
+        }
+        
         // Request repaint for real-time updates
         ctx.request_repaint_after(std::time::Duration::from_secs(2));
     }
@@ -265,13 +569,45 @@ impl OneDriveApp {
                     if ui.button("Refresh").clicked() {
                         self.refresh_data();
                     }
+                    
+                    if ui.button("Restore Files...")
+                        .on_hover_text("Download chosen OneDrive folders to another place on this computer")
+                        .clicked()
+                    {
+                        self.show_setup_wizard = true;
+                        self.setup_step = SetupStep::RestoreFolders;
+                    }
+                });
+            } else if let Some(device_code) = self.device_code.clone().filter(|_| !self.show_setup_wizard) {
+                ui.label(format!("Open {} on any device and enter this code:", device_code.verification_uri));
+                ui.horizontal(|ui| {
+                    ui.heading(&device_code.user_code);
+                    if ui.button("📋 Copy Code").clicked() {
+                        ui.output_mut(|o| o.copied_text = device_code.user_code.clone());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    busy_spinner(ui);
+                    ui.label("Waiting for you to finish signing in...");
+                    if ui.button("Cancel").clicked() {
+                        self.device_code = None;
+                    }
                 });
             } else {
                 ui.label("Not authenticated");
                 
-                if ui.button("Sign In with Microsoft").clicked() {
-                    self.authenticate(ctx);
-                }
+                ui.horizontal(|ui| {
+                    if ui.button("Sign In with Microsoft").clicked() {
+                        self.authenticate(ctx);
+                    }
+                    if ui.button("Sign In with a Code")
+                        .on_hover_text("Enter a code at microsoft.com/devicelogin on any device, for when the browser cannot reach this computer")
+                        .clicked()
+                    {
+                        let client_id = (!is_valid_client_id(&self.config.client_id)).then_some(BUILTIN_CLIENT_ID);
+                        self.start_device_code_sign_in(client_id);
+                    }
+                });
             }
         });
         
@@ -281,20 +617,81 @@ impl OneDriveApp {
         if let Some(ref drive_info) = self.drive_info {
             ui.group(|ui| {
                 ui.label("OneDrive Information");
+                if let Some(ref name) = drive_info.name {
+                    ui.label(format!("Drive: {}", name));
+                }
                 ui.label(format!("Drive Type: {}", drive_info.drive_type));
                 
                 if let Some(ref quota) = drive_info.quota {
-                    let used_gb = quota.used as f64 / (1024.0 * 1024.0 * 1024.0);
-                    let total_gb = quota.total as f64 / (1024.0 * 1024.0 * 1024.0);
-                    let used_percent = (quota.used as f64 / quota.total as f64) * 100.0;
-                    
-                    ui.label(format!("Storage: {:.2} GB / {:.2} GB ({:.1}% used)", 
-                        used_gb, total_gb, used_percent));
+                    ui.label(format!("Storage: {} / {} ({} used)",
+                        format::size(quota.used), format::size(quota.total), format::percent(quota.used, quota.total)));
                     
                     // Progress bar
                     let progress = quota.used as f32 / quota.total as f32;
-                    ui.add(egui::ProgressBar::new(progress).text(format!("{:.1}%", used_percent)));
+                    ui.add(egui::ProgressBar::new(progress).text(format::percent(quota.used, quota.total)));
+                }
+            });
+            
+            ui.add_space(10.0);
+        }
+        
+        // Graph requests against the configured budget
+        ui.group(|ui| {
+            ui.label("OneDrive Requests");
+            
+            let usage = budget::usage();
+            for (period, used, limit) in [
+                ("Last hour", usage.last_hour, self.config.request_budget_per_hour),
+                ("Last 24 hours", usage.last_day, self.config.request_budget_per_day),
+            ] {
+                if limit > 0 {
+                    ui.label(format!("{}: {} of {} requests", period, used, limit));
+                    let progress = (used as f32 / limit as f32).min(1.0);
+                    ui.add(egui::ProgressBar::new(progress).text(format!("{:.0}%", progress * 100.0)));
+                } else {
+                    ui.label(format!("{}: {} requests", period, used));
                 }
+            }
+            
+            if self.sync_status.request_budget_low {
+                ui.colored_label(egui::Color32::YELLOW, "⚠ Request budget nearly used up");
+                ui.label("Automatic syncs wait until it frees up. Sync Now still works.");
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        if let Some(skew) = self.sync_status.clock_skew_secs {
+            ui.group(|ui| {
+                let direction = if skew > 0 { "behind" } else { "ahead of" };
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("⚠ Your system clock is {} minutes {} the correct time", skew.abs() / 60, direction),
+                );
+                ui.label("Sync compensates for the difference, but other apps may misbehave.");
+                ui.label("Enable automatic time synchronization in your system settings to fix it.");
+            });
+            
+            ui.add_space(10.0);
+        }
+        
+        // Drive changed under the sync folder - syncing stays stopped until re-linked
+        if self.sync_status.relink_required {
+            ui.group(|ui| {
+                ui.colored_label(egui::Color32::YELLOW, "⚠ Your account now points to a different OneDrive");
+                ui.label("This happens after a tenant migration, when switching between personal and work accounts, or after choosing another drive.");
+                ui.label("Syncing is paused so nothing is deleted or duplicated. Re-linking keeps all local files and");
+                ui.label("merges them with the new drive as if this were the first sync.");
+                
+                ui.horizontal(|ui| {
+                    if ui.button("Re-link to new drive").clicked() {
+                        self.relink_drive();
+                    }
+                    
+                    if ui.button("Sign Out").clicked() {
+                        self.sign_out();
+                    }
+                });
             });
             
             ui.add_space(10.0);
@@ -308,12 +705,33 @@ impl OneDriveApp {
             
             if self.sync_status.is_syncing {
                 ui.label("🔄 Sync in progress...");
-                ui.label(&self.sync_status.current_operation);
+                if self.sync_status.current_file.is_some() && self.sync_status.current_file_bytes_total > 0 {
+                    ui.label(format!(
+                        "{} — {} / {}",
+                        self.sync_status.current_operation,
+                        format::size(self.sync_status.current_file_bytes_done),
+                        format::size(self.sync_status.current_file_bytes_total)
+                    ));
+                } else {
+                    ui.label(&self.sync_status.current_operation);
+                }
                 
                 // Show progress bar
                 let progress = self.sync_status.sync_progress;
                 ui.add(egui::ProgressBar::new(progress).text(format!("{:.1}%", progress * 100.0)));
                 
+                if self.sync_status.current_speed_bps > 0 {
+                    let mut speed = format::rate(self.sync_status.current_speed_bps);
+                    if let Some(remaining) = self.sync_status.estimated_remaining {
+                        speed.push_str(&format!(" · about {} left", format::duration(remaining)));
+                    }
+                    ui.weak(speed);
+                }
+                
+                if self.sync_status.sleep_inhibited {
+                    ui.label("☕ Keeping the computer awake until transfers finish");
+                }
+                
             } else if let Some(last_sync) = self.sync_status.last_sync {
                 let elapsed = std::time::SystemTime::now()
                     .duration_since(last_sync)
@@ -330,6 +748,15 @@ impl OneDriveApp {
                 ui.label("⏳ Not synced yet");
             }
             
+            if let Some(run_id) = &self.sync_status.run_id {
+                ui.weak(format!("Sync run {}", run_id))
+                    .on_hover_text("Quote this ID when reporting a sync problem; it marks this run in the logs and notifications");
+            }
+            
+            if !self.sync_status.is_syncing && self.sync_status.pending_actions > 0 {
+                ui.label(format!("⏳ {} changes waiting for the next sync", self.sync_status.pending_actions));
+            }
+            
             // Show current operation even when not syncing for better feedback
             if !self.sync_status.current_operation.is_empty() && self.sync_status.current_operation != "Ready" {
                 ui.label(format!("Status: {}", self.sync_status.current_operation));
@@ -339,20 +766,154 @@ impl OneDriveApp {
                 if ui.button("Sync Now").clicked() && self.user_info.is_some() && !self.sync_status.is_syncing {
                     self.start_manual_sync();
                 }
+                
+                let pause_label = if self.sync_status.paused { "Resume Syncing" } else { "Pause Syncing" };
+                if ui.button(pause_label).clicked() {
+                    self.toggle_pause();
+                }
+                
+                if self.sync_status.is_syncing
+                    && ui.button("Cancel Sync").on_hover_text("Stop the current sync; partly transferred files resume next time").clicked()
+                {
+                    self.sync_control.cancel();
+                    self.status_message = "Cancelling sync...".to_string();
+                }
+                
+                if ui.button("Preview Changes").on_hover_text("List what the next sync would change, without changing anything").clicked()
+                    && self.user_info.is_some()
+                    && !self.sync_status.is_syncing
+                {
+                    self.start_sync_preview();
+                }
             });
             
             // Show total files and sync statistics
             if self.sync_status.total_files > 0 {
                 ui.separator();
-                ui.label(format!("Total files tracked: {}", self.sync_status.total_files));
+                ui.label(format!("Total files tracked: {}", format::count(self.sync_status.total_files)));
             }
             
-            if self.sync_status.files_uploaded > 0 || self.sync_status.files_downloaded > 0 || self.sync_status.files_deleted > 0 {
+            if self.sync_status.files_uploaded > 0 || self.sync_status.files_downloaded > 0 || self.sync_status.files_deleted > 0 || self.sync_status.files_conflicted > 0 {
                 ui.separator();
                 ui.label("Last Sync Statistics:");
-                ui.label(format!("↑ Uploaded: {}", self.sync_status.files_uploaded));
-                ui.label(format!("↓ Downloaded: {}", self.sync_status.files_downloaded));
-                ui.label(format!("🗑 Deleted: {}", self.sync_status.files_deleted));
+                ui.label(format!("↑ Uploaded: {}", format::count(self.sync_status.files_uploaded)));
+                ui.label(format!("↓ Downloaded: {}", format::count(self.sync_status.files_downloaded)));
+                ui.label(format!("🗑 Deleted: {}", format::count(self.sync_status.files_deleted)));
+                if self.sync_status.files_conflicted > 0 {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ Conflicts (kept both): {}", format::count(self.sync_status.files_conflicted)));
+                }
+            }
+            
+            // Files the engine stopped syncing by itself wait for the user
+            if !self.sync_status.issues.is_empty() {
+                ui.separator();
+                ui.colored_label(egui::Color32::YELLOW, "Files Not Syncing:");
+                let mut dismissed = None;
+                let mut history_path = None;
+                for issue in &self.sync_status.issues {
+                    ui.horizontal(|ui| {
+                        ui.label("•");
+                        if ui.link(&issue.path)
+                            .on_hover_text(format!("Held since {}. Click for its history.", format::timestamp(issue.created)))
+                            .clicked()
+                        {
+                            history_path = Some(issue.path.clone());
+                        }
+                        if ui.button("Sync Again").clicked() {
+                            dismissed = Some(issue.path.clone());
+                        }
+                    });
+                    ui.weak(format!("  {}", issue.reason));
+                }
+                if let Some(path) = dismissed {
+                    match self.rt.block_on(self.sync_control.dismiss_issue(&path)) {
+                        Ok(_) => self.status_message = format!("{} will sync again from the next sync", path),
+                        Err(e) => self.status_message = format!("Failed to resume {}: {}", path, e),
+                    }
+                }
+                if let Some(path) = history_path {
+                    self.open_file_history(path);
+                }
+            }
+            
+            if let Some(recovery) = &self.sync_status.recovery {
+                ui.separator();
+                if recovery.database_problems.is_empty() {
+                    ui.weak(recovery.summary());
+                } else {
+                    ui.colored_label(egui::Color32::YELLOW, recovery.summary());
+                }
+                if !recovery.removed_partials.is_empty() || !recovery.dropped_upload_sessions.is_empty() {
+                    egui::CollapsingHeader::new("Details").id_source("recovery_details").show(ui, |ui| {
+                        for path in &recovery.removed_partials {
+                            ui.label(format!("• Removed unfinished download of {}", path));
+                        }
+                        for path in &recovery.dropped_upload_sessions {
+                            ui.label(format!("• Upload of {} starts over", path));
+                        }
+                    });
+                }
+            }
+            
+            if !self.sync_status.files_being_written.is_empty() {
+                ui.separator();
+                egui::CollapsingHeader::new(format!("Waiting until written: {}", self.sync_status.files_being_written.len()))
+                    .show(ui, |ui| {
+                        ui.weak("Still open or changing; these upload on a later sync");
+                        for path in &self.sync_status.files_being_written {
+                            ui.label(format!("• {}", path));
+                        }
+                    });
+            }
+            
+            if let Some(reason) = &self.sync_status.uploads_held_for_quota {
+                ui.separator();
+                ui.colored_label(egui::Color32::YELLOW, "⚠ Uploads waiting for OneDrive space");
+                ui.label(reason);
+            }
+            
+            // Uploads stay off until the user says the changes are theirs
+            if let Some(reason) = self.sync_status.uploads_paused_for_mass_change.clone() {
+                ui.separator();
+                ui.colored_label(egui::Color32::RED, "⚠ Uploads paused: many files changed at once");
+                ui.label(reason);
+                ui.weak("If files you did not change no longer open, disconnect from the network, scan the computer, and restore earlier versions from OneDrive.");
+                if self.confirm_mass_change {
+                    ui.horizontal(|ui| {
+                        ui.label("Upload the changed files, replacing their OneDrive copies?");
+                        if ui.button("Upload").clicked() {
+                            self.confirm_mass_change = false;
+                            self.status_message = match self.rt.block_on(self.sync_control.confirm_mass_change()) {
+                                Ok(_) => "Changes confirmed; they upload with the next sync".to_string(),
+                                Err(e) => format!("Failed to confirm the changes: {}", e),
+                            };
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_mass_change = false;
+                        }
+                    });
+                } else if ui.button("These Changes Are Mine...").clicked() {
+                    self.confirm_mass_change = true;
+                }
+            }
+            
+            if !self.sync_status.skipped_files.is_empty() {
+                ui.separator();
+                egui::CollapsingHeader::new(format!("⚠ Skipped in the last sync: {}", format::count(self.sync_status.skipped_files.len() as u64)))
+                    .show(ui, |ui| {
+                        ui.weak("These stay on this computer only until the reason below goes away");
+                        // The list is sorted by kind, so each kind is one run
+                        for group in self.sync_status.skipped_files.chunk_by(|a, b| a.kind == b.kind) {
+                            egui::CollapsingHeader::new(format!("{}: {}", group[0].kind.label(), format::count(group.len() as u64)))
+                                .id_source(("skipped", group[0].kind))
+                                .show(ui, |ui| {
+                                    for skipped_file in group {
+                                        ui.label(format!("• {}", skipped_file.path));
+                                        ui.weak(format!("  {}", skipped_file.reason));
+                                    }
+                                });
+                        }
+                    });
             }
             
             // Show errors if any
@@ -375,103 +936,183 @@ impl OneDriveApp {
         ui.group(|ui| {
             ui.label("Sync Folder");
             
-            ui.horizontal(|ui| {
-                ui.text_edit_singleline(&mut self.new_sync_folder);
-                
-                if ui.button("Browse").clicked() {
-                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                        self.new_sync_folder = path.to_string_lossy().to_string();
+            let locked = self.config.is_locked("sync_folder");
+            if locked {
+                self.new_sync_folder = self.config.sync_folder.display().to_string();
+            }
+            ui.add_enabled_ui(!locked, |ui| {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.new_sync_folder);
+                    
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.new_sync_folder = path.to_string_lossy().to_string();
+                        }
                     }
-                }
-                
-                if ui.button("Apply").clicked() {
-                    self.update_sync_folder();
-                }
+                    
+                    if ui.button("Apply").clicked() {
+                        self.update_sync_folder();
+                    }
+                });
             });
+            if locked {
+                show_locked_note(ui);
+            }
         });
         
         ui.add_space(10.0);
         
-        // Application settings
+        // Which of the account's drives is synced
         ui.group(|ui| {
-            ui.label("Application Settings");
+            ui.label("Drive (applies after restart)");
             
-            let mut auto_start = self.config.auto_start;
-            if ui.checkbox(&mut auto_start, "Start automatically when I sign in").clicked() {
-                let mut config = (*self.config).clone();
-                if config.set_auto_start(auto_start).is_ok() {
-                    // Config updated
-                }
+            if let Some(drives) = self.drives_fetched.lock().unwrap().take() {
+                self.drives_fetching = false;
+                self.available_drives = Some(drives);
             }
-            
-            let mut minimize_to_tray = self.config.minimize_to_tray;
-            if ui.checkbox(&mut minimize_to_tray, "Minimize to system tray").clicked() {
-                let mut config = (*self.config).clone();
-                if config.set_minimize_to_tray(minimize_to_tray).is_ok() {
-                    // Config updated
-                }
+            if self.drives_fetching {
+                ui.ctx().request_repaint_after(Duration::from_millis(250));
             }
             
-            let mut notifications = self.config.notifications;
-            if ui.checkbox(&mut notifications, "Show sync notifications").clicked() {
-                let mut config = (*self.config).clone();
-                if config.set_notifications(notifications).is_ok() {
-                    // Config updated
-                }
+            let locked = self.config.is_locked("drive_id");
+            let mut chosen = None;
+            ui.add_enabled_ui(!locked, |ui| {
+                ui.horizontal(|ui| {
+                    match &self.available_drives {
+                        Some(Ok(drives)) => {
+                            let mut selected = self.config.drive_id.clone();
+                            let selected_text = drives
+                                .iter()
+                                .find(|drive| drive.id == selected)
+                                .map(drive_label)
+                                .unwrap_or_else(|| "My OneDrive".to_string());
+                            egui::ComboBox::from_id_source("drive").selected_text(selected_text).show_ui(ui, |ui| {
+                                ui.selectable_value(&mut selected, String::new(), "My OneDrive");
+                                for drive in drives {
+                                    ui.selectable_value(&mut selected, drive.id.clone(), drive_label(drive));
+                                }
+                            });
+                            if selected != self.config.drive_id {
+                                chosen = Some(selected);
+                            }
+                        }
+                        Some(Err(error)) => {
+                            ui.colored_label(egui::Color32::RED, format!("Could not list drives: {}", error));
+                        }
+                        None if self.config.drive_id.is_empty() => {
+                            ui.label("My OneDrive");
+                        }
+                        None => {
+                            ui.label(format!("Drive {}", self.config.drive_id));
+                        }
+                    }
+                    if self.drives_fetching {
+                        ui.spinner();
+                    } else if ui.button("Find Drives").clicked() {
+                        self.load_drives();
+                    }
+                });
+            });
+            if locked {
+                show_locked_note(ui);
+            } else {
+                ui.weak("After a change, syncing waits until the sync folder is re-linked to the new drive in the Status tab.");
             }
-            
-            let mut debug_logging = self.config.debug_logging;
-            if ui.checkbox(&mut debug_logging, "Enable debug logging").clicked() {
+            if let Some(drive_id) = chosen {
                 let mut config = (*self.config).clone();
-                if config.set_debug_logging(debug_logging).is_ok() {
-                    // Config updated
+                if config.set_drive_id(drive_id).is_ok() {
+                    self.config = Arc::new(config);
+                    self.browse_cache().clear();
+                    self.browse_fresh.clear();
+                    self.browse_path.clear();
+                    self.browse_selected.clear();
+                    self.status_message = "Drive saved; restart to sync it".to_string();
                 }
             }
         });
         
         ui.add_space(10.0);
         
-        // Azure Configuration section
+        // Upload-only mirrors of folders outside the sync folder
         ui.group(|ui| {
-            ui.label("Azure Configuration");
+            ui.label("Watched Folders (upload only, applies after restart)");
+            
+            let mut remove_index = None;
+            for (index, mirror) in self.config.upload_mirrors.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} → OneDrive/{}", mirror.local_folder.display(), mirror.remote_folder.trim_matches('/')));
+                    if ui.button("Remove").clicked() {
+                        remove_index = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove_index {
+                let mut config = (*self.config).clone();
+                if config.remove_upload_mirror(index).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
             
             ui.horizontal(|ui| {
-                ui.label(format!("Client ID: {}", 
-                    if self.config.client_id == "14d82eec-204b-4c2f-b7e8-296a70dab67e" {
-                        "Not configured (using default)".to_string()
-                    } else {
-                        self.config.client_id.clone()
+                ui.text_edit_singleline(&mut self.new_mirror_folder);
+                if ui.button("Browse").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.new_mirror_folder = path.to_string_lossy().to_string();
                     }
-                ));
+                }
             });
             
             ui.horizontal(|ui| {
-                if ui.button("🔧 Setup Azure App Registration").clicked() {
-                    self.show_setup_wizard = true;
-                    self.setup_step = SetupStep::Welcome;
-                    self.client_id_input.clear();
-                }
-                
-                if ui.button("📋 Copy Redirect URI").clicked() {
-                    ui.output_mut(|o| o.copied_text = "http://localhost:8080/callback".to_string());
-                    self.status_message = "Redirect URI copied to clipboard".to_string();
+                ui.label("Remote folder:");
+                ui.text_edit_singleline(&mut self.new_mirror_remote);
+                if ui.button("Add").clicked() && !self.new_mirror_folder.trim().is_empty() {
+                    let mut config = (*self.config).clone();
+                    let local_folder = std::path::PathBuf::from(self.new_mirror_folder.trim());
+                    if local_folder.starts_with(&config.sync_folder) {
+                        self.status_message = "Watched folders must be outside the sync folder".to_string();
+                    } else if config.sync_folder.starts_with(&local_folder) {
+                        self.status_message = "Watched folders must not contain the sync folder".to_string();
+                    } else if config.add_upload_mirror(local_folder, self.new_mirror_remote.trim().to_string()).is_ok() {
+                        self.config = Arc::new(config);
+                        self.new_mirror_folder.clear();
+                        self.new_mirror_remote.clear();
+                        self.status_message = "Watched folder added; restart to start mirroring".to_string();
+                    }
                 }
             });
         });
         
         ui.add_space(10.0);
         
-        // Sync settings
         ui.group(|ui| {
-            ui.label("Sync Settings");
+            ui.label("Pause While These Apps Run (applies after restart)");
+            ui.label("Syncing waits until they exit, so half-written project files are not uploaded.");
+            
+            let mut remove_index = None;
+            for (index, app) in self.config.pause_while_running.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(app);
+                    if ui.button("Remove").clicked() {
+                        remove_index = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove_index {
+                let mut config = (*self.config).clone();
+                if config.remove_pause_while_running(index).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
             
             ui.horizontal(|ui| {
-                ui.label("Sync interval:");
-                let mut interval = self.config.sync_interval_minutes as f32;
-                if ui.add(egui::Slider::new(&mut interval, 1.0..=60.0).suffix(" minutes")).changed() {
+                ui.label("Process name:");
+                ui.text_edit_singleline(&mut self.new_pause_app)
+                    .on_hover_text("As shown by ps or System Monitor, e.g. kdenlive");
+                if ui.button("Add").clicked() && !self.new_pause_app.trim().is_empty() {
                     let mut config = (*self.config).clone();
-                    if config.set_sync_interval(interval as u64).is_ok() {
-                        // Config updated
+                    if config.add_pause_while_running(self.new_pause_app.trim().to_string()).is_ok() {
+                        self.config = Arc::new(config);
+                        self.new_pause_app.clear();
                     }
                 }
             });
@@ -479,39 +1120,1517 @@ impl OneDriveApp {
         
         ui.add_space(10.0);
         
-        // About section
         ui.group(|ui| {
-            ui.label("About");
-            ui.label("OneDrive Ubuntu Client v1.0.0");
-            ui.label("Built with Rust and egui");
-            ui.label(format!("Config directory: {}", self.config.config_dir.display()));
-        });
-    }
-    
-    fn show_logs_tab(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Sync Logs");
-        
-        ui.separator();
-        
-        // Refresh cache every 5 seconds or on manual refresh
-        let should_refresh = ui.button("Refresh Logs").clicked() || 
-                           self.last_history_refresh.elapsed() > Duration::from_secs(5);
-        
-        if should_refresh {
-            info!("Refreshing sync logs");
-            // Try to refresh cache from sync manager
-            if let Ok(history) = self.rt.block_on(async {
-                if let Ok(sync_guard) = tokio::time::timeout(
-                    Duration::from_millis(100),
-                    self.sync_manager.lock()
-                ).await {
-                    sync_guard.get_sync_history(50).await
-                } else {
-                    Err(anyhow::anyhow!("Sync manager busy"))
+            ui.label("Sync Schedule (applies after restart)");
+            ui.label("Limit when automatic syncs run. Sync Now always works.");
+            
+            let mut mode = self.config.sync_window_mode;
+            ui.radio_value(&mut mode, SyncWindowMode::Allow, "Sync automatically only during these times");
+            ui.radio_value(&mut mode, SyncWindowMode::Quiet, "Don't sync automatically during these times");
+            if mode != self.config.sync_window_mode {
+                let mut config = (*self.config).clone();
+                if config.set_sync_window_mode(mode).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            if self.config.sync_windows.is_empty() {
+                ui.weak("No times set; automatic syncs run around the clock");
+            }
+            let mut remove_index = None;
+            for (index, window) in self.config.sync_windows.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(schedule::describe(window));
+                    if ui.button("Remove").clicked() {
+                        remove_index = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove_index {
+                let mut config = (*self.config).clone();
+                if config.remove_sync_window(index).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("From:");
+                ui.add(egui::TextEdit::singleline(&mut self.new_window_start).desired_width(50.0));
+                ui.label("to:");
+                ui.add(egui::TextEdit::singleline(&mut self.new_window_end).desired_width(50.0))
+                    .on_hover_text("24-hour HH:MM; an end before the start runs past midnight");
+            });
+            ui.horizontal(|ui| {
+                const DAYS: [chrono::Weekday; 7] = [
+                    chrono::Weekday::Mon,
+                    chrono::Weekday::Tue,
+                    chrono::Weekday::Wed,
+                    chrono::Weekday::Thu,
+                    chrono::Weekday::Fri,
+                    chrono::Weekday::Sat,
+                    chrono::Weekday::Sun,
+                ];
+                for (day, selected) in DAYS.iter().zip(self.new_window_days.iter_mut()) {
+                    ui.checkbox(selected, day.to_string());
+                }
+                
+                if ui.button("Add").clicked() {
+                    if schedule::parse_time(&self.new_window_start).is_none() || schedule::parse_time(&self.new_window_end).is_none() {
+                        self.status_message = "Enter times as HH:MM, e.g. 09:00".to_string();
+                    } else if !self.new_window_days.contains(&true) {
+                        self.status_message = "Pick at least one day".to_string();
+                    } else {
+                        let days: Vec<chrono::Weekday> = DAYS
+                            .iter()
+                            .zip(self.new_window_days)
+                            .filter(|(_, selected)| *selected)
+                            .map(|(day, _)| *day)
+                            .collect();
+                        let window = SyncWindow {
+                            start: self.new_window_start.trim().to_string(),
+                            end: self.new_window_end.trim().to_string(),
+                            // Every day selected is stored as no restriction
+                            days: if days.len() == DAYS.len() { Vec::new() } else { days },
+                        };
+                        let mut config = (*self.config).clone();
+                        if config.add_sync_window(window).is_ok() {
+                            self.config = Arc::new(config);
+                        }
+                    }
+                }
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Size and Type Filters (applies after restart)");
+            ui.label("Skipped files stay where they are and are listed on the Status tab.");
+            
+            ui.horizontal(|ui| {
+                ui.label("Don't upload files larger than:");
+                let mut max_size_mb = self.config.max_upload_size_mb;
+                if ui.add(egui::DragValue::new(&mut max_size_mb).speed(10).suffix(" MB")).changed() {
+                    let mut config = (*self.config).clone();
+                    if config.set_max_upload_size_mb(max_size_mb).is_ok() {
+                        self.config = Arc::new(config);
+                    }
+                }
+                ui.weak("(0 means no limit)");
+            });
+            
+            let mut rename_illegal_names = self.config.rename_illegal_names;
+            if ui.checkbox(&mut rename_illegal_names, "Rename files whose names OneDrive does not allow")
+                .on_hover_text("e.g. \"notes: draft?.txt\" becomes \"notes_ draft_.txt\"; otherwise they are skipped")
+                .clicked()
+            {
+                let mut config = (*self.config).clone();
+                if config.set_rename_illegal_names(rename_illegal_names).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Wait before uploading files changed in the last:");
+                let mut settle_seconds = self.config.upload_settle_seconds;
+                if ui.add(egui::DragValue::new(&mut settle_seconds).speed(1).suffix(" s")).changed() {
+                    let mut config = (*self.config).clone();
+                    if config.set_upload_settle_seconds(settle_seconds).is_ok() {
+                        self.config = Arc::new(config);
+                    }
+                }
+            }).response.on_hover_text("Files still being written, like exports and recordings, are uploaded once they stop changing");
+            
+            ui.horizontal(|ui| {
+                ui.label("Never sync these file types:");
+                ui.text_edit_singleline(&mut self.excluded_extensions_input)
+                    .on_hover_text("Extensions separated by commas, e.g. iso, vdi, tmp");
+                if ui.button("Apply").clicked() {
+                    let extensions: Vec<String> = self
+                        .excluded_extensions_input
+                        .split(',')
+                        .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+                        .filter(|extension| !extension.is_empty())
+                        .collect();
+                    let mut config = (*self.config).clone();
+                    if config.set_excluded_extensions(extensions).is_ok() {
+                        self.excluded_extensions_input = config.excluded_extensions.join(", ");
+                        self.config = Arc::new(config);
+                        self.status_message = "File type filter saved; restart to apply it".to_string();
+                    }
+                }
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Sync Summary (applies after restart)");
+            ui.label("Sends a plain-text summary of changes, errors and storage to a command of");
+            ui.label("your choice, for computers nobody watches. The subject is in $ONEDRIVE_SUMMARY_SUBJECT.");
+            
+            let mut command = self.config.summary_command.clone();
+            let mut frequency = self.config.summary_frequency;
+            ui.horizontal(|ui| {
+                ui.label("Command:");
+                ui.text_edit_singleline(&mut self.summary_command_input)
+                    .on_hover_text("Receives the summary on standard input, e.g. mail -s \"$ONEDRIVE_SUMMARY_SUBJECT\" me@example.com");
+                if ui.button("Save").clicked() {
+                    command = self.summary_command_input.trim().to_string();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut frequency, SummaryFrequency::Daily, "Once a day");
+                ui.radio_value(&mut frequency, SummaryFrequency::EverySync, "After every sync");
+            });
+            
+            if command != self.config.summary_command || frequency != self.config.summary_frequency {
+                let mut config = (*self.config).clone();
+                if config.set_summary_command(command, frequency).is_ok() {
+                    self.config = Arc::new(config);
+                    self.status_message = "Summary settings saved; restart to apply them".to_string();
+                }
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Webhooks (applies after restart)");
+            ui.label("Posts JSON to these URLs when a sync finishes or fails and on conflicts,");
+            ui.label("e.g. for Home Assistant, Slack or ntfy.");
+            
+            let mut remove_index = None;
+            for (index, url) in self.config.webhook_urls.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(url);
+                    if ui.button("Remove").clicked() {
+                        remove_index = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove_index {
+                let mut config = (*self.config).clone();
+                if config.remove_webhook_url(index).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("URL:");
+                ui.text_edit_singleline(&mut self.new_webhook_url)
+                    .on_hover_text("e.g. https://hooks.slack.com/services/...");
+                let url = self.new_webhook_url.trim();
+                let valid = url.starts_with("https://") || url.starts_with("http://");
+                if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                    let mut config = (*self.config).clone();
+                    if config.add_webhook_url(url.to_string()).is_ok() {
+                        self.config = Arc::new(config);
+                        self.new_webhook_url.clear();
+                    }
+                }
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Shared Folder Activity (applies after restart)");
+            ui.label("Changes others make in these shared folders are announced with who made them,");
+            ui.label("e.g. \"Alice added Budget.xlsx to Team/Finance\".");
+            
+            let mut remove_index = None;
+            for (index, folder) in self.config.watched_folders.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(folder);
+                    if ui.button("Remove").clicked() {
+                        remove_index = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove_index {
+                let mut config = (*self.config).clone();
+                if config.remove_watched_folder(index).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Folder:");
+                ui.text_edit_singleline(&mut self.new_watched_folder)
+                    .on_hover_text("Path inside the sync folder, e.g. Team/Finance");
+                let folder = self.new_watched_folder.trim().trim_matches('/');
+                if ui.add_enabled(!folder.is_empty(), egui::Button::new("Add")).clicked() {
+                    let mut config = (*self.config).clone();
+                    if config.add_watched_folder(folder.to_string()).is_ok() {
+                        self.config = Arc::new(config);
+                        self.new_watched_folder.clear();
+                    }
+                }
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Instant Updates (applies after restart)");
+            ui.label("Microsoft can tell this computer the moment something changes on OneDrive, so");
+            ui.label("changes arrive without waiting for the sync interval. It needs a public HTTPS URL");
+            ui.label("(e.g. from a tunnel or reverse proxy) that forwards to the address below.");
+            
+            ui.horizontal(|ui| {
+                ui.label("Public URL:");
+                ui.text_edit_singleline(&mut self.push_url_input)
+                    .on_hover_text("Leave empty to sync on the interval only");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Listen on:");
+                ui.text_edit_singleline(&mut self.push_address_input);
+            });
+            
+            let url = self.push_url_input.trim();
+            let valid = url.is_empty() || url.starts_with("https://");
+            if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_push_notifications(url.to_string(), self.push_address_input.trim().to_string()).is_ok() {
+                    self.config = Arc::new(config);
+                    self.status_message = "Instant update settings saved; restart to apply them".to_string();
+                }
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Cloud-only Archive");
+            ui.label("Files in this OneDrive folder that have not been opened on this computer for a");
+            ui.label("while are removed from it once OneDrive is confirmed to have the same copy.");
+            ui.label("They stay on OneDrive and in the Files tab, and are not downloaded again.");
+
+            let locked = self.config.is_locked("archive_folder");
+            ui.horizontal(|ui| {
+                ui.label("Folder:");
+                ui.add_enabled(
+                    !locked,
+                    egui::TextEdit::singleline(&mut self.archive_folder_input).hint_text("e.g. Archive"),
+                )
+                .on_hover_text("Leave empty to keep every file on this computer");
+                if locked {
+                    show_locked_note(ui);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Free files unused for:");
+                ui.add_enabled(
+                    !locked,
+                    egui::DragValue::new(&mut self.archive_days_input).clamp_range(1..=3650).suffix(" days"),
+                );
+            });
+
+            if ui.add_enabled(!locked, egui::Button::new("Save")).clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_archive(self.archive_folder_input.clone(), self.archive_days_input).is_ok() {
+                    self.archive_folder_input = config.archive_folder.clone();
+                    self.config = Arc::new(config);
+                    self.status_message = "Archive settings saved".to_string();
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.label("Request Budget (applies after restart)");
+            ui.label("For work accounts whose organization limits OneDrive requests. Automatic syncs");
+            ui.label(format!(
+                "wait once {:.0}% of a budget is used; Sync Now always works. 0 means no limit.",
+                budget::LOW_BUDGET_FRACTION * 100.0
+            ));
+            
+            let mut per_hour = self.config.request_budget_per_hour;
+            let mut per_day = self.config.request_budget_per_day;
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("Requests per hour:");
+                changed |= ui.add(egui::DragValue::new(&mut per_hour).speed(10)).changed();
+                ui.label("per day:");
+                changed |= ui.add(egui::DragValue::new(&mut per_day).speed(100)).changed();
+            });
+            if changed {
+                let mut config = (*self.config).clone();
+                if config.set_request_budget(per_hour, per_day).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.label("Calls (applies after restart)");
+            
+            let mut enabled = self.config.throttle_uploads_during_calls;
+            let mut limit_kib = self.config.call_upload_limit_kib;
+            let mut changed = ui
+                .checkbox(&mut enabled, "Slow uploads while the camera or microphone is in use")
+                .on_hover_text("Leaves upload bandwidth for video calls; full speed returns when the call ends")
+                .changed();
+            ui.add_enabled_ui(enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Upload limit during calls:");
+                    changed |= ui.add(egui::DragValue::new(&mut limit_kib).speed(16).clamp_range(16..=102400).suffix(" KiB/s")).changed();
+                });
+            });
+            if changed {
+                let mut config = (*self.config).clone();
+                if config.set_call_upload_throttle(enabled, limit_kib).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        // Application settings
+        ui.group(|ui| {
+            ui.label("Application Settings");
+            
+            let mut auto_start = self.config.auto_start;
+            if ui.checkbox(&mut auto_start, "Start automatically when I sign in").clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_auto_start(auto_start).is_ok() {
+                    // Config updated
+                }
+            }
+            
+            let mut minimize_to_tray = self.config.minimize_to_tray;
+            if ui.checkbox(&mut minimize_to_tray, "Minimize to system tray").clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_minimize_to_tray(minimize_to_tray).is_ok() {
+                    // Config updated
+                }
+            }
+            
+            ui.label("Notify me when:");
+            let mut notifications = self.config.notifications;
+            let mut changed = false;
+            ui.indent("notification_prefs", |ui| {
+                for (enabled, label) in [
+                    (&mut notifications.sync_complete, "Files are synced"),
+                    (&mut notifications.errors, "A sync fails"),
+                    (&mut notifications.conflicts, "A file was changed in two places"),
+                    (&mut notifications.quota, "OneDrive is out of space"),
+                    (&mut notifications.shared_files, "Someone shares files with me"),
+                    (&mut notifications.folder_activity, "Someone changes a watched folder"),
+                ] {
+                    changed |= ui.checkbox(enabled, label).clicked();
+                }
+            });
+            if changed {
+                let mut config = (*self.config).clone();
+                if config.set_notifications(notifications).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            let mut folder_integration = self.config.folder_integration;
+            if ui.checkbox(&mut folder_integration, "Show OneDrive folder in the file manager sidebar").clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_folder_integration(folder_integration).is_ok() && !folder_integration {
+                    self.remove_folder_integration();
+                }
+            }
+            
+            let mut index_content = self.config.index_content;
+            if ui.checkbox(&mut index_content, "Index document contents for offline search")
+                .on_hover_text("Text, Markdown and PDF files (PDFs need poppler-utils); the index is updated after each sync")
+                .clicked()
+            {
+                let mut config = (*self.config).clone();
+                if config.set_index_content(index_content).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            let mut trash_remote_deletions = self.config.trash_remote_deletions;
+            if ui.checkbox(&mut trash_remote_deletions, "Move files deleted on OneDrive to the Trash").clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_trash_remote_deletions(trash_remote_deletions).is_ok() {
+                    // Config updated
+                }
+            }
+            
+            let mut inhibit_sleep = self.config.inhibit_sleep_during_transfers;
+            if ui.checkbox(&mut inhibit_sleep, "Keep the computer awake during large transfers")
+                .on_hover_text("Blocks suspend while a sync moves 100 MB or more")
+                .clicked()
+            {
+                let mut config = (*self.config).clone();
+                if config.set_inhibit_sleep_during_transfers(inhibit_sleep).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Show sizes in:");
+                let mut size_units = self.config.size_units;
+                ui.radio_value(&mut size_units, SizeUnits::Decimal, "GB (1000)");
+                ui.radio_value(&mut size_units, SizeUnits::Binary, "GiB (1024)");
+                if size_units != self.config.size_units {
+                    let mut config = (*self.config).clone();
+                    if config.set_size_units(size_units).is_ok() {
+                        format::set_size_units(size_units);
+                        self.config = Arc::new(config);
+                    }
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                let mut high_contrast = self.config.high_contrast;
+                let mut reduce_motion = self.config.reduce_motion;
+                let mut changed = ui.checkbox(&mut high_contrast, "High contrast").changed();
+                changed |= ui.checkbox(&mut reduce_motion, "Reduce motion")
+                    .on_hover_text("Turns off animations and spinners")
+                    .changed();
+                if self.desktop_reduces_motion {
+                    ui.weak("(motion is reduced, as your desktop asks)");
+                }
+                if changed {
+                    let mut config = (*self.config).clone();
+                    if config.set_appearance(high_contrast, reduce_motion).is_ok() {
+                        self.config = Arc::new(config);
+                    }
+                }
+            });
+            
+            let mut debug_logging = self.config.debug_logging;
+            if ui.checkbox(&mut debug_logging, "Enable debug logging").clicked() {
+                let mut config = (*self.config).clone();
+                if config.set_debug_logging(debug_logging).is_ok() {
+                    // Config updated
+                }
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        // Azure Configuration section
+        ui.group(|ui| {
+            ui.label("Azure Configuration");
+            
+            ui.horizontal(|ui| {
+                ui.label(format!("Client ID: {}", 
+                    if self.config.client_id == BUILTIN_CLIENT_ID {
+                        "Built-in app registration".to_string()
+                    } else {
+                        self.config.client_id.clone()
+                    }
+                ));
+            });
+            
+            let client_id_locked = self.config.is_locked("client_id");
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!client_id_locked, egui::Button::new("🔧 Setup Azure App Registration")).clicked() {
+                    self.show_setup_wizard = true;
+                    self.setup_step = SetupStep::Welcome;
+                    self.client_id_input.clear();
+                }
+                
+                if ui.button("📋 Copy Redirect URI").clicked() {
+                    ui.output_mut(|o| o.copied_text = "http://localhost:8080/callback".to_string());
+                    self.status_message = "Redirect URI copied to clipboard".to_string();
+                }
+            });
+            if client_id_locked {
+                show_locked_note(ui);
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        // Sync settings
+        ui.group(|ui| {
+            ui.label("Sync Settings");
+            
+            ui.horizontal(|ui| {
+                ui.label("Sync interval:");
+                let locked = self.config.is_locked("sync_interval_minutes");
+                let mut interval = self.config.sync_interval_minutes as f32;
+                if ui.add_enabled(!locked, egui::Slider::new(&mut interval, 1.0..=60.0).suffix(" minutes")).changed() {
+                    let mut config = (*self.config).clone();
+                    if config.set_sync_interval(interval as u64).is_ok() {
+                        // Config updated
+                    }
+                }
+                if locked {
+                    show_locked_note(ui);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Parallel transfers:");
+                let locked = self.config.is_locked("max_concurrent_transfers");
+                let mut transfers = self.config.max_concurrent_transfers as f32;
+                if ui.add_enabled(!locked, egui::Slider::new(&mut transfers, 1.0..=16.0)).changed() {
+                    let mut config = (*self.config).clone();
+                    if config.set_max_concurrent_transfers(transfers as usize).is_ok() {
+                        // Config updated
+                    }
+                }
+                if locked {
+                    show_locked_note(ui);
+                }
+            });
+            
+            let mut poll_enabled = self.config.remote_poll_seconds > 0;
+            let mut poll_secs = if poll_enabled { self.config.remote_poll_seconds.clamp(30, 60) } else { 45 };
+            let mut poll_changed = false;
+            let poll_locked = self.config.is_locked("remote_poll_seconds");
+            ui.horizontal(|ui| {
+                poll_changed |= ui
+                    .add_enabled(!poll_locked, egui::Checkbox::new(&mut poll_enabled, "Download OneDrive changes between syncs, checking every"))
+                    .on_hover_text("Changes made on OneDrive arrive within a minute; local changes still wait for the sync interval. Applies after restart.")
+                    .changed();
+                poll_changed |= ui
+                    .add_enabled(poll_enabled && !poll_locked, egui::DragValue::new(&mut poll_secs).clamp_range(30..=60).suffix(" s"))
+                    .changed();
+                if poll_locked {
+                    show_locked_note(ui);
+                }
+            });
+            if poll_changed {
+                let mut config = (*self.config).clone();
+                if config.set_remote_poll_seconds(if poll_enabled { poll_secs } else { 0 }).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+
+            let mut online_only = self.config.online_only;
+            if ui.checkbox(&mut online_only, "Keep new OneDrive files online-only")
+                .on_hover_text("New files on OneDrive show up as small placeholders and are downloaded only when you choose Download now in the Files tab")
+                .clicked()
+            {
+                let mut config = (*self.config).clone();
+                if config.set_online_only(online_only).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+
+            let mut newest_first = self.config.download_newest_first;
+            if ui.checkbox(&mut newest_first, "Download recently modified files first")
+                .on_hover_text("Files changed on OneDrive most recently arrive before older ones")
+                .clicked()
+            {
+                let mut config = (*self.config).clone();
+                if config.set_download_newest_first(newest_first).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+            
+            let mut retention_days = self.config.log_retention_days;
+            let mut max_rows = self.config.log_max_rows;
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("Keep sync logs for:");
+                changed |= ui.add(egui::DragValue::new(&mut retention_days).speed(1).suffix(" days")).changed();
+                ui.label("and at most");
+                changed |= ui.add(egui::DragValue::new(&mut max_rows).speed(1000).suffix(" entries")).changed();
+                ui.weak("(0 means no limit)");
+            });
+            if changed {
+                let mut config = (*self.config).clone();
+                if config.set_log_retention(retention_days, max_rows).is_ok() {
+                    self.config = Arc::new(config);
+                }
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        // About section
+        ui.group(|ui| {
+            ui.label("About");
+            ui.label("OneDrive Ubuntu Client v1.0.0");
+            ui.label("Built with Rust and egui");
+            ui.label(format!("Config directory: {}", self.config.config_dir.display()));
+        });
+    }
+    
+    fn show_files_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Files");
+        
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.files_view, FilesView::Synced, "Synced");
+            ui.selectable_value(&mut self.files_view, FilesView::OneDrive, "On OneDrive");
+            ui.selectable_value(&mut self.files_view, FilesView::OnlineOnly, "Online-only");
+        });
+        
+        ui.separator();
+        
+        match self.files_view {
+            FilesView::Synced => self.show_synced_files(ui),
+            FilesView::OneDrive => self.show_remote_browser(ui),
+            FilesView::OnlineOnly => self.show_online_only_files(ui),
+        }
+    }
+    
+    /// Placeholders, each with a button to download the file for real.
+    fn show_online_only_files(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.files_filter);
+        });
+        
+        let should_refresh = self.placeholders_cache_filter.as_deref() != Some(self.files_filter.as_str())
+            || self.last_placeholders_refresh.is_none_or(|refreshed| refreshed.elapsed() > Duration::from_secs(5));
+        if should_refresh {
+            match self.rt.block_on(self.sync_control.get_placeholders(FILE_ROW_LIMIT, &self.files_filter)) {
+                Ok(placeholders) => self.placeholders_cache = placeholders,
+                Err(e) => warn!("Failed to read the online-only files: {}", e),
+            }
+            self.placeholders_cache_filter = Some(self.files_filter.clone());
+            self.last_placeholders_refresh = Some(std::time::Instant::now());
+        }
+        
+        ui.weak("These files are on OneDrive only; each shows here as a small name.onedrive placeholder.");
+        let mut download = Vec::new();
+        if !self.files_filter.trim().is_empty() && !self.placeholders_cache.is_empty() && ui.button("Download all shown").clicked() {
+            download.extend(self.placeholders_cache.iter().filter(|file| !file.requested).map(|file| file.path.clone()));
+        }
+        ui.separator();
+        
+        if self.placeholders_cache.is_empty() {
+            ui.label("No online-only files");
+            return;
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("online_only_files").striped(true).show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("Size");
+                ui.strong("");
+                ui.end_row();
+                
+                for file in &self.placeholders_cache {
+                    ui.label(&file.path);
+                    ui.label(format::size(file.size));
+                    if file.requested {
+                        ui.weak("Downloading with the next sync");
+                    } else if ui.button("Download now").clicked() {
+                        download.push(file.path.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        
+        if !download.is_empty() {
+            self.status_message = match self.rt.block_on(self.sync_control.download_now(download)) {
+                Ok(count) => format!("Downloading {} files", format::count(count as u64)),
+                Err(e) => format!("Could not download: {}", e),
+            };
+            self.last_placeholders_refresh = None;
+        }
+    }
+    
+    fn show_synced_files(&mut self, ui: &mut egui::Ui) {
+        let (refresh_clicked, shared_links_clicked) = ui.horizontal(|ui| {
+            let refresh = ui.button("Refresh Files").clicked();
+            let shared_links = ui.button("Shared Links")
+                .on_hover_text("List every link you have shared OneDrive files through, and revoke the ones you no longer want")
+                .clicked();
+            (refresh, shared_links)
+        }).inner;
+        if shared_links_clicked && self.user_info.is_some() {
+            self.start_shared_links_audit();
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.files_filter)
+                .on_hover_text("Matches the path or content type; letters may be spread out, e.g. \"rptpdf\" finds report.pdf");
+        });
+        
+        if self.config.index_content {
+            self.show_content_search(ui);
+        }
+        
+        // Re-query as the filter changes; a busy sync manager is retried shortly
+        let should_refresh = refresh_clicked
+            || self.files_cache_filter.as_deref() != Some(self.files_filter.as_str())
+            || self.last_files_refresh.is_none_or(|refreshed| refreshed.elapsed() > Duration::from_secs(30));
+        
+        if should_refresh {
+            let filter = self.files_filter.clone();
+            match self.rt.block_on(async {
+                if let Ok(sync_guard) = tokio::time::timeout(
+                    Duration::from_millis(100),
+                    self.sync_manager.lock()
+                ).await {
+                    sync_guard.get_synced_files(FILE_ROW_LIMIT, &filter).await
+                } else {
+                    Err(anyhow::anyhow!("Sync manager busy"))
+                }
+            }) {
+                Ok(files) => {
+                    self.synced_files_cache = files;
+                    self.files_cache_filter = Some(filter);
+                    self.last_files_refresh = Some(std::time::Instant::now());
+                }
+                Err(_) => ui.ctx().request_repaint_after(Duration::from_millis(250)),
+            }
+        }
+        
+        ui.add_space(10.0);
+        
+        let shown = &self.synced_files_cache;
+        if shown.len() >= FILE_ROW_LIMIT {
+            ui.label(format!("Showing the first {} files; type to narrow the list", format::count(FILE_ROW_LIMIT as u64)));
+        } else {
+            ui.label(format!("{} files", format::count(shown.len() as u64)));
+        }
+        if !self.sync_status.file_states.is_empty() {
+            let counts: Vec<String> = self
+                .sync_status
+                .file_states
+                .iter()
+                .map(|(state, count)| format!("{} {}", count, state.label().to_lowercase()))
+                .collect();
+            ui.weak(counts.join(" · "));
+        }
+        ui.separator();
+        
+        let mut history_path = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("synced_files").striped(true).show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("State");
+                ui.strong("Type");
+                ui.strong("Size");
+                ui.strong("Last synced");
+                ui.end_row();
+                
+                for file in shown {
+                    if ui.link(&file.path).on_hover_text("Show what the client did to this file").clicked() {
+                        history_path = Some(file.path.clone());
+                    }
+                    match file.state {
+                        FileState::InSync => ui.weak(file.state.label()),
+                        FileState::Conflicted | FileState::Quarantined => ui.colored_label(egui::Color32::YELLOW, file.state.label()),
+                        _ => ui.label(file.state.label()),
+                    };
+                    match file.mime_type {
+                        Some(ref mime_type) => ui.label(content_kind(mime_type)).on_hover_text(mime_type),
+                        None => ui.weak("Unknown"),
+                    };
+                    ui.label(format::size(file.size));
+                    ui.label(format::timestamp(file.last_synced));
+                    ui.end_row();
+                }
+            });
+        });
+        
+        if let Some(path) = history_path {
+            self.open_file_history(path);
+        }
+    }
+    
+    /// Applies the high contrast and reduced motion settings whenever they
+    /// change. Reduced motion, asked for in settings or by the desktop, turns
+    /// animations off, and with them `busy_spinner`.
+    fn apply_appearance(&mut self, ctx: &egui::Context) {
+        let appearance = (self.config.high_contrast, self.config.reduce_motion || self.desktop_reduces_motion);
+        if self.applied_appearance == Some(appearance) {
+            return;
+        }
+        let mut style = self.base_style.get_or_insert_with(|| (*ctx.style()).clone()).clone();
+        let (high_contrast, reduce_motion) = appearance;
+        if high_contrast {
+            style.visuals = high_contrast_visuals();
+        }
+        if reduce_motion {
+            style.animation_time = 0.0;
+        }
+        ctx.set_style(style);
+        self.applied_appearance = Some(appearance);
+    }
+    
+    /// Lists the account's drives in the background, for the Settings tab to
+    /// pick up once they arrive.
+    fn load_drives(&mut self) {
+        self.drives_fetching = true;
+        let api = OneDriveAPI::new(self.auth.clone());
+        let fetched = self.drives_fetched.clone();
+        self.rt.spawn(async move {
+            let drives = api.list_drives().await.map_err(|e| e.to_string());
+            *fetched.lock().unwrap() = Some(drives);
+        });
+    }
+    
+    /// The browser's folder cache, read from disk the first time it is needed.
+    fn browse_cache(&mut self) -> &mut BrowseCache {
+        let file = self.config.db_file.with_file_name("remote_folders.json");
+        self.browse_cache.get_or_insert_with(|| BrowseCache::load(file))
+    }
+    
+    /// Browses OneDrive folder by folder. A folder shows its cached listing at
+    /// once, marked stale, while a fresh one is fetched in the background.
+    fn show_remote_browser(&mut self, ui: &mut egui::Ui) {
+        let fetched = self.browse_fetched.lock().unwrap().take();
+        if let Some((path, result)) = fetched {
+            self.browse_fetching = None;
+            match result {
+                Ok(items) => {
+                    self.browse_cache().insert(&path, items);
+                    self.browse_errors.remove(&path);
+                    self.browse_fresh.insert(path);
+                }
+                Err(e) => {
+                    self.browse_errors.insert(path, e);
+                }
+            }
+        }
+        
+        let path = self.browse_path.clone();
+        let mut go_to = None;
+        let refresh_clicked = ui.horizontal(|ui| {
+            if ui.link("OneDrive").clicked() {
+                go_to = Some(String::new());
+            }
+            let mut prefix = String::new();
+            for part in path.split('/').filter(|part| !part.is_empty()) {
+                ui.label("/");
+                prefix = if prefix.is_empty() { part.to_string() } else { format!("{}/{}", prefix, part) };
+                if ui.link(part).clicked() {
+                    go_to = Some(prefix.clone());
+                }
+            }
+            ui.add_space(10.0);
+            ui.button("Refresh").clicked()
+        }).inner;
+        if refresh_clicked {
+            self.browse_fresh.remove(&path);
+            self.browse_errors.remove(&path);
+        }
+        
+        let needs_fetch = !self.browse_fresh.contains(&path) && !self.browse_errors.contains_key(&path);
+        if needs_fetch && self.browse_fetching.is_none() && self.user_info.is_some() {
+            self.browse_fetching = Some(path.clone());
+            let api = OneDriveAPI::new(self.auth.clone()).with_drive(self.config.selected_drive());
+            let fetched = self.browse_fetched.clone();
+            let fetch_path = path.clone();
+            self.rt.spawn(async move {
+                let result = api.list_items(&format!("/{}", fetch_path)).await.map_err(|e| e.to_string());
+                *fetched.lock().unwrap() = Some((fetch_path, result));
+            });
+        }
+        if self.browse_fetching.is_some() {
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+        }
+        
+        let fresh = self.browse_fresh.contains(&path);
+        let fetching = self.browse_fetching.as_deref() == Some(path.as_str());
+        let error = self.browse_errors.get(&path).cloned();
+        let Some(listing) = self.browse_cache().get(&path).cloned() else {
+            match error {
+                Some(error) => ui.colored_label(egui::Color32::RED, format!("Could not list this folder: {}", error)),
+                None if self.browse_fetching.is_none() => ui.label("Not connected to OneDrive, and this folder has not been listed before"),
+                None => ui.horizontal(|ui| {
+                    busy_spinner(ui);
+                    ui.label("Listing folder...");
+                }).response,
+            };
+            if let Some(path) = go_to {
+                self.browse_path = path;
+            }
+            return;
+        };
+        
+        if !fresh {
+            let as_of = format!("Showing the listing from {}", format::timestamp(listing.fetched));
+            if fetching {
+                ui.horizontal(|ui| {
+                    busy_spinner(ui);
+                    ui.weak(format!("{}; updating...", as_of));
+                });
+            } else if let Some(error) = error {
+                ui.colored_label(egui::Color32::YELLOW, format!("Offline. {}", as_of))
+                    .on_hover_text(error);
+            } else {
+                ui.weak(as_of);
+            }
+        }
+        ui.label(format!("{} items", format::count(listing.items.len() as u64)));
+        self.show_bulk_actions(ui, &path, &listing.items);
+        ui.separator();
+        
+        let selected = &mut self.browse_selected;
+        egui::ScrollArea::vertical().id_source("remote_browser").show(ui, |ui| {
+            egui::Grid::new("remote_browser_items").striped(true).show(ui, |ui| {
+                let mut all_selected = !listing.items.is_empty() && selected.len() == listing.items.len();
+                if ui.checkbox(&mut all_selected, "").on_hover_text("Select all").changed() {
+                    selected.clear();
+                    if all_selected {
+                        selected.extend(listing.items.iter().map(|item| remote_child_path(&path, &item.name)));
+                    }
+                }
+                ui.strong("Name");
+                ui.strong("Size");
+                ui.strong("Modified");
+                ui.end_row();
+                
+                for item in &listing.items {
+                    let item_path = remote_child_path(&path, &item.name);
+                    let mut checked = selected.contains(&item_path);
+                    if ui.checkbox(&mut checked, "").changed() {
+                        if checked {
+                            selected.insert(item_path.clone());
+                        } else {
+                            selected.remove(&item_path);
+                        }
+                    }
+                    if item.folder.is_some() {
+                        if ui.link(format!("📁 {}", item.name)).clicked() {
+                            go_to = Some(item_path);
+                        }
+                    } else {
+                        ui.label(format!("📄 {}", item.name));
+                    }
+                    ui.label(format::size(item.size.unwrap_or(0)));
+                    match chrono::DateTime::parse_from_rfc3339(&item.last_modified) {
+                        Ok(modified) => ui.label(format::date_time(&modified)),
+                        Err(_) => ui.weak(&item.last_modified),
+                    };
+                    ui.end_row();
+                }
+            });
+        });
+        
+        if let Some(path) = go_to {
+            self.browse_path = path;
+            self.browse_selected.clear();
+            self.bulk_confirm_delete = false;
+        }
+    }
+    
+    /// Download, move and delete for the items ticked in the browser, or the
+    /// progress of the one running.
+    fn show_bulk_actions(&mut self, ui: &mut egui::Ui, path: &str, items: &[DriveItem]) {
+        if let Some(progress) = self.bulk_progress.as_ref().map(|progress| progress.lock().unwrap().clone()) {
+            self.show_bulk_progress(ui, &progress);
+            return;
+        }
+        let chosen: Vec<(String, DriveItem)> = items
+            .iter()
+            .map(|item| (remote_child_path(path, &item.name), item.clone()))
+            .filter(|(item_path, _)| self.browse_selected.contains(item_path))
+            .collect();
+        if chosen.is_empty() {
+            return;
+        }
+        
+        let mut operation = None;
+        ui.horizontal(|ui| {
+            ui.label(format!("{} selected", format::count(chosen.len() as u64)));
+            if ui.button("Download to Folder...").clicked() {
+                if let Some(destination) = rfd::FileDialog::new().pick_folder() {
+                    operation = Some(BulkOperation::Download { destination });
+                }
+            }
+            ui.separator();
+            ui.label("To OneDrive folder:");
+            ui.add(egui::TextEdit::singleline(&mut self.bulk_move_folder).hint_text("/Archive").desired_width(150.0));
+            let folder = self.bulk_move_folder.trim();
+            if ui.add_enabled(!folder.is_empty(), egui::Button::new("Move")).clicked() {
+                operation = Some(BulkOperation::Move { folder: folder.trim_matches('/').to_string() });
+            }
+            if ui
+                .add_enabled(!folder.is_empty(), egui::Button::new("Copy"))
+                .on_hover_text("OneDrive makes the copies; nothing is downloaded")
+                .clicked()
+            {
+                operation = Some(BulkOperation::Copy { folder: folder.trim_matches('/').to_string() });
+            }
+            ui.separator();
+            if self.bulk_confirm_delete {
+                ui.colored_label(egui::Color32::YELLOW, format!("Move {} items to the OneDrive recycle bin?", chosen.len()));
+                if ui.button("Delete").clicked() {
+                    operation = Some(BulkOperation::Delete);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.bulk_confirm_delete = false;
+                }
+            } else if ui.button("Delete").clicked() {
+                self.bulk_confirm_delete = true;
+            }
+        });
+        
+        if let Some(operation) = operation {
+            self.start_bulk(operation, chosen);
+        }
+    }
+    
+    fn start_bulk(&mut self, operation: BulkOperation, items: Vec<(String, DriveItem)>) {
+        info!("Starting {:?} of {} items from the browser", operation, items.len());
+        let api = Arc::new(OneDriveAPI::new(self.auth.clone()).with_drive(self.config.selected_drive()));
+        
+        let _guard = self.rt.enter();
+        let (progress, cancel) = bulk::start(api, operation.clone(), items);
+        self.bulk_operation = Some(operation);
+        self.bulk_progress = Some(progress);
+        self.bulk_cancel = Some(cancel);
+        self.bulk_confirm_delete = false;
+    }
+    
+    fn show_bulk_progress(&mut self, ui: &mut egui::Ui, progress: &BulkProgress) {
+        let label = self.bulk_operation.as_ref().map(BulkOperation::label).unwrap_or_default();
+        if !progress.finished {
+            ui.horizontal(|ui| {
+                if progress.listing {
+                    busy_spinner(ui);
+                    ui.label("Finding files on OneDrive...");
+                } else {
+                    let fraction = if progress.total > 0 { progress.done as f32 / progress.total as f32 } else { 0.0 };
+                    ui.add(egui::ProgressBar::new(fraction).desired_width(250.0).text(format!(
+                        "{} {} of {}",
+                        label,
+                        format::count(progress.done as u64),
+                        format::count(progress.total as u64)
+                    )));
+                    ui.weak(&progress.current);
+                }
+                if ui.button("Cancel").clicked() {
+                    if let Some(cancel) = &self.bulk_cancel {
+                        cancel.cancel();
+                    }
+                }
+            });
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+            return;
+        }
+        
+        let mut close = false;
+        ui.horizontal(|ui| {
+            let done = progress.done.saturating_sub(progress.errors.len());
+            if progress.cancelled {
+                ui.label(format!("Cancelled after {} of {} items", format::count(progress.done as u64), format::count(progress.total as u64)));
+            } else {
+                ui.label(format!("Finished: {} of {} items", format::count(done as u64), format::count(progress.total as u64)));
+            }
+            if let Some(BulkOperation::Download { destination }) = &self.bulk_operation {
+                if ui.button("Open Folder").clicked() {
+                    let _ = open::that(destination);
+                }
+            }
+            close = ui.button("Done").clicked();
+        });
+        if !progress.kept.is_empty() {
+            egui::CollapsingHeader::new(format!("Left untouched because a different file was already there: {}", progress.kept.len()))
+                .show(ui, |ui| {
+                    for path in &progress.kept {
+                        ui.label(path);
+                    }
+                });
+        }
+        if !progress.errors.is_empty() {
+            egui::CollapsingHeader::new(egui::RichText::new(format!("Failed: {}", progress.errors.len())).color(egui::Color32::RED))
+                .show(ui, |ui| {
+                    for error in &progress.errors {
+                        ui.label(error);
+                    }
+                });
+        }
+        if close {
+            self.close_bulk();
+        }
+    }
+    
+    /// Clears a finished bulk operation. Listings are fetched again after a
+    /// move or delete, since they changed on OneDrive.
+    fn close_bulk(&mut self) {
+        if let Some(cancel) = self.bulk_cancel.take() {
+            cancel.cancel();
+        }
+        self.bulk_progress = None;
+        if !matches!(self.bulk_operation.take(), Some(BulkOperation::Download { .. })) {
+            self.browse_fresh.clear();
+        }
+        self.browse_selected.clear();
+    }
+    
+    /// Finds synced documents by the words in them, from the local index.
+    fn show_content_search(&mut self, ui: &mut egui::Ui) {
+        let search_clicked = ui.horizontal(|ui| {
+            ui.label("Search contents:");
+            let response = ui.text_edit_singleline(&mut self.content_query);
+            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.button("Search").clicked() || entered
+        }).inner;
+        
+        if search_clicked {
+            let query = self.content_query.clone();
+            self.content_matches = Some(self.rt.block_on(async {
+                match tokio::time::timeout(Duration::from_millis(100), self.sync_manager.lock()).await {
+                    Ok(sync_guard) => sync_guard.search_content(&query, FILE_ROW_LIMIT).await.map_err(|e| e.to_string()),
+                    Err(_) => Err("The sync manager is busy; try again in a moment".to_string()),
+                }
+            }));
+        }
+        
+        let mut history_path = None;
+        match &self.content_matches {
+            None => {}
+            Some(Err(error)) => {
+                ui.colored_label(egui::Color32::RED, format!("Search failed: {}", error));
+            }
+            Some(Ok(matches)) if matches.is_empty() => {
+                ui.label("No indexed document contains those words");
+            }
+            Some(Ok(matches)) => {
+                egui::CollapsingHeader::new(format!("{} documents found", format::count(matches.len() as u64)))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_source("content_matches").max_height(200.0).show(ui, |ui| {
+                            for found in matches {
+                                if ui.link(&found.path).on_hover_text("Show what the client did to this file").clicked() {
+                                    history_path = Some(found.path.clone());
+                                }
+                                ui.weak(&found.snippet);
+                            }
+                        });
+                    });
+            }
+        }
+        if let Some(path) = history_path {
+            self.open_file_history(path);
+        }
+    }
+    
+    fn open_file_history(&mut self, path: String) {
+        let history = self.rt.block_on(async {
+            match tokio::time::timeout(Duration::from_millis(100), self.sync_manager.lock()).await {
+                Ok(sync_guard) => sync_guard.get_file_history(&path).await.map_err(|e| e.to_string()),
+                Err(_) => Err("The sync manager is busy; try again in a moment".to_string()),
+            }
+        });
+        self.file_history = Some((path, history));
+    }
+    
+    /// Offers the crash reports saved since the user last looked, for attaching
+    /// to a bug report. Closing the window stops offering them.
+    fn show_crash_report_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut dismissed = false;
+        
+        egui::Window::new("OneDrive Closed Unexpectedly")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label("A crash report was saved. It has not been sent anywhere; attach it to a bug report if you file one.");
+                ui.add_space(5.0);
+                for report in &self.crash_reports {
+                    ui.monospace(report.display().to_string());
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Open Folder").clicked() {
+                        let _ = open::that(crash::crash_dir());
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        dismissed = true;
+                    }
+                });
+            });
+        
+        if !open || dismissed {
+            if let Err(e) = crash::mark_reviewed() {
+                warn!("Failed to mark crash reports as seen: {}", e);
+            }
+            self.crash_reports.clear();
+        }
+    }
+    
+    /// Every logged upload, download, conflict and failure of one file, so
+    /// "what happened to this file" has a single answer.
+    fn show_file_history_window(&mut self, ctx: &egui::Context) {
+        let Some((path, history)) = &self.file_history else {
+            return;
+        };
+        let mut open = true;
+        
+        egui::Window::new(format!("History of {}", path))
+            .open(&mut open)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| match history {
+                Err(error) => {
+                    ui.colored_label(egui::Color32::RED, format!("Could not load the history: {}", error));
+                }
+                Ok(entries) if entries.is_empty() => {
+                    ui.label("Nothing has been logged for this file yet.");
+                }
+                Ok(entries) => {
+                    ui.label(format!("{} entries, oldest first:", format::count(entries.len() as u64)));
+                    ui.separator();
+                    
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for entry in entries {
+                            ui.horizontal(|ui| {
+                                ui.label(format::timestamp(entry.timestamp));
+                                ui.colored_label(status_color(&entry.status), entry.status.to_uppercase());
+                                ui.label(&entry.action);
+                                if let Some(ref run_id) = entry.run_id {
+                                    ui.weak(format!("run {}", run_id));
+                                }
+                            });
+                            if let Some(ref error) = entry.error {
+                                ui.weak(format!("  {}", error));
+                            }
+                        }
+                    });
+                }
+            });
+        
+        if !open {
+            self.file_history = None;
+        }
+    }
+    
+    fn show_logs_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Sync Logs");
+        
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.logs_view, LogsView::History, "History");
+            ui.selectable_value(&mut self.logs_view, LogsView::Runs, "Runs");
+            ui.selectable_value(&mut self.logs_view, LogsView::Queue, "Queue");
+            ui.selectable_value(&mut self.logs_view, LogsView::Live, "Live");
+        });
+        
+        ui.separator();
+        
+        match self.logs_view {
+            LogsView::History => self.show_sync_history(ui),
+            LogsView::Runs => self.show_sync_runs(ui),
+            LogsView::Queue => self.show_sync_queue(ui),
+            LogsView::Live => self.show_live_log(ui),
+        }
+    }
+    
+    /// What each recent sync run did, with totals; kept in the database, so
+    /// the numbers survive restarts.
+    fn show_sync_runs(&mut self, ui: &mut egui::Ui) {
+        let refresh_clicked = ui.button("Refresh").clicked();
+        let should_refresh = refresh_clicked
+            || self.last_runs_refresh.is_none_or(|refreshed| refreshed.elapsed() > Duration::from_secs(5));
+        
+        if should_refresh {
+            match self.rt.block_on(async {
+                if let Ok(sync_guard) = tokio::time::timeout(
+                    Duration::from_millis(100),
+                    self.sync_manager.lock()
+                ).await {
+                    sync_guard.get_sync_runs(RUN_ROW_LIMIT).await
+                } else {
+                    Err(anyhow::anyhow!("Sync manager busy"))
+                }
+            }) {
+                Ok(runs) => {
+                    self.sync_runs_cache = runs;
+                    self.last_runs_refresh = Some(std::time::Instant::now());
+                }
+                Err(_) => ui.ctx().request_repaint_after(Duration::from_millis(250)),
+            }
+        }
+        
+        let runs = &self.sync_runs_cache;
+        if runs.is_empty() {
+            ui.label("No sync runs recorded yet");
+            return;
+        }
+        
+        let failed = runs.iter().filter(|run| run.outcome.as_deref() == Some("failed")).count();
+        let sum = |field: fn(&SyncRun) -> u64| runs.iter().map(field).sum::<u64>();
+        ui.label(format!(
+            "Last {} runs: {} failed · {} uploaded · {} downloaded · {} deleted · {} transferred",
+            format::count(runs.len() as u64),
+            format::count(failed as u64),
+            format::count(sum(|run| run.uploaded)),
+            format::count(sum(|run| run.downloaded)),
+            format::count(sum(|run| run.deleted)),
+            format::size(sum(|run| run.bytes_transferred))
+        ));
+        ui.separator();
+        
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("sync_runs").striped(true).show(ui, |ui| {
+                ui.strong("Started");
+                ui.strong("Took");
+                ui.strong("Outcome");
+                ui.strong("Up");
+                ui.strong("Down");
+                ui.strong("Deleted");
+                ui.strong("Skipped");
+                ui.strong("Transferred");
+                ui.strong("Errors");
+                ui.strong("Run");
+                ui.end_row();
+                
+                for run in runs {
+                    ui.label(format::timestamp(run.started));
+                    match run.finished {
+                        Some(finished) => ui.label(format::duration(Duration::from_secs(finished.saturating_sub(run.started)))),
+                        None => ui.weak("—"),
+                    };
+                    match run.outcome.as_deref() {
+                        Some(outcome) => ui.colored_label(status_color(outcome), outcome),
+                        None if self.sync_status.run_id.as_deref() == Some(run.run_id.as_str()) && self.sync_status.is_syncing => {
+                            ui.label("running")
+                        }
+                        None => ui.colored_label(egui::Color32::YELLOW, "interrupted"),
+                    };
+                    ui.label(format::count(run.uploaded));
+                    ui.label(format::count(run.downloaded));
+                    ui.label(format::count(run.deleted));
+                    ui.label(format::count(run.skipped));
+                    ui.label(format::size(run.bytes_transferred));
+                    if run.errors > 0 {
+                        ui.colored_label(egui::Color32::RED, format::count(run.errors));
+                    } else {
+                        ui.weak("0");
+                    }
+                    ui.weak(&run.run_id);
+                    ui.end_row();
+                }
+            });
+        });
+    }
+    
+    /// What the current sync still has to do, or how the last one ended. Read
+    /// through the sync control, so it stays current while a sync runs.
+    fn show_sync_queue(&mut self, ui: &mut egui::Ui) {
+        let refresh_clicked = ui.button("Refresh").clicked();
+        let should_refresh = refresh_clicked
+            || self.last_queue_refresh.is_none_or(|refreshed| refreshed.elapsed() > Duration::from_secs(2));
+        
+        if should_refresh {
+            let result = self.rt.block_on(async {
+                let counts = self.sync_control.get_queue_counts().await?;
+                let actions = self.sync_control.get_queue(QUEUE_ROW_LIMIT).await?;
+                anyhow::Ok((counts, actions))
+            });
+            match result {
+                Ok((counts, actions)) => {
+                    self.queue_counts = counts;
+                    self.queue_cache = actions;
+                }
+                Err(e) => warn!("Failed to read the sync queue: {}", e),
+            }
+            self.last_queue_refresh = Some(std::time::Instant::now());
+        }
+        if self.sync_status.is_syncing {
+            ui.ctx().request_repaint_after(Duration::from_secs(2));
+        }
+        
+        if self.queue_cache.is_empty() {
+            ui.label("Nothing queued");
+            return;
+        }
+        
+        let count = |status: &str| self.queue_counts.get(status).copied().unwrap_or(0);
+        ui.label(format!(
+            "{} queued · {} in progress · {} done · {} failed",
+            format::count(count("queued")),
+            format::count(count("in_progress")),
+            format::count(count("done")),
+            format::count(count("failed"))
+        ));
+        ui.weak("Queued actions survive pausing, quitting and crashes; the next sync picks them up first.");
+        ui.separator();
+        
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("sync_queue").striped(true).show(ui, |ui| {
+                ui.strong("Action");
+                ui.strong("Status");
+                ui.strong("Updated");
+                ui.end_row();
+                
+                for action in &self.queue_cache {
+                    ui.label(&action.description);
+                    let status = ui.colored_label(queue_status_color(&action.status), action.status.replace('_', " "));
+                    if let Some(error) = &action.error {
+                        status.on_hover_text(error);
+                    }
+                    ui.weak(format::timestamp(action.updated));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+    
+    fn clear_logs(&mut self) {
+        self.confirm_clear_logs = false;
+        let result = self.rt.block_on(async {
+            match tokio::time::timeout(Duration::from_millis(100), self.sync_manager.lock()).await {
+                Ok(sync_guard) => sync_guard.clear_logs().await.map_err(|e| e.to_string()),
+                Err(_) => Err("the sync manager is busy; try again in a moment".to_string()),
+            }
+        });
+        self.status_message = match result {
+            Ok(()) => {
+                self.last_runs_refresh = None;
+                "Sync logs cleared".to_string()
+            }
+            Err(e) => format!("Could not clear the sync logs: {}", e),
+        };
+    }
+    
+    fn show_sync_history(&mut self, ui: &mut egui::Ui) {
+        let mut refresh_clicked = false;
+        ui.horizontal(|ui| {
+            refresh_clicked = ui.button("Refresh Logs").clicked();
+            if self.confirm_clear_logs {
+                ui.label("Delete the whole sync history?");
+                if ui.button("Delete").clicked() {
+                    self.clear_logs();
+                    refresh_clicked = true;
+                }
+                if ui.button("Keep").clicked() {
+                    self.confirm_clear_logs = false;
+                }
+            } else if ui.button("Clear Logs").on_hover_text("Delete every sync log entry and run record").clicked() {
+                self.confirm_clear_logs = true;
+            }
+        });
+        
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.logs_filter)
+                .on_hover_text("Matches the action, path, status, message or run ID; letters may be spread out");
+        });
+        
+        // Refresh cache every 5 seconds, on manual refresh or as the filter changes
+        let should_refresh = refresh_clicked
+            || self.history_cache_filter.as_deref() != Some(self.logs_filter.as_str())
+            || self.last_history_refresh.elapsed() > Duration::from_secs(5);
+        
+        if should_refresh {
+            debug!("Refreshing sync logs");
+            // Try to refresh cache from sync manager
+            let filter = self.logs_filter.clone();
+            match self.rt.block_on(async {
+                if let Ok(sync_guard) = tokio::time::timeout(
+                    Duration::from_millis(100),
+                    self.sync_manager.lock()
+                ).await {
+                    sync_guard.get_sync_history(LOG_ROW_LIMIT, &filter).await
+                } else {
+                    Err(anyhow::anyhow!("Sync manager busy"))
                 }
             }) {
-                self.sync_history_cache = history;
-                self.last_history_refresh = std::time::Instant::now();
+                Ok(history) => {
+                    self.sync_history_cache = history;
+                    self.history_cache_filter = Some(filter);
+                    self.last_history_refresh = std::time::Instant::now();
+                }
+                Err(_) => ui.ctx().request_repaint_after(Duration::from_millis(250)),
             }
         }
         
@@ -519,7 +2638,9 @@ impl OneDriveApp {
         
         // Show cached sync history
         egui::ScrollArea::vertical().show(ui, |ui| {
-            if self.sync_history_cache.is_empty() {
+            if self.sync_history_cache.is_empty() && !self.logs_filter.trim().is_empty() {
+                ui.label("No log entries match the filter");
+            } else if self.sync_history_cache.is_empty() {
                 ui.label("No sync history yet");
                 ui.label("Start a sync to see log entries here");
                 
@@ -542,25 +2663,25 @@ impl OneDriveApp {
                 ui.separator();
                 
                 for entry in &self.sync_history_cache {
-                    let timestamp = std::time::UNIX_EPOCH + Duration::from_secs(entry.timestamp);
-                    let datetime = chrono::DateTime::<chrono::Utc>::from(timestamp);
-                    let formatted_time = datetime.format("%Y-%m-%d %H:%M:%S UTC");
-                    
-                    let status_color = match entry.status.as_str() {
-                        "success" => egui::Color32::GREEN,
-                        "failed" => egui::Color32::RED,
-                        _ => egui::Color32::GRAY,
-                    };
-                    
                     ui.horizontal(|ui| {
-                        ui.label(format!("{}", formatted_time));
-                        ui.colored_label(status_color, &entry.status.to_uppercase());
+                        ui.label(format::timestamp(entry.timestamp));
+                        ui.colored_label(status_color(&entry.status), &entry.status.to_uppercase());
                         ui.label(&entry.action);
                         ui.label(&entry.file_path);
+                        if let Some(ref mime_type) = entry.mime_type {
+                            ui.weak(content_kind(mime_type)).on_hover_text(mime_type);
+                        }
+                        if let Some(ref run_id) = entry.run_id {
+                            ui.weak(format!("run {}", run_id));
+                        }
                     });
                     
                     if let Some(ref error) = entry.error {
-                        ui.colored_label(egui::Color32::RED, format!("  Error: {}", error));
+                        match entry.status.as_str() {
+                            "conflict" => ui.colored_label(egui::Color32::YELLOW, format!("  {}", error)),
+                            "success" => ui.label(format!("  {}", error)),
+                            _ => ui.colored_label(egui::Color32::RED, format!("  Error: {}", error)),
+                        };
                     }
                     
                     ui.separator();
@@ -573,6 +2694,67 @@ impl OneDriveApp {
         });
     }
     
+    /// What the client is logging right now, without opening a terminal.
+    fn show_live_log(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Show:");
+            ui.radio_value(&mut self.live_log_level, Level::ERROR, "Errors");
+            ui.radio_value(&mut self.live_log_level, Level::WARN, "Warnings and errors");
+            ui.radio_value(&mut self.live_log_level, Level::INFO, "Everything");
+        });
+        
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.live_log_paused, "Pause")
+                .on_hover_text("Freeze the view; new lines are still collected");
+            ui.checkbox(&mut self.live_log_follow, "Scroll to newest")
+                .on_hover_text("Keep the newest line in view as it arrives");
+            if ui.button("Clear").clicked() {
+                live_log::clear();
+                self.live_log_lines.clear();
+            }
+        });
+        
+        if !self.live_log_paused {
+            self.live_log_lines = live_log::recent();
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+        }
+        
+        ui.separator();
+        
+        // Level ordering runs from ERROR (least verbose) to TRACE
+        let lines: Vec<&LogLine> = self.live_log_lines
+            .iter()
+            .filter(|line| line.level <= self.live_log_level)
+            .collect();
+        if lines.is_empty() {
+            ui.label("Nothing logged yet");
+            return;
+        }
+        
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .stick_to_bottom(self.live_log_follow)
+            .show_rows(ui, row_height, lines.len(), |ui, rows| {
+                for line in &lines[rows] {
+                    let color = match line.level {
+                        Level::ERROR => egui::Color32::RED,
+                        Level::WARN => egui::Color32::YELLOW,
+                        _ => ui.visuals().text_color(),
+                    };
+                    let text = format!(
+                        "{} {:<5} {}: {}",
+                        line.time.format("%H:%M:%S"),
+                        line.level,
+                        line.target,
+                        // One row per line keeps the rows evenly spaced
+                        line.message.replace('\n', " ")
+                    );
+                    ui.add(egui::Label::new(egui::RichText::new(text).monospace().color(color)).wrap(false));
+                }
+            });
+    }
+    
     fn authenticate(&mut self, ctx: &egui::Context) {
         info!("Starting authentication");
         self.status_message = "Opening browser for authentication...".to_string();
@@ -605,13 +2787,30 @@ impl OneDriveApp {
             self.auth.lock().await
         });
         if auth_guard.logout().is_ok() {
+            drop(auth_guard);
+            self.remove_folder_integration();
             self.user_info = None;
             self.drive_info = None;
+            self.browse_cache().clear();
+            self.browse_fresh.clear();
+            self.browse_path.clear();
+            self.browse_selected.clear();
             self.status_message = "Signed out successfully".to_string();
             info!("User signed out");
         }
     }
     
+    fn remove_folder_integration(&mut self) {
+        let result = self.rt.block_on(async {
+            let sync_guard = self.sync_manager.lock().await;
+            sync_guard.remove_folder_integration().await
+        });
+        
+        if let Err(e) = result {
+            error!("Failed to remove file manager integration: {}", e);
+        }
+    }
+    
     fn start_manual_sync(&mut self) {
         info!("Starting manual sync from GUI");
         self.status_message = "Starting sync...".to_string();
@@ -632,6 +2831,230 @@ impl OneDriveApp {
         });
     }
     
+    fn toggle_pause(&mut self) {
+        let resume = self.sync_control.is_paused();
+        let result = self.rt.block_on(async {
+            if resume {
+                self.sync_control.resume().await
+            } else {
+                self.sync_control.pause().await
+            }
+        });
+        
+        match result {
+            Ok(_) if resume => {
+                self.status_message = "Syncing resumed".to_string();
+                // Finish the work left over from the paused run straight away
+                if self.user_info.is_some() {
+                    self.start_manual_sync();
+                }
+            }
+            Ok(_) => self.status_message = "Syncing paused; transfers in progress will finish first".to_string(),
+            Err(e) => {
+                error!("Failed to change pause state: {}", e);
+                self.status_message = format!("Failed to change pause state: {}", e);
+            }
+        }
+    }
+    
+    fn start_sync_preview(&mut self) {
+        info!("Previewing sync from GUI");
+        self.status_message = "Working out what would change...".to_string();
+        *self.sync_preview.lock().unwrap() = None;
+        self.show_sync_preview = true;
+        
+        let sync_manager = self.sync_manager.clone();
+        let sync_preview = self.sync_preview.clone();
+        
+        self.rt.spawn(async move {
+            let mut sync_guard = sync_manager.lock().await;
+            let result = sync_guard
+                .preview_sync()
+                .await
+                .map(|actions| actions.iter().map(|action| (action.kind().to_string(), action.summary())).collect())
+                .map_err(|e| e.to_string());
+            *sync_preview.lock().unwrap() = Some(result);
+        });
+    }
+    
+    fn show_sync_preview_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_sync_preview;
+        let mut sync_now = false;
+        
+        egui::Window::new("Preview Changes")
+            .open(&mut open)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                match self.sync_preview.lock().unwrap().as_ref() {
+                    None => {
+                        ui.horizontal(|ui| {
+                            busy_spinner(ui);
+                            ui.label("Scanning local files and OneDrive...");
+                        });
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::RED, format!("Preview failed: {}", error));
+                    }
+                    Some(Ok(actions)) if actions.is_empty() => {
+                        ui.label("Everything is up to date.");
+                    }
+                    Some(Ok(actions)) => {
+                        ui.label(format!("The next sync would make {} changes:", actions.len()));
+                        ui.separator();
+                        
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            for (kind, summary) in actions {
+                                let color = match kind.as_str() {
+                                    "delete" => egui::Color32::RED,
+                                    "conflict" => egui::Color32::YELLOW,
+                                    _ => ui.visuals().text_color(),
+                                };
+                                ui.colored_label(color, summary);
+                            }
+                        });
+                        
+                        ui.separator();
+                        sync_now = ui.button("Sync Now").clicked();
+                    }
+                }
+            });
+        
+        if sync_now && !self.sync_status.is_syncing {
+            self.start_manual_sync();
+            open = false;
+        }
+        self.show_sync_preview = open;
+    }
+    
+    fn start_shared_links_audit(&mut self) {
+        info!("Listing shared links from GUI");
+        *self.shared_links.lock().unwrap() = None;
+        self.show_shared_links = true;
+        
+        let api = OneDriveAPI::new(self.auth.clone()).with_drive(self.config.selected_drive());
+        let shared_links = self.shared_links.clone();
+        self.rt.spawn(async move {
+            let result = sharing::find_shared_links(&api, &CancellationToken::new())
+                .await
+                .map_err(|e| e.to_string());
+            *shared_links.lock().unwrap() = Some(result);
+        });
+    }
+    
+    /// Every sharing link on the user's items, with who it works for and when
+    /// it expires, and a way to revoke it.
+    fn show_shared_links_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_shared_links;
+        let mut revoke = None;
+        
+        egui::Window::new("Shared Links")
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                match self.shared_links.lock().unwrap().as_ref() {
+                    None => {
+                        ui.horizontal(|ui| {
+                            busy_spinner(ui);
+                            ui.label("Checking who each shared item is shared with...");
+                        });
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::RED, format!("Could not list shared links: {}", error));
+                    }
+                    Some(Ok(links)) if links.is_empty() => {
+                        ui.label("You have not shared anything through a link.");
+                    }
+                    Some(Ok(links)) => {
+                        let public = links.iter().filter(|link| link.is_public()).count();
+                        ui.label(format!(
+                            "{} links, {} of them open to anyone who has the link",
+                            format::count(links.len() as u64),
+                            format::count(public as u64)
+                        ));
+                        ui.separator();
+                        
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            egui::Grid::new("shared_links").striped(true).show(ui, |ui| {
+                                ui.strong("Item");
+                                ui.strong("Who");
+                                ui.strong("Access");
+                                ui.strong("Expires");
+                                ui.label("");
+                                ui.end_row();
+                                
+                                for (index, link) in links.iter().enumerate() {
+                                    match &link.web_url {
+                                        Some(url) => ui.hyperlink_to(&link.path, url),
+                                        None => ui.label(&link.path),
+                                    };
+                                    if link.is_public() {
+                                        ui.colored_label(egui::Color32::YELLOW, link.audience());
+                                    } else {
+                                        ui.label(link.audience());
+                                    }
+                                    ui.label(&link.link_type);
+                                    match link.expires.as_deref().and_then(|expires| chrono::DateTime::parse_from_rfc3339(expires).ok()) {
+                                        Some(expires) => ui.label(format::date_time(&expires)),
+                                        None => ui.weak("Never"),
+                                    };
+                                    if ui.button("Revoke").clicked() {
+                                        revoke = Some(index);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                }
+            });
+        
+        if let Some(index) = revoke {
+            self.revoke_shared_link(index);
+        }
+        self.show_shared_links = open;
+    }
+    
+    fn revoke_shared_link(&mut self, index: usize) {
+        let Some(link) = self.shared_links.lock().unwrap().as_ref().and_then(|links| links.as_ref().ok()?.get(index).cloned()) else {
+            return;
+        };
+        
+        let api = OneDriveAPI::new(self.auth.clone()).with_drive(self.config.selected_drive());
+        match self.rt.block_on(api.delete_permission(&link.item_id, &link.permission_id)) {
+            Ok(()) => {
+                info!("Revoked sharing link on {}", link.path);
+                self.status_message = format!("Revoked the link to {}", link.path);
+                if let Some(Ok(links)) = self.shared_links.lock().unwrap().as_mut() {
+                    links.retain(|other| other.permission_id != link.permission_id || other.item_id != link.item_id);
+                }
+            }
+            Err(e) => {
+                error!("Failed to revoke sharing link on {}: {}", link.path, e);
+                self.status_message = format!("Could not revoke the link to {}: {}", link.path, e);
+            }
+        }
+    }
+    
+    fn relink_drive(&mut self) {
+        info!("Re-linking sync folder to the current drive");
+        
+        let result = self.rt.block_on(async {
+            let mut sync_guard = self.sync_manager.lock().await;
+            sync_guard.relink_drive().await
+        });
+        
+        match result {
+            Ok(_) => {
+                self.status_message = "Sync folder re-linked to the new drive".to_string();
+                self.start_manual_sync();
+            }
+            Err(e) => {
+                self.status_message = format!("Failed to re-link drive: {}", e);
+                error!("Failed to re-link drive: {}", e);
+            }
+        }
+    }
+    
     fn update_sync_folder(&mut self) {
         let new_path = std::path::PathBuf::from(&self.new_sync_folder);
         let mut config = (*self.config).clone();
@@ -655,9 +3078,13 @@ impl OneDriveApp {
                 
                 match self.setup_step {
                     SetupStep::Welcome => self.show_welcome_step(ui),
+                    SetupStep::BuiltInSignIn => self.show_builtin_sign_in_step(ui),
                     SetupStep::AzureInstructions => self.show_azure_instructions_step(ui),
                     SetupStep::ClientIdInput => self.show_client_id_input_step(ui),
                     SetupStep::Complete => self.show_complete_step(ui),
+                    SetupStep::RestoreFolders => self.show_restore_folders_step(ui),
+                    SetupStep::RestoreDestination => self.show_restore_destination_step(ui),
+                    SetupStep::Restoring => self.show_restoring_step(ui),
                 }
             });
         });
@@ -667,16 +3094,129 @@ impl OneDriveApp {
         ui.label("Welcome to the OneDrive Ubuntu Client!");
         ui.add_space(20.0);
         
-        ui.label("To get started, you need to create an Azure App Registration.");
-        ui.label("This allows the client to securely connect to your OneDrive account.");
+        ui.label("Choose how this client connects to your OneDrive account.");
         ui.add_space(20.0);
         
-        ui.label("Don't worry - we'll guide you through the process step by step.");
-        ui.add_space(30.0);
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Quick setup (recommended)");
+                ui.label("Sign in with a code using a built-in, publicly shared Microsoft app registration.");
+                ui.colored_label(egui::Color32::GRAY,
+                    "The sign-in page will show that app's name instead of \"OneDrive Ubuntu Client\". \
+                     Your files only travel between this computer and Microsoft. \
+                     Some work or school accounts block shared apps; use your own registration if sign-in is refused.");
+                if ui.button("Use built-in app registration →").clicked() {
+                    self.start_builtin_sign_in();
+                }
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Advanced setup");
+                ui.label("Create your own Azure App Registration. We'll guide you through it step by step.");
+                if ui.button("Use my own app registration →").clicked() {
+                    self.setup_step = SetupStep::AzureInstructions;
+                }
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Restore files only");
+                ui.label("Download chosen folders from OneDrive to this computer, e.g. after reinstalling, \
+                          without setting up continuous sync.");
+                if ui.button("Restore from OneDrive →").clicked() {
+                    self.restore_only = true;
+                    if self.user_info.is_some() {
+                        self.setup_step = SetupStep::RestoreFolders;
+                    } else {
+                        self.start_builtin_sign_in();
+                    }
+                }
+            });
+        });
+    }
+    
+    fn start_builtin_sign_in(&mut self) {
+        if self.start_device_code_sign_in(Some(BUILTIN_CLIENT_ID)) {
+            self.setup_step = SetupStep::BuiltInSignIn;
+        }
+    }
+    
+    /// Asks for a sign-in code, switching to `client_id` first if given, and
+    /// waits for the user to enter it in the background. Returns whether the
+    /// code could be had.
+    fn start_device_code_sign_in(&mut self, client_id: Option<&str>) -> bool {
+        let auth = self.auth.clone();
+        let client_id = client_id.map(str::to_string).unwrap_or_else(|| self.config.client_id.clone());
+        let result = self.rt.block_on(async {
+            let mut auth_guard = auth.lock().await;
+            auth_guard.use_client_id(&client_id)?;
+            auth_guard.request_device_code().await
+        });
+        
+        let device_code = match result {
+            Ok(device_code) => device_code,
+            Err(e) => {
+                error!("Failed to start sign-in: {}", e);
+                self.status_message = format!("Failed to start sign-in: {}", e);
+                return false;
+            }
+        };
+        
+        let _ = open::that(&device_code.verification_uri);
+        self.device_code = Some(device_code.clone());
+        
+        // Wait for the user in the background; the update loop notices the tokens
+        self.rt.spawn(async move {
+            match AuthManager::poll_device_code(&client_id, &device_code).await {
+                Ok(tokens) => {
+                    if let Err(e) = auth.lock().await.store_tokens(tokens) {
+                        error!("Failed to save tokens: {}", e);
+                    }
+                }
+                Err(e) => error!("Device code sign-in failed: {}", e),
+            }
+        });
+        true
+    }
+    
+    fn show_builtin_sign_in_step(&mut self, ui: &mut egui::Ui) {
+        ui.label("Sign in with Microsoft");
+        ui.add_space(20.0);
         
-        if ui.button("Get Started").clicked() {
-            self.setup_step = SetupStep::AzureInstructions;
+        if let Some(device_code) = &self.device_code {
+            ui.label("Open the page below and enter this code:");
+            ui.add_space(10.0);
+            ui.heading(&device_code.user_code);
+            ui.add_space(10.0);
+            if ui.link(&device_code.verification_uri).clicked() {
+                let _ = open::that(&device_code.verification_uri);
+            }
+            if ui.button("📋 Copy Code").clicked() {
+                ui.output_mut(|o| o.copied_text = device_code.user_code.clone());
+            }
+            ui.add_space(20.0);
+            busy_spinner(ui);
+            ui.label("Waiting for you to finish signing in...");
         }
+        
+        ui.add_space(20.0);
+        
+        ui.horizontal(|ui| {
+            if ui.button("← Back").clicked() {
+                self.device_code = None;
+                self.setup_step = SetupStep::Welcome;
+            }
+            if ui.button("Get a new code").clicked() {
+                self.start_builtin_sign_in();
+            }
+        });
     }
     
     fn show_azure_instructions_step(&mut self, ui: &mut egui::Ui) {
@@ -763,7 +3303,7 @@ impl OneDriveApp {
                 self.setup_step = SetupStep::AzureInstructions;
             }
             
-            let is_valid_uuid = self.is_valid_client_id(&self.client_id_input);
+            let is_valid_uuid = is_valid_client_id(&self.client_id_input);
             ui.add_enabled_ui(is_valid_uuid, |ui| {
                 if ui.button("Save Configuration →").clicked() {
                     if self.save_client_id() {
@@ -773,7 +3313,7 @@ impl OneDriveApp {
             });
         });
         
-        if !self.client_id_input.is_empty() && !self.is_valid_client_id(&self.client_id_input) {
+        if !self.client_id_input.is_empty() && !is_valid_client_id(&self.client_id_input) {
             ui.add_space(10.0);
             ui.colored_label(egui::Color32::RED, "Please enter a valid UUID format client ID");
         }
@@ -814,15 +3354,265 @@ impl OneDriveApp {
         }
     }
     
-    fn is_valid_client_id(&self, client_id: &str) -> bool {
-        // Basic UUID format validation
-        client_id.len() == 36 && 
-        client_id.chars().enumerate().all(|(i, c)| {
-            match i {
-                8 | 13 | 18 | 23 => c == '-',
-                _ => c.is_ascii_hexdigit(),
+    fn show_restore_folders_step(&mut self, ui: &mut egui::Ui) {
+        ui.label("Restore: Choose Folders");
+        ui.add_space(20.0);
+        
+        ui.label("Pick the OneDrive folders to download. Nothing is synced, uploaded or deleted.");
+        ui.add_space(10.0);
+        
+        let fetched: Vec<FolderFetch> = self.restore_fetched.lock().unwrap().drain(..).collect();
+        for (path, listing) in fetched {
+            self.restore_fetching.remove(&path);
+            self.restore_subfolders.insert(path, listing);
+        }
+        if !self.restore_fetching.is_empty() {
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+        }
+        
+        ui.group(|ui| {
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                ui.vertical(|ui| {
+                    let mut everything = self.restore_selected.contains("");
+                    if ui.checkbox(&mut everything, "Everything on OneDrive").changed() {
+                        self.toggle_restore_folder("", everything);
+                    }
+                    self.show_restore_subfolders(ui, "", everything);
+                });
+            });
+        });
+        
+        ui.add_space(20.0);
+        
+        ui.horizontal(|ui| {
+            if ui.button("Cancel").clicked() {
+                self.close_restore();
+            }
+            ui.add_enabled_ui(!self.restore_selected.is_empty(), |ui| {
+                if ui.button("Next →").clicked() {
+                    self.setup_step = SetupStep::RestoreDestination;
+                }
+            });
+        });
+    }
+    
+    /// The subfolders of the OneDrive folder `path`, each expandable to its own
+    /// subfolders, which are listed in the background the first time they are
+    /// shown.
+    fn show_restore_subfolders(&mut self, ui: &mut egui::Ui, path: &str, parent_selected: bool) {
+        let Some(subfolders) = self.restore_subfolders.get(path).cloned() else {
+            if self.restore_fetching.insert(path.to_string()) {
+                let api = OneDriveAPI::new(self.auth.clone()).with_drive(self.config.selected_drive());
+                let fetched = self.restore_fetched.clone();
+                let fetch_path = path.to_string();
+                self.rt.spawn(async move {
+                    let listing = api
+                        .list_items(&format!("/{}", fetch_path))
+                        .await
+                        .map(|items| {
+                            let mut folders: Vec<DriveItem> = items.into_iter().filter(|item| item.folder.is_some()).collect();
+                            folders.sort_by_key(|folder| folder.name.to_lowercase());
+                            folders
+                        })
+                        .map_err(|e| e.to_string());
+                    fetched.lock().unwrap().push((fetch_path, listing));
+                });
+                ui.ctx().request_repaint_after(Duration::from_millis(250));
+            }
+            ui.horizontal(|ui| {
+                busy_spinner(ui);
+                ui.label("Listing folder...");
+            });
+            return;
+        };
+        
+        let subfolders = match subfolders {
+            Ok(subfolders) => subfolders,
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, format!("Could not list this folder: {}", e));
+                return;
+            }
+        };
+        
+        for folder in subfolders {
+            let folder_path = if path.is_empty() { folder.name.clone() } else { format!("{}/{}", path, folder.name) };
+            let selected = parent_selected || self.restore_selected.contains(&folder_path);
+            let id = ui.make_persistent_id(("restore_folder", &folder_path));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+                .show_header(ui, |ui| {
+                    let mut checked = selected;
+                    let label = format!("📁 {} ({})", folder.name, format::size(folder.size.unwrap_or(0)));
+                    if ui.add_enabled(!parent_selected, egui::Checkbox::new(&mut checked, label)).changed() {
+                        self.toggle_restore_folder(&folder_path, checked);
+                    }
+                })
+                .body(|ui| self.show_restore_subfolders(ui, &folder_path, selected));
+        }
+    }
+    
+    /// Selecting a folder includes everything in it, so selections inside it go.
+    fn toggle_restore_folder(&mut self, path: &str, selected: bool) {
+        if selected {
+            self.restore_selected.retain(|other| !is_inside_folder(path, other));
+            self.restore_selected.insert(path.to_string());
+        } else {
+            self.restore_selected.remove(path);
+        }
+    }
+    
+    fn show_restore_destination_step(&mut self, ui: &mut egui::Ui) {
+        ui.label("Restore: Choose Where to Save");
+        ui.add_space(20.0);
+        
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Save the files in:");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.restore_destination);
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.restore_destination = path.to_string_lossy().to_string();
+                        }
+                    }
+                });
+                ui.add_space(10.0);
+                if let Some(example) = self.restore_selected.iter().find(|path| !path.is_empty()) {
+                    ui.label(format!(
+                        "Folders keep their place in OneDrive: {} is saved as {}",
+                        example,
+                        PathBuf::from(&self.restore_destination).join(example).display()
+                    ));
+                }
+                ui.label("Files already in that folder are never overwritten.");
+            });
+        });
+        
+        ui.add_space(20.0);
+        
+        ui.horizontal(|ui| {
+            if ui.button("← Back").clicked() {
+                self.setup_step = SetupStep::RestoreFolders;
+            }
+            ui.add_enabled_ui(!self.restore_destination.trim().is_empty(), |ui| {
+                if ui.button("Start Restore →").clicked() {
+                    self.start_restore();
+                }
+            });
+        });
+    }
+    
+    fn start_restore(&mut self) {
+        let api = Arc::new(OneDriveAPI::new(self.auth.clone()).with_drive(self.config.selected_drive()));
+        let folders = self.restore_selected.iter().map(|path| format!("/{}", path)).collect();
+        let destination = PathBuf::from(self.restore_destination.trim());
+        info!("Starting restore of {:?} to {}", self.restore_selected, destination.display());
+        
+        let _guard = self.rt.enter();
+        let (progress, cancel) = restore::start(api, folders, destination);
+        self.restore_progress = Some(progress);
+        self.restore_cancel = Some(cancel);
+        self.setup_step = SetupStep::Restoring;
+    }
+    
+    fn show_restoring_step(&mut self, ui: &mut egui::Ui) {
+        let Some(progress) = self.restore_progress.as_ref().map(|progress| progress.lock().unwrap().clone()) else {
+            self.setup_step = SetupStep::RestoreFolders;
+            return;
+        };
+        
+        if !progress.finished {
+            ui.label("Restoring Files");
+            ui.add_space(20.0);
+            
+            if progress.listing {
+                busy_spinner(ui);
+                ui.label("Finding files on OneDrive...");
+            } else {
+                let fraction = if progress.total_bytes > 0 {
+                    progress.done_bytes as f32 / progress.total_bytes as f32
+                } else {
+                    0.0
+                };
+                ui.add(egui::ProgressBar::new(fraction).text(format!(
+                    "{} of {}",
+                    format::size(progress.done_bytes),
+                    format::size(progress.total_bytes)
+                )));
+                ui.label(format!("{} of {} files", format::count(progress.done_files as u64), format::count(progress.total_files as u64)));
+                ui.weak(&progress.current_file);
+            }
+            
+            ui.add_space(20.0);
+            if ui.button("Cancel").clicked() {
+                if let Some(cancel) = &self.restore_cancel {
+                    cancel.cancel();
+                }
+            }
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+            return;
+        }
+        
+        ui.label(if progress.cancelled { "Restore Cancelled" } else { "🎉 Restore Complete" });
+        ui.add_space(20.0);
+        
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label(format!("Downloaded: {} files", format::count(progress.downloaded_files as u64)));
+                if progress.existing_files > 0 {
+                    ui.label(format!("Already there: {} files", format::count(progress.existing_files as u64)));
+                }
+                if progress.cancelled {
+                    ui.label(format!("Not restored: {} files", format::count((progress.total_files - progress.done_files) as u64)));
+                }
+                if !progress.kept_files.is_empty() {
+                    egui::CollapsingHeader::new(format!(
+                        "Left untouched because a different file was already there: {}",
+                        progress.kept_files.len()
+                    ))
+                    .show(ui, |ui| {
+                        for path in &progress.kept_files {
+                            ui.label(path);
+                        }
+                    });
+                }
+                if !progress.errors.is_empty() {
+                    egui::CollapsingHeader::new(
+                        egui::RichText::new(format!("Failed: {}", progress.errors.len())).color(egui::Color32::RED),
+                    )
+                    .show(ui, |ui| {
+                        for error in &progress.errors {
+                            ui.label(error);
+                        }
+                    });
+                }
+            });
+        });
+        
+        ui.add_space(20.0);
+        
+        ui.horizontal(|ui| {
+            if ui.button("Open Folder").clicked() {
+                let _ = open::that(self.restore_destination.trim());
+            }
+            if ui.button("Done").clicked() {
+                self.close_restore();
             }
-        })
+        });
+    }
+    
+    /// Leaves the restore flow for the main window. After a restore-only
+    /// sign-in nothing syncs until the user starts it.
+    fn close_restore(&mut self) {
+        if let Some(cancel) = self.restore_cancel.take() {
+            cancel.cancel();
+        }
+        self.restore_progress = None;
+        self.restore_subfolders.clear();
+        self.restore_fetching.clear();
+        self.restore_fetched.lock().unwrap().clear();
+        self.restore_selected.clear();
+        self.show_setup_wizard = false;
+        self.setup_step = SetupStep::Welcome;
     }
     
     fn save_client_id(&mut self) -> bool {
@@ -850,7 +3640,7 @@ debug_logging = {}
             self.config.sync_interval_minutes,
             self.config.auto_start,
             self.config.minimize_to_tray,
-            self.config.notifications,
+            self.config.notifications.any(),
             self.config.debug_logging
         );
         
@@ -866,3 +3656,95 @@ debug_logging = {}
         }
     }
 }
+
+/// Short human-readable kind for a MIME type, e.g. "Image" for `image/png`.
+fn content_kind(mime_type: &str) -> &'static str {
+    let (category, subtype) = mime_type.split_once('/').unwrap_or((mime_type, ""));
+    match category {
+        "image" => "Image",
+        "video" => "Video",
+        "audio" => "Audio",
+        "text" => "Text",
+        _ if subtype == "pdf" => "PDF",
+        _ if subtype.contains("zip") || subtype.contains("compressed") || subtype.contains("tar") => "Archive",
+        _ if subtype.contains("officedocument") || subtype.contains("opendocument") || subtype == "msword" => "Document",
+        _ => "File",
+    }
+}
+
+/// Bytes as MB, or GB from 1 GB on.
+/// Color of a `sync_log` status in the Logs tab and file histories.
+fn status_color(status: &str) -> egui::Color32 {
+    match status {
+        "success" => egui::Color32::GREEN,
+        "failed" => egui::Color32::RED,
+        "conflict" => egui::Color32::YELLOW,
+        _ => egui::Color32::GRAY,
+    }
+}
+
+fn queue_status_color(status: &str) -> egui::Color32 {
+    match status {
+        "done" => egui::Color32::GREEN,
+        "failed" => egui::Color32::RED,
+        "in_progress" => egui::Color32::LIGHT_BLUE,
+        _ => egui::Color32::GRAY,
+    }
+}
+
+/// Path of `name` in the OneDrive folder `folder`, relative to the drive root.
+fn remote_child_path(folder: &str, name: &str) -> String {
+    if folder.is_empty() { name.to_string() } else { format!("{}/{}", folder, name) }
+}
+
+/// Whether the OneDrive path `path` is inside `folder` ("" is the root).
+fn is_inside_folder(folder: &str, path: &str) -> bool {
+    folder.is_empty() || path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// A drive as the drive picker lists it, e.g. "Documents (documentLibrary)".
+fn drive_label(drive: &DriveInfo) -> String {
+    format!("{} ({})", drive.name.as_deref().unwrap_or(&drive.id), drive.drive_type)
+}
+
+/// A spinner, left out when motion is reduced (see `apply_appearance`).
+fn busy_spinner(ui: &mut egui::Ui) {
+    if ui.style().animation_time > 0.0 {
+        ui.spinner();
+    }
+}
+
+/// White on black with strong outlines and a yellow selection.
+fn high_contrast_visuals() -> egui::Visuals {
+    let mut visuals = egui::Visuals::dark();
+    visuals.override_text_color = Some(egui::Color32::WHITE);
+    visuals.panel_fill = egui::Color32::BLACK;
+    visuals.window_fill = egui::Color32::BLACK;
+    visuals.extreme_bg_color = egui::Color32::BLACK;
+    visuals.faint_bg_color = egui::Color32::from_gray(35);
+    visuals.window_stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
+    visuals.hyperlink_color = egui::Color32::from_rgb(0x80, 0xd8, 0xff);
+    visuals.selection.bg_fill = egui::Color32::from_rgb(0xff, 0xd7, 0x00);
+    visuals.selection.stroke = egui::Stroke::new(2.0, egui::Color32::BLACK);
+
+    let widgets = &mut visuals.widgets;
+    for (state, fill) in [
+        (&mut widgets.noninteractive, egui::Color32::BLACK),
+        (&mut widgets.inactive, egui::Color32::BLACK),
+        (&mut widgets.hovered, egui::Color32::from_gray(60)),
+        (&mut widgets.active, egui::Color32::from_gray(90)),
+        (&mut widgets.open, egui::Color32::from_gray(60)),
+    ] {
+        state.bg_fill = fill;
+        state.weak_bg_fill = fill;
+        state.bg_stroke = egui::Stroke::new(1.5, egui::Color32::WHITE);
+        state.fg_stroke = egui::Stroke::new(1.5, egui::Color32::WHITE);
+    }
+    visuals
+}
+
+/// Marks a control whose setting the administrator locked in
+/// `SYSTEM_CONFIG_FILE`; the control still shows the value in effect.
+fn show_locked_note(ui: &mut egui::Ui) {
+    ui.weak("🔒 Set by your administrator");
+}
@@ -0,0 +1,85 @@
+//! Text extraction for the optional full-text index of synced documents
+//! (`Config::index_content`). The index itself is the `content_index` FTS5
+//! table in the sync database, so searching works offline.
+
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+/// Extensions read as plain text
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "rst", "csv", "log", "org", "tex"];
+
+/// Text beyond this many bytes of a document is not indexed
+pub const MAX_INDEXED_TEXT: usize = 1024 * 1024;
+
+/// A file whose indexed text matches a search, with the matching passage.
+#[derive(Debug, Clone)]
+pub struct ContentMatch {
+    pub path: String,
+    /// The words around the match, with the matched terms in [brackets]
+    pub snippet: String,
+}
+
+/// Whether the indexer can read text out of `path`.
+pub fn is_indexable(path: &str) -> bool {
+    match extension(path) {
+        Some(extension) => extension == "pdf" || TEXT_EXTENSIONS.contains(&extension.as_str()),
+        None => false,
+    }
+}
+
+/// The text of the document at `path`, cut to `MAX_INDEXED_TEXT`. PDFs go
+/// through `pdftotext` (poppler-utils), which has to be installed.
+pub fn extract_text(path: &Path) -> Result<String> {
+    let mut text = match extension(&path.to_string_lossy()).as_deref() {
+        Some("pdf") => {
+            let output = Command::new("pdftotext")
+                .args(["-q", "-enc", "UTF-8"])
+                .arg(path)
+                .arg("-")
+                .output()
+                .map_err(|e| anyhow!("Could not run pdftotext (install poppler-utils): {}", e))?;
+            if !output.status.success() {
+                return Err(anyhow!("pdftotext could not read {}", path.display()));
+            }
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        _ => {
+            // Only as much as is indexed, so a huge log never lands in memory whole
+            let mut bytes = Vec::new();
+            File::open(path)?.take(MAX_INDEXED_TEXT as u64).read_to_end(&mut bytes)?;
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+    };
+
+    if text.len() > MAX_INDEXED_TEXT {
+        let mut end = MAX_INDEXED_TEXT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    Ok(text)
+}
+
+/// An FTS5 query matching documents that contain every word the user typed,
+/// the last one as a prefix so results show up while typing. Each word is
+/// quoted, so punctuation is never read as query syntax.
+pub fn match_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Most lines kept for the Logs tab; older ones are dropped first
+const CAPACITY: usize = 2000;
+
+/// Recent log output of this process, oldest first
+static LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+
+/// One formatted tracing event.
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Subscriber layer that keeps the latest `CAPACITY` events in memory so the
+/// GUI can show them as they happen.
+pub struct LiveLogLayer;
+
+impl<S: Subscriber> Layer<S> for LiveLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let line = LogLine {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+        };
+
+        let mut lines = LINES.lock().unwrap();
+        if lines.len() == CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+/// The buffered log lines, oldest first.
+pub fn recent() -> Vec<LogLine> {
+    LINES.lock().unwrap().iter().cloned().collect()
+}
+
+/// The buffered log lines, or `None` while another thread holds them, as
+/// when it panicked halfway through logging.
+pub fn try_recent() -> Option<Vec<LogLine>> {
+    Some(LINES.try_lock().ok()?.iter().cloned().collect())
+}
+
+/// Forgets the buffered lines, e.g. before watching a new sync.
+pub fn clear() {
+    LINES.lock().unwrap().clear();
+}
+
+/// Formats the `message` field followed by any other fields as `name=value`,
+/// like the terminal output does.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
@@ -2,30 +2,111 @@ use anyhow::Result;
 use eframe::egui;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{info, error};
+use tracing::{info, error, warn};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
 
 mod config;
 mod auth;
+mod bench;
+mod browse;
+mod budget;
+mod bulk;
+mod cli;
+mod clock;
+mod crash;
 mod api;
+mod desktop;
+mod doctor;
+mod filesystem;
+mod filters;
+mod format;
+mod index;
+mod live_log;
+mod mirror;
+#[cfg(feature = "fuse")]
+mod mount;
+mod notifications;
+mod pacing;
+mod processes;
+mod push;
+mod quickxor;
+mod recovery;
+mod remote;
+mod report;
+mod restore;
+mod schedule;
+mod sharing;
+mod shutdown;
+mod store;
 mod sync;
+mod throughput;
 mod tray;
+mod verify;
+mod webhooks;
 mod gui;
+#[cfg(test)]
+mod tests;
 
 use config::Config;
 use auth::AuthManager;
 use api::OneDriveAPI;
 use sync::SyncManager;
 use gui::OneDriveApp;
+use notifications::Notifier;
 use tray::TrayManager;
 
+/// Serves the runtime's tasks to `tokio-console` when built with the
+/// `tokio-console` feature (and `RUSTFLAGS="--cfg tokio_unstable"`).
+#[cfg(feature = "tokio-console")]
+fn console_layer<S>() -> Option<impl tracing_subscriber::Layer<S>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    Some(console_subscriber::spawn())
+}
+
+#[cfg(not(feature = "tokio-console"))]
+fn console_layer() -> Option<tracing_subscriber::layer::Identity> {
+    None
+}
+
 fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
+    let args: Vec<String> = std::env::args().collect();
+    let cli_command = args.get(1).filter(|arg| cli::COMMANDS.contains(&arg.as_str()));
+
+    // Initialize logging (quiet for CLI commands so their output stays readable),
+    // keeping recent lines in memory for the Logs tab. RUST_LOG overrides the
+    // level, e.g. RUST_LOG=onedrive_ubuntu=debug for every Graph request, and
+    // ONEDRIVE_LOG_SPANS=1 logs how long each sync phase, transfer and request
+    // took when it ends.
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(if cli_command.is_some() { "warn" } else { "info" }));
+    let span_events = if std::env::var_os("ONEDRIVE_LOG_SPANS").is_some() { FmtSpan::CLOSE } else { FmtSpan::NONE };
+    tracing_subscriber::registry()
+        .with(console_layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_span_events(span_events)
+                .and_then(live_log::LiveLogLayer)
+                .with_filter(filter),
+        )
         .init();
+    crash::install_panic_hook();
 
     info!("Starting OneDrive Ubuntu Client v1.0.0");
 
+    // Remote file commands run alongside a running client and skip the instance lock
+    if let Some(command) = cli_command {
+        if let Err(e) = cli::run(command, &args[2..]) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Check for single instance
     if !check_single_instance()? {
         info!("Another instance is already running");
@@ -33,8 +114,6 @@ fn main() -> Result<()> {
     }
 
     // Check if we should start async or sync mode
-    let args: Vec<String> = std::env::args().collect();
-    
     if args.len() > 1 {
         match args[1].as_str() {
             "--tray-only" => {
@@ -55,6 +134,9 @@ fn main() -> Result<()> {
                 println!("  onedrive-ubuntu --tray-only        # Run in system tray only");
                 println!("  onedrive-ubuntu --setup-autostart  # Setup autostart");
                 println!("  onedrive-ubuntu --help             # Show this help");
+                println!();
+                println!("Remote file commands:");
+                cli::print_usage();
                 Ok(())
             }
             _ => {
@@ -72,16 +154,32 @@ fn main() -> Result<()> {
 async fn run_tray_mode() -> Result<()> {
     // Initialize configuration
     let config = Arc::new(Config::new()?);
+    format::set_size_units(config.size_units);
     info!("Configuration loaded");
 
     // Initialize authentication
     let auth = Arc::new(Mutex::new(AuthManager::new(config.clone())?));
     
     // Initialize OneDrive API client
-    let api = Arc::new(OneDriveAPI::new(auth.clone()));
+    let api = Arc::new(
+        OneDriveAPI::new(auth.clone())
+            .with_upload_chunk_size(config.upload_chunk_size_kib * 1024)
+            .with_call_upload_limit(config.call_upload_limit())
+            .with_drive(config.selected_drive()),
+    );
     
     // Initialize sync manager
-    let sync_manager = Arc::new(Mutex::new(SyncManager::new(config.clone(), api.clone())?));
+    let sync_manager = SyncManager::new(config.clone(), api.clone())?;
+    shutdown::register(sync_manager.control());
+    let sync_manager = Arc::new(Mutex::new(sync_manager));
+
+    // The GUI shows these in a window instead
+    if let Some(report) = crash::unreviewed_reports().first() {
+        Notifier::new(&config).notify_crash_report(&report.display().to_string());
+        if let Err(e) = crash::mark_reviewed() {
+            warn!("Failed to mark crash reports as seen: {}", e);
+        }
+    }
 
     info!("Starting in tray-only mode");
     let tray = TrayManager::new(config.clone(), auth.clone(), sync_manager.clone())?;
@@ -93,16 +191,24 @@ async fn run_tray_mode() -> Result<()> {
 fn run_gui_mode() -> Result<()> {
     // Initialize configuration
     let config = Arc::new(Config::new()?);
+    format::set_size_units(config.size_units);
     info!("Configuration loaded");
 
     // Initialize authentication
     let auth = Arc::new(Mutex::new(AuthManager::new(config.clone())?));
     
     // Initialize OneDrive API client
-    let api = Arc::new(OneDriveAPI::new(auth.clone()));
+    let api = Arc::new(
+        OneDriveAPI::new(auth.clone())
+            .with_upload_chunk_size(config.upload_chunk_size_kib * 1024)
+            .with_call_upload_limit(config.call_upload_limit())
+            .with_drive(config.selected_drive()),
+    );
     
     // Initialize sync manager
-    let sync_manager = Arc::new(Mutex::new(SyncManager::new(config.clone(), api.clone())?));
+    let sync_manager = SyncManager::new(config.clone(), api.clone())?;
+    shutdown::register(sync_manager.control());
+    let sync_manager = Arc::new(Mutex::new(sync_manager));
 
     // Start GUI application
     info!("Starting GUI application");
@@ -122,6 +228,7 @@ fn run_gui_mode() -> Result<()> {
         options,
         Box::new(|_cc| Box::new(app)),
     );
+    shutdown::release_instance_lock();
     
     Ok(())
 }
@@ -186,10 +293,7 @@ fn check_single_instance() -> Result<bool> {
     use std::fs;
     use std::process;
     
-    let lock_file = dirs::runtime_dir()
-        .or_else(|| dirs::cache_dir())
-        .unwrap_or_else(|| std::env::temp_dir())
-        .join("onedrive-ubuntu.lock");
+    let lock_file = shutdown::instance_lock_path();
     
     // Try to read existing lock file
     if lock_file.exists() {
@@ -211,12 +315,8 @@ fn check_single_instance() -> Result<bool> {
     let current_pid = process::id();
     fs::write(&lock_file, current_pid.to_string())?;
     
-    // Set up cleanup on exit
-    let lock_file_clone = lock_file.clone();
-    ctrlc::set_handler(move || {
-        let _ = fs::remove_file(&lock_file_clone);
-        std::process::exit(0);
-    })?;
+    // Ctrl+C, SIGTERM and SIGHUP stop the sync cleanly and release the lock
+    ctrlc::set_handler(|| shutdown::exit_on_signal())?;
     
     Ok(true)
 }
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{OptionalExtension, params};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{info, error, warn};
+use walkdir::WalkDir;
+
+use crate::api::OneDriveAPI;
+use crate::config::{Config, UploadMirror};
+use crate::store::SyncStore;
+
+/// Quiet period after the last file event before a watched folder is rescanned,
+/// so a file still being written (e.g. by a scanner) is uploaded only once
+const SETTLE_DELAY: Duration = Duration::from_secs(3);
+
+/// Starts one upload-only watcher per configured mirror. Must be called from
+/// within a Tokio runtime; each watcher runs until the runtime shuts down.
+pub fn start_upload_mirrors(config: Arc<Config>, api: Arc<OneDriveAPI>) {
+    for mirror in config.upload_mirrors.clone() {
+        let config = config.clone();
+        let api = api.clone();
+        tokio::spawn(async move {
+            if let Err(e) = watch_mirror(&config, &api, &mirror).await {
+                error!("Upload mirror for {} stopped: {}", mirror.local_folder.display(), e);
+            }
+        });
+    }
+}
+
+async fn watch_mirror(config: &Config, api: &OneDriveAPI, mirror: &UploadMirror) -> Result<()> {
+    if mirror.local_folder.starts_with(&config.sync_folder) {
+        warn!("Skipping upload mirror inside the sync folder: {}", mirror.local_folder.display());
+        return Ok(());
+    }
+    // The sync folder's downloads would be uploaded again through the mirror
+    if config.sync_folder.starts_with(&mirror.local_folder) {
+        warn!("Skipping upload mirror that contains the sync folder: {}", mirror.local_folder.display());
+        return Ok(());
+    }
+
+    let store = SyncStore::open(&config.db_file)?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if event.is_ok() {
+            let _ = tx.send(());
+        }
+    })?;
+    watcher.watch(&mirror.local_folder, RecursiveMode::Recursive)?;
+    info!("Watching {} -> OneDrive {} (upload only)", mirror.local_folder.display(), mirror.remote_folder);
+
+    // Catch up on anything added while the app was not running
+    mirror_new_files(&store, api, mirror).await;
+
+    while rx.recv().await.is_some() {
+        // Coalesce bursts of events into a single rescan
+        loop {
+            tokio::select! {
+                event = rx.recv() => if event.is_none() { return Ok(()) },
+                _ = sleep(SETTLE_DELAY) => break,
+            }
+        }
+        mirror_new_files(&store, api, mirror).await;
+    }
+
+    Ok(())
+}
+
+/// Uploads every file under the mirror that is new or changed since it was last
+/// uploaded. Nothing is ever downloaded or deleted.
+async fn mirror_new_files(store: &SyncStore, api: &OneDriveAPI, mirror: &UploadMirror) {
+    let remote_folder = mirror.remote_folder.trim_matches('/');
+    let mut uploaded = 0;
+
+    for entry in WalkDir::new(&mirror.local_folder).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let Ok(relative_path) = entry.path().strip_prefix(&mirror.local_folder) else {
+            continue;
+        };
+        let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+        let remote_path = if remote_folder.is_empty() {
+            relative_path
+        } else {
+            format!("{}/{}", remote_folder, relative_path)
+        };
+
+        match upload_if_changed(store, api, entry.path(), &remote_path).await {
+            Ok(true) => uploaded += 1,
+            Ok(false) => {}
+            Err(e) => error!("Failed to mirror {}: {}", entry.path().display(), e),
+        }
+    }
+
+    if uploaded > 0 {
+        info!("Mirrored {} new file(s) from {}", uploaded, mirror.local_folder.display());
+    }
+}
+
+async fn upload_if_changed(store: &SyncStore, api: &OneDriveAPI, local_path: &Path, remote_path: &str) -> Result<bool> {
+    let metadata = tokio::fs::metadata(local_path).await?;
+    let size = metadata.len();
+    let modified = metadata.modified()?.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+    let local_key = local_path.to_string_lossy().to_string();
+
+    let key = local_key.clone();
+    let recorded: Option<(u64, u64)> = store
+        .call(move |db| {
+            Ok(db
+                .query_row(
+                    "SELECT size, modified FROM mirror_uploads WHERE local_path = ?1",
+                    params![key],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?)
+        })
+        .await?;
+
+    if recorded == Some((size, modified)) {
+        return Ok(false);
+    }
+
+    info!("Mirroring upload: {} -> {}", local_path.display(), remote_path);
+    api.upload_file(local_path, remote_path).await?;
+
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+    let remote_path = remote_path.to_string();
+    store
+        .call(move |db| {
+            db.execute(
+                "INSERT OR REPLACE INTO mirror_uploads (local_path, remote_path, size, modified, uploaded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![local_key, remote_path, size, modified, now],
+            )?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(true)
+}
@@ -0,0 +1,378 @@
+//! `onedrive-ubuntu mount PATH`: the whole drive as a read-only FUSE
+//! filesystem, for reading files without syncing them. Folders are listed
+//! from OneDrive as they are opened, and a file is downloaded into a cache
+//! when it is opened, so reads come from disk. The most recently used files
+//! stay cached until the cache outgrows `MAX_CACHE_BYTES`.
+//!
+//! Built only with the `fuse` feature, which needs `fusermount` at run time.
+
+use anyhow::{Result, anyhow};
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry,
+    ReplyOpen, Request, FUSE_ROOT_ID,
+};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tokio::runtime::Handle;
+use tracing::{debug, info, warn};
+
+use crate::api::{DriveItem, OneDriveAPI};
+
+/// How long the kernel, and this filesystem, trust a listing or attributes
+const LISTING_TTL: Duration = Duration::from_secs(30);
+
+/// Cached file contents kept at most; the least recently opened go first
+const MAX_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+const BLOCK_SIZE: u32 = 512;
+
+/// A file or folder the kernel has been told about.
+struct Node {
+    /// Relative to the drive root; empty for the root
+    path: String,
+    parent: u64,
+    item: DriveItem,
+    /// Child inodes as last listed, for folders
+    children: Option<(Instant, Vec<u64>)>,
+}
+
+/// A downloaded file in the cache directory.
+struct CachedFile {
+    path: PathBuf,
+    size: u64,
+    /// The OneDrive version it holds
+    modified: String,
+    last_used: Instant,
+}
+
+struct OneDriveFs {
+    api: Arc<OneDriveAPI>,
+    runtime: Handle,
+    cache_dir: PathBuf,
+    nodes: HashMap<u64, Node>,
+    next_inode: u64,
+    /// Cached contents by OneDrive item ID
+    cache: HashMap<String, CachedFile>,
+    open_files: HashMap<u64, (String, File)>,
+    next_handle: u64,
+    uid: u32,
+    gid: u32,
+}
+
+/// Mounts the drive at `mount_point` and serves it until it is unmounted
+/// (`fusermount -u`). Must be called off the runtime's worker threads, e.g.
+/// from `spawn_blocking`.
+pub fn mount(api: Arc<OneDriveAPI>, runtime: Handle, mount_point: &Path) -> Result<()> {
+    let cache_dir = dirs::cache_dir()
+        .ok_or_else(|| anyhow!("Could not find the cache directory"))?
+        .join("onedrive-ubuntu")
+        .join("mount");
+    std::fs::create_dir_all(&cache_dir)?;
+
+    let root = DriveItem {
+        id: "root".to_string(),
+        name: String::new(),
+        last_modified: String::new(),
+        size: None,
+        file: None,
+        folder: Some(serde_json::Value::Null),
+        download_url: None,
+        file_system_info: None,
+        shared: None,
+        last_modified_by: None,
+        parent_reference: None,
+        deleted: None,
+    };
+    let mut nodes = HashMap::new();
+    nodes.insert(FUSE_ROOT_ID, Node { path: String::new(), parent: FUSE_ROOT_ID, item: root, children: None });
+
+    let filesystem = OneDriveFs {
+        api,
+        runtime,
+        cache_dir,
+        nodes,
+        next_inode: FUSE_ROOT_ID + 1,
+        cache: HashMap::new(),
+        open_files: HashMap::new(),
+        next_handle: 1,
+        // SAFETY: getuid and getgid cannot fail
+        uid: unsafe { libc::getuid() },
+        gid: unsafe { libc::getgid() },
+    };
+
+    info!("Mounting OneDrive read-only at {}", mount_point.display());
+    let options = [
+        MountOption::RO,
+        MountOption::FSName("onedrive".to_string()),
+        MountOption::Subtype("onedrive-ubuntu".to_string()),
+        MountOption::DefaultPermissions,
+        MountOption::NoExec,
+    ];
+    fuser::mount2(filesystem, mount_point, &options)?;
+    info!("Unmounted {}", mount_point.display());
+    Ok(())
+}
+
+impl OneDriveFs {
+    fn attr(&self, inode: u64, item: &DriveItem) -> FileAttr {
+        let modified = chrono::DateTime::parse_from_rfc3339(&item.last_modified)
+            .map(|time| UNIX_EPOCH + Duration::from_secs(time.timestamp().max(0) as u64))
+            .unwrap_or(UNIX_EPOCH);
+        let is_folder = item.folder.is_some();
+        let size = if is_folder { 0 } else { item.size.unwrap_or(0) };
+        FileAttr {
+            ino: inode,
+            size,
+            blocks: size.div_ceil(BLOCK_SIZE as u64),
+            atime: modified,
+            mtime: modified,
+            ctime: modified,
+            crtime: modified,
+            kind: if is_folder { FileType::Directory } else { FileType::RegularFile },
+            perm: if is_folder { 0o555 } else { 0o444 },
+            nlink: if is_folder { 2 } else { 1 },
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+
+    /// The folder's children, listed again once the last listing is older
+    /// than `LISTING_TTL`. Nodes already handed out keep their inode.
+    fn children(&mut self, inode: u64) -> Result<Vec<u64>, libc::c_int> {
+        let node = self.nodes.get(&inode).ok_or(libc::ENOENT)?;
+        if node.item.folder.is_none() {
+            return Err(libc::ENOTDIR);
+        }
+        if let Some((listed, children)) = &node.children {
+            if listed.elapsed() < LISTING_TTL {
+                return Ok(children.clone());
+            }
+        }
+
+        let folder_path = node.path.clone();
+        let items = self
+            .runtime
+            .block_on(self.api.list_items(&format!("/{}", folder_path)))
+            .map_err(|e| {
+                warn!("Could not list /{}: {}", folder_path, e);
+                libc::EIO
+            })?;
+
+        let existing: HashMap<String, u64> = self
+            .nodes
+            .iter()
+            .filter(|(child, node)| node.parent == inode && **child != inode)
+            .map(|(child, node)| (node.item.name.clone(), *child))
+            .collect();
+        let mut children = Vec::with_capacity(items.len());
+        for item in items {
+            let path = if folder_path.is_empty() { item.name.clone() } else { format!("{}/{}", folder_path, item.name) };
+            let child = match existing.get(&item.name) {
+                Some(&child) => child,
+                None => {
+                    let child = self.next_inode;
+                    self.next_inode += 1;
+                    child
+                }
+            };
+            let children_listed = self.nodes.remove(&child).and_then(|node| node.children);
+            self.nodes.insert(child, Node { path, parent: inode, item, children: children_listed });
+            children.push(child);
+        }
+        // Whatever OneDrive no longer lists is forgotten; the kernel gets
+        // ENOENT for its inode from then on
+        for (_, gone) in existing.into_iter().filter(|(_, child)| !children.contains(child)) {
+            self.nodes.remove(&gone);
+        }
+
+        if let Some(node) = self.nodes.get_mut(&inode) {
+            node.children = Some((Instant::now(), children.clone()));
+        }
+        Ok(children)
+    }
+
+    /// The cached copy of `item`, downloaded first when there is none or it
+    /// is of an older version.
+    fn cached_file(&mut self, item: &DriveItem) -> Result<PathBuf> {
+        if let Some(cached) = self.cache.get_mut(&item.id) {
+            if cached.modified == item.last_modified && cached.path.exists() {
+                cached.last_used = Instant::now();
+                return Ok(cached.path.clone());
+            }
+        }
+
+        let path = self.cache_dir.join(&item.id);
+        // The listing's download link may have expired; without one the API
+        // downloads through the item's content endpoint
+        let mut item = item.clone();
+        item.download_url = None;
+        debug!("Downloading {} into the mount cache", item.name);
+        self.runtime.block_on(self.api.download_file(&item, &path))?;
+
+        self.cache.insert(
+            item.id.clone(),
+            CachedFile {
+                path: path.clone(),
+                size: item.size.unwrap_or(0),
+                modified: item.last_modified.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        self.evict();
+        Ok(path)
+    }
+
+    /// Drops the least recently used files until the cache fits, never one
+    /// that is open.
+    fn evict(&mut self) {
+        let mut total: u64 = self.cache.values().map(|cached| cached.size).sum();
+        if total <= MAX_CACHE_BYTES {
+            return;
+        }
+        let mut by_age: Vec<(Instant, String)> =
+            self.cache.iter().map(|(id, cached)| (cached.last_used, id.clone())).collect();
+        by_age.sort();
+        for (_, id) in by_age {
+            if total <= MAX_CACHE_BYTES {
+                break;
+            }
+            if self.open_files.values().any(|(open_id, _)| *open_id == id) {
+                continue;
+            }
+            if let Some(cached) = self.cache.remove(&id) {
+                if let Err(e) = std::fs::remove_file(&cached.path) {
+                    warn!("Failed to remove {} from the mount cache: {}", cached.path.display(), e);
+                }
+                total -= cached.size;
+            }
+        }
+    }
+}
+
+impl Filesystem for OneDriveFs {
+    fn destroy(&mut self) {
+        // A later mount starts with an empty cache index, so the files go too
+        for cached in self.cache.values() {
+            let _ = std::fs::remove_file(&cached.path);
+        }
+    }
+
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let children = match self.children(parent) {
+            Ok(children) => children,
+            Err(errno) => return reply.error(errno),
+        };
+        let name = name.to_string_lossy();
+        let found = children
+            .into_iter()
+            .find_map(|child| self.nodes.get(&child).filter(|node| node.item.name == name).map(|node| (child, node)));
+        match found {
+            Some((child, node)) => reply.entry(&LISTING_TTL, &self.attr(child, &node.item), 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, inode: u64, reply: ReplyAttr) {
+        match self.nodes.get(&inode) {
+            Some(node) => reply.attr(&LISTING_TTL, &self.attr(inode, &node.item)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readdir(&mut self, _req: &Request<'_>, inode: u64, _fh: u64, offset: i64, mut reply: ReplyDirectory) {
+        let children = match self.children(inode) {
+            Ok(children) => children,
+            Err(errno) => return reply.error(errno),
+        };
+        let parent = self.nodes.get(&inode).map(|node| node.parent).unwrap_or(FUSE_ROOT_ID);
+
+        let mut entries = vec![(inode, FileType::Directory, ".".to_string()), (parent, FileType::Directory, "..".to_string())];
+        for child in children {
+            if let Some(node) = self.nodes.get(&child) {
+                let kind = if node.item.folder.is_some() { FileType::Directory } else { FileType::RegularFile };
+                entries.push((child, kind, node.item.name.clone()));
+            }
+        }
+        for (index, (entry, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            // The offset given is that of the next entry
+            if reply.add(entry, (index + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn open(&mut self, _req: &Request<'_>, inode: u64, flags: i32, reply: ReplyOpen) {
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            return reply.error(libc::EROFS);
+        }
+        let Some(item) = self.nodes.get(&inode).map(|node| node.item.clone()) else {
+            return reply.error(libc::ENOENT);
+        };
+        if item.folder.is_some() {
+            return reply.error(libc::EISDIR);
+        }
+
+        let file = match self.cached_file(&item).and_then(|path| Ok(File::open(path)?)) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Could not open {}: {}", item.name, e);
+                return reply.error(libc::EIO);
+            }
+        };
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.open_files.insert(handle, (item.id, file));
+        reply.opened(handle, 0);
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        _inode: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Some((_, file)) = self.open_files.get(&fh) else {
+            return reply.error(libc::EBADF);
+        };
+        let mut buffer = vec![0; size as usize];
+        let mut filled = 0;
+        // Short reads are only allowed at the end of the file
+        while filled < buffer.len() {
+            match file.read_at(&mut buffer[filled..], offset as u64 + filled as u64) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return reply.error(e.raw_os_error().unwrap_or(libc::EIO)),
+            }
+        }
+        reply.data(&buffer[..filled]);
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _inode: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.open_files.remove(&fh);
+        reply.ok();
+    }
+}
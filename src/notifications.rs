@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+use tracing::{debug, warn};
+
+use crate::config::{Config, NotificationPrefs};
+use crate::filters::SyncFilter;
+use crate::format;
+
+const APP_NAME: &str = "OneDrive";
+const ICON_NAME: &str = "folder-cloud";
+
+/// Sends desktop notifications for sync activity, honouring the user's
+/// per-event `notifications` choices and `notification_exclude_patterns`.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    prefs: NotificationPrefs,
+    /// Paths matching these rules still sync but never produce a notification,
+    /// for folders that applications rewrite constantly (e.g. `.obsidian/`)
+    suppressed: SyncFilter,
+    /// `Config::watched_folders`, whose changes are announced one by one
+    watched: Vec<String>,
+}
+
+/// A file downloaded into a watched folder, and who changed it on OneDrive.
+#[derive(Debug, Clone)]
+pub struct FolderChange {
+    pub path: String,
+    pub changed_by: Option<String>,
+    /// The file was not here before
+    pub added: bool,
+}
+
+impl Notifier {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            prefs: config.notifications,
+            suppressed: SyncFilter::from_patterns(&config.notification_exclude_patterns),
+            watched: config.watched_folders.clone(),
+        }
+    }
+
+    /// The watched folder `path` is in, the innermost if they nest.
+    pub fn watched_folder(&self, path: &str) -> Option<&str> {
+        self.watched
+            .iter()
+            .filter(|folder| path.strip_prefix(folder.as_str()).is_some_and(|rest| rest.starts_with('/')))
+            .max_by_key(|folder| folder.len())
+            .map(String::as_str)
+    }
+
+    /// Summarizes the files changed by a sync run in a single notification.
+    pub fn notify_changes(&self, paths: &[String], run_id: &str) {
+        if !self.prefs.sync_complete {
+            return;
+        }
+        // Watched folders get their own notifications
+        let shown: Vec<&String> = paths
+            .iter()
+            .filter(|path| !self.suppressed.is_excluded(path, false))
+            .filter(|path| !self.prefs.folder_activity || self.watched_folder(path).is_none())
+            .collect();
+
+        if shown.len() < paths.len() {
+            debug!("Suppressed notifications for {} files", paths.len() - shown.len());
+        }
+
+        match shown.as_slice() {
+            [] => {}
+            [path] => self.send("File synced", path, run_id),
+            [first, rest @ ..] => self.send(
+                &format!("{} files synced", format::count(shown.len() as u64)),
+                &format!("{} and {} more", first, format::count(rest.len() as u64)),
+                run_id,
+            ),
+        }
+    }
+
+    pub fn notify_error(&self, message: &str, run_id: &str) {
+        if self.prefs.errors {
+            self.send("Sync failed", message, run_id);
+        }
+    }
+
+    pub fn notify_conflict(&self, path: &str, copy_path: &str, run_id: &str) {
+        if self.prefs.conflicts && !self.suppressed.is_excluded(path, false) {
+            self.send("File changed in two places", &format!("{}\nYour local version was kept as {}", path, copy_path), run_id);
+        }
+    }
+
+    /// Many files were emptied at once and their uploads held.
+    pub fn notify_emptied_files(&self, message: &str, run_id: &str) {
+        if self.prefs.errors {
+            self.send("Files emptied unexpectedly", message, run_id);
+        }
+    }
+
+    /// The mass-change circuit breaker tripped and paused uploads.
+    pub fn notify_mass_change(&self, reason: &str, run_id: &str) {
+        if self.prefs.errors {
+            self.send("Uploads paused: many files changed at once", reason, run_id);
+        }
+    }
+
+    /// Uploads started waiting for space; `reason` says how much is missing.
+    pub fn notify_quota(&self, reason: &str, run_id: &str) {
+        if self.prefs.quota {
+            self.send("OneDrive is full", reason, run_id);
+        }
+    }
+
+    /// Says who changed what in each watched folder, e.g. "Alice added
+    /// Budget.xlsx to Team/Finance"; several changes to one folder are summed up.
+    pub fn notify_folder_activity(&self, changes: &[FolderChange], run_id: &str) {
+        if !self.prefs.folder_activity {
+            return;
+        }
+        let mut by_folder: BTreeMap<&str, Vec<&FolderChange>> = BTreeMap::new();
+        for change in changes {
+            if self.suppressed.is_excluded(&change.path, false) {
+                continue;
+            }
+            if let Some(folder) = self.watched_folder(&change.path) {
+                by_folder.entry(folder).or_default().push(change);
+            }
+        }
+
+        for (folder, changes) in by_folder {
+            let body = match changes.as_slice() {
+                [change] => {
+                    let name = &change.path[folder.len() + 1..];
+                    let who = change.changed_by.as_deref().unwrap_or("Someone");
+                    if change.added {
+                        format!("{} added {} to {}", who, name, folder)
+                    } else {
+                        format!("{} updated {} in {}", who, name, folder)
+                    }
+                }
+                _ => {
+                    let mut people: Vec<&str> = changes
+                        .iter()
+                        .map(|change| change.changed_by.as_deref().unwrap_or("Someone"))
+                        .collect();
+                    people.sort_unstable();
+                    people.dedup();
+                    format!("{} changed {} files in {}", list_people(&people), format::count(changes.len() as u64), folder)
+                }
+            };
+            self.send(folder, &body, run_id);
+        }
+    }
+
+    /// A crash report from an earlier run was saved at `path`.
+    pub fn notify_crash_report(&self, path: &str) {
+        if self.prefs.errors {
+            self.send(
+                "OneDrive closed unexpectedly",
+                &format!("A crash report was saved to {}\nNothing was sent; attach it to a bug report if you file one", path),
+                "",
+            );
+        }
+    }
+
+    /// Shows a notification, with the sync run's ID on its last line so a
+    /// reported problem can be matched to the logs.
+    fn send(&self, summary: &str, body: &str, run_id: &str) {
+        let body = if run_id.is_empty() {
+            body.to_string()
+        } else {
+            format!("{}\nSync run {}", body, run_id)
+        };
+
+        // Notifications are best effort; a missing notify-send must not fail the sync
+        match Command::new("notify-send")
+            .args(["--app-name", APP_NAME, "--icon", ICON_NAME, summary, &body])
+            .output()
+        {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => warn!("Failed to show notification: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => warn!("Failed to run notify-send: {}", e),
+        }
+    }
+}
+
+/// "Alice", "Alice and Bob", "Alice, Bob and 3 others".
+fn list_people(people: &[&str]) -> String {
+    match people {
+        [] => "Someone".to_string(),
+        [one] => one.to_string(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, second, third] => format!("{}, {} and {}", first, second, third),
+        [first, second, rest @ ..] => format!("{}, {} and {} others", first, second, rest.len()),
+    }
+}
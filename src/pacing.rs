@@ -0,0 +1,114 @@
+//! Upload pacing for `Config::throttle_uploads_during_calls`: while a camera
+//! or microphone is in use, upload bodies are sent in small pieces spaced out
+//! to stay under the configured rate, so a video call keeps the uplink.
+
+use futures::stream::{self, Stream};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{desktop, format, processes};
+
+/// Whether a call is going on is checked again after this long
+const CALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Paced bodies are sent in pieces of this many bytes
+const PIECE_SIZE: usize = 64 * 1024;
+
+/// Limits upload speed while the camera or microphone is in use. One pacer is
+/// shared by every upload of an API client, so parallel transfers share the cap.
+#[derive(Debug)]
+pub struct UploadPacer {
+    /// Bytes per second allowed during a call
+    limit: u64,
+    state: Mutex<PacerState>,
+}
+
+#[derive(Debug)]
+struct PacerState {
+    /// When the camera and microphone were last checked
+    checked: Option<Instant>,
+    in_call: bool,
+    /// Earliest time the next piece may be sent
+    next_send: Instant,
+}
+
+impl UploadPacer {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            limit: bytes_per_second.max(1),
+            state: Mutex::new(PacerState {
+                checked: None,
+                in_call: false,
+                next_send: Instant::now(),
+            }),
+        }
+    }
+
+    /// Whether a call is going on, checked at most every `CALL_CHECK_INTERVAL`.
+    /// Logs when uploads start and stop being held back.
+    pub async fn in_call(&self) -> bool {
+        let mut state = self.state.lock().await;
+        if !state.checked.is_some_and(|checked| checked.elapsed() < CALL_CHECK_INTERVAL) {
+            let in_call = tokio::task::spawn_blocking(call_in_progress).await.unwrap_or(false);
+            if in_call && !state.in_call {
+                info!("Camera or microphone in use; limiting uploads to {}", format::rate(self.limit));
+                state.next_send = Instant::now();
+            } else if !in_call && state.in_call {
+                info!("Camera and microphone no longer in use; uploading at full speed");
+            }
+            state.in_call = in_call;
+            state.checked = Some(Instant::now());
+        }
+        state.in_call
+    }
+
+    /// Waits until `bytes` more may be sent. Returns at once outside a call.
+    async fn wait(&self, bytes: usize) {
+        if !self.in_call().await {
+            return;
+        }
+
+        let delay = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let start = state.next_send.max(now);
+            state.next_send = start + Duration::from_secs_f64(bytes as f64 / self.limit as f64);
+            start - now
+        };
+        tokio::time::sleep(delay).await;
+    }
+
+    /// A request body sending `content` piece by piece, each piece waiting
+    /// for its turn under the cap, which is rechecked as the body goes out.
+    pub fn paced_body(self: &Arc<Self>, content: Vec<u8>) -> reqwest::Body {
+        reqwest::Body::wrap_stream(self.clone().pieces(content))
+    }
+
+    fn pieces(self: Arc<Self>, content: Vec<u8>) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> {
+        stream::unfold((self, content, 0), |(pacer, content, sent)| async move {
+            if sent == content.len() {
+                return None;
+            }
+            let end = content.len().min(sent + PIECE_SIZE);
+            let piece = content[sent..end].to_vec();
+            pacer.wait(piece.len()).await;
+            Some((Ok(piece), (pacer, content, end)))
+        })
+    }
+}
+
+/// Whether a webcam is open or an application is recording from a microphone.
+fn call_in_progress() -> bool {
+    if processes::camera_in_use() {
+        return true;
+    }
+    match desktop::microphone_in_use() {
+        Ok(in_use) => in_use,
+        Err(e) => {
+            debug!("Could not check whether the microphone is in use: {}", e);
+            false
+        }
+    }
+}
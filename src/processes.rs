@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest process name the kernel keeps in `/proc/<pid>/comm`
+const COMM_MAX_LEN: usize = 15;
+
+/// Returns the first of `names` that matches a running process, compared
+/// case-insensitively against both the kernel process name and the executable
+/// name, so `kdenlive` and `/usr/bin/kdenlive` both match "kdenlive".
+pub fn find_running(names: &[String]) -> Option<String> {
+    let wanted: Vec<(String, &String)> = names
+        .iter()
+        .map(|name| (name.trim().to_lowercase(), name))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    if wanted.is_empty() {
+        return None;
+    }
+
+    let entries = fs::read_dir("/proc").ok()?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let file_name = entry.file_name();
+        if !file_name.to_string_lossy().bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+
+        let process_names = process_names(&entry.path());
+        for (name, original) in &wanted {
+            let truncated: String = name.chars().take(COMM_MAX_LEN).collect();
+            if process_names.iter().any(|process| *process == *name || *process == truncated) {
+                return Some((*original).clone());
+            }
+        }
+    }
+
+    None
+}
+
+/// Files under `folder` that another process has open for writing, found
+/// through `/proc/<pid>/fd` and the access mode in `/proc/<pid>/fdinfo`.
+/// Processes of other users cannot be inspected and are missed.
+pub fn files_open_for_writing(folder: &Path) -> HashSet<PathBuf> {
+    let mut open = HashSet::new();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return open;
+    };
+    let own_pid = std::process::id().to_string();
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let pid = entry.file_name().to_string_lossy().to_string();
+        if pid == own_pid || !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.filter_map(|e| e.ok()) {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            if !target.starts_with(folder) {
+                continue;
+            }
+            let fdinfo = entry.path().join("fdinfo").join(fd.file_name());
+            if is_write_mode(&fs::read_to_string(fdinfo).unwrap_or_default()) {
+                open.insert(target);
+            }
+        }
+    }
+
+    open
+}
+
+/// Whether a process has a video capture device (`/dev/video*`) open, as a
+/// webcam is while a call or recording uses it.
+pub fn camera_in_use() -> bool {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return false;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        if !entry.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let open = fds
+            .filter_map(|e| e.ok())
+            .filter_map(|fd| fs::read_link(fd.path()).ok())
+            .any(|target| target.to_string_lossy().starts_with("/dev/video"));
+        if open {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Whether an fdinfo's octal `flags:` line has O_WRONLY or O_RDWR set.
+fn is_write_mode(fdinfo: &str) -> bool {
+    fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok())
+        .is_some_and(|flags| flags & 0o3 != 0)
+}
+
+/// The kernel name and executable file name of a process, lowercased.
+fn process_names(proc_dir: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+
+    if let Ok(comm) = fs::read_to_string(proc_dir.join("comm")) {
+        names.push(comm.trim().to_lowercase());
+    }
+
+    if let Ok(cmdline) = fs::read(proc_dir.join("cmdline")) {
+        let program = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+        let program = String::from_utf8_lossy(program);
+        if let Some(executable) = program.rsplit('/').next().filter(|name| !name.is_empty()) {
+            names.push(executable.to_lowercase());
+        }
+    }
+
+    names
+}
@@ -0,0 +1,158 @@
+//! Push notifications of remote changes (`Config::push_notification_url`). A
+//! Microsoft Graph change subscription on the drive posts to a small HTTP
+//! listener, which starts a sync at once instead of waiting for the polling
+//! interval. Graph only posts to public HTTPS URLs, so the listener sits
+//! behind a relay or reverse proxy (e.g. a tunnel service) forwarding that URL
+//! to `Config::push_listen_address`. Polling carries on underneath, so a lost
+//! notification only delays a change until the next interval.
+
+use chrono::Utc;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use oauth2::CsrfToken;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::api::{OneDriveAPI, Subscription};
+use crate::config::Config;
+use crate::sync::SyncControl;
+
+/// How long each subscription is asked for; it is renewed halfway through
+const SUBSCRIPTION_LIFETIME: Duration = Duration::from_secs(2 * 24 * 60 * 60);
+
+/// Wait before subscribing again after Graph refused
+const SUBSCRIBE_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// The body Graph posts: one or more change notifications.
+#[derive(Debug, Default, Deserialize)]
+struct NotificationBatch {
+    #[serde(default)]
+    value: Vec<ChangeNotification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangeNotification {
+    #[serde(rename = "clientState")]
+    client_state: Option<String>,
+}
+
+/// Starts the listener and keeps a change subscription pointed at it, when a
+/// notification URL is configured. Must be called from within a Tokio runtime.
+pub fn start_push_notifications(config: &Config, api: Arc<OneDriveAPI>, control: SyncControl) {
+    let notification_url = config.push_notification_url.trim().to_string();
+    if notification_url.is_empty() {
+        return;
+    }
+
+    let address: SocketAddr = match config.push_listen_address.trim().parse() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Invalid push notification listen address {}: {}", config.push_listen_address, e);
+            return;
+        }
+    };
+
+    // Comes back with every notification, so posts from anyone else are ignored
+    let client_state = Arc::new(CsrfToken::new_random().secret().clone());
+
+    let service_state = client_state.clone();
+    let make_service = make_service_fn(move |_| {
+        let (client_state, control) = (service_state.clone(), control.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle(request, client_state.clone(), control.clone())))
+        }
+    });
+    let server = match Server::try_bind(&address) {
+        Ok(builder) => builder.serve(make_service),
+        Err(e) => {
+            error!("Could not listen for OneDrive change notifications on {}: {}", address, e);
+            return;
+        }
+    };
+    info!("Listening for OneDrive change notifications on {} (public URL {})", address, notification_url);
+
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("Change notification listener stopped: {}", e);
+        }
+    });
+    tokio::spawn(keep_subscribed(api, notification_url, client_state));
+}
+
+async fn handle(request: Request<Body>, client_state: Arc<String>, control: SyncControl) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST {
+        return Ok(status_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    // Graph checks a new subscription's URL by posting a token to echo back
+    let validation_token = request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "validationToken")
+            .map(|(_, token)| token.into_owned())
+    });
+    if let Some(token) = validation_token {
+        debug!("Answering change subscription validation");
+        let response = Response::builder()
+            .header("Content-Type", "text/plain")
+            .body(Body::from(token))
+            .unwrap_or_default();
+        return Ok(response);
+    }
+
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let batch: NotificationBatch = serde_json::from_slice(&body).unwrap_or_default();
+    if batch.value.iter().any(|notification| notification.client_state.as_deref() == Some(client_state.as_str())) {
+        info!("OneDrive reported a change; syncing");
+        control.sync_soon();
+    } else {
+        warn!("Ignoring a change notification that did not come from our subscription");
+    }
+
+    // Graph retries anything but a quick 2xx
+    Ok(status_response(StatusCode::ACCEPTED))
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// Subscribes, then renews the subscription before it lapses, subscribing
+/// afresh whenever renewing fails.
+async fn keep_subscribed(api: Arc<OneDriveAPI>, notification_url: String, client_state: Arc<String>) {
+    let mut subscription: Option<Subscription> = None;
+    loop {
+        let expires = Utc::now() + chrono::Duration::from_std(SUBSCRIPTION_LIFETIME).unwrap_or_default();
+        let renewed = match &subscription {
+            Some(current) => match api.renew_subscription(&current.id, expires).await {
+                Ok(renewed) => Some(renewed),
+                Err(e) => {
+                    warn!("Could not renew the drive change subscription, subscribing again: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let result = match renewed {
+            Some(renewed) => Ok(renewed),
+            None => api.create_subscription(&notification_url, &client_state, expires).await,
+        };
+
+        match result {
+            Ok(current) => {
+                subscription = Some(current);
+                tokio::time::sleep(SUBSCRIPTION_LIFETIME / 2).await;
+            }
+            Err(e) => {
+                error!("Could not subscribe to OneDrive changes; syncing on the interval only: {}", e);
+                subscription = None;
+                tokio::time::sleep(SUBSCRIBE_RETRY_DELAY).await;
+            }
+        }
+    }
+}
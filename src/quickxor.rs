@@ -0,0 +1,100 @@
+use anyhow::Result;
+use base64::Engine;
+use std::path::Path;
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+/// Width of the hash in bits
+const WIDTH_IN_BITS: usize = 160;
+/// Bits the insertion point moves for every input byte
+const SHIFT: usize = 11;
+
+/// Microsoft's QuickXorHash, the content hash OneDrive reports for every file
+/// (`file.hashes.quickXorHash`) on both personal and business drives.
+pub struct QuickXorHash {
+    data: [u64; 3],
+    length_so_far: u64,
+    shift_so_far: usize,
+}
+
+impl Default for QuickXorHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuickXorHash {
+    pub fn new() -> Self {
+        Self {
+            data: [0; 3],
+            length_so_far: 0,
+            shift_so_far: 0,
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let cells = self.data.len();
+        let mut vector_array_index = self.shift_so_far / 64;
+        let mut vector_offset = self.shift_so_far % 64;
+        let iterations = bytes.len().min(WIDTH_IN_BITS);
+
+        for i in 0..iterations {
+            let is_last_cell = vector_array_index == cells - 1;
+            let bits_in_vector_cell = if is_last_cell { WIDTH_IN_BITS % 64 } else { 64 };
+
+            // Every WIDTH_IN_BITS-th byte lands on the same bit position
+            let xored_byte = bytes[i..].iter().step_by(WIDTH_IN_BITS).fold(0u8, |acc, b| acc ^ b) as u64;
+
+            if vector_offset <= bits_in_vector_cell - 8 {
+                self.data[vector_array_index] ^= xored_byte << vector_offset;
+            } else {
+                let next_index = if is_last_cell { 0 } else { vector_array_index + 1 };
+                let low = bits_in_vector_cell - vector_offset;
+                self.data[vector_array_index] ^= xored_byte << vector_offset;
+                self.data[next_index] ^= xored_byte >> low;
+            }
+
+            vector_offset += SHIFT;
+            while vector_offset >= bits_in_vector_cell {
+                vector_array_index = if is_last_cell { 0 } else { vector_array_index + 1 };
+                vector_offset -= bits_in_vector_cell;
+            }
+        }
+
+        self.shift_so_far = (self.shift_so_far + SHIFT * (bytes.len() % WIDTH_IN_BITS)) % WIDTH_IN_BITS;
+        self.length_so_far += bytes.len() as u64;
+    }
+
+    /// The hash in the base64 form Graph uses.
+    pub fn finalize(self) -> String {
+        let mut result = [0u8; WIDTH_IN_BITS / 8];
+        for (i, cell) in self.data.iter().enumerate() {
+            let start = i * 8;
+            let end = (start + 8).min(result.len());
+            result[start..end].copy_from_slice(&cell.to_le_bytes()[..end - start]);
+        }
+
+        // The total length is folded into the last eight bytes
+        let length_start = result.len() - 8;
+        for (byte, length_byte) in result[length_start..].iter_mut().zip(self.length_so_far.to_le_bytes()) {
+            *byte ^= length_byte;
+        }
+
+        base64::engine::general_purpose::STANDARD.encode(result)
+    }
+}
+
+/// The QuickXorHash of the file at `path`, read a megabyte at a time.
+pub async fn hash_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = QuickXorHash::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize())
+}
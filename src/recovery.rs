@@ -0,0 +1,58 @@
+/// What `SyncManager::recover_interrupted` found left over from a previous run
+/// that did not finish (a crash, a power cut, a killed process).
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryReport {
+    /// `.partial` downloads deleted because they can no longer be resumed
+    pub removed_partials: Vec<String>,
+    /// `.partial` downloads kept for the next sync to resume
+    pub resumable_downloads: usize,
+    /// Saved upload sessions dropped because the file changed or OneDrive
+    /// expired the session
+    pub dropped_upload_sessions: Vec<String>,
+    /// Saved upload sessions the next sync continues
+    pub resumable_uploads: usize,
+    /// The database was not closed cleanly; SQLite replayed its write-ahead log
+    pub journal_replayed: bool,
+    /// Problems `PRAGMA quick_check` reported; empty when the database is sound
+    pub database_problems: Vec<String>,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed_partials.is_empty()
+            && self.resumable_downloads == 0
+            && self.dropped_upload_sessions.is_empty()
+            && self.resumable_uploads == 0
+            && !self.journal_replayed
+            && self.database_problems.is_empty()
+    }
+
+    /// One line, for the sync log and the Status tab.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.resumable_downloads > 0 {
+            parts.push(format!("{} downloads will resume", self.resumable_downloads));
+        }
+        if self.resumable_uploads > 0 {
+            parts.push(format!("{} uploads will resume", self.resumable_uploads));
+        }
+        if !self.removed_partials.is_empty() {
+            parts.push(format!("{} unfinished downloads removed", self.removed_partials.len()));
+        }
+        if !self.dropped_upload_sessions.is_empty() {
+            parts.push(format!("{} expired uploads will start over", self.dropped_upload_sessions.len()));
+        }
+        if self.journal_replayed {
+            parts.push("unsaved database changes replayed".to_string());
+        }
+        if !self.database_problems.is_empty() {
+            parts.push(format!("database check failed: {}", self.database_problems.join("; ")));
+        }
+
+        if parts.is_empty() {
+            "Nothing was left over from the last run".to_string()
+        } else {
+            format!("Recovered from an interrupted run: {}", parts.join(", "))
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+use crate::api::{DriveInfo, DriveItem, OneDriveAPI, UploadSession};
+
+/// The remote side of a sync, as the sync engine sees it. `OneDriveAPI` is the
+/// implementation used in practice; the engine only goes through this trait,
+/// so it can run against an in-memory fake or another drive (a SharePoint
+/// library, a second account) without changes.
+///
+/// Paths follow the conventions of the matching `OneDriveAPI` methods. Methods
+/// return boxed futures so the engine can hold an `Arc<dyn RemoteStore>`.
+pub trait RemoteStore: Send + Sync {
+    /// The drive's identity and quota.
+    fn get_drive_info(&self) -> BoxFuture<'_, Result<DriveInfo>>;
+
+    /// The files and folders directly inside `path`.
+    fn list_items<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<DriveItem>>>;
+
+    /// The file or folder at `path`.
+    fn get_item<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<DriveItem>>;
+
+    /// The items at `paths`, in as few requests as possible, with one result
+    /// per path.
+    fn get_items<'a>(&'a self, paths: &'a [String]) -> BoxFuture<'a, Result<Vec<Result<DriveItem>>>>;
+
+    /// A change-tracking link that starts from the drive as it is now.
+    fn latest_delta_link(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// The items changed or deleted since `delta_link`, and the link to use next.
+    fn delta_changes<'a>(&'a self, delta_link: &'a str) -> BoxFuture<'a, Result<(Vec<DriveItem>, String)>>;
+
+    /// Downloads `item` to `local_path`, resuming a partial download from `offset`
+    /// and reporting the bytes written so far to `on_progress`. Fails with
+    /// `HashMismatch`, leaving `local_path` as it was, when the content does not
+    /// match the item's QuickXorHash.
+    fn download_file_from<'a>(
+        &'a self,
+        item: &'a DriveItem,
+        local_path: &'a Path,
+        offset: u64,
+        cancel: &'a CancellationToken,
+        on_progress: &'a (dyn Fn(u64) + Send + Sync),
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Uploads a small file in a single request.
+    fn upload_file<'a>(&'a self, local_path: &'a Path, remote_name: &'a str) -> BoxFuture<'a, Result<DriveItem>>;
+
+    /// Starts a resumable upload of a large file.
+    fn create_upload_session<'a>(&'a self, local_path: &'a Path, remote_name: &'a str) -> BoxFuture<'a, Result<UploadSession>>;
+
+    /// Where an unfinished upload session continues, or `None` once it has expired.
+    fn upload_session_offset<'a>(&'a self, upload_url: &'a str) -> BoxFuture<'a, Result<Option<u64>>>;
+
+    /// Sends the file from `offset` onwards to an upload session, reporting each
+    /// confirmed offset to `on_progress`.
+    fn upload_to_session<'a>(
+        &'a self,
+        upload_url: &'a str,
+        local_path: &'a Path,
+        offset: u64,
+        total_size: u64,
+        cancel: &'a CancellationToken,
+        on_progress: &'a (dyn Fn(u64) + Send + Sync),
+    ) -> BoxFuture<'a, Result<DriveItem>>;
+
+    /// Moves and/or renames an item without transferring its content.
+    fn move_item<'a>(&'a self, item_id: &'a str, new_parent_path: &'a str, new_name: &'a str) -> BoxFuture<'a, Result<DriveItem>>;
+
+    fn create_folder<'a>(&'a self, folder_name: &'a str, parent_path: &'a str) -> BoxFuture<'a, Result<DriveItem>>;
+
+    /// Creates each `(folder_name, parent_path)` folder in as few requests as
+    /// possible, with one result per folder. Parents must exist already.
+    fn create_folders<'a>(&'a self, folders: &'a [(String, String)]) -> BoxFuture<'a, Result<Vec<Result<DriveItem>>>>;
+}
+
+impl RemoteStore for OneDriveAPI {
+    fn get_drive_info(&self) -> BoxFuture<'_, Result<DriveInfo>> {
+        Box::pin(OneDriveAPI::get_drive_info(self))
+    }
+
+    fn list_items<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<DriveItem>>> {
+        Box::pin(OneDriveAPI::list_items(self, path))
+    }
+
+    fn get_item<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(OneDriveAPI::get_item(self, path))
+    }
+
+    fn latest_delta_link(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(OneDriveAPI::latest_delta_link(self))
+    }
+
+    fn get_items<'a>(&'a self, paths: &'a [String]) -> BoxFuture<'a, Result<Vec<Result<DriveItem>>>> {
+        Box::pin(OneDriveAPI::get_items(self, paths))
+    }
+
+    fn delta_changes<'a>(&'a self, delta_link: &'a str) -> BoxFuture<'a, Result<(Vec<DriveItem>, String)>> {
+        Box::pin(OneDriveAPI::delta_changes(self, delta_link))
+    }
+
+    fn download_file_from<'a>(
+        &'a self,
+        item: &'a DriveItem,
+        local_path: &'a Path,
+        offset: u64,
+        cancel: &'a CancellationToken,
+        on_progress: &'a (dyn Fn(u64) + Send + Sync),
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(OneDriveAPI::download_file_from(self, item, local_path, offset, cancel, on_progress))
+    }
+
+    fn upload_file<'a>(&'a self, local_path: &'a Path, remote_name: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(OneDriveAPI::upload_file(self, local_path, remote_name))
+    }
+
+    fn create_upload_session<'a>(&'a self, local_path: &'a Path, remote_name: &'a str) -> BoxFuture<'a, Result<UploadSession>> {
+        Box::pin(OneDriveAPI::create_upload_session(self, local_path, remote_name))
+    }
+
+    fn upload_session_offset<'a>(&'a self, upload_url: &'a str) -> BoxFuture<'a, Result<Option<u64>>> {
+        Box::pin(OneDriveAPI::upload_session_offset(self, upload_url))
+    }
+
+    fn upload_to_session<'a>(
+        &'a self,
+        upload_url: &'a str,
+        local_path: &'a Path,
+        offset: u64,
+        total_size: u64,
+        cancel: &'a CancellationToken,
+        on_progress: &'a (dyn Fn(u64) + Send + Sync),
+    ) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(OneDriveAPI::upload_to_session(self, upload_url, local_path, offset, total_size, cancel, on_progress))
+    }
+
+    fn move_item<'a>(&'a self, item_id: &'a str, new_parent_path: &'a str, new_name: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(OneDriveAPI::move_item(self, item_id, new_parent_path, new_name))
+    }
+
+    fn create_folder<'a>(&'a self, folder_name: &'a str, parent_path: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(OneDriveAPI::create_folder(self, folder_name, parent_path))
+    }
+
+    fn create_folders<'a>(&'a self, folders: &'a [(String, String)]) -> BoxFuture<'a, Result<Vec<Result<DriveItem>>>> {
+        Box::pin(OneDriveAPI::create_folders(self, folders))
+    }
+}
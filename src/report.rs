@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::{info, warn};
+
+use crate::api::DriveQuota;
+use crate::format;
+
+/// `sync_log` actions counted in a summary, with how they are listed
+const CHANGE_LABELS: &[(&str, &str)] = &[
+    ("upload", "Uploaded"),
+    ("download", "Downloaded"),
+    ("delete_local", "Removed locally"),
+    ("move", "Moved on OneDrive"),
+    ("move_local", "Moved locally"),
+    ("conflict", "Conflicts (both versions kept)"),
+    ("hold", "Held for review"),
+];
+
+/// What happened over a reporting period: a single sync run, or everything
+/// since the previous daily summary.
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    /// Unix times the summary covers
+    pub from: u64,
+    pub to: u64,
+    /// Set when the summary covers a single run
+    pub run_id: Option<String>,
+    pub sync_runs: u64,
+    pub failed_runs: u64,
+    /// Number of log entries per `sync_log` action
+    pub changes: BTreeMap<String, u64>,
+    /// Errors from the most recent sync
+    pub errors: Vec<String>,
+    /// Files held out of sync until the user looks at them
+    pub held_files: usize,
+    pub quota: Option<DriveQuota>,
+}
+
+impl SyncSummary {
+    /// One line for a mail subject or notification title.
+    pub fn subject(&self) -> String {
+        let outcome = if self.failed_runs > 0 || !self.errors.is_empty() {
+            "problems need attention"
+        } else {
+            "all good"
+        };
+        format!("OneDrive sync on {}: {}", hostname(), outcome)
+    }
+
+    /// Plain text that reads the same in a mail, a push notification or on paper.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        text.push_str(&self.subject());
+        text.push_str("\n\n");
+
+        match &self.run_id {
+            Some(run_id) => text.push_str(&format!("Sync run {} at {}\n", run_id, format::timestamp(self.to))),
+            None => text.push_str(&format!("From {} to {}\n", format::timestamp(self.from), format::timestamp(self.to))),
+        }
+        text.push_str(&format!("Syncs: {} ({} failed)\n", format::count(self.sync_runs), format::count(self.failed_runs)));
+
+        text.push_str("\nChanges\n");
+        let mut any_changes = false;
+        for (action, label) in CHANGE_LABELS {
+            if let Some(count) = self.changes.get(*action).filter(|count| **count > 0) {
+                text.push_str(&format!("  {:<32}{}\n", format!("{}:", label), format::count(*count)));
+                any_changes = true;
+            }
+        }
+        if !any_changes {
+            text.push_str("  No files changed\n");
+        }
+
+        if let Some(quota) = &self.quota {
+            text.push_str(&format!(
+                "\nStorage: {} of {} used ({})\n",
+                format::size(quota.used),
+                format::size(quota.total),
+                format::percent(quota.used, quota.total)
+            ));
+        }
+
+        if self.held_files > 0 {
+            text.push_str(&format!("\nFiles not syncing until reviewed: {}\n", format::count(self.held_files as u64)));
+        }
+
+        if !self.errors.is_empty() {
+            text.push_str("\nErrors from the last sync\n");
+            for error in &self.errors {
+                text.push_str(&format!("  - {}\n", error));
+            }
+        }
+
+        text
+    }
+}
+
+/// Runs the user's summary command through the shell with the summary on
+/// standard input and its subject in `ONEDRIVE_SUMMARY_SUBJECT`, e.g.
+/// `mail -s "$ONEDRIVE_SUMMARY_SUBJECT" me@example.com`. Waits for the command
+/// on a separate thread; a failing command is only logged.
+pub fn send(command: &str, summary: &SyncSummary) {
+    let command = command.to_string();
+    let subject = summary.subject();
+    let body = summary.render();
+
+    std::thread::spawn(move || {
+        let child = Command::new("sh")
+            .args(["-c", &command])
+            .env("ONEDRIVE_SUMMARY_SUBJECT", &subject)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("Failed to run the summary command: {}", e);
+                return;
+            }
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Err(e) = stdin.write_all(body.as_bytes()) {
+                warn!("Failed to pass the summary to the summary command: {}", e);
+            }
+        }
+
+        match child.wait_with_output() {
+            Ok(output) if output.status.success() => info!("Sync summary sent"),
+            Ok(output) => warn!(
+                "Summary command failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => warn!("Failed to wait for the summary command: {}", e),
+        }
+    });
+}
+
+pub fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "this computer".to_string())
+}
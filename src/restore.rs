@@ -0,0 +1,170 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::api::{partial_download_path, DriveItem, OneDriveAPI};
+use crate::quickxor;
+
+/// How far a restore has got, shared with the GUI while it runs.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreProgress {
+    /// Still listing the chosen folders; the totals are not known yet
+    pub listing: bool,
+    pub total_files: usize,
+    pub total_bytes: u64,
+    pub done_files: usize,
+    pub done_bytes: u64,
+    pub current_file: String,
+    pub downloaded_files: usize,
+    /// Files already at the destination with the same content
+    pub existing_files: usize,
+    /// Files at the destination that differ from OneDrive, left untouched
+    pub kept_files: Vec<String>,
+    pub errors: Vec<String>,
+    pub cancelled: bool,
+    pub finished: bool,
+}
+
+/// Starts `restore_folders` on the current Tokio runtime and returns the
+/// progress it reports and a token that stops it.
+pub fn start(
+    api: Arc<OneDriveAPI>,
+    folders: Vec<String>,
+    destination: PathBuf,
+) -> (Arc<Mutex<RestoreProgress>>, CancellationToken) {
+    let progress = Arc::new(Mutex::new(RestoreProgress {
+        listing: true,
+        ..RestoreProgress::default()
+    }));
+    let cancel = CancellationToken::new();
+
+    let (task_progress, task_cancel) = (progress.clone(), cancel.clone());
+    tokio::spawn(async move {
+        restore_folders(&api, &folders, &destination, &task_progress, &task_cancel).await;
+    });
+
+    (progress, cancel)
+}
+
+/// Downloads the OneDrive `folders` (e.g. `/Documents`) and everything in
+/// them into `destination`, keeping their place in the OneDrive tree. This is
+/// a one-off copy: nothing is recorded for sync, nothing is uploaded and no
+/// existing local file is overwritten, so it is safe to run on a machine
+/// that already has some of the files.
+pub async fn restore_folders(
+    api: &OneDriveAPI,
+    folders: &[String],
+    destination: &Path,
+    progress: &Mutex<RestoreProgress>,
+    cancel: &CancellationToken,
+) {
+    update(progress, |progress| {
+        *progress = RestoreProgress {
+            listing: true,
+            ..RestoreProgress::default()
+        }
+    });
+
+    let mut files = Vec::new();
+    for folder in folders {
+        if let Err(e) = list_files(api, folder, &mut files, cancel).await {
+            warn!("Failed to list {} for restore: {}", folder, e);
+            update(progress, |progress| progress.errors.push(format!("{}: {}", folder, e)));
+        }
+    }
+
+    info!("Restoring {} files from {} folders to {}", files.len(), folders.len(), destination.display());
+    update(progress, |progress| {
+        progress.listing = false;
+        progress.total_files = files.len();
+        progress.total_bytes = files.iter().map(|(_, item)| item.size.unwrap_or(0)).sum();
+    });
+
+    for (path, item) in &files {
+        if cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| progress.current_file = path.clone());
+
+        let local_path = destination.join(path);
+        let result = restore_file(api, item, &local_path, cancel).await;
+        let size = item.size.unwrap_or(0);
+        if result.is_err() && cancel.is_cancelled() {
+            break;
+        }
+        update(progress, |progress| {
+            match result {
+                Ok(Restored::Downloaded) => progress.downloaded_files += 1,
+                Ok(Restored::AlreadyThere) => progress.existing_files += 1,
+                Ok(Restored::KeptLocal) => progress.kept_files.push(path.clone()),
+                Err(e) => progress.errors.push(format!("{}: {}", path, e)),
+            }
+            progress.done_files += 1;
+            progress.done_bytes += size;
+        });
+    }
+
+    update(progress, |progress| {
+        progress.current_file.clear();
+        progress.cancelled = cancel.is_cancelled();
+        progress.finished = true;
+    });
+    info!("Restore to {} finished", destination.display());
+}
+
+/// What `restore_file` did with one file.
+pub enum Restored {
+    Downloaded,
+    AlreadyThere,
+    KeptLocal,
+}
+
+/// Downloads `item` to `local_path` unless a file is there already.
+pub async fn restore_file(api: &OneDriveAPI, item: &DriveItem, local_path: &Path, cancel: &CancellationToken) -> Result<Restored> {
+    if let Ok(metadata) = fs::metadata(local_path).await {
+        let same_content = metadata.len() == item.size.unwrap_or(0)
+            && match item.quick_xor_hash() {
+                Some(remote_hash) => quickxor::hash_file(local_path).await? == remote_hash,
+                None => false,
+            };
+        return Ok(if same_content { Restored::AlreadyThere } else { Restored::KeptLocal });
+    }
+
+    if let Err(e) = api.download_file_from(item, local_path, 0, cancel, |_| {}).await {
+        let _ = fs::remove_file(partial_download_path(local_path)).await;
+        return Err(e);
+    }
+    Ok(Restored::Downloaded)
+}
+
+/// Collects every file under the OneDrive folder `folder` as
+/// `(path relative to the drive root, item)`.
+pub async fn list_files(
+    api: &OneDriveAPI,
+    folder: &str,
+    files: &mut Vec<(String, DriveItem)>,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let mut pending = vec![folder.trim_matches('/').to_string()];
+    while let Some(folder) = pending.pop() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        for item in api.list_items(&format!("/{}", folder)).await? {
+            let path = if folder.is_empty() { item.name.clone() } else { format!("{}/{}", folder, item.name) };
+            if item.folder.is_some() {
+                pending.push(path);
+            } else {
+                files.push((path, item));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn update(progress: &Mutex<RestoreProgress>, change: impl FnOnce(&mut RestoreProgress)) {
+    change(&mut progress.lock().unwrap());
+}
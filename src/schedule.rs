@@ -0,0 +1,50 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::config::{Config, SyncWindow, SyncWindowMode};
+
+/// Parses a window boundary written as `HH:MM`.
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+/// Whether the sync schedule lets an automatic sync start at `now` (local
+/// time). Without any windows automatic syncs always run.
+pub fn allows_sync(config: &Config, now: NaiveDateTime) -> bool {
+    if config.sync_windows.is_empty() {
+        return true;
+    }
+
+    let inside = config.sync_windows.iter().any(|window| contains(window, now));
+    match config.sync_window_mode {
+        SyncWindowMode::Allow => inside,
+        SyncWindowMode::Quiet => !inside,
+    }
+}
+
+/// Whether `now` falls inside the window. A window ending at or before its
+/// start runs past midnight and belongs to the day it starts on, so
+/// "22:00–06:00 Fri" covers Friday night into Saturday morning.
+fn contains(window: &SyncWindow, now: NaiveDateTime) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&window.start), parse_time(&window.end)) else {
+        return false;
+    };
+    let time = NaiveTime::from_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now.time());
+    let today = now.weekday();
+    let on_day = |day: chrono::Weekday| window.days.is_empty() || window.days.contains(&day);
+
+    if start < end {
+        on_day(today) && time >= start && time < end
+    } else {
+        (on_day(today) && time >= start) || (on_day(today.pred()) && time < end)
+    }
+}
+
+/// A window as shown in the settings, e.g. "09:00–18:00, Mon Tue Wed".
+pub fn describe(window: &SyncWindow) -> String {
+    let days = if window.days.is_empty() {
+        "every day".to_string()
+    } else {
+        window.days.iter().map(|day| day.to_string()).collect::<Vec<_>>().join(" ")
+    };
+    format!("{}–{}, {}", window.start.trim(), window.end.trim(), days)
+}
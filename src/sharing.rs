@@ -0,0 +1,89 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::api::OneDriveAPI;
+
+/// A sharing link the user created on one of their OneDrive items.
+#[derive(Debug, Clone)]
+pub struct SharedLink {
+    /// Path of the item relative to the drive root
+    pub path: String,
+    pub item_id: String,
+    pub permission_id: String,
+    /// `view`, `edit` or `embed`
+    pub link_type: String,
+    /// `anonymous` (anyone with the link), `organization` or `users`
+    pub scope: String,
+    /// RFC 3339 time the link stops working, if it ever does
+    pub expires: Option<String>,
+    pub web_url: Option<String>,
+}
+
+impl SharedLink {
+    /// Anyone who has the link can open the item, without signing in.
+    pub fn is_public(&self) -> bool {
+        self.scope == "anonymous"
+    }
+
+    /// Who the link works for, in words.
+    pub fn audience(&self) -> &str {
+        match self.scope.as_str() {
+            "anonymous" => "Anyone with the link",
+            "organization" => "People in your organization",
+            "users" => "Specific people",
+            other => other,
+        }
+    }
+}
+
+/// Walks the whole drive and lists every sharing link on the items the user
+/// has shared, public links first. Links a folder passes down to its contents
+/// are only listed on the folder, where they can be revoked.
+pub async fn find_shared_links(api: &OneDriveAPI, cancel: &CancellationToken) -> Result<Vec<SharedLink>> {
+    let mut links = Vec::new();
+    let mut pending = vec![String::new()];
+    while let Some(folder) = pending.pop() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        for item in api.list_items(&format!("/{}", folder)).await? {
+            let path = if folder.is_empty() { item.name.clone() } else { format!("{}/{}", folder, item.name) };
+            if item.folder.is_some() {
+                pending.push(path.clone());
+            }
+            if item.shared.is_none() {
+                continue;
+            }
+
+            let permissions = match api.list_permissions(&item.id).await {
+                Ok(permissions) => permissions,
+                Err(e) => {
+                    warn!("Could not read who {} is shared with: {}", path, e);
+                    continue;
+                }
+            };
+            for permission in permissions {
+                let Some(link) = permission.link else {
+                    continue;
+                };
+                if permission.inherited_from.is_some() {
+                    continue;
+                }
+                links.push(SharedLink {
+                    path: path.clone(),
+                    item_id: item.id.clone(),
+                    permission_id: permission.id,
+                    link_type: link.link_type.unwrap_or_default(),
+                    scope: link.scope.unwrap_or_default(),
+                    expires: permission.expiration,
+                    web_url: link.web_url,
+                });
+            }
+        }
+    }
+
+    links.sort_by(|a, b| (!a.is_public(), &a.path).cmp(&(!b.is_public(), &b.path)));
+    info!("Found {} sharing links", links.len());
+    Ok(links)
+}
@@ -0,0 +1,63 @@
+//! Quitting without losing work. Whether the user quits from the tray or the
+//! window, or the session ends the process with SIGTERM, the running sync is
+//! stopped at a checkpoint with its remaining actions saved (see
+//! `SyncControl::shutdown`), and the single-instance lock file is removed so
+//! the next start does not have to find it stale.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::sync::SyncControl;
+
+/// Longest wait for the running sync to stop before quitting anyway
+const STOP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The sync engine of this process, once it has one
+static SYNC_CONTROL: Mutex<Option<SyncControl>> = Mutex::new(None);
+
+/// File holding the PID of the running instance.
+pub fn instance_lock_path() -> PathBuf {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("onedrive-ubuntu.lock")
+}
+
+/// Makes `control` the sync engine stopped when the process quits.
+pub fn register(control: SyncControl) {
+    *SYNC_CONTROL.lock().unwrap() = Some(control);
+}
+
+/// Stops the running sync, if any, and saves what it had left to do.
+pub async fn stop_sync() {
+    let control = SYNC_CONTROL.lock().unwrap().clone();
+    let Some(control) = control else {
+        return;
+    };
+    info!("Stopping sync before quitting");
+    if !control.shutdown(STOP_TIMEOUT).await {
+        warn!("Sync did not stop within {} seconds; quitting anyway", STOP_TIMEOUT.as_secs());
+    }
+}
+
+pub fn release_instance_lock() {
+    if let Err(e) = std::fs::remove_file(instance_lock_path()) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove the instance lock: {}", e);
+        }
+    }
+}
+
+/// Stops syncing, releases the lock and exits. Runs on the signal handler's
+/// own thread, outside any Tokio runtime.
+pub fn exit_on_signal() -> ! {
+    info!("Received a termination signal");
+    match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+        Ok(runtime) => runtime.block_on(stop_sync()),
+        Err(e) => warn!("Could not stop the sync before quitting: {}", e),
+    }
+    release_instance_lock();
+    std::process::exit(0);
+}
@@ -0,0 +1,366 @@
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{info, warn, Instrument};
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// How long a statement waits for another connection's write lock (the CLI
+/// and upload mirrors open their own) before failing with "database is locked"
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A call queued behind others for longer than this is logged, as a sign of
+/// something holding the database thread up
+const SLOW_QUEUE_WAIT: Duration = Duration::from_secs(1);
+
+/// The sync database. The connection lives on a dedicated thread that runs
+/// queries one at a time, so blocking SQLite calls never run on the async
+/// runtime and no lock is held across an `.await`. Clones share the thread,
+/// which exits once the last clone is dropped.
+#[derive(Clone)]
+pub struct SyncStore {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl SyncStore {
+    /// Opens the database at `path`, creating or upgrading its schema.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut db = Connection::open(path)?;
+        // Write-ahead logging lets other connections, such as the CLI and the
+        // upload mirrors, read while a sync is writing instead of waiting for it
+        let journal_mode: String = db.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            warn!("Sync database is using {} journaling instead of WAL", journal_mode);
+        }
+        db.busy_timeout(BUSY_TIMEOUT)?;
+        init_schema(&db)?;
+        info!("Sync database initialized");
+
+        let (jobs, queue) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("sync-db".to_string())
+            .spawn(move || {
+                for job in queue {
+                    job(&mut db);
+                }
+            })?;
+
+        Ok(Self { jobs })
+    }
+
+    /// Runs `f` against the connection on the database thread and waits for it
+    /// without blocking the runtime.
+    pub async fn call<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Connection) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_tx, result_rx) = oneshot::channel();
+        let queued = Instant::now();
+        self.jobs
+            .send(Box::new(move |db| {
+                let waited = queued.elapsed();
+                if waited >= SLOW_QUEUE_WAIT {
+                    warn!("Waited {} ms for the sync database", waited.as_millis());
+                }
+                let _ = result_tx.send(f(db));
+            }))
+            .map_err(|_| anyhow!("The sync database has shut down"))?;
+        result_rx
+            .instrument(tracing::debug_span!("sync_db"))
+            .await
+            .map_err(|_| anyhow!("The sync database has shut down"))?
+    }
+
+    /// Like `call`, but blocks the calling thread; for setup code that is not
+    /// running on the runtime.
+    pub fn call_blocking<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Connection) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_tx, result_rx) = mpsc::channel();
+        self.jobs
+            .send(Box::new(move |db| {
+                let _ = result_tx.send(f(db));
+            }))
+            .map_err(|_| anyhow!("The sync database has shut down"))?;
+        result_rx.recv().map_err(|_| anyhow!("The sync database has shut down"))?
+    }
+
+    /// A value from the `sync_state` key/value table.
+    pub async fn get_state(&self, key: &str) -> Result<Option<String>> {
+        let key = key.to_string();
+        self.call(move |db| {
+            Ok(db
+                .query_row("SELECT value FROM sync_state WHERE key = ?1", params![key], |row| row.get(0))
+                .optional()?)
+        })
+        .await
+    }
+
+    pub async fn set_state(&self, key: &str, value: &str) -> Result<()> {
+        let (key, value) = (key.to_string(), value.to_string());
+        self.call(move |db| {
+            db.execute(
+                "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn init_schema(db: &Connection) -> Result<()> {
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS files (
+            path TEXT PRIMARY KEY,
+            hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            modified INTEGER NOT NULL,
+            onedrive_id TEXT,
+            last_synced INTEGER NOT NULL,
+            quick_xor_hash TEXT,
+            mime_type TEXT
+        )",
+        [],
+    )?;
+
+    // Databases created by older versions lack the later columns
+    for column in ["quick_xor_hash", "mime_type"] {
+        let exists = db
+            .prepare("SELECT 1 FROM pragma_table_info('files') WHERE name = ?1")?
+            .exists(params![column])?;
+        if !exists {
+            db.execute(&format!("ALTER TABLE files ADD COLUMN {} TEXT", column), [])?;
+        }
+    }
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS folders (
+            path TEXT PRIMARY KEY,
+            onedrive_id TEXT,
+            last_synced INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS initial_download_plan (
+            position INTEGER PRIMARY KEY,
+            path TEXT NOT NULL,
+            item_json TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS partial_downloads (
+            path TEXT PRIMARY KEY,
+            onedrive_id TEXT NOT NULL,
+            last_modified TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            started INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS upload_sessions (
+            path TEXT PRIMARY KEY,
+            upload_url TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            modified INTEGER NOT NULL,
+            offset INTEGER NOT NULL,
+            created INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // Where every known path stands; `detail` says why a file is quarantined
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS file_states (
+            path TEXT PRIMARY KEY,
+            state TEXT NOT NULL,
+            detail TEXT,
+            updated INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS file_states_state ON file_states (state)",
+        [],
+    )?;
+
+    // Held files used to live in their own table
+    let has_sync_issues = db
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sync_issues'")?
+        .exists([])?;
+    if has_sync_issues {
+        db.execute(
+            "INSERT OR REPLACE INTO file_states (path, state, detail, updated)
+             SELECT path, 'quarantined', reason, created FROM sync_issues",
+            [],
+        )?;
+        db.execute("DROP TABLE sync_issues", [])?;
+    }
+
+    // The plan the running or last sync works through, one row per action;
+    // `status` is "queued", "in_progress", "done" or "failed", and whatever is
+    // not done yet is picked up again after a pause, a quit or a crash, unless
+    // its files changed in the meantime
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS pending_actions (
+            position INTEGER PRIMARY KEY AUTOINCREMENT,
+            action_json TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'queued',
+            error TEXT,
+            updated INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS pending_actions_status ON pending_actions (status)",
+        [],
+    )?;
+
+    // Only the actions a pause left over used to be kept
+    let has_paused_actions = db
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'paused_actions'")?
+        .exists([])?;
+    if has_paused_actions {
+        db.execute(
+            "INSERT INTO pending_actions (action_json)
+             SELECT action_json FROM paused_actions ORDER BY position",
+            [],
+        )?;
+        db.execute("DROP TABLE paused_actions", [])?;
+    }
+
+    // Files in the archive folder that were freed from this computer once
+    // OneDrive had them; they are not downloaded again until they reappear
+    // locally
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS archived_files (
+            path TEXT PRIMARY KEY,
+            onedrive_id TEXT,
+            size INTEGER NOT NULL,
+            archived INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // Folders where OneDrive refused a change for lack of write access, such
+    // as folders shared with view access only; changes beneath them are held
+    // rather than uploaded
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS read_only_folders (
+            path TEXT PRIMARY KEY,
+            detected INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // OneDrive files shown here only as placeholders; `requested` is set by
+    // "Download now" for the next sync to download the file
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS placeholders (
+            path TEXT PRIMARY KEY,
+            onedrive_id TEXT,
+            size INTEGER NOT NULL,
+            modified TEXT NOT NULL,
+            requested INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS mirror_uploads (
+            local_path TEXT PRIMARY KEY,
+            remote_path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            modified INTEGER NOT NULL,
+            uploaded_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS sync_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS sync_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            status TEXT NOT NULL,
+            error TEXT
+        )",
+        [],
+    )?;
+
+    let has_run_id = db
+        .prepare("SELECT 1 FROM pragma_table_info('sync_log') WHERE name = 'run_id'")?
+        .exists([])?;
+    if !has_run_id {
+        db.execute("ALTER TABLE sync_log ADD COLUMN run_id TEXT", [])?;
+    }
+
+    // One row per sync run; a row without `finished` is a run that is still
+    // going or never ended
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS sync_runs (
+            run_id TEXT PRIMARY KEY,
+            started INTEGER NOT NULL,
+            finished INTEGER,
+            outcome TEXT,
+            uploaded INTEGER NOT NULL DEFAULT 0,
+            downloaded INTEGER NOT NULL DEFAULT 0,
+            deleted INTEGER NOT NULL DEFAULT 0,
+            skipped INTEGER NOT NULL DEFAULT 0,
+            bytes_transferred INTEGER NOT NULL DEFAULT 0,
+            errors INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS sync_runs_started ON sync_runs (started)",
+        [],
+    )?;
+
+    // Full-text index of synced documents (`Config::index_content`); `hash` is
+    // the `files.hash` the text was extracted from
+    db.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS content_index USING fts5(path UNINDEXED, hash UNINDEXED, body)",
+        [],
+    )?;
+
+    // For finding the tracked file behind a OneDrive item
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS files_onedrive_id ON files (onedrive_id)",
+        [],
+    )?;
+
+    // Lets the newest-first history stop reading once it has enough rows
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS sync_log_timestamp ON sync_log (timestamp)",
+        [],
+    )?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS sync_log_file_path ON sync_log (file_path)",
+        [],
+    )?;
+
+    Ok(())
+}
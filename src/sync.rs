@@ -1,24 +1,248 @@
 use anyhow::{Result, anyhow};
-use rusqlite::{Connection, params};
+use futures::stream::{self, StreamExt};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::SystemTime;
 use tokio::fs;
-use tokio::sync::Mutex as TokioMutex;
-use tokio::time::{interval, Duration};
-use tracing::{info, error, debug, warn};
+use tokio::io::AsyncReadExt;
+use tokio::sync::{Mutex as TokioMutex, Notify};
+use tokio::time::{interval, sleep, Duration, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, error, debug, warn, Instrument};
 use walkdir::WalkDir;
 
-use crate::api::{OneDriveAPI, DriveItem};
-use crate::config::Config;
+use crate::api::{DriveItem, DriveInfo, Cancelled, GraphError, HashMismatch, MAX_FILE_SIZE, PARTIAL_DOWNLOAD_SUFFIX, SIMPLE_UPLOAD_LIMIT, partial_download_path};
+use crate::auth::NotAuthenticated;
+use crate::budget;
+use crate::clock;
+use crate::crash;
+use crate::config::{Config, SummaryFrequency};
+use crate::desktop;
+use crate::filesystem;
+use crate::filters::{has_extension, illegal_name_reason, legal_name, matches_mime_type, SyncFilter};
+use crate::format;
+use crate::index::{self, ContentMatch};
+use crate::notifications::{FolderChange, Notifier};
+use crate::processes;
+use crate::report::{self, SyncSummary};
+use crate::schedule;
+use crate::store::SyncStore;
+use crate::throughput::Throughput;
+use crate::quickxor::QuickXorHash;
+use crate::recovery::RecoveryReport;
+use crate::remote::RemoteStore;
+use crate::verify::{Mismatch, VerifyReport};
+use crate::webhooks::Webhooks;
 
-#[derive(Debug, Clone)]
+/// Minimum time between status snapshots written while a sync is running, so a
+/// burst of completed transfers does not turn into a burst of database writes
+const STATUS_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often a sync paused for a running app checks whether the app has exited
+const PAUSED_APP_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Shortest and longest gap between checks for remote changes between syncs
+const REMOTE_POLL_SECS: std::ops::RangeInclusive<u64> = 30..=60;
+
+/// Transfers moving at least this much data keep the computer from sleeping
+const INHIBIT_SLEEP_MIN_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Space left free on the sync folder's disk after a sync's downloads, so they
+/// never fill it completely
+const DOWNLOAD_FREE_SPACE_MARGIN: u64 = 256 * 1024 * 1024;
+
+/// `.partial` downloads this old are given up on at startup rather than resumed
+const PARTIAL_DOWNLOAD_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Uploads adding up to at least this much are checked against the space left
+/// on OneDrive before they start
+const QUOTA_CHECK_MIN_BYTES: u64 = 100 * 1024 * 1024;
+
+/// A file transferred in alternating directions on this many consecutive syncs
+/// is ping-ponging and gets held
+const PING_PONG_SYNCS: usize = 4;
+
+/// The sync issue of a change OneDrive refused for lack of write access
+const READ_ONLY_REASON: &str = "You don't have write access here, so your changes stay on this computer. If this is in a folder shared with you, ask the owner for edit access, then dismiss this issue to try again.";
+
+/// A sync that would delete at least this many local files, and more than half
+/// of the tracked ones, because they vanished from OneDrive is refused, as is
+/// one that would delete every tracked file of a smaller drive. That is what a
+/// drive emptied by mistake looks like.
+const MASS_DELETE_MIN_FILES: usize = 10;
+
+/// When at least this many synced files are found emptied to 0 bytes at once,
+/// as a failing disk or ransomware leaves them, none of them is uploaded
+const EMPTIED_FILES_MIN: usize = 5;
+
+/// A sync finding at least this many tracked files changed, and more than a
+/// quarter of them, trips the mass-change circuit breaker: that is what
+/// ransomware encrypting the sync folder looks like
+const MASS_CHANGE_MIN_FILES: usize = 20;
+
+/// Why the mass-change circuit breaker holds uploads; empty while it is not
+/// tripped. A `status_` key, so the status loads it at startup.
+const MASS_CHANGE_KEY: &str = "status_mass_change";
+
+/// Set by `SyncControl::confirm_mass_change` for the next sync to upload the
+/// confirmed changes without tripping the breaker again
+const MASS_CHANGE_CONFIRMED_KEY: &str = "mass_change_confirmed";
+
+/// How often an automatic sync held back by the sync schedule checks it again
+const SYNC_WINDOW_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Least time between two daily sync summaries
+const DAILY_SUMMARY_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// How often an automatic sync held back by the request budget checks it again
+const REQUEST_BUDGET_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Added to the name of an online-only file's placeholder (`Config::online_only`)
+pub const PLACEHOLDER_SUFFIX: &str = ".onedrive";
+
+/// Files that mark a folder as being managed by another sync client
+const FOREIGN_SYNC_MARKERS: &[&str] = &[
+    ".dropbox",
+    ".dropbox.cache",
+    ".nextcloudsync.log",
+    ".owncloudsync.log",
+    ".stfolder",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SyncAction {
     Upload { local_path: String, remote_path: String },
     Download { remote_item: DriveItem, local_path: String },
     RemoveFromDatabase { path: String },
+    CreateRemoteFolder { path: String },
+    CreateLocalFolder { remote_item: DriveItem, path: String },
+    RemoveFolderFromDatabase { path: String },
+    ResolveConflict { remote_item: DriveItem, local_path: String },
+    MoveRemote { from: String, to: String, onedrive_id: String },
+    MoveLocal { from: String, to: String },
+    MoveLocalFolder { from: String, to: String },
+    DeleteLocal { path: String },
+    /// Frees an unused file in the archive folder from this computer; the
+    /// OneDrive copy stays
+    ArchiveLocal { path: String },
+    /// Writes or refreshes the placeholder of an online-only file
+    CreatePlaceholder { remote_item: DriveItem, local_path: String },
+}
+
+impl SyncAction {
+    /// Category shown in sync previews.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SyncAction::Upload { .. } => "upload",
+            SyncAction::Download { .. } => "download",
+            SyncAction::DeleteLocal { .. } => "delete",
+            SyncAction::ArchiveLocal { .. } => "archive",
+            SyncAction::CreatePlaceholder { .. } => "placeholder",
+            SyncAction::ResolveConflict { .. } => "conflict",
+            SyncAction::MoveRemote { .. } | SyncAction::MoveLocal { .. } | SyncAction::MoveLocalFolder { .. } => "move",
+            SyncAction::CreateRemoteFolder { .. } | SyncAction::CreateLocalFolder { .. } => "folder",
+            SyncAction::RemoveFromDatabase { .. } | SyncAction::RemoveFolderFromDatabase { .. } => "cleanup",
+        }
+    }
+
+    /// One-line description of what the action will do, for sync previews.
+    pub fn summary(&self) -> String {
+        match self {
+            SyncAction::Upload { local_path, .. } => format!("Upload {}", local_path),
+            SyncAction::Download { local_path, .. } => format!("Download {}", local_path),
+            SyncAction::RemoveFromDatabase { path } => format!("Forget {} (deleted on both sides)", path),
+            SyncAction::CreateRemoteFolder { path } => format!("Create folder {} on OneDrive", path),
+            SyncAction::CreateLocalFolder { path, .. } => format!("Create local folder {}", path),
+            SyncAction::RemoveFolderFromDatabase { path } => format!("Forget folder {} (deleted on both sides)", path),
+            SyncAction::ResolveConflict { local_path, .. } => format!("Keep both versions of {} (changed on both sides)", local_path),
+            SyncAction::MoveRemote { from, to, .. } => format!("Move {} to {} on OneDrive", from, to),
+            SyncAction::MoveLocal { from, to } => format!("Move local {} to {}", from, to),
+            SyncAction::MoveLocalFolder { from, to } => format!("Move local folder {} to {}", from, to),
+            SyncAction::DeleteLocal { path } => format!("Remove local {} (deleted on OneDrive)", path),
+            SyncAction::ArchiveLocal { path } => format!("Free up {} (kept on OneDrive)", path),
+            SyncAction::CreatePlaceholder { local_path, .. } => format!("Show {} as online-only", local_path),
+        }
+    }
+}
+
+/// Where a file stands with respect to OneDrive. The reconciler decides it for
+/// every known path when it plans a sync, actions move files to `InSync` as
+/// they complete, and the GUI, tray and file manager emblems all read it from
+/// the `file_states` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileState {
+    InSync,
+    PendingUpload,
+    PendingDownload,
+    /// Changed on both sides; both versions are kept when the conflict is resolved
+    Conflicted,
+    /// Held out of sync by the engine until the user dismisses the issue
+    Quarantined,
+    /// Skipped by the exclude patterns or an excluded folder
+    Excluded,
+}
+
+impl FileState {
+    pub const ALL: [FileState; 6] = [
+        FileState::InSync,
+        FileState::PendingUpload,
+        FileState::PendingDownload,
+        FileState::Conflicted,
+        FileState::Quarantined,
+        FileState::Excluded,
+    ];
+
+    /// Value stored in the `state` column.
+    fn as_str(&self) -> &'static str {
+        match self {
+            FileState::InSync => "in_sync",
+            FileState::PendingUpload => "pending_upload",
+            FileState::PendingDownload => "pending_download",
+            FileState::Conflicted => "conflicted",
+            FileState::Quarantined => "quarantined",
+            FileState::Excluded => "excluded",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileState::InSync => "Up to date",
+            FileState::PendingUpload => "Waiting to upload",
+            FileState::PendingDownload => "Waiting to download",
+            FileState::Conflicted => "Changed on both sides",
+            FileState::Quarantined => "Not syncing",
+            FileState::Excluded => "Excluded",
+        }
+    }
+
+    /// Emblem the file manager shows on files in this state; only states that
+    /// need the user's attention get one.
+    pub fn emblem(&self) -> Option<&'static str> {
+        match self {
+            FileState::Conflicted | FileState::Quarantined => Some("emblem-important"),
+            _ => None,
+        }
+    }
+
+    /// The state an action moves its file through until it completes.
+    fn planned_for(action: &SyncAction) -> Option<(&str, FileState)> {
+        match action {
+            SyncAction::Upload { local_path, .. } => Some((local_path, FileState::PendingUpload)),
+            SyncAction::Download { local_path, .. } => Some((local_path, FileState::PendingDownload)),
+            SyncAction::ResolveConflict { local_path, .. } => Some((local_path, FileState::Conflicted)),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,10 +252,63 @@ pub struct SyncStatus {
     pub files_uploaded: u64,
     pub files_downloaded: u64,
     pub files_deleted: u64,
+    pub files_conflicted: u64,
+    /// Actions planned by the current (or interrupted) sync that have not run yet
+    pub pending_actions: u64,
     pub sync_errors: Vec<String>,
+    pub relink_required: bool,
+    /// The last sync failed because the Microsoft sign-in is no longer valid
+    pub auth_failed: bool,
+    /// Seconds the system clock is behind (positive) or ahead of Microsoft's
+    /// servers, when far enough off to matter
+    pub clock_skew_secs: Option<i64>,
+    /// App from `Config::pause_while_running` currently holding syncing back
+    pub paused_by: Option<String>,
+    /// Automatic syncing is waiting for the sync schedule to allow it
+    pub outside_sync_window: bool,
+    /// Automatic syncing is waiting until the Graph request budget frees up
+    pub request_budget_low: bool,
+    /// Sleep is blocked until the current transfers finish
+    pub sleep_inhibited: bool,
+    /// Files held out of sync until the user looks at them
+    pub issues: Vec<SyncIssue>,
+    /// Why the last sync left its uploads for later because OneDrive has too
+    /// little space left for them
+    pub uploads_held_for_quota: Option<String>,
+    /// Why uploads wait for the user to confirm a burst of local changes (see
+    /// `SyncControl::confirm_mass_change`)
+    pub uploads_paused_for_mass_change: Option<String>,
+    /// What was left over from an interrupted earlier run and tidied up at startup
+    pub recovery: Option<RecoveryReport>,
+    /// Local files and folders left out of the last sync: excluded, filtered
+    /// by size or type, or with names OneDrive cannot take
+    pub skipped_files: Vec<SkippedFile>,
+    /// Local changes the last sync left for later because the file was still
+    /// open for writing or changed too recently
+    pub files_being_written: Vec<String>,
+    /// Number of known files in each state, as of the last plan or sync
+    pub file_states: BTreeMap<FileState, u64>,
+    /// Syncing was paused by the user and stays paused, across restarts, until resumed
+    pub paused: bool,
+    /// ID of the current or most recent sync run, as it appears in the logs,
+    /// the activity history and notifications
+    pub run_id: Option<String>,
+    /// Outcome of the most recent sync runs, newest last; `true` means the run
+    /// completed without any errors
+    pub recent_runs: VecDeque<bool>,
     pub total_files: u64,
     pub current_operation: String,
     pub sync_progress: f32, // 0.0 to 1.0
+    /// File whose transfer the byte counts below describe: the most recently
+    /// started upload or download still running
+    pub current_file: Option<String>,
+    pub current_file_bytes_done: u64,
+    pub current_file_bytes_total: u64,
+    /// Bytes per second moved by all running transfers over the last few
+    /// seconds; 0 when nothing is transferring
+    pub current_speed_bps: u64,
+    /// Time the current sync's remaining transfers take at that speed
+    pub estimated_remaining: Option<Duration>,
 }
 
 impl Default for SyncStatus {
@@ -42,10 +319,77 @@ impl Default for SyncStatus {
             files_uploaded: 0,
             files_downloaded: 0,
             files_deleted: 0,
+            files_conflicted: 0,
+            pending_actions: 0,
             sync_errors: Vec::new(),
+            relink_required: false,
+            auth_failed: false,
+            clock_skew_secs: None,
+            paused_by: None,
+            outside_sync_window: false,
+            request_budget_low: false,
+            sleep_inhibited: false,
+            issues: Vec::new(),
+            uploads_held_for_quota: None,
+            uploads_paused_for_mass_change: None,
+            recovery: None,
+            skipped_files: Vec::new(),
+            files_being_written: Vec::new(),
+            file_states: BTreeMap::new(),
+            paused: false,
+            run_id: None,
+            recent_runs: VecDeque::new(),
             total_files: 0,
             current_operation: "Ready".to_string(),
             sync_progress: 0.0,
+            current_file: None,
+            current_file_bytes_done: 0,
+            current_file_bytes_total: 0,
+            current_speed_bps: 0,
+            estimated_remaining: None,
+        }
+    }
+}
+
+/// Number of sync runs kept in `SyncStatus::recent_runs`
+const HEALTH_WINDOW: usize = 10;
+
+/// Overall sync health, derived from recent results and the sign-in state so
+/// every view agrees on when to worry the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncHealth {
+    Healthy,
+    Degraded,
+    Failing,
+}
+
+impl SyncHealth {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncHealth::Healthy => "healthy",
+            SyncHealth::Degraded => "degraded",
+            SyncHealth::Failing => "failing",
+        }
+    }
+}
+
+impl SyncStatus {
+    /// Failing when sync cannot proceed without the user (signed out, drive
+    /// changed) or the last three runs all failed; degraded when any recent run
+    /// had errors.
+    pub fn health(&self) -> SyncHealth {
+        let recent_failures = self.recent_runs.iter().rev().take(3).filter(|ok| !**ok).count();
+
+        if self.auth_failed || self.relink_required || self.uploads_paused_for_mass_change.is_some() || recent_failures == 3 {
+            SyncHealth::Failing
+        } else if !self.sync_errors.is_empty()
+            || !self.issues.is_empty()
+            || self.uploads_held_for_quota.is_some()
+            || self.recent_runs.iter().any(|ok| !ok)
+        {
+            SyncHealth::Degraded
+        } else {
+            SyncHealth::Healthy
         }
     }
 }
@@ -58,6 +402,53 @@ pub struct FileRecord {
     pub modified: u64,
     pub onedrive_id: Option<String>,
     pub last_synced: u64,
+    /// QuickXorHash of the content; for stored records, the content as of the
+    /// last sync, which is what OneDrive reported then
+    pub quick_xor_hash: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FolderRecord {
+    pub path: String,
+    pub onedrive_id: Option<String>,
+    pub last_synced: u64,
+}
+
+/// One sync run as recorded in the `sync_runs` table.
+#[derive(Debug, Clone)]
+pub struct SyncRun {
+    pub run_id: String,
+    pub started: u64,
+    /// `None` while the run is going, or if the process died during it
+    pub finished: Option<u64>,
+    /// "success", "failed" or "cancelled", once finished
+    pub outcome: Option<String>,
+    pub uploaded: u64,
+    pub downloaded: u64,
+    pub deleted: u64,
+    pub skipped: u64,
+    pub bytes_transferred: u64,
+    pub errors: u64,
+}
+
+/// An action in the `pending_actions` queue.
+#[derive(Debug, Clone)]
+pub struct QueuedAction {
+    pub position: i64,
+    /// What the action does, as the status line puts it
+    pub description: String,
+    /// "queued", "in_progress", "done" or "failed"
+    pub status: String,
+    pub error: Option<String>,
+    /// Unix time of the last change of status
+    pub updated: u64,
+}
+
+/// Counters as a run started, to work out what the run itself did.
+struct RunStart {
+    uploaded: u64,
+    downloaded: u64,
+    deleted: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -67,54 +458,415 @@ pub struct SyncLogEntry {
     pub file_path: String,
     pub status: String,
     pub error: Option<String>,
+    /// Content type of the file, when it is still tracked
+    pub mime_type: Option<String>,
+    /// Sync run that logged the entry; empty for entries from older versions
+    pub run_id: Option<String>,
+}
+
+/// A file the sync engine stopped syncing on its own, and why.
+#[derive(Debug, Clone)]
+pub struct SyncIssue {
+    pub path: String,
+    pub reason: String,
+    pub created: u64,
+}
+
+/// A local file or folder the last sync left out, and why.
+#[derive(Debug, Clone)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+    pub kind: SkipKind,
+}
+
+/// What kind of rule left a `SkippedFile` out, for grouping them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SkipKind {
+    /// Matches an exclude pattern or lies in an excluded folder
+    Excluded,
+    /// Over the upload size limit or of an excluded file type
+    Filtered,
+    /// Has a name OneDrive refuses
+    InvalidName,
+    /// Collides with another local name when letter case is ignored
+    CaseConflict,
+    /// Longer than the (encrypted) sync folder can store
+    NameTooLong,
+}
+
+impl SkipKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkipKind::Excluded => "Excluded",
+            SkipKind::Filtered => "Too large or excluded type",
+            SkipKind::InvalidName => "Name not allowed",
+            SkipKind::CaseConflict => "Name clash",
+            SkipKind::NameTooLong => "Name too long for this disk",
+        }
+    }
+}
+
+/// An online-only file, as shown in the Files tab.
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    pub path: String,
+    pub onedrive_id: Option<String>,
+    pub size: u64,
+    /// OneDrive's last-modified time when the placeholder was written
+    pub modified: String,
+    /// "Download now" was chosen; the next sync downloads it
+    pub requested: bool,
+}
+
+/// A tracked file as shown in the Files tab.
+#[derive(Debug, Clone)]
+pub struct SyncedFile {
+    pub path: String,
+    pub state: FileState,
+    pub size: u64,
+    pub mime_type: Option<String>,
+    pub last_synced: u64,
 }
 
 pub struct SyncManager {
     config: Arc<Config>,
-    api: Arc<OneDriveAPI>,
-    db: Arc<TokioMutex<Connection>>,
+    api: Arc<dyn RemoteStore>,
+    store: SyncStore,
+    status: Arc<TokioMutex<SyncStatus>>,
+    filter: SyncFilter,
+    notifier: Notifier,
+    webhooks: Webhooks,
+    control: SyncControl,
+    /// ID of the sync run in progress, attached to everything it logs
+    run_id: Option<String>,
+    /// Remote listing of the last sync, reused while OneDrive reports no changes
+    remote_snapshot: Option<RemoteSnapshot>,
+    /// The database's write-ahead log was not empty when it was opened
+    journal_left_over: bool,
+    /// Whether `recover_interrupted` has run in this process
+    recovery_done: bool,
+    /// Bytes moved by the current sync's transfers, for its speed and time left
+    throughput: std::sync::Mutex<Throughput>,
+}
+
+/// An upload left for the next sync because OneDrive filled up during this one.
+#[derive(Debug, thiserror::Error)]
+#[error("Waiting for space on OneDrive")]
+struct HeldForQuota;
+
+/// A full listing of OneDrive and the delta link that reports changes made
+/// after it was taken.
+struct RemoteSnapshot {
+    delta_link: String,
+    files: HashMap<String, DriveItem>,
+    folders: HashMap<String, DriveItem>,
+}
+
+/// Pauses, resumes and cancels a `SyncManager` without waiting for its lock,
+/// which a running sync holds until it finishes. Cloned into the GUI and tray.
+#[derive(Clone)]
+pub struct SyncControl {
+    paused: Arc<AtomicBool>,
+    /// Token of the current sync run; replaced at the start of every run
+    cancel: Arc<std::sync::Mutex<CancellationToken>>,
+    store: SyncStore,
     status: Arc<TokioMutex<SyncStatus>>,
+    /// Sync folder, when file manager emblems are enabled
+    emblem_folder: Option<PathBuf>,
+    /// Wakes the auto-sync loop ahead of its interval
+    wake: Arc<Notify>,
+    /// Set once the app is quitting; no sync starts after it
+    stopping: Arc<AtomicBool>,
+}
+
+impl SyncControl {
+    /// Stops the sync engine at the next checkpoint between actions. Transfers
+    /// already running finish; the rest are kept for `resume`.
+    pub async fn pause(&self) -> Result<()> {
+        self.set_paused(true).await
+    }
+
+    /// Lets syncing continue; the next sync first finishes the actions left
+    /// over from the paused run, those whose files have not changed since.
+    pub async fn resume(&self) -> Result<()> {
+        self.set_paused(false).await
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Starts the next automatic sync now rather than at the end of the
+    /// interval, as when OneDrive reports a change. Requests made while a sync
+    /// runs add up to a single sync after it.
+    pub fn sync_soon(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Stops the running sync: transfers in flight are abandoned with their
+    /// partial data kept for resuming, and nothing else is started. Does
+    /// nothing when no sync is running.
+    pub fn cancel(&self) {
+        let token = self.cancel.lock().unwrap();
+        if !token.is_cancelled() {
+            info!("Cancelling sync");
+            token.cancel();
+        }
+    }
+
+    /// Stops syncing for good, as when the app quits. Transfers in flight are
+    /// abandoned with their partial downloads and upload sessions kept, and
+    /// every action not yet done is saved, so the next start resumes those its
+    /// files still allow instead of planning the sync again. Returns whether
+    /// the running sync stopped within `timeout`.
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        self.stopping.store(true, Ordering::Relaxed);
+        self.cancel.lock().unwrap().cancel();
+
+        let started = std::time::Instant::now();
+        while self.status.lock().await.is_syncing {
+            if started.elapsed() >= timeout {
+                return false;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        true
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Relaxed)
+    }
+
+    /// The first `limit` actions of the current or last sync plan in plan
+    /// order, those done last. Read from the database, so it works while a
+    /// sync is running.
+    pub async fn get_queue(&self, limit: usize) -> Result<Vec<QueuedAction>> {
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT position, action_json, status, error, updated
+                     FROM pending_actions ORDER BY status = 'done', position LIMIT ?1"
+                )?;
+                let actions = stmt
+                    .query_map(params![limit as i64], |row| {
+                        let action_json: String = row.get(1)?;
+                        let description = serde_json::from_str::<SyncAction>(&action_json)
+                            .map(|action| describe_action(&action))
+                            .unwrap_or_else(|_| "Unreadable action".to_string());
+                        Ok(QueuedAction {
+                            position: row.get(0)?,
+                            description,
+                            status: row.get(2)?,
+                            error: row.get(3)?,
+                            updated: row.get(4)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(actions)
+            })
+            .await
+    }
+
+    /// How many queued actions are in each status.
+    pub async fn get_queue_counts(&self) -> Result<HashMap<String, u64>> {
+        self.store
+            .call(|db| {
+                let mut stmt = db.prepare("SELECT status, COUNT(*) FROM pending_actions GROUP BY status")?;
+                let counts = stmt
+                    .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?
+                    .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+                Ok(counts)
+            })
+            .await
+    }
+
+    /// Lets uploads run again once the user has checked the files that tripped
+    /// the mass-change circuit breaker and found the changes their own. The
+    /// next sync uploads them without tripping it again.
+    pub async fn confirm_mass_change(&self) -> Result<()> {
+        self.store.set_state(MASS_CHANGE_CONFIRMED_KEY, "1").await?;
+        self.store.set_state(MASS_CHANGE_KEY, "").await?;
+        self.status.lock().await.uploads_paused_for_mass_change = None;
+        info!("Mass change confirmed; uploads resume with the next sync");
+        Ok(())
+    }
+
+    /// Online-only files whose path matches `filter` (see the Files tab
+    /// filter), at most `limit` of them, by path.
+    pub async fn get_placeholders(&self, limit: usize, filter: &str) -> Result<Vec<Placeholder>> {
+        let pattern = fuzzy_like_pattern(filter);
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT path, onedrive_id, size, modified, requested FROM placeholders
+                     WHERE path LIKE ?1 ESCAPE '\\' ORDER BY path LIMIT ?2"
+                )?;
+                let placeholders = stmt
+                    .query_map(params![pattern, limit as i64], |row| {
+                        Ok(Placeholder {
+                            path: row.get(0)?,
+                            onedrive_id: row.get(1)?,
+                            size: row.get(2)?,
+                            modified: row.get(3)?,
+                            requested: row.get(4)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(placeholders)
+            })
+            .await
+    }
+
+    /// Has the next sync download the online-only files at `paths`, or inside
+    /// them when they are folders, and starts it. Returns how many files that is.
+    pub async fn download_now(&self, paths: Vec<String>) -> Result<usize> {
+        let requested = self
+            .store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                let mut requested = 0;
+                for path in paths {
+                    let path = path.trim_matches('/').to_string();
+                    requested += tx.execute(
+                        "UPDATE placeholders SET requested = 1
+                         WHERE ?1 = '' OR path = ?1 OR substr(path, 1, length(?1) + 1) = ?1 || '/'",
+                        params![path],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(requested)
+            })
+            .await?;
+        if requested > 0 {
+            info!("Downloading {} online-only files with the next sync", requested);
+            self.sync_soon();
+        }
+        Ok(requested)
+    }
+
+    /// Lets a held file sync again from the next run on.
+    pub async fn dismiss_issue(&self, path: &str) -> Result<()> {
+        let dismissed = path.to_string();
+        let (issues, file_states) = self
+            .store
+            .call(move |db| {
+                db.execute(
+                    "DELETE FROM file_states WHERE path = ?1 AND state = 'quarantined'",
+                    params![dismissed],
+                )?;
+                // Write access may have been granted since; the next change
+                // beneath the folder is tried again and tells
+                db.execute(
+                    "DELETE FROM read_only_folders
+                     WHERE path = ?1 OR substr(?1, 1, length(path) + 1) = path || '/'",
+                    params![dismissed],
+                )?;
+                Ok((load_sync_issues(db)?, load_file_state_counts(db)?))
+            })
+            .await?;
+        if let Some(folder) = &self.emblem_folder {
+            desktop::set_emblem(&folder.join(path), None);
+        }
+        {
+            let mut status = self.status.lock().await;
+            status.issues = issues;
+            status.file_states = file_states;
+        }
+        info!("Resuming sync of {}", path);
+        Ok(())
+    }
+
+    /// Gives a new sync run a fresh token, so an earlier cancel does not carry over.
+    fn start_run(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+        *self.cancel.lock().unwrap() = token.clone();
+        token
+    }
+
+    /// Picks up a pause or resume saved by another process, such as the
+    /// `pause`/`resume` commands.
+    async fn reload(&self) -> Result<()> {
+        let saved = self.store.get_state("status_paused").await?;
+        let paused = saved.as_deref() == Some("1");
+
+        if paused != self.is_paused() {
+            self.paused.store(paused, Ordering::Relaxed);
+            self.status.lock().await.paused = paused;
+            info!("Sync {} from another process", if paused { "paused" } else { "resumed" });
+        }
+        Ok(())
+    }
+
+    async fn set_paused(&self, paused: bool) -> Result<()> {
+        self.paused.store(paused, Ordering::Relaxed);
+        info!("Sync {}", if paused { "paused" } else { "resumed" });
+
+        {
+            let mut status = self.status.lock().await;
+            status.paused = paused;
+            if !status.is_syncing {
+                status.current_operation = if paused { "Paused" } else { "Ready" }.to_string();
+            }
+        }
+
+        self.store.set_state("status_paused", if paused { "1" } else { "0" }).await
+    }
 }
 
 impl SyncManager {
-    pub fn new(config: Arc<Config>, api: Arc<OneDriveAPI>) -> Result<Self> {
-        let db = Connection::open(&config.db_file)?;
-        
-        // Initialize database schema
-        db.execute(
-            "CREATE TABLE IF NOT EXISTS files (
-                path TEXT PRIMARY KEY,
-                hash TEXT NOT NULL,
-                size INTEGER NOT NULL,
-                modified INTEGER NOT NULL,
-                onedrive_id TEXT,
-                last_synced INTEGER NOT NULL
-            )",
-            [],
-        )?;
-
-        db.execute(
-            "CREATE TABLE IF NOT EXISTS sync_log (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp INTEGER NOT NULL,
-                action TEXT NOT NULL,
-                file_path TEXT NOT NULL,
-                status TEXT NOT NULL,
-                error TEXT
-            )",
-            [],
-        )?;
-
-        info!("Sync database initialized");
+    pub fn new(config: Arc<Config>, api: Arc<dyn RemoteStore>) -> Result<Self> {
+        let journal_left_over = std::fs::metadata(format!("{}-wal", config.db_file.display()))
+            .is_ok_and(|metadata| metadata.len() > 0);
+        let store = SyncStore::open(&config.db_file)?;
+        let status = store.call_blocking(|db| {
+            let mut status = load_status_snapshot(db)?;
+            status.issues = load_sync_issues(db)?;
+            status.file_states = load_file_state_counts(db)?;
+            Ok(status)
+        })?;
+        let notifier = Notifier::new(&config);
+        let webhooks = Webhooks::new(&config);
+        let control = SyncControl {
+            paused: Arc::new(AtomicBool::new(status.paused)),
+            cancel: Arc::new(std::sync::Mutex::new(CancellationToken::new())),
+            store: store.clone(),
+            status: Arc::new(TokioMutex::new(status)),
+            emblem_folder: config.folder_integration.then(|| config.sync_folder.clone()),
+            wake: Arc::new(Notify::new()),
+            stopping: Arc::new(AtomicBool::new(false)),
+        };
 
         Ok(Self {
             config,
             api,
-            db: Arc::new(TokioMutex::new(db)),
-            status: Arc::new(TokioMutex::new(SyncStatus::default())),
+            store,
+            status: control.status.clone(),
+            filter: SyncFilter::default(),
+            notifier,
+            webhooks,
+            control,
+            run_id: None,
+            remote_snapshot: None,
+            journal_left_over,
+            recovery_done: false,
+            throughput: std::sync::Mutex::new(Throughput::default()),
         })
     }
 
+    /// Handle for pausing, resuming and cancelling this manager from other tasks.
+    pub fn control(&self) -> SyncControl {
+        self.control.clone()
+    }
+
+    pub async fn pause(&self) -> Result<()> {
+        self.control.pause().await
+    }
+
+    pub async fn resume(&self) -> Result<()> {
+        self.control.resume().await
+    }
+
     pub async fn get_status(&self) -> SyncStatus {
         self.status.lock().await.clone()
     }
@@ -125,6 +877,7 @@ impl SyncManager {
     {
         let mut status = self.status.lock().await;
         updater(&mut *status);
+        crash::record_sync_phase(&status.current_operation);
     }
 
     pub async fn start_auto_sync(&mut self) {
@@ -133,8 +886,46 @@ impl SyncManager {
         
         info!("Starting auto-sync every {} minutes", self.config.sync_interval_minutes);
 
+        if let Err(e) = self.recover_offline_changes().await {
+            warn!("Startup recovery scan failed: {}", e);
+        }
+
+        let poll_secs = self.config.remote_poll_seconds;
+        let mut remote_poll = tokio::time::interval(Duration::from_secs(poll_secs.clamp(*REMOTE_POLL_SECS.start(), *REMOTE_POLL_SECS.end())));
+        remote_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        if poll_secs > 0 {
+            info!("Checking OneDrive for changes every {} seconds between syncs", remote_poll.period().as_secs());
+        }
+
+        let wake = self.control.wake.clone();
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = wake.notified() => info!("Syncing ahead of schedule"),
+                _ = remote_poll.tick(), if poll_secs > 0 => {
+                    // Held back by the same rules as a sync, but never waits for them
+                    if !self.check_paused_by_app().await && self.check_sync_window().await && self.check_request_budget().await {
+                        if let Err(e) = self.pull_remote_changes().await {
+                            warn!("Failed to download changes from OneDrive: {}", e);
+                        }
+                    }
+                    continue;
+                }
+            }
+            
+            // A sync held back by a running app, the schedule or the request
+            // budget starts as soon as it is allowed, rather than waiting for the next interval
+            loop {
+                if self.check_paused_by_app().await {
+                    sleep(PAUSED_APP_POLL_INTERVAL).await;
+                } else if !self.check_sync_window().await {
+                    sleep(SYNC_WINDOW_POLL_INTERVAL).await;
+                } else if !self.check_request_budget().await {
+                    sleep(REQUEST_BUDGET_POLL_INTERVAL).await;
+                } else {
+                    break;
+                }
+            }
             
             let is_syncing = {
                 let status = self.status.lock().await;
@@ -155,6 +946,75 @@ impl SyncManager {
         }
     }
 
+    /// Whether syncing is held back because an app from `pause_while_running`
+    /// is open, keeping `SyncStatus::paused_by` up to date either way.
+    async fn check_paused_by_app(&self) -> bool {
+        let running = processes::find_running(&self.config.pause_while_running);
+        let mut status = self.status.lock().await;
+
+        match running {
+            Some(app) => {
+                if status.paused_by.as_deref() != Some(app.as_str()) {
+                    info!("Sync paused while {} is running", app);
+                }
+                status.current_operation = format!("Paused while {} is running", app);
+                status.paused_by = Some(app);
+                true
+            }
+            None => {
+                if let Some(app) = status.paused_by.take() {
+                    info!("{} exited, resuming sync", app);
+                    status.current_operation = "Ready".to_string();
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether the configured sync windows allow an automatic sync right now,
+    /// keeping `SyncStatus::outside_sync_window` up to date either way.
+    async fn check_sync_window(&self) -> bool {
+        let allowed = schedule::allows_sync(&self.config, chrono::Local::now().naive_local());
+        let mut status = self.status.lock().await;
+
+        if !allowed {
+            if !status.outside_sync_window {
+                info!("Automatic sync waiting for the sync schedule");
+            }
+            status.outside_sync_window = true;
+            status.current_operation = "Waiting for scheduled sync hours".to_string();
+        } else if status.outside_sync_window {
+            info!("Sync schedule allows syncing again");
+            status.outside_sync_window = false;
+            status.current_operation = "Ready".to_string();
+        }
+        allowed
+    }
+
+    /// Whether the Graph request budget leaves room for an automatic sync,
+    /// keeping `SyncStatus::request_budget_low` up to date either way.
+    async fn check_request_budget(&self) -> bool {
+        let usage = budget::usage();
+        let low = budget::is_low(&self.config, usage);
+        let mut status = self.status.lock().await;
+
+        if low {
+            if !status.request_budget_low {
+                info!(
+                    "Automatic sync waiting for the request budget ({} requests in the last hour, {} today)",
+                    usage.last_hour, usage.last_day
+                );
+            }
+            status.request_budget_low = true;
+            status.current_operation = "Waiting for the request budget".to_string();
+        } else if status.request_budget_low {
+            info!("Request budget allows syncing again");
+            status.request_budget_low = false;
+            status.current_operation = "Ready".to_string();
+        }
+        !low
+    }
+
     pub async fn sync(&mut self) -> Result<()> {
         let is_syncing = {
             let status = self.status.lock().await;
@@ -165,16 +1025,83 @@ impl SyncManager {
             return Err(anyhow!("Sync already in progress"));
         }
 
+        if self.control.is_stopping() {
+            return Ok(());
+        }
+        if let Err(e) = self.control.reload().await {
+            warn!("Failed to read the saved pause state: {}", e);
+        }
+        if self.control.is_paused() {
+            debug!("Skipping sync - syncing is paused");
+            return Ok(());
+        }
+
+        if self.check_paused_by_app().await {
+            return Ok(());
+        }
+
+        let run_id = self.start_run_id().await;
+        // Opened before the run counts as started, so a database error cannot
+        // leave `is_syncing` set and lock out every later sync
+        let run_start = match self.start_run_record(&run_id).await {
+            Ok(run_start) => run_start,
+            Err(e) => {
+                self.run_id = None;
+                return Err(e);
+            }
+        };
         self.update_status(|status| {
             status.is_syncing = true;
             status.sync_errors.clear();
             status.current_operation = "Starting sync...".to_string();
             status.sync_progress = 0.0;
         }).await;
+        self.throughput.lock().unwrap().start();
         
-        info!("Starting bidirectional sync");
+        info!("Starting bidirectional sync (run {})", run_id);
+        
+        if !self.recovery_done {
+            self.recovery_done = true;
+            match self.recover_interrupted().await {
+                Ok(report) if !report.is_empty() => {
+                    let summary = report.summary();
+                    info!("{}", summary);
+                    if let Err(e) = self.log_sync_event("recover_startup", "", "success", Some(&summary)).await {
+                        warn!("Failed to log the recovery from an interrupted run: {}", e);
+                    }
+                    self.update_status(|status| status.recovery = Some(report)).await;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to check for an interrupted earlier run: {}", e),
+            }
+        }
         
-        let sync_result = self.perform_sync().await;
+        let cancel = self.control.start_run();
+        let sync_result = self
+            .perform_sync(&cancel)
+            .instrument(tracing::info_span!("sync", run = %run_id))
+            .await;
+
+        // A cancelled run is neither a success nor a failure; the next sync
+        // picks up from whatever it left behind
+        if cancel.is_cancelled() {
+            info!("Sync cancelled");
+            self.update_status(|status| {
+                status.is_syncing = false;
+                status.current_operation = "Sync cancelled".to_string();
+            }).await;
+            if let Err(e) = self.save_status_snapshot().await {
+                warn!("Failed to save sync status: {}", e);
+            }
+            if let Err(e) = self.finish_run_record(&run_id, "cancelled", run_start).await {
+                warn!("Failed to record the end of the sync run: {}", e);
+            }
+            if let Err(e) = self.log_sync_event("sync_complete", "", "cancelled", None).await {
+                warn!("Failed to log the end of the sync: {}", e);
+            }
+            self.run_id = None;
+            return Ok(());
+        }
         
         self.update_status(|status| {
             status.is_syncing = false;
@@ -182,21 +1109,63 @@ impl SyncManager {
             status.sync_progress = 1.0;
         }).await;
         
+        let auth_failed = sync_result.as_ref().err().is_some_and(is_auth_error);
+        self.update_status(|status| {
+            if let Err(e) = &sync_result {
+                status.sync_errors.push(e.to_string());
+            }
+            status.auth_failed = auth_failed;
+            status.clock_skew_secs = clock::significant_skew_secs();
+            status.recent_runs.push_back(status.sync_errors.is_empty());
+            if status.recent_runs.len() > HEALTH_WINDOW {
+                status.recent_runs.pop_front();
+            }
+        }).await;
+        if let Err(e) = self.save_status_snapshot().await {
+            warn!("Failed to save sync status: {}", e);
+        }
+        let outcome = if sync_result.is_ok() { "success" } else { "failed" };
+        if let Err(e) = self.finish_run_record(&run_id, outcome, run_start).await {
+            warn!("Failed to record the end of the sync run: {}", e);
+        }
+        if let Err(e) = self.prune_logs().await {
+            warn!("Failed to prune the sync log: {}", e);
+        }
+        
         match sync_result {
             Ok(_) => {
                 info!("Sync completed successfully");
                 self.update_status(|status| {
                     status.current_operation = "Sync completed".to_string();
                 }).await;
-                self.log_sync_event("sync_complete", "", "success", None).await?;
+                if let Err(e) = self.log_sync_event("sync_complete", "", "success", None).await {
+                    warn!("Failed to log the end of the sync: {}", e);
+                }
+                if self.webhooks.is_enabled() {
+                    match self.load_summary(0, Some(run_id.clone())).await {
+                        Ok(summary) => self.webhooks.sync_completed(&run_id, &summary.changes),
+                        Err(e) => warn!("Failed to count this run's changes for webhooks: {}", e),
+                    }
+                }
+                self.send_summary().await;
+                self.run_id = None;
+                self.ensure_folder_integration().await;
+                if let Err(e) = self.update_content_index().await {
+                    warn!("Failed to update the content index: {}", e);
+                }
             }
             Err(e) => {
                 error!("Sync failed: {}", e);
                 self.update_status(|status| {
-                    status.sync_errors.push(e.to_string());
                     status.current_operation = "Sync failed".to_string();
                 }).await;
-                self.log_sync_event("sync_complete", "", "failed", Some(&e.to_string())).await?;
+                if let Err(log_error) = self.log_sync_event("sync_complete", "", "failed", Some(&e.to_string())).await {
+                    warn!("Failed to log the end of the sync: {}", log_error);
+                }
+                self.notifier.notify_error(&e.to_string(), &run_id);
+                self.webhooks.sync_failed(&run_id, &e.to_string());
+                self.send_summary().await;
+                self.run_id = None;
                 return Err(e);
             }
         }
@@ -204,435 +1173,4776 @@ impl SyncManager {
         Ok(())
     }
 
-    async fn perform_sync(&mut self) -> Result<()> {
-        info!("=== STARTING SYNC PROCESS ===");
-        
-        // Step 1: Get local file state
-        self.update_status(|status| {
-            status.current_operation = "Scanning local files...".to_string();
-            status.sync_progress = 0.1;
-        }).await;
-        
-        let local_files = self.scan_local_files().await?;
-        info!("=== LOCAL SCAN COMPLETE: {} files ===", local_files.len());
+    /// Hands a summary to `Config::summary_command`, if one is set: of the run
+    /// that just finished, or of everything since the last daily summary once a
+    /// day has passed. Best effort; problems are only logged.
+    async fn send_summary(&self) {
+        let command = self.config.summary_command.trim();
+        if command.is_empty() {
+            return;
+        }
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let (from, run_id) = match self.config.summary_frequency {
+            SummaryFrequency::EverySync => (now, self.run_id.clone()),
+            SummaryFrequency::Daily => {
+                let last_sent = match self.store.get_state("last_summary_sent").await {
+                    Ok(last_sent) => last_sent.and_then(|value| value.parse::<u64>().ok()),
+                    Err(e) => {
+                        warn!("Failed to read when the last summary was sent: {}", e);
+                        return;
+                    }
+                };
+                match last_sent {
+                    Some(last_sent) if now < last_sent + DAILY_SUMMARY_INTERVAL_SECS => return,
+                    Some(last_sent) => (last_sent, None),
+                    None => (now.saturating_sub(DAILY_SUMMARY_INTERVAL_SECS), None),
+                }
+            }
+        };
+
+        let mut summary = match self.load_summary(from, run_id).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                warn!("Failed to build the sync summary: {}", e);
+                return;
+            }
+        };
+        summary.to = now;
+        {
+            let status = self.status.lock().await;
+            summary.errors = status.sync_errors.clone();
+            summary.held_files = status.issues.len();
+        }
+        summary.quota = match self.api.get_drive_info().await {
+            Ok(drive_info) => drive_info.quota,
+            Err(e) => {
+                warn!("Failed to read the storage quota for the summary: {}", e);
+                None
+            }
+        };
+
+        report::send(command, &summary);
+        if let Err(e) = self.store.set_state("last_summary_sent", &now.to_string()).await {
+            warn!("Failed to record when the summary was sent: {}", e);
+        }
+    }
+
+    /// Counts sync runs and changes in the log, for one run or since `from`.
+    async fn load_summary(&self, from: u64, run_id: Option<String>) -> Result<SyncSummary> {
+        self.store
+            .call(move |db| {
+                let (condition, key) = match &run_id {
+                    Some(run_id) => ("run_id = ?1", rusqlite::types::Value::Text(run_id.clone())),
+                    None => ("timestamp >= ?1", rusqlite::types::Value::Integer(from as i64)),
+                };
+                let mut summary = SyncSummary {
+                    from,
+                    run_id,
+                    ..SyncSummary::default()
+                };
+
+                let mut stmt = db.prepare(&format!(
+                    "SELECT action, status, COUNT(*) FROM sync_log WHERE {} GROUP BY action, status",
+                    condition
+                ))?;
+                let rows = stmt.query_map(params![key], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u64>(2)?))
+                })?;
+                for row in rows {
+                    let (action, status, count) = row?;
+                    if action == "sync_complete" {
+                        summary.sync_runs += count;
+                        if status == "failed" {
+                            summary.failed_runs += count;
+                        }
+                    } else {
+                        *summary.changes.entry(action).or_default() += count;
+                    }
+                }
+                Ok(summary)
+            })
+            .await
+    }
+
+    /// Compares tracked files against their current size and mtime on disk and
+    /// uploads the ones edited while the client was not running, ahead of the
+    /// rest of the first sync. Holds back for the same reasons `sync` does: a
+    /// sync already running, a quit, a saved pause or an app that pauses syncing.
+    pub async fn recover_offline_changes(&mut self) -> Result<usize> {
+        if self.status.lock().await.is_syncing {
+            return Err(anyhow!("Sync already in progress"));
+        }
+        if self.control.is_stopping() {
+            return Ok(0);
+        }
+        if let Err(e) = self.control.reload().await {
+            warn!("Failed to read the saved pause state: {}", e);
+        }
+        if self.control.is_paused() {
+            debug!("Skipping startup recovery scan - syncing is paused");
+            return Ok(0);
+        }
+        if self.check_paused_by_app().await {
+            return Ok(0);
+        }
+
+        let run_id = self.start_run_id().await;
+        info!("Running startup recovery scan (run {})", run_id);
 
-        // Step 2: Get remote file state
         self.update_status(|status| {
-            status.current_operation = "Scanning remote files...".to_string();
-            status.sync_progress = 0.3;
+            status.is_syncing = true;
+            status.current_operation = "Checking for offline changes...".to_string();
         }).await;
-        
-        let remote_files = self.scan_remote_files().await?;
-        info!("=== REMOTE SCAN COMPLETE: {} files ===", remote_files.len());
 
-        // Step 3: Get stored sync state
+        let result = self
+            .upload_offline_changes()
+            .instrument(tracing::info_span!("recovery", run = %run_id))
+            .await;
+
         self.update_status(|status| {
-            status.current_operation = "Loading sync database...".to_string();
-            status.sync_progress = 0.4;
+            status.is_syncing = false;
+            status.current_operation = "Ready".to_string();
         }).await;
-        
+
+        // Logged while the run's ID still tags events, which stops before
+        // any error is passed on
+        let logged = match &result {
+            Ok(uploaded) if *uploaded > 0 => self.log_sync_event("startup_recovery", "", "success", None).await,
+            _ => Ok(()),
+        };
+        self.run_id = None;
+
+        let uploaded = result?;
+        logged?;
+        info!("Startup recovery scan complete: {} offline changes uploaded", uploaded);
+        Ok(uploaded)
+    }
+
+    /// Asks OneDrive's delta link what changed since the last scan and
+    /// downloads just the files and folders the answer reports as new or
+    /// changed there, leaving local changes, deletions, moves and conflicts to
+    /// the next full sync. Costs a single request when nothing changed, and
+    /// lists no folders otherwise. Does nothing before the first full sync has
+    /// listed the drive, or while syncing is paused. Returns the number of
+    /// actions run.
+    pub async fn pull_remote_changes(&mut self) -> Result<usize> {
+        if self.status.lock().await.is_syncing || self.control.is_paused() || self.control.is_stopping() {
+            return Ok(0);
+        }
+        let Some(snapshot) = &self.remote_snapshot else {
+            return Ok(0);
+        };
+        let (changed, delta_link) = match self.api.delta_changes(&snapshot.delta_link).await {
+            Ok((changed, delta_link)) if changed.is_empty() => {
+                if let Some(snapshot) = &mut self.remote_snapshot {
+                    snapshot.delta_link = delta_link;
+                }
+                return Ok(0);
+            }
+            Ok((changed, delta_link)) => {
+                info!("{} items changed on OneDrive; downloading them ahead of the next sync", changed.len());
+                (changed, delta_link)
+            }
+            Err(e) => {
+                // An expired link is rescanned by the next full sync
+                debug!("Failed to check OneDrive for changes: {}", e);
+                return Ok(0);
+            }
+        };
+
+        let run_id = self.start_run_id().await;
+        self.update_status(|status| {
+            status.is_syncing = true;
+            status.current_operation = "Fetching changes from OneDrive...".to_string();
+        }).await;
+
+        let cancel = self.control.start_run();
+        let result = self
+            .download_remote_changes(changed, delta_link, &cancel)
+            .instrument(tracing::info_span!("pull", run = %run_id))
+            .await;
+
+        self.update_status(|status| {
+            status.is_syncing = false;
+            status.current_operation = "Ready".to_string();
+        }).await;
+        self.run_id = None;
+
+        let pulled = result?;
+        if pulled > 0 {
+            info!("Downloaded {} changes from OneDrive", pulled);
+        }
+        Ok(pulled)
+    }
+
+    /// Downloads the `changed` items of a delta query and moves the kept remote
+    /// listing on to `delta_link`. When some change cannot be placed in that
+    /// listing, it is dropped instead, so the next full sync lists OneDrive again.
+    async fn download_remote_changes(&mut self, changed: Vec<DriveItem>, delta_link: String, cancel: &CancellationToken) -> Result<usize> {
+        // A queue left unfinished is for the next full sync to resume
+        if !self.load_queued_actions().await?.is_empty() {
+            return Ok(0);
+        }
+        self.check_drive_identity().await?;
+        self.reload_filter();
+        // Gone when the exclude rules changed, and then listed again in full
+        let Some(mut snapshot) = self.remote_snapshot.take() else {
+            return Ok(0);
+        };
+
+        let (files, folders, complete) = self.place_remote_changes(&snapshot, changed);
+        let actions = self.plan_remote_downloads(&files, &folders).await?;
+        if complete {
+            snapshot.files.extend(files);
+            snapshot.folders.extend(folders);
+            snapshot.delta_link = delta_link;
+            self.remote_snapshot = Some(snapshot);
+        } else {
+            debug!("Some changes on OneDrive need a full listing; the next sync lists the drive again");
+        }
+
+        let actions = self.hold_unsyncable_files(actions, true).await?;
+        let count = actions.len();
+        self.execute_actions(actions, cancel).await?;
+        Ok(count)
+    }
+
+    /// Works out where the items a delta query reported are, from their parent
+    /// folder's ID in the kept remote listing. Returns the files and folders
+    /// placed, minus those the exclude rules skip, and whether every change was
+    /// placed: deletions, moves and renames, and items in folders the listing
+    /// does not know are left for a full listing. A folder deleted or moved
+    /// leaves its contents' paths unknown, so nothing is placed then.
+    fn place_remote_changes(
+        &self,
+        snapshot: &RemoteSnapshot,
+        changed: Vec<DriveItem>,
+    ) -> (HashMap<String, DriveItem>, HashMap<String, DriveItem>, bool) {
+        let known_paths: HashMap<&str, &str> = snapshot
+            .files
+            .iter()
+            .chain(&snapshot.folders)
+            .map(|(path, item)| (item.id.as_str(), path.as_str()))
+            .collect();
+        let mut folder_paths: HashMap<String, String> = snapshot
+            .folders
+            .iter()
+            .map(|(path, item)| (item.id.clone(), path.clone()))
+            .collect();
+        let root_id = snapshot
+            .files
+            .iter()
+            .chain(&snapshot.folders)
+            .find(|(path, _)| !path.contains('/'))
+            .and_then(|(_, item)| item.parent_reference.as_ref()?.id.clone());
+        if let Some(root_id) = root_id {
+            folder_paths.insert(root_id, String::new());
+        }
+
+        // The drive's root has no parent folder, and nothing of it syncs
+        let (folder_changes, file_changes): (Vec<DriveItem>, Vec<DriveItem>) = changed
+            .into_iter()
+            .filter(|item| item.parent_reference.as_ref().is_some_and(|parent| parent.id.is_some()))
+            .partition(|item| item.folder.is_some() || (item.deleted.is_some() && snapshot.folders.values().any(|folder| folder.id == item.id)));
+
+        let parent_path = |item: &DriveItem, folder_paths: &HashMap<String, String>| -> Option<String> {
+            let parent = item.parent_reference.as_ref()?;
+            if parent.path.as_deref().is_some_and(|path| path.ends_with("/root:")) {
+                return Some(String::new());
+            }
+            folder_paths.get(parent.id.as_deref()?).cloned()
+        };
+        let item_path = |parent: &str, name: &str| if parent.is_empty() { name.to_string() } else { format!("{}/{}", parent, name) };
+
+        let mut folders = HashMap::new();
+        let mut complete = true;
+        // A new folder may be reported before the new folder it is in
+        let mut unplaced = folder_changes;
+        loop {
+            let before = unplaced.len();
+            let mut still_unplaced = Vec::new();
+            for item in unplaced {
+                if item.deleted.is_some() {
+                    debug!("Folder deleted on OneDrive: {}", known_paths.get(item.id.as_str()).unwrap_or(&""));
+                    return (HashMap::new(), HashMap::new(), false);
+                }
+                let Some(parent) = parent_path(&item, &folder_paths) else {
+                    still_unplaced.push(item);
+                    continue;
+                };
+                let path = item_path(&parent, &item.name);
+                if known_paths.get(item.id.as_str()).is_some_and(|known| *known != path) {
+                    debug!("Folder moved on OneDrive: {} -> {}", known_paths[item.id.as_str()], path);
+                    return (HashMap::new(), HashMap::new(), false);
+                }
+                if self.config.excluded_folder_names.contains(&item.name) || self.filter.is_excluded(&path, true) {
+                    continue;
+                }
+                folder_paths.insert(item.id.clone(), path.clone());
+                folders.insert(path, item);
+            }
+            unplaced = still_unplaced;
+            if unplaced.is_empty() || unplaced.len() == before {
+                break;
+            }
+        }
+        if !unplaced.is_empty() {
+            complete = false;
+        }
+
+        let mut files = HashMap::new();
+        for item in file_changes {
+            if item.deleted.is_some() {
+                complete = false;
+                continue;
+            }
+            let Some(parent) = parent_path(&item, &folder_paths) else {
+                complete = false;
+                continue;
+            };
+            let path = item_path(&parent, &item.name);
+            if known_paths.get(item.id.as_str()).is_some_and(|known| *known != path) {
+                complete = false;
+                continue;
+            }
+            if self.filter.is_excluded(&path, false) || has_extension(&self.config.excluded_extensions, &path) {
+                continue;
+            }
+            files.insert(path, item);
+        }
+
+        (files, folders, complete)
+    }
+
+    /// Downloads, new local folders and placeholder updates for the changed
+    /// remote `files` and `folders`, where the local side is as last synced.
+    /// Anything else, such as a local edit of the same file, waits for the
+    /// next full sync.
+    async fn plan_remote_downloads(
+        &self,
+        files: &HashMap<String, DriveItem>,
+        folders: &HashMap<String, DriveItem>,
+    ) -> Result<Vec<SyncAction>> {
         let stored_files = self.get_stored_files().await?;
-        info!("=== DATABASE SCAN COMPLETE: {} files ===", stored_files.len());
+        let archived = self.get_archived_files().await?;
+        let placeholders = self.get_placeholders().await?;
+        let name_limit = filesystem::name_limit(&self.config.sync_folder);
 
-        // Step 4: Determine sync actions
+        // Which of the changed paths and their folders exist here, and which of
+        // those folders are excluded, looked up once off the async runtime
+        let candidates: HashSet<String> = files
+            .keys()
+            .chain(folders.keys())
+            .flat_map(|path| path_and_ancestors(path))
+            .map(str::to_string)
+            .collect();
+        let config = self.config.clone();
+        let (existing, excluded_folders) = tokio::task::spawn_blocking(move || {
+            let existing: HashSet<String> = candidates.into_iter().filter(|path| config.sync_folder.join(path).exists()).collect();
+            let excluded_folders: HashSet<String> = existing
+                .iter()
+                .filter(|path| {
+                    let folder = config.sync_folder.join(path);
+                    folder.is_dir() && excluded_folder_reason(&config, &folder).is_some()
+                })
+                .cloned()
+                .collect();
+            (existing, excluded_folders)
+        })
+        .await?;
+        let skipped = |path: &str| {
+            // Held for names too long for an encrypted folder, and for local exclusions
+            if name_limit.as_ref().is_some_and(|limit| path.split('/').any(|name| name.len() > limit.max_bytes)) {
+                return true;
+            }
+            path_and_ancestors(path).any(|folder| excluded_folders.contains(folder))
+        };
+
+        let mut actions = Vec::new();
+        let mut new_folders: Vec<(&String, &DriveItem)> = folders
+            .iter()
+            .filter(|(path, _)| !existing.contains(path.as_str()) && !skipped(path))
+            .collect();
+        new_folders.sort_by(|a, b| a.0.cmp(b.0));
+        for (path, item) in new_folders {
+            info!("New remote folder found: {}", path);
+            actions.push(SyncAction::CreateLocalFolder { remote_item: item.clone(), path: path.clone() });
+        }
+
+        let mut changed_files: Vec<(&String, &DriveItem)> = files.iter().collect();
+        changed_files.sort_by(|a, b| a.0.cmp(b.0));
+        for (path, remote_file) in changed_files {
+            if archived.contains(path) || skipped(path) {
+                continue;
+            }
+            if remote_file.mime_type().is_some_and(|mime_type| matches_mime_type(&self.config.download_exclude_mime_types, mime_type)) {
+                continue;
+            }
+            if let Some(placeholder) = placeholders.get(path) {
+                if placeholder.modified != remote_file.last_modified || Some(placeholder.size) != remote_file.size {
+                    info!("Online-only file changed on OneDrive: {}", path);
+                    actions.push(SyncAction::CreatePlaceholder { remote_item: remote_file.clone(), local_path: path.clone() });
+                }
+                continue;
+            }
+
+            let local = fs::metadata(self.config.sync_folder.join(path)).await.ok();
+            let download = match (stored_files.get(path), local) {
+                (None, None) if self.config.online_only => {
+                    info!("New remote file found, kept online-only: {}", path);
+                    actions.push(SyncAction::CreatePlaceholder { remote_item: remote_file.clone(), local_path: path.clone() });
+                    false
+                }
+                (None, None) => {
+                    info!("New remote file found: {}", path);
+                    true
+                }
+                (Some(stored_file), Some(metadata)) => {
+                    let modified = metadata
+                        .modified()
+                        .unwrap_or(SystemTime::UNIX_EPOCH)
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    let unchanged = metadata.is_file() && metadata.len() == stored_file.size && modified == stored_file.modified;
+                    if unchanged && is_remote_newer(remote_file, stored_file) {
+                        info!("Remote file newer than local: {}", path);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                // Deleted here or new on both sides: for the full sync to decide
+                _ => false,
+            };
+            if download {
+                actions.push(SyncAction::Download { remote_item: remote_file.clone(), local_path: path.clone() });
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Gives a new sync run its ID and publishes it in the status.
+    async fn start_run_id(&mut self) -> String {
+        let run_id = new_run_id();
+        self.run_id = Some(run_id.clone());
+        let status_run_id = run_id.clone();
         self.update_status(|status| {
-            status.current_operation = "Determining sync actions...".to_string();
-            status.sync_progress = 0.5;
+            status.run_id = Some(status_run_id);
         }).await;
-        
-        let actions = self.determine_sync_actions(&local_files, &remote_files, &stored_files)?;
-        info!("=== SYNC ACTIONS DETERMINED: {} actions ===", actions.len());
+        run_id
+    }
 
-        // Update total files count
+    /// Uploads what was edited while the client was not running, planned like
+    /// a sync so an edit also made on OneDrive becomes a conflict and the
+    /// exclude rules, the drive check and every hold apply. Tracked files whose
+    /// size and mtime are unchanged skip the remote scan altogether.
+    async fn upload_offline_changes(&mut self) -> Result<usize> {
+        // A queue left unfinished is for the next full sync to resume
+        if !self.load_queued_actions().await?.is_empty() {
+            return Ok(0);
+        }
+
+        let stored_files = self.get_stored_files().await?;
+        let mut edited = false;
+        for (path, stored_file) in &stored_files {
+            let Ok(metadata) = fs::metadata(self.config.sync_folder.join(path)).await else {
+                continue;
+            };
+            let modified = metadata
+                .modified()
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            if metadata.len() != stored_file.size || modified != stored_file.modified {
+                debug!("Tracked file changed while the client was not running: {}", path);
+                edited = true;
+                break;
+            }
+        }
+        if !edited {
+            return Ok(0);
+        }
+
+        self.check_drive_identity().await?;
+        self.reload_filter();
+
+        // Planned like a preview, so nothing is recorded for the actions left out
+        let cancel = self.control.start_run();
+        let actions: Vec<SyncAction> = self
+            .plan_sync(true, &cancel)
+            .await?
+            .into_iter()
+            .filter(|action| {
+                matches!(action, SyncAction::Upload { local_path, .. } | SyncAction::ResolveConflict { local_path, .. }
+                    if stored_files.contains_key(local_path))
+            })
+            .collect();
+        let count = actions.len();
+        self.execute_actions(actions, &cancel).await?;
+        Ok(count)
+    }
+
+    async fn perform_sync(&mut self, cancel: &CancellationToken) -> Result<()> {
+        info!("=== STARTING SYNC PROCESS ===");
+
+        self.check_drive_identity().await?;
+
+        // Pick up edits to the exclude rules since the last run
+        self.reload_filter();
+
+        // An interrupted initial download picks up from its persisted plan instead
+        // of rescanning and replanning the whole drive
+        let planned_downloads = self.load_initial_download_plan().await?;
+        if !planned_downloads.is_empty() {
+            info!("=== RESUMING INITIAL DOWNLOAD: {} files remaining ===", planned_downloads.len());
+            // Whatever it left queued is in the plan too
+            self.drop_queued_actions().await?;
+            let remaining = self.skip_completed_downloads(planned_downloads).await?;
+            let span = tracing::info_span!("execute", actions = remaining.len(), resumed = true);
+            self.execute_actions(remaining, cancel).instrument(span).await?;
+            info!("=== SYNC PROCESS COMPLETE ===");
+            return Ok(());
+        }
+
+        // Actions a pause, a quit or a crash left in the queue go ahead where
+        // the files are still as they were planned against. Planning afresh is
+        // only needed when some of them no longer fit.
+        let queued_actions = self.load_queued_actions().await?;
+        if !queued_actions.is_empty() {
+            info!("=== RESUMING: {} queued actions left from an earlier sync ===", queued_actions.len());
+            let queued = queued_actions.len();
+            let resumed = self.recheck_queued_actions(queued_actions, true).await?;
+            let stale = queued - resumed.len();
+            self.drop_queued_actions().await?;
+
+            let span = tracing::info_span!("execute", actions = resumed.len(), resumed = true);
+            self.execute_actions(resumed, cancel).instrument(span).await?;
+            if stale == 0 || self.control.is_paused() || self.control.is_stopping() || cancel.is_cancelled() {
+                info!("=== SYNC PROCESS COMPLETE ===");
+                return Ok(());
+            }
+            info!("{} queued actions no longer apply; planning afresh", stale);
+        }
+
+        let actions = self.plan_sync(false, cancel).instrument(tracing::info_span!("plan")).await?;
+        let span = tracing::info_span!("execute", actions = actions.len());
+        self.execute_actions(actions, cancel).instrument(span).await?;
+
+        info!("=== SYNC PROCESS COMPLETE ===");
+        Ok(())
+    }
+
+    /// Reads the exclude rules again. The remote listing kept from the last run
+    /// was filtered with the old rules, so it goes when they change.
+    fn reload_filter(&mut self) {
+        let filter = SyncFilter::load(&self.config.sync_folder, &self.config.exclude_patterns);
+        if filter != self.filter {
+            self.remote_snapshot = None;
+        }
+        self.filter = filter;
+    }
+
+    /// Works out what the next sync would do without changing anything locally,
+    /// on OneDrive or in the database.
+    pub async fn preview_sync(&mut self) -> Result<Vec<SyncAction>> {
+        if self.status.lock().await.is_syncing {
+            return Err(anyhow!("Sync already in progress"));
+        }
+
+        info!("=== PREVIEWING SYNC ===");
+        self.check_drive_identity().await?;
+        self.reload_filter();
+
+        // An interrupted initial download resumes its saved plan rather than
+        // replanning, and so does a queue whose actions all still apply. Only
+        // their local side is checked here, to spare OneDrive the lookups.
+        let mut actions = self.load_initial_download_plan().await?;
+        if actions.is_empty() {
+            let queued = self.load_queued_actions().await?;
+            let queued_count = queued.len();
+            actions = self.recheck_queued_actions(queued, false).await?;
+            if actions.len() < queued_count {
+                actions.clear();
+            }
+        }
+        if actions.is_empty() {
+            actions = self.plan_sync(true, &CancellationToken::new()).await?;
+        }
+
+        let summary = format!("Preview: {} changes to sync", actions.len());
         self.update_status(|status| {
-            status.total_files = (local_files.len() + remote_files.len()) as u64;
+            status.current_operation = summary;
+            status.sync_progress = 0.0;
         }).await;
 
-        // Step 5: Execute sync actions
-        let total_actions = actions.len();
-        if total_actions == 0 {
-            info!("=== NO SYNC ACTIONS NEEDED - EVERYTHING UP TO DATE ===");
+        Ok(actions)
+    }
+
+    /// Runs SQLite's full integrity check on the sync database and returns the
+    /// problems it finds; none when the database is sound.
+    pub async fn check_database(&self) -> Result<Vec<String>> {
+        self.store
+            .call(|db| {
+                let problems = db
+                    .prepare("PRAGMA integrity_check")?
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?
+                    .into_iter()
+                    .filter(|line| line != "ok")
+                    .collect();
+                Ok(problems)
+            })
+            .await
+    }
+
+    /// Tidies up after an earlier run that was interrupted: `.partial` downloads
+    /// that are a week old are deleted, and `.partial` files the database has
+    /// no record of are left alone as the user's own;
+    /// saved upload sessions for files that changed since, or that OneDrive has
+    /// expired, are dropped; and the database is checked. Downloads and uploads
+    /// that can still be resumed are left for the sync to continue. Runs before
+    /// the first sync of each process.
+    pub async fn recover_interrupted(&self) -> Result<RecoveryReport> {
+        let mut report = RecoveryReport {
+            journal_replayed: self.journal_left_over,
+            ..RecoveryReport::default()
+        };
+
+        let (partial_records, sessions, problems) = self
+            .store
+            .call(|db| {
+                let partial_records: HashMap<String, u64> = db
+                    .prepare("SELECT path, started FROM partial_downloads")?
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<rusqlite::Result<_>>()?;
+                let sessions: Vec<(String, String, u64, u64)> = db
+                    .prepare("SELECT path, upload_url, size, modified FROM upload_sessions")?
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+                    .collect::<rusqlite::Result<_>>()?;
+                let problems: Vec<String> = db
+                    .prepare("PRAGMA quick_check")?
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?
+                    .into_iter()
+                    .filter(|line| line != "ok")
+                    .collect();
+                Ok((partial_records, sessions, problems))
+            })
+            .await?;
+        report.database_problems = problems;
+
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let mut found_partials = HashSet::new();
+        for entry in WalkDir::new(&self.config.sync_folder).into_iter().filter_map(|e| e.ok()) {
+            let Some(path) = entry
+                .path()
+                .strip_prefix(&self.config.sync_folder)
+                .ok()
+                .map(|path| path.to_string_lossy().replace('\\', "/"))
+                .and_then(|path| path.strip_suffix(PARTIAL_DOWNLOAD_SUFFIX).map(str::to_string))
+            else {
+                continue;
+            };
+            // Not one of ours: a file of the user's that ends in `.partial`
+            let Some(started) = partial_records.get(&path) else {
+                continue;
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let resumable = now.saturating_sub(*started) < PARTIAL_DOWNLOAD_MAX_AGE.as_secs();
+            if resumable {
+                report.resumable_downloads += 1;
+                found_partials.insert(path);
+            } else {
+                info!("Removing leftover partial download: {}", entry.path().display());
+                if let Err(e) = fs::remove_file(entry.path()).await {
+                    warn!("Failed to remove {}: {}", entry.path().display(), e);
+                    continue;
+                }
+                report.removed_partials.push(path);
+            }
+        }
+
+        for (path, upload_url, size, modified) in sessions {
+            let local_modified = std::fs::metadata(self.config.sync_folder.join(&path)).ok().and_then(|metadata| {
+                let modified = metadata.modified().ok()?.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs();
+                Some((metadata.len(), modified))
+            });
+            let resumable = local_modified == Some((size, modified))
+                && matches!(self.api.upload_session_offset(&upload_url).await, Ok(Some(_)));
+            if resumable {
+                report.resumable_uploads += 1;
+            } else {
+                info!("Dropping upload session that can no longer be resumed: {}", path);
+                report.dropped_upload_sessions.push(path);
+            }
+        }
+
+        // Records of partial downloads whose file is gone are of no further use
+        let dropped_sessions = report.dropped_upload_sessions.clone();
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                for path in partial_records.keys().filter(|path| !found_partials.contains(*path)) {
+                    tx.execute("DELETE FROM partial_downloads WHERE path = ?1", params![path])?;
+                }
+                for path in &dropped_sessions {
+                    tx.execute("DELETE FROM upload_sessions WHERE path = ?1", params![path])?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(report)
+    }
+
+    /// Checks every tracked file against the hashes recorded at its last sync:
+    /// the local copy is hashed again and OneDrive is listed afresh. Nothing is
+    /// transferred or changed; the result only reports what no longer matches,
+    /// e.g. after a crash or disk problems.
+    pub async fn verify(&mut self) -> Result<VerifyReport> {
+        if self.status.lock().await.is_syncing {
+            return Err(anyhow!("Sync already in progress"));
+        }
+
+        info!("=== VERIFYING SYNCED FILES ===");
+        self.check_drive_identity().await?;
+        self.reload_filter();
+
+        let stored_files = self.get_stored_files().await?;
+        let mut remote_files = HashMap::new();
+        let mut remote_folders = HashMap::new();
+        self.scan_remote_folder(&mut remote_files, &mut remote_folders, "/").await?;
+
+        let mut paths: Vec<&String> = stored_files
+            .keys()
+            .filter(|path| !self.filter.is_excluded(path, false) && !has_extension(&self.config.excluded_extensions, path))
+            .collect();
+        paths.sort();
+
+        let mut report = VerifyReport::default();
+        for (index, path) in paths.iter().enumerate() {
+            let stored = &stored_files[*path];
             self.update_status(|status| {
-                status.current_operation = "All files are up to date".to_string();
-                status.sync_progress = 1.0;
+                status.current_operation = format!("Verifying {} of {}: {}", index + 1, paths.len(), path);
             }).await;
-        } else {
-            info!("=== EXECUTING {} SYNC ACTIONS ===", total_actions);
-            for (i, action) in actions.into_iter().enumerate() {
-                let progress = 0.5 + (0.4 * (i as f32 / total_actions as f32));
-                
-                let operation_desc = match &action {
-                    SyncAction::Upload { local_path, .. } => format!("Uploading {}", local_path),
-                    SyncAction::Download { local_path, .. } => format!("Downloading {}", local_path),
-                    SyncAction::RemoveFromDatabase { path } => format!("Cleaning up {}", path),
-                };
-                
-                info!("=== EXECUTING: {} ===", operation_desc);
-                
-                self.update_status(|status| {
-                    status.current_operation = operation_desc;
-                    status.sync_progress = progress;
-                }).await;
-                
-                if let Err(e) = self.execute_sync_action(action).await {
-                    error!("Sync action failed: {}", e);
-                    self.update_status(|status| {
-                        status.sync_errors.push(e.to_string());
-                    }).await;
-                    // Continue with other actions
+            report.checked_files += 1;
+
+            let local_path = self.config.sync_folder.join(path);
+            if fs::symlink_metadata(&local_path).await.is_err() {
+                report.mismatches.push((path.to_string(), Mismatch::MissingLocally));
+            } else {
+                match self.calculate_file_hashes(&local_path).await {
+                    Ok((hash, _)) if hash != stored.hash => {
+                        report.mismatches.push((path.to_string(), Mismatch::LocalContentChanged));
+                    }
+                    Ok(_) => {}
+                    Err(e) => report.unreadable.push((path.to_string(), e.to_string())),
+                }
+            }
+
+            match remote_files.get(*path) {
+                None => report.mismatches.push((path.to_string(), Mismatch::MissingRemotely)),
+                Some(remote_file) => {
+                    let changed = match (remote_file.quick_xor_hash(), stored.quick_xor_hash.as_deref()) {
+                        (Some(remote_hash), Some(stored_hash)) => remote_hash != stored_hash,
+                        _ => remote_file.size.unwrap_or(0) != stored.size,
+                    };
+                    if changed {
+                        report.mismatches.push((path.to_string(), Mismatch::RemoteContentChanged));
+                    }
+                }
+            }
+        }
+
+        let summary = format!(
+            "Verified {} files: {} mismatches, {} unreadable",
+            report.checked_files,
+            report.mismatches.len(),
+            report.unreadable.len()
+        );
+        info!("{}", summary);
+        self.log_sync_event("verify", "", if report.is_clean() { "success" } else { "conflict" }, Some(&summary)).await?;
+        self.update_status(|status| status.current_operation = summary).await;
+
+        Ok(report)
+    }
+
+    /// Scans local files, OneDrive and the database and decides the sync actions.
+    /// A `dry_run` records nothing, so the plan can be shown as a preview.
+    async fn plan_sync(&mut self, dry_run: bool, cancel: &CancellationToken) -> Result<Vec<SyncAction>> {
+        // Step 1: Get local file state
+        self.update_status(|status| {
+            status.current_operation = "Scanning local files...".to_string();
+            status.sync_progress = 0.1;
+        }).await;
+        
+        if self.config.rename_illegal_names && !dry_run {
+            self.rename_illegal_names().await?;
+        }
+        let (mut local_files, mut local_folders, excluded, mut skipped) =
+            self.scan_local_files().instrument(tracing::info_span!("scan_local")).await?;
+        info!("=== LOCAL SCAN COMPLETE: {} files, {} folders ===", local_files.len(), local_folders.len());
+        if cancel.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+
+        // Step 2: Get remote file state
+        self.update_status(|status| {
+            status.current_operation = "Scanning remote files...".to_string();
+            status.sync_progress = 0.3;
+        }).await;
+        
+        let (mut remote_files, mut remote_folders) = self.scan_remote_files().instrument(tracing::info_span!("scan_remote")).await?;
+
+        // OneDrive ignores letter case, so local paths that differ only in case
+        // would overwrite each other there; both, and their OneDrive copy, are
+        // left alone until one is renamed
+        let case_conflicts = find_case_conflicts(local_files.keys().chain(local_folders.iter()));
+        if !case_conflicts.is_empty() {
+            let conflict_of = |path: &str| {
+                let path = path.to_lowercase();
+                case_conflicts.iter().find(|paths| is_same_or_inside(&path, &paths[0].to_lowercase()))
+            };
+            local_files.retain(|path, _| match conflict_of(path) {
+                Some(paths) => {
+                    skipped.push(SkippedFile {
+                        path: path.clone(),
+                        reason: format!("Names differ only in letter case ({}), which OneDrive cannot tell apart; rename one", paths.join(", ")),
+                        kind: SkipKind::CaseConflict,
+                    });
+                    false
+                }
+                None => true,
+            });
+            local_folders.retain(|path| conflict_of(path).is_none());
+            remote_files.retain(|path, _| conflict_of(path).is_none());
+            remote_folders.retain(|path, _| conflict_of(path).is_none());
+
+            for paths in &case_conflicts {
+                warn!("Not syncing paths that differ only in case: {}", paths.join(", "));
+            }
+            if !dry_run {
+                let messages: Vec<String> = case_conflicts
+                    .iter()
+                    .map(|paths| format!(
+                        "Not syncing {}: the names differ only in letter case, which OneDrive cannot tell apart. Rename one of them.",
+                        paths.join(" and ")
+                    ))
+                    .collect();
+                self.update_status(|status| status.sync_errors.extend(messages)).await;
+            }
+        }
+
+        // Names longer than an encrypted sync folder can store would fail to
+        // download; the file or folder is left on OneDrive until renamed
+        if let Some(limit) = filesystem::name_limit(&self.config.sync_folder) {
+            let too_long: BTreeSet<String> = remote_files
+                .keys()
+                .chain(remote_folders.keys())
+                .filter_map(|path| {
+                    let mut end = 0;
+                    for name in path.split('/') {
+                        end += name.len();
+                        if name.len() > limit.max_bytes {
+                            return Some(path[..end].to_string());
+                        }
+                        end += 1;
+                    }
+                    None
+                })
+                .collect();
+            if !too_long.is_empty() {
+                let inside_too_long = |path: &str| too_long.iter().any(|long| is_same_or_inside(path, long));
+                remote_files.retain(|path, _| !inside_too_long(path));
+                remote_folders.retain(|path, _| !inside_too_long(path));
+
+                let reason = format!("The name is longer than the {} bytes {} can store; rename it on OneDrive", limit.max_bytes, limit.filesystem);
+                warn!("Not downloading {} items whose names are too long for {}", too_long.len(), limit.filesystem);
+                if !dry_run {
+                    let message = format!(
+                        "{} items on OneDrive have names longer than {} can store ({} bytes) and were not downloaded. Rename them on OneDrive to sync them.",
+                        format::count(too_long.len() as u64),
+                        limit.filesystem,
+                        limit.max_bytes
+                    );
+                    self.update_status(|status| status.sync_errors.push(message)).await;
+                }
+                skipped.extend(too_long.into_iter().map(|path| SkippedFile {
+                    path,
+                    reason: reason.clone(),
+                    kind: SkipKind::NameTooLong,
+                }));
+            }
+        }
+
+        // A skipped file is left alone on both sides; its OneDrive copy must not
+        // be downloaded over it
+        for skipped_file in &skipped {
+            remote_files.remove(&skipped_file.path);
+        }
+        // Neither is anything in a folder excluded locally, such as one another
+        // sync client's marker file claims; the remote listing only knows names
+        if !excluded.is_empty() {
+            let is_excluded = |path: &str| excluded.iter().any(|folder| is_same_or_inside(path, folder));
+            remote_files.retain(|path, _| !is_excluded(path));
+            remote_folders.retain(|path, _| !is_excluded(path));
+        }
+        info!("=== REMOTE SCAN COMPLETE: {} files, {} folders ===", remote_files.len(), remote_folders.len());
+        if cancel.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+
+        // Step 3: Get stored sync state
+        self.update_status(|status| {
+            status.current_operation = "Loading sync database...".to_string();
+            status.sync_progress = 0.4;
+        }).await;
+        
+        let span = tracing::info_span!("load_database");
+        let stored_files = self.get_stored_files().instrument(span.clone()).await?;
+        let stored_folders = self.get_stored_folders().instrument(span).await?;
+        info!("=== DATABASE SCAN COMPLETE: {} files, {} folders ===", stored_files.len(), stored_folders.len());
+
+        // Archived files stay on OneDrive only until they show up locally again
+        let archived = self.get_archived_files().await?;
+        if !dry_run {
+            let restored: Vec<String> = archived
+                .iter()
+                .filter(|path| local_files.contains_key(*path) || !remote_files.contains_key(*path))
+                .cloned()
+                .collect();
+            self.forget_archived_files(restored).await?;
+        }
+        remote_files.retain(|path, _| !archived.contains(path) || local_files.contains_key(path));
+
+        // A placeholder goes once the file is here for real or gone from OneDrive
+        let mut placeholders = self.get_placeholders().await?;
+        if !dry_run {
+            let stale: Vec<String> = placeholders
+                .keys()
+                .filter(|path| local_files.contains_key(*path) || !remote_files.contains_key(*path))
+                .cloned()
+                .collect();
+            self.remove_placeholders(stale).await?;
+        }
+        placeholders.retain(|path, _| !local_files.contains_key(path) && remote_files.contains_key(path));
+
+        // Step 4: Determine sync actions
+        self.update_status(|status| {
+            status.current_operation = "Determining sync actions...".to_string();
+            status.sync_progress = 0.5;
+        }).await;
+        
+        // Folders renamed on OneDrive are renamed locally in one step, contents included
+        let mut actions = tracing::info_span!("reconcile").in_scope(|| {
+            let folder_moves = detect_remote_folder_moves(&local_folders, &remote_folders, &stored_folders, &local_files, &stored_files);
+            let mut actions = self.determine_folder_actions(&local_folders, &remote_folders, &stored_folders, &folder_moves);
+            actions.extend(self.determine_sync_actions(&local_files, &remote_files, &stored_files, &placeholders, &folder_moves)?);
+            Ok::<_, anyhow::Error>(actions)
+        })?;
+        actions = self.hold_unsyncable_files(actions, dry_run).await?;
+        actions = self.hold_emptied_files(actions, &local_files, &stored_files, dry_run).await?;
+        actions = self.hold_uploads_for_mass_change(actions, &stored_files, dry_run).await?;
+        actions = self.defer_files_being_written(actions, dry_run).await;
+        actions.extend(self.plan_archiving(&local_files, &remote_files, &stored_files));
+        info!("=== SYNC ACTIONS DETERMINED: {} actions ===", actions.len());
+
+        let deletions = actions.iter().filter(|action| matches!(action, SyncAction::DeleteLocal { .. })).count();
+        let mass_delete = deletions * 2 > stored_files.len() && (deletions >= MASS_DELETE_MIN_FILES || deletions == stored_files.len());
+        if deletions > 1 && mass_delete {
+            error!("Refusing to delete {} of {} tracked files", deletions, stored_files.len());
+            return Err(anyhow!(
+                "OneDrive no longer lists {} of your {} synced files, so syncing would delete them from this computer. Nothing was deleted. If you removed them on purpose, delete the local copies too.",
+                deletions,
+                stored_files.len()
+            ));
+        }
+
+        if stored_files.is_empty() {
+            self.schedule_initial_download(&mut actions);
+        } else if self.config.download_newest_first {
+            schedule_newest_first(&mut actions);
+        }
+
+        if dry_run {
+            return Ok(actions);
+        }
+
+        // Folders that already exist on both sides only need to be tracked
+        self.record_existing_folders(&local_folders, &remote_folders, &stored_folders).await?;
+        skipped.extend(excluded.into_iter().map(|path| SkippedFile {
+            path,
+            reason: "Matches an exclude pattern or excluded folder in settings".to_string(),
+            kind: SkipKind::Excluded,
+        }));
+        skipped.sort_by(|a, b| (a.kind, &a.path).cmp(&(b.kind, &b.path)));
+        self.record_file_states(&actions, skipped.iter().map(|skipped_file| skipped_file.path.clone()).collect()).await?;
+
+        // Update total files count
+        self.update_status(|status| {
+            status.total_files = (local_files.len() + remote_files.len()) as u64;
+            status.skipped_files = skipped;
+        }).await;
+
+        if stored_files.is_empty() {
+            self.save_initial_download_plan(&actions).await?;
+        }
+
+        Ok(actions)
+    }
+
+    /// Refuses to sync when the account now resolves to a different drive than the
+    /// one the database was built against (tenant migration, personal to business).
+    /// Reconciling two unrelated drives would either delete or duplicate everything.
+    async fn check_drive_identity(&self) -> Result<()> {
+        let drive_info = self.api.get_drive_info().await?;
+
+        match self.store.get_state("drive_id").await? {
+            None => {
+                info!("Linking sync database to drive {}", drive_info.id);
+                self.store.set_state("drive_id", &drive_info.id).await?;
+            }
+            Some(stored_drive_id) if stored_drive_id != drive_info.id => {
+                error!("Drive ID changed from {} to {}", stored_drive_id, drive_info.id);
+                self.update_status(|status| {
+                    status.relink_required = true;
+                }).await;
+                return Err(anyhow!(
+                    "Your account now points to a different OneDrive ({}). Syncing is stopped until you re-link the sync folder.",
+                    drive_info.drive_type
+                ));
+            }
+            Some(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Re-links the sync folder to the account's current drive. Tracking state from
+    /// the old drive is dropped so the next sync reconciles both sides as a first
+    /// sync: files present on both sides are kept, nothing is deleted.
+    pub async fn relink_drive(&mut self) -> Result<()> {
+        let drive_info = self.api.get_drive_info().await?;
+
+        let drive_id = drive_info.id.clone();
+        let placeholders: Vec<String> = self.get_placeholders().await?.into_keys().collect();
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                tx.execute("DELETE FROM files", [])?;
+                tx.execute("DELETE FROM folders", [])?;
+                tx.execute("DELETE FROM initial_download_plan", [])?;
+                tx.execute("DELETE FROM archived_files", [])?;
+                tx.execute("DELETE FROM read_only_folders", [])?;
+                // Queued for the old drive, and would replay against the new one
+                tx.execute("DELETE FROM pending_actions", [])?;
+                tx.execute("DELETE FROM upload_sessions", [])?;
+                // Stand for the old drive's files
+                tx.execute("DELETE FROM placeholders", [])?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO sync_state (key, value) VALUES ('drive_id', ?1)",
+                    params![drive_id],
+                )?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await?;
+        self.remote_snapshot = None;
+        for path in placeholders {
+            let placeholder_file = self.config.sync_folder.join(placeholder_path(&path));
+            if let Err(e) = fs::remove_file(&placeholder_file).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove the placeholder {}: {}", placeholder_file.display(), e);
+                }
+            }
+        }
+
+        self.update_status(|status| {
+            status.relink_required = false;
+            status.sync_errors.clear();
+        }).await;
+        self.log_sync_event("relink_drive", "", "success", None).await?;
+        info!("Sync folder re-linked to drive {}", drive_info.id);
+
+        Ok(())
+    }
+
+    /// Adds the sidebar bookmark and folder icon after the first successful sync.
+    async fn ensure_folder_integration(&self) {
+        if !self.config.folder_integration {
+            return;
+        }
+
+        match self.store.get_state("folder_integration_installed").await {
+            Ok(Some(_)) => {}
+            Ok(None) => match desktop::install_folder_integration(&self.config.sync_folder) {
+                Ok(_) => {
+                    if let Err(e) = self.store.set_state("folder_integration_installed", "1").await {
+                        warn!("Failed to record folder integration state: {}", e);
+                    }
+                }
+                Err(e) => warn!("Failed to set up file manager integration: {}", e),
+            },
+            Err(e) => warn!("Failed to read folder integration state: {}", e),
+        }
+    }
+
+    /// Removes the sidebar bookmark and folder icon, e.g. when the account is removed.
+    pub async fn remove_folder_integration(&self) -> Result<()> {
+        desktop::remove_folder_integration(&self.config.sync_folder)?;
+
+        self.store
+            .call(|db| {
+                db.execute("DELETE FROM sync_state WHERE key = 'folder_integration_installed'", [])?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Persists the parts of the status worth showing after a restart: when the
+    /// last sync finished, its last error and how much work was left.
+    async fn save_status_snapshot(&self) -> Result<()> {
+        let status = self.get_status().await;
+        let last_sync = status
+            .last_sync
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs().to_string())
+            .unwrap_or_default();
+        let last_error = status.sync_errors.last().cloned().unwrap_or_default();
+
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                for (key, value) in [
+                    ("status_last_sync", last_sync),
+                    ("status_last_error", last_error),
+                    ("status_pending_actions", status.pending_actions.to_string()),
+                    ("status_run_id", status.run_id.unwrap_or_default()),
+                ] {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+                        params![key, value],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn execute_actions(&mut self, actions: Vec<SyncAction>, cancel: &CancellationToken) -> Result<()> {
+        let total_actions = actions.len();
+        if total_actions == 0 {
+            info!("=== NO SYNC ACTIONS NEEDED - EVERYTHING UP TO DATE ===");
+            self.update_status(|status| {
+                status.current_operation = "All files are up to date".to_string();
+                status.sync_progress = 1.0;
+                status.pending_actions = 0;
+            }).await;
+        } else {
+            info!("=== EXECUTING {} SYNC ACTIONS ===", total_actions);
+            self.check_free_space(&actions)?;
+            self.update_status(|status| {
+                status.pending_actions = total_actions as u64;
+            }).await;
+
+            // Kept in the database as it runs, so neither the GUI nor a restart
+            // depends on this process to know what is left
+            let actions = self.queue_actions(actions).await?;
+
+            // Folder and bookkeeping actions run first and in order, so transfers
+            // never race the creation of the folders they land in
+            let (transfers, others): (Vec<(i64, SyncAction)>, Vec<(i64, SyncAction)>) = actions
+                .into_iter()
+                .partition(|(_, action)| matches!(
+                    action,
+                    SyncAction::Upload { .. } | SyncAction::Download { .. } | SyncAction::ResolveConflict { .. }
+                ));
+
+            // Pausing stops at the next checkpoint between actions; whatever has
+            // not started yet stays queued for when syncing resumes. Quitting
+            // requeues the transfers it abandoned too.
+            let mut deferred = Vec::new();
+
+            // New OneDrive folders go out in batched requests ahead of the rest
+            let (new_folders, others): (Vec<(i64, SyncAction)>, Vec<(i64, SyncAction)>) = others
+                .into_iter()
+                .partition(|(_, action)| matches!(action, SyncAction::CreateRemoteFolder { .. }));
+            let new_folders: Vec<(i64, String)> = new_folders
+                .into_iter()
+                .filter_map(|(position, action)| match action {
+                    SyncAction::CreateRemoteFolder { path } => Some((position, path)),
+                    _ => None,
+                })
+                .collect();
+
+            let mut completed = 0;
+            if self.control.is_paused() || self.control.is_stopping() {
+                deferred.extend(new_folders.iter().map(|(position, _)| *position));
+            } else if !new_folders.is_empty() && !cancel.is_cancelled() {
+                completed += self.create_remote_folders(new_folders).await?;
+            }
+            for (position, action) in others {
+                if self.control.is_paused() || self.control.is_stopping() {
+                    deferred.push(position);
+                    continue;
+                }
+                if cancel.is_cancelled() {
+                    break;
+                }
+
+                let operation_desc = describe_action(&action);
+                info!("=== EXECUTING: {} ===", operation_desc);
+
+                let progress = 0.5 + (0.4 * (completed as f32 / total_actions as f32));
+                self.update_status(|status| {
+                    status.current_operation = operation_desc;
+                    status.sync_progress = progress;
+                }).await;
+
+                self.set_action_status(vec![position], "in_progress", None).await?;
+                let span = tracing::info_span!("action", kind = action.kind(), summary = %action.summary());
+                let result = self.execute_sync_action(action, cancel).instrument(span).await;
+                self.finish_queued_action(position, &result).await?;
+                if let Err(e) = result {
+                    error!("Sync action failed: {}", e);
+                    self.update_status(|status| {
+                        status.sync_errors.push(e.to_string());
+                    }).await;
+                    // Continue with other actions
+                }
+                completed += 1;
+                self.update_status(|status| {
+                    status.pending_actions = status.pending_actions.saturating_sub(1);
+                }).await;
+            }
+
+            let transfers = self.hold_uploads_over_quota(transfers).await?;
+            // Looked up before the downloads replace the local copies
+            let folder_changes: HashMap<String, FolderChange> = transfers
+                .iter()
+                .filter_map(|(_, action)| match action {
+                    SyncAction::Download { remote_item, local_path } if self.notifier.watched_folder(local_path).is_some() => Some((
+                        local_path.clone(),
+                        FolderChange {
+                            path: local_path.clone(),
+                            changed_by: remote_item.modified_by().map(str::to_string),
+                            added: !self.config.sync_folder.join(local_path).exists(),
+                        },
+                    )),
+                    _ => None,
+                })
+                .collect();
+            let transfer_bytes = self.transfer_bytes(transfers.iter().map(|(_, action)| action));
+            self.throughput.lock().unwrap().expect(transfer_bytes);
+
+            // Uploads and downloads run through a bounded worker pool; buffer_unordered
+            // starts them in plan order, so the initial download schedule still holds
+            let max_concurrent = self.config.max_concurrent_transfers.max(1);
+            info!("Running {} transfers with up to {} in parallel", transfers.len(), max_concurrent);
+
+            // A laptop suspending halfway through a large upload or download
+            // would stall it until the next sync
+            let sleep_inhibitor = if self.config.inhibit_sleep_during_transfers
+                && transfer_bytes >= INHIBIT_SLEEP_MIN_BYTES
+            {
+                match desktop::inhibit_sleep("Syncing files with OneDrive") {
+                    Ok(inhibitor) => Some(inhibitor),
+                    Err(e) => {
+                        warn!("Could not prevent sleep during transfers: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            let sleep_inhibited = sleep_inhibitor.is_some();
+            self.update_status(|status| {
+                status.sleep_inhibited = sleep_inhibited;
+            }).await;
+
+            // Set when OneDrive reports itself full mid-sync, so the uploads
+            // still queued wait for the next sync instead of failing one by one
+            let quota_full = AtomicBool::new(false);
+
+            let this = &*self;
+            let quota_full = &quota_full;
+            let mut pending = stream::iter(transfers.into_iter().map(|(position, action)| async move {
+                if this.control.is_paused() || this.control.is_stopping() {
+                    return Err(position);
+                }
+                if cancel.is_cancelled() {
+                    return Ok((position, Err(Cancelled.into())));
+                }
+                if matches!(action, SyncAction::Upload { .. }) && quota_full.load(Ordering::Relaxed) {
+                    return Ok((position, Err(HeldForQuota.into())));
+                }
+
+                let operation_desc = describe_action(&action);
+                info!("=== EXECUTING: {} ===", operation_desc);
+                this.update_status(|status| {
+                    status.current_operation = operation_desc;
+                }).await;
+                let path = match &action {
+                    SyncAction::Upload { local_path, .. }
+                    | SyncAction::Download { local_path, .. }
+                    | SyncAction::ResolveConflict { local_path, .. } => local_path.clone(),
+                    _ => String::new(),
+                };
+                if let Err(e) = this.set_action_status(vec![position], "in_progress", None).await {
+                    return Ok((position, Err(e)));
+                }
+                let span = tracing::info_span!("transfer", kind = action.kind(), summary = %action.summary());
+                match this.execute_sync_action(action, cancel).instrument(span).await {
+                    Err(e) if is_cancelled(&e) && this.control.is_stopping() => Err(position),
+                    result => Ok((position, result.map(|_| path))),
+                }
+            }))
+            .buffer_unordered(max_concurrent);
+
+            let mut last_snapshot = std::time::Instant::now();
+            let mut changed_paths = Vec::new();
+            while let Some(outcome) = pending.next().await {
+                let (position, result) = match outcome {
+                    Ok(outcome) => outcome,
+                    Err(position) => {
+                        deferred.push(position);
+                        continue;
+                    }
+                };
+                if result.as_ref().is_err_and(is_cancelled) {
+                    continue;
+                }
+                self.finish_queued_action(position, &result).await?;
+                if result.as_ref().is_err_and(|e| e.is::<HeldForQuota>()) {
+                    continue;
+                }
+                if let Err(e) = &result {
+                    if is_quota_error(e) && !quota_full.swap(true, Ordering::Relaxed) {
+                        self.hold_uploads_for_full_drive(e).await?;
+                    }
+                }
+
+                completed += 1;
+                let progress = 0.5 + (0.4 * (completed as f32 / total_actions as f32));
+                self.update_status(|status| {
+                    status.sync_progress = progress;
+                    status.pending_actions = status.pending_actions.saturating_sub(1);
+                    if let Err(e) = &result {
+                        status.sync_errors.push(e.to_string());
+                    }
+                }).await;
+
+                match result {
+                    Ok(path) => changed_paths.push(path),
+                    Err(e) => error!("Sync action failed: {}", e),
+                }
+
+                if last_snapshot.elapsed() >= STATUS_SNAPSHOT_INTERVAL {
+                    if let Err(e) = self.save_status_snapshot().await {
+                        warn!("Failed to save sync status: {}", e);
+                    }
+                    last_snapshot = std::time::Instant::now();
+                }
+            }
+
+            drop(pending);
+            drop(sleep_inhibitor);
+            self.update_status(|status| {
+                status.sleep_inhibited = false;
+                status.current_speed_bps = 0;
+                status.estimated_remaining = None;
+            }).await;
+            self.notifier.notify_changes(&changed_paths, self.run_id.as_deref().unwrap_or_default());
+            let folder_activity: Vec<FolderChange> = changed_paths
+                .iter()
+                .filter_map(|path| folder_changes.get(path).cloned())
+                .collect();
+            self.notifier.notify_folder_activity(&folder_activity, self.run_id.as_deref().unwrap_or_default());
+            self.refresh_file_states().await?;
+
+            if !deferred.is_empty() {
+                let stopping = self.control.is_stopping();
+                if stopping {
+                    info!("=== SYNC STOPPED: {} actions left for the next start ===", deferred.len());
+                } else {
+                    info!("=== SYNC PAUSED: {} actions left ===", deferred.len());
+                }
+                let deferred_count = deferred.len() as u64;
+                self.set_action_status(deferred, "queued", None).await?;
+                self.update_status(|status| {
+                    status.current_operation = if stopping { "Stopped" } else { "Paused" }.to_string();
+                    status.pending_actions = deferred_count;
+                }).await;
+            }
+        }
+
+        // A cancelled initial download keeps its plan so the next sync resumes it;
+        // anything else still queued is planned again
+        if cancel.is_cancelled() {
+            info!("=== SYNC CANCELLED ===");
+            if !self.control.is_stopping() {
+                self.drop_queued_actions().await?;
+            }
+            return Ok(());
+        }
+
+        // Every planned download has now been attempted; failures are picked up
+        // again by the next full reconcile
+        self.clear_initial_download_plan().await?;
+
+        Ok(())
+    }
+
+    /// Drops transfers of files held by a sync issue, and holds files OneDrive
+    /// cannot take, changes inside folders OneDrive refused to let this user
+    /// change, and files that have flipped between upload and download on
+    /// each of the last `PING_PONG_SYNCS` syncs, which means something keeps
+    /// changing them on both sides (or their timestamps are unstable). A held
+    /// file keeps its local copy. A ping-ponging file's pending upload goes
+    /// through one last time, its pending download is skipped, and OneDrive's
+    /// version history has the other side.
+    async fn hold_unsyncable_files(&self, actions: Vec<SyncAction>, dry_run: bool) -> Result<Vec<SyncAction>> {
+        let (held, read_only, recent_runs) = self
+            .store
+            .call(|db| {
+                let held: HashSet<String> = load_sync_issues(db)?.into_iter().map(|issue| issue.path).collect();
+                let read_only = db
+                    .prepare("SELECT path FROM read_only_folders")?
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                let recent_runs = db
+                    .prepare(
+                        "SELECT run_id FROM sync_log WHERE action = 'sync_complete' AND run_id IS NOT NULL
+                         ORDER BY id DESC LIMIT ?1",
+                    )?
+                    .query_map(params![PING_PONG_SYNCS], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok((held, read_only, recent_runs))
+            })
+            .await?;
+
+        let mut kept = Vec::with_capacity(actions.len());
+        for action in actions {
+            // Refused before, and would be again until the owner grants edit access
+            if let Some(path) = changed_remote_path(&action) {
+                if read_only.iter().any(|folder| is_same_or_inside(path, folder)) {
+                    debug!("Not changing {} in a read-only folder", path);
+                    if !dry_run && !held.contains(path) {
+                        self.hold_file(path, READ_ONLY_REASON).await?;
+                    }
+                    continue;
+                }
+            }
+
+            let (path, direction) = match &action {
+                SyncAction::Upload { local_path, .. } => (local_path.clone(), "upload"),
+                SyncAction::Download { local_path, .. } => (local_path.clone(), "download"),
+                _ => {
+                    kept.push(action);
+                    continue;
+                }
+            };
+
+            if held.contains(&path) {
+                debug!("Not syncing held file: {}", path);
+                continue;
+            }
+
+            // OneDrive would reject the upload however often it is retried
+            if direction == "upload" {
+                let size = fs::metadata(self.config.sync_folder.join(&path))
+                    .await
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                if size > MAX_FILE_SIZE {
+                    let reason = format!(
+                        "At {}, larger than the {} OneDrive allows for a single file. Split or compress it, or move it out of the sync folder.",
+                        format::size(size),
+                        format::size(MAX_FILE_SIZE)
+                    );
+                    warn!("Holding oversized file {}: {}", path, reason);
+                    if !dry_run {
+                        self.hold_file(&path, &reason).await?;
+                    }
+                    continue;
+                }
+            }
+
+            if recent_runs.len() < PING_PONG_SYNCS || !self.is_ping_ponging(&path, direction, &recent_runs).await? {
+                kept.push(action);
+                continue;
+            }
+
+            let reason = format!(
+                "Switched between upload and download on each of the last {} syncs; kept the local copy. The OneDrive version is in its version history.",
+                PING_PONG_SYNCS
+            );
+            warn!("Holding ping-ponging file {}: {}", path, reason);
+            if direction == "upload" {
+                kept.push(action);
+            }
+            if !dry_run {
+                self.hold_file(&path, &reason).await?;
+            }
+        }
+
+        Ok(kept)
+    }
+
+    /// Holds the uploads of files that were synced with content and are now
+    /// empty, when `EMPTIED_FILES_MIN` or more turn up in one sync, so the empty
+    /// copies do not replace the good ones on OneDrive. Each stays held until
+    /// the user dismisses its issue; a file or two emptied on purpose uploads
+    /// as usual.
+    async fn hold_emptied_files(
+        &self,
+        actions: Vec<SyncAction>,
+        local_files: &HashMap<String, FileRecord>,
+        stored_files: &HashMap<String, FileRecord>,
+        dry_run: bool,
+    ) -> Result<Vec<SyncAction>> {
+        let emptied: HashSet<String> = actions
+            .iter()
+            .filter_map(|action| match action {
+                SyncAction::Upload { local_path, .. } => Some(local_path),
+                _ => None,
+            })
+            .filter(|path| {
+                local_files.get(*path).is_some_and(|local| local.size == 0)
+                    && stored_files.get(*path).is_some_and(|stored| stored.size > 0)
+            })
+            .cloned()
+            .collect();
+        if emptied.len() < EMPTIED_FILES_MIN {
+            return Ok(actions);
+        }
+
+        let message = format!(
+            "{} synced files were suddenly emptied to 0 bytes, which can mean a failing disk or ransomware. Their OneDrive copies were left alone; check the files, then dismiss each issue to upload it anyway.",
+            format::count(emptied.len() as u64)
+        );
+        error!("{}", message);
+        if !dry_run {
+            let reason = "Emptied to 0 bytes along with many other files; the OneDrive copy was kept";
+            for path in &emptied {
+                self.hold_file(path, reason).await?;
+            }
+            self.notifier.notify_emptied_files(&message, self.run_id.as_deref().unwrap_or_default());
+            self.update_status(|status| status.sync_errors.push(message)).await;
+        }
+
+        Ok(actions
+            .into_iter()
+            .filter(|action| !matches!(action, SyncAction::Upload { local_path, .. } if emptied.contains(local_path)))
+            .collect())
+    }
+
+    /// Trips the mass-change circuit breaker when `MASS_CHANGE_MIN_FILES` or
+    /// more tracked files, and over a quarter of them, changed since the last
+    /// sync. While it is tripped, across restarts, nothing that writes to
+    /// OneDrive runs, so neither the OneDrive copies nor their version history
+    /// are replaced by encrypted files; downloads carry on. It stays tripped
+    /// until the user confirms the changes.
+    async fn hold_uploads_for_mass_change(
+        &self,
+        actions: Vec<SyncAction>,
+        stored_files: &HashMap<String, FileRecord>,
+        dry_run: bool,
+    ) -> Result<Vec<SyncAction>> {
+        let mut tripped = self.store.get_state(MASS_CHANGE_KEY).await?.filter(|reason| !reason.is_empty());
+        if tripped.is_none() {
+            let confirmed = self.store.get_state(MASS_CHANGE_CONFIRMED_KEY).await?.as_deref() == Some("1");
+            let changed = actions
+                .iter()
+                .filter(|action| matches!(action, SyncAction::Upload { local_path, .. } if stored_files.contains_key(local_path)))
+                .count();
+            if !confirmed && changed >= MASS_CHANGE_MIN_FILES && changed * 4 > stored_files.len() {
+                let reason = format!(
+                    "{} of your {} synced files changed at once, which can mean ransomware is encrypting them. Uploads are paused so the OneDrive copies stay safe; check the files, then confirm the changes to upload them.",
+                    format::count(changed as u64),
+                    format::count(stored_files.len() as u64)
+                );
+                error!("{}", reason);
+                if !dry_run {
+                    self.store.set_state(MASS_CHANGE_KEY, &reason).await?;
+                    self.log_sync_event("hold_uploads", "", "conflict", Some(&reason)).await?;
+                    self.notifier.notify_mass_change(&reason, self.run_id.as_deref().unwrap_or_default());
+                }
+                tripped = Some(reason);
+            }
+            // A confirmation covers the changes the sync after it finds
+            if confirmed && !dry_run {
+                self.store.set_state(MASS_CHANGE_CONFIRMED_KEY, "0").await?;
+            }
+        }
+        if !dry_run {
+            let paused = tripped.clone();
+            self.update_status(|status| status.uploads_paused_for_mass_change = paused).await;
+        }
+
+        if tripped.is_none() {
+            return Ok(actions);
+        }
+        Ok(actions
+            .into_iter()
+            .filter(|action| !matches!(
+                action,
+                SyncAction::Upload { .. } | SyncAction::ResolveConflict { .. } | SyncAction::MoveRemote { .. }
+            ))
+            .collect())
+    }
+
+    /// Quarantines `path` with `reason` as its sync issue, until the user
+    /// dismisses it.
+    async fn hold_file(&self, path: &str, reason: &str) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let (issue_path, issue_reason) = (path.to_string(), reason.to_string());
+        let issues = self
+            .store
+            .call(move |db| {
+                db.execute(
+                    "INSERT OR REPLACE INTO file_states (path, state, detail, updated) VALUES (?1, ?2, ?3, ?4)",
+                    params![issue_path, FileState::Quarantined.as_str(), issue_reason, now],
+                )?;
+                load_sync_issues(db)
+            })
+            .await?;
+        self.status.lock().await.issues = issues;
+        self.show_emblem(path, FileState::Quarantined.emblem());
+        self.log_sync_event("hold", path, "conflict", Some(reason)).await
+    }
+
+    /// Holds `path` after OneDrive refused to let this user change it. When
+    /// its folder is shared, the folder is remembered as read-only so later
+    /// changes beneath it are held without being tried; anywhere else only
+    /// this file is held, as the refusal may be about the file alone.
+    async fn hold_read_only(&self, path: &str) -> Result<()> {
+        if let Some((folder, _)) = path.rsplit_once('/') {
+            let shared = match self.api.get_item(&format!("/{}", folder)).await {
+                Ok(item) => item.shared.is_some(),
+                Err(e) => {
+                    debug!("Could not look up {} after a refused change: {}", folder, e);
+                    false
+                }
+            };
+            if shared {
+                warn!("No write access to the shared folder {}; holding changes beneath it", folder);
+                let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+                let folder = folder.to_string();
+                self.store
+                    .call(move |db| {
+                        db.execute(
+                            "INSERT OR REPLACE INTO read_only_folders (path, detected) VALUES (?1, ?2)",
+                            params![folder, now],
+                        )?;
+                        Ok(())
+                    })
+                    .await?;
+            }
+        }
+        self.hold_file(path, READ_ONLY_REASON).await
+    }
+
+    /// Whether `path` was transferred on each of `recent_runs` (newest first),
+    /// alternating direction, with `direction` continuing the pattern.
+    async fn is_ping_ponging(&self, path: &str, direction: &str, recent_runs: &[String]) -> Result<bool> {
+        let (path, limit) = (path.to_string(), recent_runs.len());
+        let history = self
+            .store
+            .call(move |db| {
+                Ok(db
+                    .prepare(
+                        "SELECT action, run_id FROM sync_log
+                         WHERE file_path = ?1 AND action IN ('upload', 'download') AND status = 'success'
+                         ORDER BY id DESC LIMIT ?2",
+                    )?
+                    .query_map(params![path, limit], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<(String, Option<String>)>>>()?)
+            })
+            .await?;
+
+        Ok(history.len() == recent_runs.len()
+            && history.iter().map(|(_, run_id)| run_id.as_deref()).eq(recent_runs.iter().map(|run_id| Some(run_id.as_str())))
+            && history.first().is_some_and(|(last, _)| last != direction)
+            && history.windows(2).all(|pair| pair[0].0 != pair[1].0))
+    }
+
+    /// Plans freeing the files in `Config::archive_folder` that nothing on this
+    /// computer has opened or changed for `Config::archive_after_days`. Only a
+    /// file whose OneDrive copy is verified to match it is freed: unchanged
+    /// since the last sync on both sides, with the same size and content hash,
+    /// so no other action is planned for it.
+    fn plan_archiving(
+        &self,
+        local_files: &HashMap<String, FileRecord>,
+        remote_files: &HashMap<String, DriveItem>,
+        stored_files: &HashMap<String, FileRecord>,
+    ) -> Vec<SyncAction> {
+        let folder = self.config.archive_folder.as_str();
+        if folder.is_empty() {
+            return Vec::new();
+        }
+        let unused_for = Duration::from_secs(self.config.archive_after_days * 24 * 60 * 60);
+
+        let mut unused_paths = Vec::new();
+        for (path, local) in local_files {
+            if !is_same_or_inside(path, folder) {
+                continue;
+            }
+            let (Some(stored), Some(remote)) = (stored_files.get(path), remote_files.get(path)) else {
+                continue;
+            };
+            let verified = !local.hash.is_empty()
+                && local.hash == stored.hash
+                && remote.size == Some(local.size)
+                && !is_remote_newer(remote, stored)
+                && match (local.quick_xor_hash.as_deref(), remote.quick_xor_hash()) {
+                    (Some(local_hash), Some(remote_hash)) => local_hash == remote_hash,
+                    _ => true,
+                };
+            if !verified {
+                continue;
+            }
+
+            // Opening a file updates its access time, so the later of that
+            // and the last change is when it was last used here
+            let Ok(metadata) = std::fs::metadata(self.config.sync_folder.join(path)) else {
+                continue;
+            };
+            let last_used = [metadata.accessed(), metadata.modified()].into_iter().flatten().max();
+            let unused = last_used
+                .and_then(|time| time.elapsed().ok())
+                .is_some_and(|age| age >= unused_for);
+            if unused {
+                info!("{} has not been used for {} days; freeing it from this computer", path, self.config.archive_after_days);
+                unused_paths.push(path.clone());
+            }
+        }
+        unused_paths.sort();
+        unused_paths.into_iter().map(|path| SyncAction::ArchiveLocal { path }).collect()
+    }
+
+    /// Paths freed from this computer by the archive folder.
+    async fn get_archived_files(&self) -> Result<HashSet<String>> {
+        self.store
+            .call(|db| {
+                let mut stmt = db.prepare("SELECT path FROM archived_files")?;
+                let paths = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+                Ok(paths)
+            })
+            .await
+    }
+
+    /// Stops treating `paths` as archived, once they are back on this computer
+    /// or gone from OneDrive.
+    async fn forget_archived_files(&self, paths: Vec<String>) -> Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                for path in &paths {
+                    tx.execute("DELETE FROM archived_files WHERE path = ?1", params![path])?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Online-only files by path.
+    async fn get_placeholders(&self) -> Result<HashMap<String, Placeholder>> {
+        self.store
+            .call(|db| {
+                let mut stmt = db.prepare("SELECT path, onedrive_id, size, modified, requested FROM placeholders")?;
+                let placeholders = stmt
+                    .query_map([], |row| {
+                        Ok(Placeholder {
+                            path: row.get(0)?,
+                            onedrive_id: row.get(1)?,
+                            size: row.get(2)?,
+                            modified: row.get(3)?,
+                            requested: row.get(4)?,
+                        })
+                    })?
+                    .map(|placeholder| placeholder.map(|placeholder| (placeholder.path.clone(), placeholder)))
+                    .collect::<rusqlite::Result<_>>()?;
+                Ok(placeholders)
+            })
+            .await
+    }
+
+    /// Deletes the placeholders of `paths`, where there are any.
+    async fn remove_placeholders(&self, paths: Vec<String>) -> Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let removed = self
+            .store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                let mut removed = Vec::new();
+                for path in paths {
+                    if tx.execute("DELETE FROM placeholders WHERE path = ?1", params![path])? > 0 {
+                        removed.push(path);
+                    }
+                }
+                tx.commit()?;
+                Ok(removed)
+            })
+            .await?;
+
+        for path in removed {
+            let placeholder_file = self.config.sync_folder.join(placeholder_path(&path));
+            if let Err(e) = fs::remove_file(&placeholder_file).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove the placeholder {}: {}", placeholder_file.display(), e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The reconciler's verdict for every known path: tracked files are in
+    /// sync unless an action is planned for them, excluded local paths are
+    /// excluded, and quarantined files stay as they are.
+    async fn record_file_states(&self, actions: &[SyncAction], excluded: Vec<String>) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let planned: Vec<(String, FileState)> = actions
+            .iter()
+            .filter_map(FileState::planned_for)
+            .map(|(path, state)| (path.to_string(), state))
+            .collect();
+
+        let emblem_states = FileState::ALL
+            .iter()
+            .filter(|state| state.emblem().is_some())
+            .map(|state| format!("'{}'", state.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (before, after) = self
+            .store
+            .call(move |db| {
+                let with_emblems = |db: &Connection| -> Result<HashMap<String, FileState>> {
+                    let mut stmt = db.prepare(&format!(
+                        "SELECT path, state FROM file_states WHERE state IN ({})",
+                        emblem_states
+                    ))?;
+                    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+                    let mut paths = HashMap::new();
+                    for row in rows {
+                        let (path, state) = row?;
+                        if let Some(state) = FileState::parse(&state) {
+                            paths.insert(path, state);
+                        }
+                    }
+                    Ok(paths)
+                };
+
+                let before = with_emblems(db)?;
+                let tx = db.transaction()?;
+                tx.execute("DELETE FROM file_states WHERE state != 'quarantined'", [])?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO file_states (path, state, detail, updated)
+                     SELECT path, ?1, NULL, ?2 FROM files",
+                    params![FileState::InSync.as_str(), now],
+                )?;
+                for path in excluded {
+                    update_file_state(&tx, &path, Some(FileState::Excluded), now)?;
+                }
+                for (path, state) in planned {
+                    update_file_state(&tx, &path, Some(state), now)?;
+                }
+                tx.commit()?;
+                Ok((before, with_emblems(db)?))
+            })
+            .await?;
+
+        for (path, state) in &after {
+            if before.get(path) != Some(state) {
+                self.show_emblem(path, state.emblem());
+            }
+        }
+        for path in before.keys().filter(|path| !after.contains_key(*path)) {
+            self.show_emblem(path, None);
+        }
+
+        self.refresh_file_states().await
+    }
+
+    /// Records that an action left `path` in `state` (`None` once the file is
+    /// gone from both sides).
+    async fn mark_file(&self, path: &str, state: Option<FileState>) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let marked = path.to_string();
+        let (before, after) = self.store.call(move |db| update_file_state(db, &marked, state, now)).await?;
+
+        let emblem = after.and_then(|state| state.emblem());
+        if before.and_then(|state| state.emblem()) != emblem {
+            self.show_emblem(path, emblem);
+        }
+        Ok(())
+    }
+
+    fn show_emblem(&self, path: &str, emblem: Option<&str>) {
+        if self.config.folder_integration {
+            desktop::set_emblem(&self.config.sync_folder.join(path), emblem);
+        }
+    }
+
+    /// Reloads the per-state file counts and held files shown by the GUI and tray.
+    async fn refresh_file_states(&self) -> Result<()> {
+        let (issues, file_states) = self
+            .store
+            .call(|db| Ok((load_sync_issues(db)?, load_file_state_counts(db)?)))
+            .await?;
+        self.update_status(|status| {
+            status.issues = issues;
+            status.file_states = file_states;
+        }).await;
+        Ok(())
+    }
+
+    /// Bytes the transfers will move: remote sizes for downloads, current local
+    /// sizes for uploads.
+    fn transfer_bytes<'a>(&self, transfers: impl IntoIterator<Item = &'a SyncAction>) -> u64 {
+        transfers
+            .into_iter()
+            .map(|action| match action {
+                SyncAction::Download { remote_item, .. } | SyncAction::ResolveConflict { remote_item, .. } => {
+                    remote_item.size.unwrap_or(0)
+                }
+                SyncAction::Upload { local_path, .. } => std::fs::metadata(self.config.sync_folder.join(local_path))
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Leaves out uploads (and conflict resolutions, which upload too) of files
+    /// another program still has open for writing or changed within
+    /// `Config::upload_settle_seconds`, so half-finished exports, recordings and
+    /// downloads are not uploaded. They are planned again on a later sync.
+    async fn defer_files_being_written(&self, actions: Vec<SyncAction>, dry_run: bool) -> Vec<SyncAction> {
+        let settle = Duration::from_secs(self.config.upload_settle_seconds);
+        let has_uploads = actions
+            .iter()
+            .any(|action| matches!(action, SyncAction::Upload { .. } | SyncAction::ResolveConflict { .. }));
+        let open_for_writing = if has_uploads {
+            processes::files_open_for_writing(&self.config.sync_folder)
+        } else {
+            HashSet::new()
+        };
+
+        let mut deferred = Vec::new();
+        let mut kept = Vec::with_capacity(actions.len());
+        for action in actions {
+            let path = match &action {
+                SyncAction::Upload { local_path, .. } | SyncAction::ResolveConflict { local_path, .. } => local_path,
+                _ => {
+                    kept.push(action);
+                    continue;
+                }
+            };
+
+            let full_path = self.config.sync_folder.join(path);
+            let recently_modified = std::fs::metadata(&full_path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age < settle);
+            if recently_modified || open_for_writing.contains(&full_path) {
+                info!("Not uploading {} yet; it is still being written", path);
+                deferred.push(path.clone());
+                continue;
+            }
+            kept.push(action);
+        }
+
+        if !dry_run {
+            self.update_status(|status| status.files_being_written = deferred).await;
+        }
+        kept
+    }
+
+    /// Leaves out the uploads that would not fit in the space left on OneDrive,
+    /// so they wait as one clear issue instead of each failing with a full-quota
+    /// error. Uploads that fit still go, in plan order, as do downloads and
+    /// conflict copies. The held uploads are planned and checked again by the
+    /// next sync.
+    async fn hold_uploads_over_quota(&self, transfers: Vec<(i64, SyncAction)>) -> Result<Vec<(i64, SyncAction)>> {
+        let is_upload = |action: &SyncAction| matches!(action, SyncAction::Upload { .. });
+        let upload_count = transfers.iter().filter(|(_, action)| is_upload(action)).count();
+        let needed: u64 = self.transfer_bytes(transfers.iter().map(|(_, action)| action).filter(|action| is_upload(action)));
+
+        // Small uploads only get a check while earlier ones are waiting for space
+        let was_held = self.status.lock().await.uploads_held_for_quota.is_some();
+        let remaining = if upload_count == 0 || (needed < QUOTA_CHECK_MIN_BYTES && !was_held) {
+            None
+        } else {
+            match self.api.get_drive_info().await {
+                Ok(DriveInfo { quota: Some(quota), .. }) if quota.remaining < needed => Some(quota.remaining),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("Could not check OneDrive space before uploading: {}", e);
+                    None
+                }
+            }
+        };
+
+        // Uploads that fit go ahead in plan order; the rest wait for space
+        let mut held_positions = Vec::new();
+        let transfers = match remaining {
+            Some(remaining) => {
+                let mut room = remaining;
+                transfers
+                    .into_iter()
+                    .filter(|(position, action)| {
+                        if !is_upload(action) {
+                            return true;
+                        }
+                        let size = self.transfer_bytes([action]);
+                        if size <= room {
+                            room -= size;
+                            true
+                        } else {
+                            held_positions.push(*position);
+                            false
+                        }
+                    })
+                    .collect()
+            }
+            None => transfers,
+        };
+        let held = remaining.map(|remaining| {
+            format!(
+                "Not enough OneDrive space for {} of {} uploads (need {}, have {}). Free up space on OneDrive or upgrade your plan.",
+                format::count(held_positions.len() as u64),
+                format::count(upload_count as u64),
+                format::size(needed),
+                format::size(remaining)
+            )
+        });
+
+        if let Some(reason) = &held {
+            warn!("Holding uploads: {}", reason);
+            self.log_sync_event("hold_uploads", "", "conflict", Some(reason)).await?;
+            self.set_action_status(held_positions, "failed", Some(reason.clone())).await?;
+            // Once, not again on every sync while space stays short
+            if !was_held {
+                self.notifier.notify_quota(reason, self.run_id.as_deref().unwrap_or_default());
+            }
+        }
+        self.update_status(|status| status.uploads_held_for_quota = held).await;
+        Ok(transfers)
+    }
+
+    /// Holds the rest of this sync's uploads after one failed because OneDrive
+    /// is full, which the check before uploading can miss when another device
+    /// fills the drive meanwhile.
+    async fn hold_uploads_for_full_drive(&self, error: &anyhow::Error) -> Result<()> {
+        let reason = "OneDrive is full. Free up space on OneDrive or upgrade your plan; the remaining uploads wait for the next sync.".to_string();
+        warn!("Holding uploads after a failed upload: {}", error);
+        self.log_sync_event("hold_uploads", "", "conflict", Some(&reason)).await?;
+        let was_held = self.status.lock().await.uploads_held_for_quota.is_some();
+        if !was_held {
+            self.notifier.notify_quota(&reason, self.run_id.as_deref().unwrap_or_default());
+        }
+        self.update_status(|status| status.uploads_held_for_quota = Some(reason)).await;
+        Ok(())
+    }
+
+    /// Fails before anything runs when the downloads in `actions` would not fit
+    /// on the sync folder's disk, rather than filling it halfway through.
+    fn check_free_space(&self, actions: &[SyncAction]) -> Result<()> {
+        let needed: u64 = actions
+            .iter()
+            .map(|action| match action {
+                SyncAction::Download { remote_item, .. } | SyncAction::ResolveConflict { remote_item, .. } => {
+                    remote_item.size.unwrap_or(0)
+                }
+                _ => 0,
+            })
+            .sum();
+        if needed == 0 {
+            return Ok(());
+        }
+
+        let available = match desktop::available_space(&self.config.sync_folder) {
+            Ok(available) => available,
+            Err(e) => {
+                warn!("Could not check free disk space: {}", e);
+                return Ok(());
+            }
+        };
+        if needed + DOWNLOAD_FREE_SPACE_MARGIN > available {
+            error!("Downloads need {} bytes but only {} are free", needed, available);
+            return Err(anyhow!(
+                "Not enough disk space to download from OneDrive: need {}, have {} free in {}. Free up space or exclude some folders, then sync again.",
+                format::size(needed + DOWNLOAD_FREE_SPACE_MARGIN),
+                format::size(available),
+                self.config.sync_folder.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Orders the downloads of a first sync breadth-first, with the user's priority
+    /// folders ahead of everything else and smaller files first within a level, so
+    /// the most useful part of a large drive becomes available early.
+    fn schedule_initial_download(&self, actions: &mut [SyncAction]) {
+        let priority_folders: Vec<String> = self
+            .config
+            .priority_folders
+            .iter()
+            .map(|folder| folder.trim_matches('/').to_lowercase())
+            .collect();
+
+        actions.sort_by_key(|action| match action {
+            SyncAction::Download { remote_item, local_path } => {
+                let top_level = local_path.split('/').next().unwrap_or_default().to_lowercase();
+                let rank = priority_folders
+                    .iter()
+                    .position(|folder| *folder == top_level)
+                    .unwrap_or(priority_folders.len());
+                let depth = local_path.matches('/').count();
+                (1, rank, depth, remote_item.size.unwrap_or(0))
+            }
+            // Folder creation and uploads keep their place ahead of the downloads
+            _ => (0, 0, 0, 0),
+        });
+    }
+
+    async fn save_initial_download_plan(&self, actions: &[SyncAction]) -> Result<()> {
+        let mut plan = Vec::new();
+        for action in actions {
+            if let SyncAction::Download { remote_item, local_path } = action {
+                // Pre-authenticated download URLs expire, so resumed downloads go by item ID
+                let mut item = remote_item.clone();
+                item.download_url = None;
+                plan.push((local_path.clone(), serde_json::to_string(&item)?));
+            }
+        }
+
+        let planned = plan.len();
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                tx.execute("DELETE FROM initial_download_plan", [])?;
+                for (local_path, item_json) in plan {
+                    tx.execute(
+                        "INSERT INTO initial_download_plan (path, item_json) VALUES (?1, ?2)",
+                        params![local_path, item_json],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await?;
+
+        if planned > 0 {
+            info!("Saved initial download plan with {} files", planned);
+        }
+        Ok(())
+    }
+
+    async fn load_initial_download_plan(&self) -> Result<Vec<SyncAction>> {
+        let rows = self
+            .store
+            .call(|db| {
+                let mut stmt = db.prepare("SELECT path, item_json FROM initial_download_plan ORDER BY position")?;
+                let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+                Ok(rows.collect::<rusqlite::Result<Vec<(String, String)>>>()?)
+            })
+            .await?;
+
+        let mut actions = Vec::new();
+        for (local_path, item_json) in rows {
+            match serde_json::from_str::<DriveItem>(&item_json) {
+                Ok(remote_item) => actions.push(SyncAction::Download { remote_item, local_path }),
+                Err(e) => warn!("Dropping unreadable planned download {}: {}", local_path, e),
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Drops planned downloads whose file is already on disk with the remote
+    /// content (the app was killed after writing them but before the plan was
+    /// updated), recording them as synced instead of downloading them again.
+    async fn skip_completed_downloads(&self, actions: Vec<SyncAction>) -> Result<Vec<SyncAction>> {
+        let mut remaining = Vec::new();
+        let mut resumed_files = 0;
+        let mut resumed_bytes = 0;
+        let mut redone_bytes = 0;
+
+        for action in actions {
+            let SyncAction::Download { remote_item, local_path } = &action else {
+                remaining.push(action);
+                continue;
+            };
+
+            let local_full_path = self.config.sync_folder.join(local_path);
+            let size = remote_item.size.unwrap_or(0);
+            let already_complete = match fs::metadata(&local_full_path).await {
+                Ok(metadata) if metadata.is_file() && metadata.len() == size => {
+                    let (hash, quick_xor_hash) = self.calculate_file_hashes(&local_full_path).await?;
+                    let matches = match remote_item.quick_xor_hash() {
+                        Some(remote_hash) => remote_hash == quick_xor_hash,
+                        None => remote_sha256(remote_item).is_some_and(|remote_hash| remote_hash.eq_ignore_ascii_case(&hash)),
+                    };
+                    let modified = metadata
+                        .modified()?
+                        .duration_since(SystemTime::UNIX_EPOCH)?
+                        .as_secs();
+                    matches.then_some((hash, quick_xor_hash, modified))
+                }
+                _ => None,
+            };
+
+            match already_complete {
+                Some((hash, quick_xor_hash, modified)) => {
+                    debug!("Already downloaded, skipping: {}", local_path);
+                    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+                    let (path, id) = (local_path.clone(), remote_item.id.clone());
+                    let mime_type = remote_item.mime_type().map(str::to_string);
+                    self.store
+                        .call(move |db| {
+                            db.execute(
+                                "INSERT OR REPLACE INTO files (path, hash, size, modified, onedrive_id, last_synced, quick_xor_hash, mime_type) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                                params![path, hash, size, modified, id, now, quick_xor_hash, mime_type],
+                            )?;
+                            db.execute("DELETE FROM initial_download_plan WHERE path = ?1", params![path])?;
+                            Ok(())
+                        })
+                        .await?;
+
+                    resumed_files += 1;
+                    resumed_bytes += size;
+                }
+                None => {
+                    redone_bytes += size;
+                    remaining.push(action);
+                }
+            }
+        }
+
+        info!(
+            "Download resume: {} files ({} bytes) already complete, {} files ({} bytes) still to download",
+            resumed_files, resumed_bytes, remaining.len(), redone_bytes
+        );
+        if resumed_files > 0 {
+            self.log_sync_event(
+                "resume_download",
+                "",
+                "success",
+                Some(&format!(
+                    "Skipped {} already downloaded files ({} bytes); {} files ({} bytes) to download",
+                    resumed_files, resumed_bytes, remaining.len(), redone_bytes
+                )),
+            ).await?;
+        }
+
+        Ok(remaining)
+    }
+
+    /// Creates the folders of queued `CreateRemoteFolder` actions in batched
+    /// requests, a level of the tree at a time so every parent exists before
+    /// its children are created. Returns how many actions were attempted.
+    async fn create_remote_folders(&self, folders: Vec<(i64, String)>) -> Result<usize> {
+        let attempted = folders.len();
+        let mut levels: BTreeMap<usize, Vec<(i64, String)>> = BTreeMap::new();
+        for (position, path) in folders {
+            levels.entry(path.matches('/').count()).or_default().push((position, path));
+        }
+
+        for level in levels.into_values() {
+            info!("=== CREATING {} REMOTE FOLDERS ===", level.len());
+            let operation = format!("Creating {} folders on OneDrive", format::count(level.len() as u64));
+            self.update_status(|status| status.current_operation = operation).await;
+            self.set_action_status(level.iter().map(|(position, _)| *position).collect(), "in_progress", None).await?;
+
+            let requests: Vec<(String, String)> = level.iter().map(|(_, path)| remote_name_and_parent(path)).collect();
+            let results = match self.api.create_folders(&requests).await {
+                Ok(results) => results,
+                Err(e) => {
+                    let message = e.to_string();
+                    level.iter().map(|_| Err(anyhow!(message.clone()))).collect()
+                }
+            };
+
+            for ((position, path), result) in level.into_iter().zip(results) {
+                let result = match result {
+                    Ok(remote_item) => self.record_folder(&path, &remote_item.id).await,
+                    Err(e) => Err(e),
+                };
+                self.finish_queued_action(position, &result).await?;
+                match result {
+                    Ok(()) => self.log_sync_event("create_remote_folder", &path, "success", None).await?,
+                    Err(e) => {
+                        error!("Failed to create remote folder {}: {}", path, e);
+                        if is_access_denied(&e) {
+                            self.hold_read_only(&path).await?;
+                        }
+                        self.update_status(|status| status.sync_errors.push(e.to_string())).await;
+                    }
+                }
+                self.update_status(|status| {
+                    status.pending_actions = status.pending_actions.saturating_sub(1);
+                }).await;
+            }
+        }
+
+        Ok(attempted)
+    }
+
+    /// Makes `actions` the queue in `pending_actions`, replacing the last one,
+    /// and gives each its position there.
+    async fn queue_actions(&self, actions: Vec<SyncAction>) -> Result<Vec<(i64, SyncAction)>> {
+        let action_json = actions.iter().map(serde_json::to_string).collect::<serde_json::Result<Vec<_>>>()?;
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let positions = self
+            .store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                tx.execute("DELETE FROM pending_actions", [])?;
+                let mut positions = Vec::with_capacity(action_json.len());
+                for json in action_json {
+                    tx.execute(
+                        "INSERT INTO pending_actions (action_json, status, updated) VALUES (?1, 'queued', ?2)",
+                        params![json, now],
+                    )?;
+                    positions.push(tx.last_insert_rowid());
+                }
+                tx.commit()?;
+                Ok(positions)
+            })
+            .await?;
+
+        Ok(positions.into_iter().zip(actions).collect())
+    }
+
+    /// Moves queued actions to `status` ("queued", "in_progress", "done" or
+    /// "failed").
+    async fn set_action_status(&self, positions: Vec<i64>, status: &'static str, error: Option<String>) -> Result<()> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                for position in positions {
+                    tx.execute(
+                        "UPDATE pending_actions SET status = ?2, error = ?3, updated = ?4 WHERE position = ?1",
+                        params![position, status, error, now],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Records how a queued action ended.
+    async fn finish_queued_action<T>(&self, position: i64, result: &Result<T>) -> Result<()> {
+        match result {
+            Ok(_) => self.set_action_status(vec![position], "done", None).await,
+            Err(e) => self.set_action_status(vec![position], "failed", Some(e.to_string())).await,
+        }
+    }
+
+    /// Actions of the last queue that never finished, in plan order: those a
+    /// pause or quit left waiting, and any that were running when the
+    /// process died.
+    async fn load_queued_actions(&self) -> Result<Vec<SyncAction>> {
+        let rows = self
+            .store
+            .call(|db| {
+                let mut stmt = db.prepare(
+                    "SELECT action_json FROM pending_actions
+                     WHERE status IN ('queued', 'in_progress') ORDER BY position"
+                )?;
+                let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+            })
+            .await?;
+
+        let mut actions = Vec::new();
+        for row in rows {
+            match serde_json::from_str::<SyncAction>(&row) {
+                Ok(action) => actions.push(action),
+                Err(e) => warn!("Dropping unreadable queued action: {}", e),
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Drops the actions a cancelled sync did not get to; the next sync plans
+    /// afresh. Finished ones stay listed until then.
+    async fn drop_queued_actions(&self) -> Result<()> {
+        self.store
+            .call(|db| {
+                db.execute("DELETE FROM pending_actions WHERE status IN ('queued', 'in_progress')", [])?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// The queued `actions` whose files are still as they were when the
+    /// actions were planned, locally, in the database and, with
+    /// `check_remote`, on OneDrive. The rest are dropped, for a fresh plan to
+    /// redo what is still needed. The remote items are looked up in batches.
+    async fn recheck_queued_actions(&self, actions: Vec<SyncAction>, check_remote: bool) -> Result<Vec<SyncAction>> {
+        if actions.is_empty() {
+            return Ok(actions);
+        }
+        let stored_files = self.get_stored_files().await?;
+        let stored_folders = self.get_stored_folders().await?;
+        let remote = if check_remote {
+            let paths: Vec<String> = actions
+                .iter()
+                .flat_map(rechecked_remote_paths)
+                .map(str::to_string)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            Some(self.remote_items(paths).await?)
+        } else {
+            None
+        };
+
+        let mut resumed = Vec::with_capacity(actions.len());
+        for action in actions {
+            if self.still_applies(&action, &stored_files, &stored_folders, remote.as_ref()) {
+                resumed.push(action);
+            } else {
+                info!("Queued action no longer applies: {}", action.summary());
+            }
+        }
+        Ok(resumed)
+    }
+
+    /// Whether a queued action's preconditions still hold: local files are
+    /// unchanged since the last sync or still absent, and the item in `remote`
+    /// is the same one, with the same content, or still gone. Without `remote`
+    /// only the local side is checked; a path missing from it could not be
+    /// looked up, and fails the check.
+    fn still_applies(
+        &self,
+        action: &SyncAction,
+        stored_files: &HashMap<String, FileRecord>,
+        stored_folders: &HashMap<String, FolderRecord>,
+        remote: Option<&HashMap<String, Option<DriveItem>>>,
+    ) -> bool {
+        let local = |path: &str| std::fs::symlink_metadata(self.config.sync_folder.join(path)).ok();
+        // Unchanged since the last sync, or still absent with nothing recorded
+        let local_as_stored = |path: &str| match (local(path), stored_files.get(path)) {
+            (None, None) => true,
+            (Some(metadata), Some(stored_file)) => {
+                let modified = metadata
+                    .modified()
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                metadata.is_file() && metadata.len() == stored_file.size && modified == stored_file.modified
+            }
+            _ => false,
+        };
+        let remote_is = |path: &str, check: &dyn Fn(Option<&DriveItem>) -> bool| match remote {
+            Some(remote) => remote.get(path).is_some_and(|item| check(item.as_ref())),
+            None => true,
+        };
+        let same_remote = |path: &str, planned: &DriveItem| {
+            remote_is(path, &|item| {
+                item.is_some_and(|now| {
+                    now.id == planned.id && now.last_modified == planned.last_modified && now.quick_xor_hash() == planned.quick_xor_hash()
+                })
+            })
+        };
+
+        match action {
+            SyncAction::Upload { local_path, .. } => {
+                local(local_path).is_some_and(|metadata| metadata.is_file())
+                    && remote_is(local_path, &|item| match (item, stored_files.get(local_path)) {
+                        (None, _) => true,
+                        (Some(remote), Some(stored_file)) => !is_remote_newer(remote, stored_file),
+                        (Some(_), None) => false,
+                    })
+            }
+            SyncAction::Download { remote_item, local_path } | SyncAction::CreatePlaceholder { remote_item, local_path } => {
+                local_as_stored(local_path) && same_remote(local_path, remote_item)
+            }
+            SyncAction::ResolveConflict { remote_item, local_path } => {
+                local(local_path).is_some_and(|metadata| metadata.is_file()) && same_remote(local_path, remote_item)
+            }
+            SyncAction::DeleteLocal { path } => {
+                (stored_files.contains_key(path) || stored_folders.contains_key(path))
+                    && (local(path).is_none_or(|metadata| metadata.is_dir()) || local_as_stored(path))
+                    && remote_is(path, &|item| item.is_none())
+            }
+            SyncAction::ArchiveLocal { path } => {
+                local_as_stored(path)
+                    && stored_files.get(path).is_some_and(|stored_file| stored_file.onedrive_id.is_some())
+                    && remote_is(path, &|item| match (item, stored_files.get(path)) {
+                        (Some(remote), Some(stored_file)) => !is_remote_newer(remote, stored_file),
+                        _ => false,
+                    })
+            }
+            SyncAction::RemoveFromDatabase { path } | SyncAction::RemoveFolderFromDatabase { path } => {
+                local(path).is_none() && remote_is(path, &|item| item.is_none())
+            }
+            SyncAction::CreateRemoteFolder { path } => local(path).is_some_and(|metadata| metadata.is_dir()),
+            SyncAction::CreateLocalFolder { remote_item, path } => {
+                local(path).is_none_or(|metadata| metadata.is_dir())
+                    && remote_is(path, &|item| item.is_some_and(|remote| remote.id == remote_item.id))
+            }
+            SyncAction::MoveRemote { from, to, onedrive_id } => {
+                local(to).is_some()
+                    && remote_is(from, &|item| item.is_some_and(|remote| remote.id == *onedrive_id))
+                    && remote_is(to, &|item| item.is_none())
+            }
+            SyncAction::MoveLocal { from, to } | SyncAction::MoveLocalFolder { from, to } => local(from).is_some() && local(to).is_none(),
+        }
+    }
+
+    /// The items at `paths` on OneDrive now, by path: `None` where there is
+    /// none, and left out where the lookup failed.
+    async fn remote_items(&self, paths: Vec<String>) -> Result<HashMap<String, Option<DriveItem>>> {
+        let lookups: Vec<String> = paths.iter().map(|path| format!("/{}", path)).collect();
+        let results = self.api.get_items(&lookups).await?;
+        let mut items = HashMap::with_capacity(paths.len());
+        for (path, result) in paths.into_iter().zip(results) {
+            match result {
+                Ok(item) => {
+                    items.insert(path, Some(item));
+                }
+                Err(e) if e.downcast_ref::<GraphError>().is_some_and(|graph_error| graph_error.status == 404) => {
+                    items.insert(path, None);
+                }
+                Err(e) => debug!("Could not look up {} on OneDrive: {}", path, e),
+            }
+        }
+        Ok(items)
+    }
+
+    async fn clear_initial_download_plan(&self) -> Result<()> {
+        self.store
+            .call(|db| {
+                db.execute("DELETE FROM initial_download_plan", [])?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Folders owned by another sync tool or a package manager cache churn constantly,
+    /// so they are pruned from the local scan by name or by the marker files those
+    /// tools leave behind.
+    fn is_excluded_folder(&self, path: &Path) -> bool {
+        match excluded_folder_reason(&self.config, path) {
+            Some(reason) => {
+                info!("Skipping {}: {}", reason, path.display());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tracked files and folders under the sync folder, the paths skipped by
+    /// the exclude rules (an excluded folder is listed, not its contents) and
+    /// the files skipped by the size and type filters or for names OneDrive
+    /// refuses (a folder with such a name is skipped with its contents).
+    async fn scan_local_files(&self) -> Result<(HashMap<String, FileRecord>, HashSet<String>, Vec<String>, Vec<SkippedFile>)> {
+        let mut files = HashMap::new();
+        let mut folders = HashSet::new();
+        let excluded = std::sync::Mutex::new(Vec::new());
+        let illegal_folders = std::sync::Mutex::new(Vec::new());
+        let mut skipped = Vec::new();
+        
+        if !self.config.sync_folder.exists() {
+            info!("Creating sync folder: {}", self.config.sync_folder.display());
+            fs::create_dir_all(&self.config.sync_folder).await?;
+            return Ok((files, folders, Vec::new(), skipped));
+        }
+
+        info!("Scanning local files in: {}", self.config.sync_folder.display());
+        // Files whose size and mtime still match their record keep its hash
+        let stored_files = self.get_stored_files().await?;
+        let partial_files = self.partial_download_files().await?;
+        let placeholder_files = self.placeholder_files().await?;
+        let mut hashed = 0;
+        
+        for entry in WalkDir::new(&self.config.sync_folder)
+            .into_iter()
+            .filter_entry(|e| {
+                if e.depth() == 0 {
+                    return true;
+                }
+                let relative_path = e.path().strip_prefix(&self.config.sync_folder).unwrap_or(e.path());
+                let relative_path_str = relative_path.to_string_lossy().replace('\\', "/");
+                let is_dir = e.file_type().is_dir();
+                if self.filter.is_excluded(&relative_path_str, is_dir)
+                    || (is_dir && self.is_excluded_folder(e.path()))
+                {
+                    debug!("Skipping excluded path: {}", relative_path_str);
+                    excluded.lock().unwrap().push(relative_path_str);
+                    return false;
+                }
+                if is_dir && !relative_path_str.starts_with('.') {
+                    if let Some(reason) = illegal_name_reason(&e.file_name().to_string_lossy()) {
+                        debug!("Skipping folder {}: {}", relative_path_str, reason);
+                        illegal_folders.lock().unwrap().push(SkippedFile {
+                            path: relative_path_str,
+                            reason: format!("{}; rename the folder to sync it", reason),
+                            kind: SkipKind::InvalidName,
+                        });
+                        return false;
+                    }
+                }
+                true
+            })
+            .filter_map(|e| e.ok())
+        {
+            if entry.file_type().is_dir() && entry.depth() > 0 {
+                let relative_path = entry.path().strip_prefix(&self.config.sync_folder)?;
+                let relative_path_str = relative_path.to_string_lossy().replace('\\', "/");
+
+                // Skip hidden folders
+                if !relative_path_str.starts_with('.') {
+                    debug!("Found local folder: {}", relative_path_str);
+                    folders.insert(relative_path_str);
+                }
+            } else if entry.file_type().is_file() {
+                let path = entry.path();
+                let relative_path = path.strip_prefix(&self.config.sync_folder)?;
+                let relative_path_str = relative_path.to_string_lossy().replace('\\', "/");
+
+                // Skip hidden files, system files, in-progress downloads and
+                // placeholders
+                if relative_path_str.starts_with('.')
+                    || partial_files.contains(&relative_path_str)
+                    || placeholder_files.contains(&relative_path_str)
+                {
+                    continue;
+                }
+
+                if let Some(reason) = illegal_name_reason(&entry.file_name().to_string_lossy()) {
+                    debug!("Skipping {}: {}", relative_path_str, reason);
+                    skipped.push(SkippedFile {
+                        path: relative_path_str,
+                        reason: format!("{}; rename the file to sync it", reason),
+                        kind: SkipKind::InvalidName,
+                    });
+                    continue;
+                }
+
+                if let Ok(metadata) = entry.metadata() {
+                    let size = metadata.len();
+                    if let Some(reason) = self.upload_skip_reason(&relative_path_str, size) {
+                        debug!("Skipping {}: {}", relative_path_str, reason);
+                        skipped.push(SkippedFile {
+                            path: relative_path_str,
+                            reason,
+                            kind: SkipKind::Filtered,
+                        });
+                        continue;
+                    }
+
+                    let modified = metadata
+                        .modified()
+                        .unwrap_or(SystemTime::UNIX_EPOCH)
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+
+                    let unchanged = stored_files.get(&relative_path_str).filter(|stored| {
+                        // A record taken in the same second as a write may
+                        // predate it, so that file is hashed again
+                        stored.size == size
+                            && stored.modified == modified
+                            && stored.modified < stored.last_synced
+                            && !stored.hash.is_empty()
+                    });
+                    let (hash, quick_xor_hash) = if size > MAX_FILE_SIZE {
+                        // Too large to upload anyway, so not worth reading every sync
+                        (String::new(), None)
+                    } else if let Some(stored) = unchanged {
+                        (stored.hash.clone(), stored.quick_xor_hash.clone())
+                    } else {
+                        hashed += 1;
+                        match self.calculate_file_hashes(path).await {
+                            Ok((hash, quick_xor_hash)) => (hash, Some(quick_xor_hash)),
+                            Err(e) => {
+                                warn!("Failed to calculate hash for {}: {}", path.display(), e);
+                                (String::new(), None)
+                            }
+                        }
+                    };
+
+                    info!("Found local file: {} (size: {}, hash: {})", relative_path_str, size, hash.get(..8).unwrap_or("none"));
+
+                    files.insert(relative_path_str.clone(), FileRecord {
+                        path: relative_path_str,
+                        hash,
+                        size,
+                        modified,
+                        onedrive_id: None,
+                        last_synced: 0,
+                        quick_xor_hash,
+                    });
+                }
+            }
+        }
+
+        skipped.extend(illegal_folders.into_inner().unwrap());
+        info!(
+            "Scanned {} local files and {} local folders; {} files new or changed since their last sync were hashed",
+            files.len(),
+            folders.len(),
+            hashed
+        );
+        Ok((files, folders, excluded.into_inner().unwrap(), skipped))
+    }
+
+    /// The `.partial` files of downloads in progress, by path. Only these are
+    /// the sync's own; a file of the user's that happens to end in `.partial`
+    /// syncs like any other.
+    async fn partial_download_files(&self) -> Result<HashSet<String>> {
+        self.store
+            .call(|db| {
+                let paths = db
+                    .prepare("SELECT path FROM partial_downloads")?
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .map(|path| path.map(|path| format!("{}{}", path, PARTIAL_DOWNLOAD_SUFFIX)))
+                    .collect::<rusqlite::Result<_>>()?;
+                Ok(paths)
+            })
+            .await
+    }
+
+    /// The placeholder files of online-only files, by path; other files ending
+    /// in `.onedrive` are the user's and sync as usual.
+    async fn placeholder_files(&self) -> Result<HashSet<String>> {
+        self.store
+            .call(|db| {
+                let paths = db
+                    .prepare("SELECT path FROM placeholders")?
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .map(|path| path.map(|path| placeholder_path(&path)))
+                    .collect::<rusqlite::Result<_>>()?;
+                Ok(paths)
+            })
+            .await
+    }
+
+    /// Renames local files and folders whose names OneDrive refuses to the
+    /// nearest name it accepts (`Config::rename_illegal_names`). A name whose
+    /// accepted form is already taken is left alone for the scan to report.
+    async fn rename_illegal_names(&self) -> Result<()> {
+        let sync_folder = &self.config.sync_folder;
+        if !sync_folder.exists() {
+            return Ok(());
+        }
+
+        let partial_files = self.partial_download_files().await?;
+        let placeholder_files = self.placeholder_files().await?;
+        let mut renamed = Vec::new();
+        // Contents first, so renaming a folder never moves paths still to visit
+        for entry in WalkDir::new(sync_folder)
+            .min_depth(1)
+            .contents_first(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if illegal_name_reason(&name).is_none() {
+                continue;
+            }
+            let relative_path = entry.path().strip_prefix(sync_folder)?.to_string_lossy().replace('\\', "/");
+            if relative_path.starts_with('.')
+                || partial_files.contains(&relative_path)
+                || placeholder_files.contains(&relative_path)
+                || self.filter.is_excluded(&relative_path, entry.file_type().is_dir())
+            {
+                continue;
+            }
+
+            let target = entry.path().with_file_name(legal_name(&name));
+            if target.exists() {
+                warn!("Not renaming {}: {} already exists", relative_path, target.display());
+                continue;
+            }
+            match std::fs::rename(entry.path(), &target) {
+                Ok(()) => {
+                    let new_path = target.strip_prefix(sync_folder)?.to_string_lossy().replace('\\', "/");
+                    info!("Renamed {} to {} so OneDrive accepts it", relative_path, new_path);
+                    renamed.push((relative_path, new_path));
+                }
+                Err(e) => warn!("Failed to rename {}: {}", relative_path, e),
+            }
+        }
+
+        for (old_path, new_path) in renamed {
+            self.log_sync_event(
+                "rename_illegal",
+                &new_path,
+                "success",
+                Some(&format!("Renamed from {} because OneDrive does not allow that name", old_path)),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Why the size and type filters keep a local file from syncing, if they do.
+    fn upload_skip_reason(&self, path: &str, size: u64) -> Option<String> {
+        if has_extension(&self.config.excluded_extensions, path) {
+            return Some("File type excluded in settings".to_string());
+        }
+        let max_size_mb = self.config.max_upload_size_mb;
+        if max_size_mb > 0 && size > max_size_mb * 1024 * 1024 {
+            return Some(format!("Larger than the {} MB upload limit", max_size_mb));
+        }
+        None
+    }
+
+    /// Lists OneDrive, or reuses the last listing when a delta query shows
+    /// nothing has changed since. The listing is always a full one otherwise, so
+    /// a delta link OneDrive has invalidated only costs a rescan: the sync
+    /// reconciles it against the database as usual and nothing is applied twice.
+    async fn scan_remote_files(&mut self) -> Result<(HashMap<String, DriveItem>, HashMap<String, DriveItem>)> {
+        if let Some(snapshot) = &mut self.remote_snapshot {
+            match self.api.delta_changes(&snapshot.delta_link).await {
+                Ok((changed, delta_link)) if changed.is_empty() => {
+                    info!("No changes on OneDrive since the last scan");
+                    snapshot.delta_link = delta_link;
+                    return Ok((snapshot.files.clone(), snapshot.folders.clone()));
+                }
+                Ok((changed, _)) => info!("{} items changed on OneDrive since the last scan", changed.len()),
+                Err(e) if is_resync_required(&e) => {
+                    warn!("OneDrive invalidated its change tracking, rescanning everything: {}", e);
+                    self.log_sync_event(
+                        "delta_resync",
+                        "",
+                        "success",
+                        Some("OneDrive asked for a full resynchronization; the whole drive was rescanned"),
+                    ).await?;
+                }
+                Err(e) => warn!("Failed to check OneDrive for changes, rescanning: {}", e),
+            }
+        }
+        self.remote_snapshot = None;
+
+        // Taken before the listing, so changes made while it runs show up next time
+        let delta_link = match self.api.latest_delta_link().await {
+            Ok(delta_link) => Some(delta_link),
+            Err(e) => {
+                warn!("Failed to start tracking OneDrive changes: {}", e);
+                None
+            }
+        };
+
+        let mut files = HashMap::new();
+        let mut folders = HashMap::new();
+        
+        info!("Scanning remote OneDrive files...");
+        
+        match self.scan_remote_folder(&mut files, &mut folders, "/").await {
+            Ok(_) => {
+                info!("Scanned {} remote files and {} remote folders", files.len(), folders.len());
+                if let Some(delta_link) = delta_link {
+                    self.remote_snapshot = Some(RemoteSnapshot {
+                        delta_link,
+                        files: files.clone(),
+                        folders: folders.clone(),
+                    });
+                }
+                Ok((files, folders))
+            }
+            Err(e) => {
+                // A listing cut short looks like files deleted on OneDrive, so
+                // nothing is planned from it
+                error!("Failed to scan remote files: {}", e);
+                Err(e.context("Failed to list the files on OneDrive"))
+            }
+        }
+    }
+
+    fn scan_remote_folder<'a>(
+        &'a self,
+        files: &'a mut HashMap<String, DriveItem>,
+        folders: &'a mut HashMap<String, DriveItem>,
+        folder_path: &'a str,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let items = self.api.list_items(folder_path).await?;
+            
+            for item in items {
+                let item_path = if folder_path == "/" {
+                    item.name.clone()
+                } else {
+                    format!("{}/{}", folder_path.trim_start_matches('/'), item.name)
+                };
+
+                if item.file.is_some() {
+                    if self.filter.is_excluded(&item_path, false)
+                        || has_extension(&self.config.excluded_extensions, &item_path)
+                    {
+                        debug!("Skipping excluded remote file: {}", item_path);
+                        continue;
+                    }
+                    files.insert(item_path, item);
+                } else if item.folder.is_some() {
+                    if self.config.excluded_folder_names.contains(&item.name)
+                        || self.filter.is_excluded(&item_path, true)
+                    {
+                        info!("Skipping excluded remote folder: {}", item_path);
+                        continue;
+                    }
+
+                    // Recursively scan subfolders
+                    self.scan_remote_folder(files, folders, &format!("/{}", item_path)).await?;
+                    folders.insert(item_path, item);
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    async fn get_stored_files(&self) -> Result<HashMap<String, FileRecord>> {
+        self.store
+            .call(|db| {
+                let mut files = HashMap::new();
+
+                let mut stmt = db.prepare(
+                    "SELECT path, hash, size, modified, onedrive_id, last_synced, quick_xor_hash FROM files"
+                )?;
+
+                let file_iter = stmt.query_map([], |row| {
+                    Ok(FileRecord {
+                        path: row.get(0)?,
+                        hash: row.get(1)?,
+                        size: row.get(2)?,
+                        modified: row.get(3)?,
+                        onedrive_id: row.get(4)?,
+                        last_synced: row.get(5)?,
+                        quick_xor_hash: row.get(6)?,
+                    })
+                })?;
+
+                for file in file_iter {
+                    let file = file?;
+                    files.insert(file.path.clone(), file);
+                }
+
+                Ok(files)
+            })
+            .await
+    }
+
+    async fn get_stored_folders(&self) -> Result<HashMap<String, FolderRecord>> {
+        self.store
+            .call(|db| {
+                let mut folders = HashMap::new();
+
+                let mut stmt = db.prepare("SELECT path, onedrive_id, last_synced FROM folders")?;
+                let folder_iter = stmt.query_map([], |row| {
+                    Ok(FolderRecord {
+                        path: row.get(0)?,
+                        onedrive_id: row.get(1)?,
+                        last_synced: row.get(2)?,
+                    })
+                })?;
+
+                for folder in folder_iter {
+                    let folder = folder?;
+                    folders.insert(folder.path.clone(), folder);
+                }
+
+                Ok(folders)
+            })
+            .await
+    }
+
+    fn determine_folder_actions(
+        &self,
+        local_folders: &HashSet<String>,
+        remote_folders: &HashMap<String, DriveItem>,
+        stored_folders: &HashMap<String, FolderRecord>,
+        folder_moves: &[(String, String)],
+    ) -> Vec<SyncAction> {
+        let mut actions = Vec::new();
+
+        for (from, to) in folder_moves {
+            info!("Remote folder moved: {} -> {}", from, to);
+            actions.push(SyncAction::MoveLocalFolder {
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+
+        // Parents sort before their children, so nested folders are created top-down
+        let mut local_only: Vec<&String> = local_folders
+            .iter()
+            .filter(|path| !remote_folders.contains_key(*path))
+            .filter(|path| !folder_moves.iter().any(|(from, _)| is_same_or_inside(path, from)))
+            .collect();
+        local_only.sort();
+
+        for path in local_only {
+            info!("New local folder found: {}", path);
+            actions.push(SyncAction::CreateRemoteFolder { path: path.clone() });
+        }
+
+        let mut remote_only: Vec<(&String, &DriveItem)> = remote_folders
+            .iter()
+            .filter(|(path, _)| !local_folders.contains(*path))
+            .filter(|(path, _)| !folder_moves.iter().any(|(_, to)| is_same_or_inside(path, to)))
+            .collect();
+        remote_only.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (path, item) in remote_only {
+            info!("New remote folder found: {}", path);
+            actions.push(SyncAction::CreateLocalFolder {
+                remote_item: item.clone(),
+                path: path.clone(),
+            });
+        }
+
+        for path in stored_folders.keys() {
+            if !local_folders.contains(path) && !remote_folders.contains_key(path) {
+                info!("Folder deleted both locally and remotely: {}", path);
+                actions.push(SyncAction::RemoveFolderFromDatabase { path: path.clone() });
+            }
+        }
+
+        actions
+    }
+
+    async fn record_existing_folders(
+        &self,
+        local_folders: &HashSet<String>,
+        remote_folders: &HashMap<String, DriveItem>,
+        stored_folders: &HashMap<String, FolderRecord>,
+    ) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        let new_folders: Vec<(String, String)> = remote_folders
+            .iter()
+            .filter(|(path, _)| local_folders.contains(*path) && !stored_folders.contains_key(*path))
+            .map(|(path, item)| (path.clone(), item.id.clone()))
+            .collect();
+
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                for (path, onedrive_id) in new_folders {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO folders (path, onedrive_id, last_synced) VALUES (?1, ?2, ?3)",
+                        params![path, onedrive_id, now],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    fn determine_sync_actions(
+        &self,
+        local_files: &HashMap<String, FileRecord>,
+        remote_files: &HashMap<String, DriveItem>,
+        stored_files: &HashMap<String, FileRecord>,
+        placeholders: &HashMap<String, Placeholder>,
+        folder_moves: &[(String, String)],
+    ) -> Result<Vec<SyncAction>> {
+        let mut actions = Vec::new();
+
+        info!("Determining sync actions...");
+        info!("Local files: {}, Remote files: {}, Stored files: {}", 
+              local_files.len(), remote_files.len(), stored_files.len());
+
+        // Local renames/moves become a remote move instead of a re-upload
+        let moves = detect_local_moves(local_files, remote_files, stored_files);
+        let moved_from: HashSet<&String> = moves.iter().map(|(from, _, _)| from).collect();
+        let moved_to: HashSet<&String> = moves.iter().map(|(_, to, _)| to).collect();
+        for (from, to, onedrive_id) in &moves {
+            info!("Local file moved: {} -> {}", from, to);
+            actions.push(SyncAction::MoveRemote {
+                from: from.clone(),
+                to: to.clone(),
+                onedrive_id: onedrive_id.clone(),
+            });
+        }
+
+        // Remote renames/moves are replayed locally instead of re-downloading
+        let remote_moves = detect_remote_moves(local_files, remote_files, stored_files);
+        let remote_moved_from: HashSet<&String> = remote_moves.iter().map(|(from, _)| from).collect();
+        let remote_moved_to: HashSet<&String> = remote_moves.iter().map(|(_, to)| to).collect();
+        for (from, to) in &remote_moves {
+            if folder_moves.iter().any(|(folder_from, folder_to)| moved_with_folder(from, to, folder_from, folder_to)) {
+                continue;
+            }
+            info!("Remote file moved: {} -> {}", from, to);
+            actions.push(SyncAction::MoveLocal {
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+
+        // Check for uploads (local files not in remote or modified locally)
+        for (path, local_file) in local_files {
+            info!("Checking local file: {}", path);
+            
+            if moved_to.contains(path) {
+                continue;
+            }
+
+            // Anything left in a folder that is about to move is handled at its
+            // new path on the next run
+            if folder_moves.iter().any(|(from, _)| is_same_or_inside(path, from)) {
+                continue;
+            }
+            
+            if let Some(stored_file) = stored_files.get(path) {
+                let remote_changed = remote_files
+                    .get(path)
+                    .map(|remote_file| is_remote_newer(remote_file, stored_file))
+                    .unwrap_or(false);
+
+                if local_file.hash != stored_file.hash && remote_changed {
+                    // Modified on both sides since the last sync - keep both versions
+                    warn!("Conflict detected: {} (modified locally and remotely)", path);
+                    actions.push(SyncAction::ResolveConflict {
+                        remote_item: remote_files[path].clone(),
+                        local_path: path.clone(),
+                    });
+                } else if local_file.hash != stored_file.hash {
+                    // File modified locally
+                    info!("Local file modified: {} (hash changed)", path);
+                    actions.push(SyncAction::Upload {
+                        local_path: path.clone(),
+                        remote_path: path.clone(),
+                    });
+                } else if !remote_files.contains_key(path) && !remote_moved_from.contains(path) {
+                    // Unchanged since the last sync and gone from OneDrive
+                    info!("File deleted remotely: {}", path);
+                    actions.push(SyncAction::DeleteLocal { path: path.clone() });
+                } else {
+                    info!("Local file unchanged: {}", path);
+                }
+            } else if !remote_files.contains_key(path) {
+                // New local file
+                info!("New local file found: {}", path);
+                actions.push(SyncAction::Upload {
+                    local_path: path.clone(),
+                    remote_path: path.clone(),
+                });
+            } else {
+                info!("Local file exists remotely but not in database: {}", path);
+                // File exists remotely but not in our database - treat as already synced
+                // This can happen if database was cleared
+            }
+        }
+
+        // Check for downloads (remote files not in local or modified remotely)
+        for (path, remote_file) in remote_files {
+            info!("Checking remote file: {}", path);
+            
+            if moved_from.contains(path) || remote_moved_to.contains(path) {
+                continue;
+            }
+
+            if let Some(mime_type) = remote_file.mime_type() {
+                if matches_mime_type(&self.config.download_exclude_mime_types, mime_type) {
+                    debug!("Not downloading {} ({})", path, mime_type);
+                    continue;
+                }
+            }
+            
+            if let Some(placeholder) = placeholders.get(path) {
+                if placeholder.requested {
+                    info!("Downloading online-only file: {}", path);
+                    actions.push(SyncAction::Download {
+                        remote_item: remote_file.clone(),
+                        local_path: path.clone(),
+                    });
+                } else if placeholder.modified != remote_file.last_modified || Some(placeholder.size) != remote_file.size {
+                    info!("Online-only file changed on OneDrive: {}", path);
+                    actions.push(SyncAction::CreatePlaceholder {
+                        remote_item: remote_file.clone(),
+                        local_path: path.clone(),
+                    });
+                } else {
+                    debug!("Online-only file unchanged: {}", path);
+                }
+            } else if !local_files.contains_key(path) && self.config.online_only && !stored_files.contains_key(path) {
+                info!("New remote file found, kept online-only: {}", path);
+                actions.push(SyncAction::CreatePlaceholder {
+                    remote_item: remote_file.clone(),
+                    local_path: path.clone(),
+                });
+            } else if !local_files.contains_key(path) {
+                // New remote file
+                info!("New remote file found: {}", path);
+                actions.push(SyncAction::Download {
+                    remote_item: remote_file.clone(),
+                    local_path: path.clone(),
+                });
+            } else if let Some(stored_file) = stored_files.get(path) {
+                let local_changed = local_files[path].hash != stored_file.hash;
+                if local_changed && is_remote_newer(remote_file, stored_file) {
+                    info!("Remote file conflicts with local changes: {}", path);
+                } else if is_remote_newer(remote_file, stored_file) {
+                    info!("Remote file newer than local: {}", path);
+                    actions.push(SyncAction::Download {
+                        remote_item: remote_file.clone(),
+                        local_path: path.clone(),
+                    });
+                } else {
+                    info!("Remote file up to date: {}", path);
+                }
+            } else {
+                info!("Remote file exists locally but not in database: {}", path);
+            }
+        }
+
+        // Check for deletions (files in stored but not in local or remote)
+        for (path, _) in stored_files {
+            if !local_files.contains_key(path) && !remote_files.contains_key(path) {
+                info!("File deleted both locally and remotely: {}", path);
+                actions.push(SyncAction::RemoveFromDatabase {
+                    path: path.clone(),
+                });
+            }
+        }
+
+        info!("Determined {} sync actions", actions.len());
+        for action in &actions {
+            match action {
+                SyncAction::Upload { local_path, .. } => info!("Action: Upload {}", local_path),
+                SyncAction::Download { local_path, .. } => info!("Action: Download {}", local_path),
+                SyncAction::RemoveFromDatabase { path } => info!("Action: Cleanup {}", path),
+                SyncAction::CreateRemoteFolder { path } => info!("Action: Create remote folder {}", path),
+                SyncAction::CreateLocalFolder { path, .. } => info!("Action: Create local folder {}", path),
+                SyncAction::RemoveFolderFromDatabase { path } => info!("Action: Cleanup folder {}", path),
+                SyncAction::ResolveConflict { local_path, .. } => info!("Action: Resolve conflict {}", local_path),
+                SyncAction::MoveRemote { from, to, .. } => info!("Action: Move {} -> {}", from, to),
+                SyncAction::MoveLocal { from, to } => info!("Action: Move local {} -> {}", from, to),
+                SyncAction::MoveLocalFolder { from, to } => info!("Action: Move local folder {} -> {}", from, to),
+                SyncAction::DeleteLocal { path } => info!("Action: Delete local {}", path),
+                SyncAction::ArchiveLocal { path } => info!("Action: Archive {}", path),
+                SyncAction::CreatePlaceholder { local_path, .. } => info!("Action: Placeholder {}", local_path),
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Runs `action`, holding what it would have changed on OneDrive when
+    /// OneDrive refuses it for lack of write access.
+    async fn execute_sync_action(&self, action: SyncAction, cancel: &CancellationToken) -> Result<()> {
+        let changed = changed_remote_path(&action).map(str::to_string);
+        let result = self.apply_sync_action(action, cancel).await;
+        if let (Err(e), Some(path)) = (&result, changed) {
+            if is_access_denied(e) {
+                self.hold_read_only(&path).await?;
+            }
+        }
+        result
+    }
+
+    async fn apply_sync_action(&self, action: SyncAction, cancel: &CancellationToken) -> Result<()> {
+        match action {
+            SyncAction::Upload { local_path, remote_path } => {
+                self.upload_and_record(&local_path, &remote_path, cancel).await?;
+
+                self.update_status(|status| {
+                    status.files_uploaded += 1;
+                }).await;
+                self.log_sync_event("upload", &local_path, "success", None).await?;
+            }
+
+            SyncAction::Download { remote_item, local_path } => {
+                self.download_and_record(&remote_item, &local_path, cancel).await?;
+
+                let planned_path = local_path.clone();
+                self.store
+                    .call(move |db| {
+                        db.execute("DELETE FROM initial_download_plan WHERE path = ?1", params![planned_path])?;
+                        Ok(())
+                    })
+                    .await?;
+                self.remove_placeholders(vec![local_path.clone()]).await?;
+
+                self.update_status(|status| {
+                    status.files_downloaded += 1;
+                }).await;
+                self.log_sync_event("download", &local_path, "success", None).await?;
+            }
+
+            SyncAction::RemoveFromDatabase { path } => {
+                self.forget_file(&path).await?;
+                
+                self.update_status(|status| {
+                    status.files_deleted += 1;
+                }).await;
+                self.log_sync_event("remove_from_db", &path, "success", None).await?;
+            }
+
+            SyncAction::CreateRemoteFolder { path } => {
+                let (folder_name, parent_path) = remote_name_and_parent(&path);
+
+                info!("Creating remote folder: {}", path);
+                let remote_item = self.api.create_folder(&folder_name, &parent_path).await?;
+                self.record_folder(&path, &remote_item.id).await?;
+                self.log_sync_event("create_remote_folder", &path, "success", None).await?;
+            }
+
+            SyncAction::CreateLocalFolder { remote_item, path } => {
+                let local_full_path = self.config.sync_folder.join(&path);
+
+                info!("Creating local folder: {}", path);
+                fs::create_dir_all(&local_full_path).await?;
+                self.record_folder(&path, &remote_item.id).await?;
+                self.log_sync_event("create_local_folder", &path, "success", None).await?;
+            }
+
+            SyncAction::RemoveFolderFromDatabase { path } => {
+                let folder_path = path.clone();
+                self.store
+                    .call(move |db| {
+                        db.execute("DELETE FROM folders WHERE path = ?1", params![folder_path])?;
+                        Ok(())
+                    })
+                    .await?;
+
+                self.log_sync_event("remove_folder_from_db", &path, "success", None).await?;
+            }
+
+            SyncAction::MoveRemote { from, to, onedrive_id } => {
+                let (parent_path, new_name) = match to.rsplit_once('/') {
+                    Some((parent, name)) => (format!("/{}", parent), name.to_string()),
+                    None => ("/".to_string(), to.clone()),
+                };
+
+                info!("Moving remote item: {} -> {}", from, to);
+                self.api.move_item(&onedrive_id, &parent_path, &new_name).await?;
+
+                self.record_file_move(&from, &to).await?;
+
+                self.log_sync_event("move", &to, "success", Some(&format!("Moved from {}", from))).await?;
+            }
+
+            SyncAction::MoveLocal { from, to } => {
+                let to_full_path = self.config.sync_folder.join(&to);
+                if let Some(parent) = to_full_path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+
+                info!("Moving local file: {} -> {}", from, to);
+                fs::rename(self.config.sync_folder.join(&from), &to_full_path).await?;
+
+                self.record_file_move(&from, &to).await?;
+
+                self.log_sync_event("move_local", &to, "success", Some(&format!("Moved from {}", from))).await?;
+            }
+
+            SyncAction::MoveLocalFolder { from, to } => {
+                let to_full_path = self.config.sync_folder.join(&to);
+                if let Some(parent) = to_full_path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+
+                info!("Moving local folder: {} -> {}", from, to);
+                fs::rename(self.config.sync_folder.join(&from), &to_full_path).await?;
+
+                // Everything under the folder moves with it, in the database too
+                let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+                let from_prefix = format!("{}/", from);
+                let (moved_from, moved_to) = (from.clone(), to.clone());
+                self.store
+                    .call(move |db| {
+                        let tx = db.transaction()?;
+                        for (table, time_column) in [("files", "last_synced"), ("folders", "last_synced"), ("file_states", "updated")] {
+                            tx.execute(
+                                &format!(
+                                    "UPDATE {} SET path = ?1 || substr(path, ?2), {} = ?3
+                                     WHERE path = ?4 OR substr(path, 1, ?5) = ?6",
+                                    table, time_column
+                                ),
+                                params![
+                                    moved_to,
+                                    moved_from.len() as i64 + 1,
+                                    now,
+                                    moved_from,
+                                    from_prefix.len() as i64,
+                                    from_prefix
+                                ],
+                            )?;
+                        }
+                        tx.commit()?;
+                        Ok(())
+                    })
+                    .await?;
+
+                self.log_sync_event("move_local", &to, "success", Some(&format!("Moved folder from {}", from))).await?;
+            }
+
+            SyncAction::DeleteLocal { path } => {
+                let local_full_path = self.config.sync_folder.join(&path);
+                let recorded = path.clone();
+                let recorded_hash: Option<String> = self
+                    .store
+                    .call(move |db| {
+                        Ok(db
+                            .query_row("SELECT hash FROM files WHERE path = ?1", params![recorded], |row| row.get(0))
+                            .optional()?)
+                    })
+                    .await?;
+                // Removed here too since the action was planned, as by the user
+                if fs::symlink_metadata(&local_full_path)
+                    .await
+                    .is_err_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+                {
+                    info!("Remotely deleted file is already gone locally: {}", path);
+                    self.forget_file(&path).await?;
+                    self.log_sync_event("delete_local", &path, "success", Some("Already removed")).await?;
+                    return Ok(());
+                }
+
+                // Checked again in case it was written to since the sync
+                // started; the next sync uploads the edit instead
+                let (hash, _) = self.calculate_file_hashes(&local_full_path).await?;
+                if recorded_hash.as_deref() != Some(hash.as_str()) {
+                    info!("Not removing {}; it changed since the sync started", path);
+                    return Ok(());
+                }
+
+                let outcome = if self.config.trash_remote_deletions {
+                    info!("Moving remotely deleted file to the Trash: {}", path);
+                    let trashed = local_full_path.clone();
+                    tokio::task::spawn_blocking(move || desktop::move_to_trash(&trashed)).await??;
+                    "Moved to Trash"
+                } else {
+                    info!("Deleting remotely deleted file: {}", path);
+                    fs::remove_file(&local_full_path).await?;
+                    "Deleted"
+                };
+
+                self.forget_file(&path).await?;
+
+                self.update_status(|status| {
+                    status.files_deleted += 1;
+                }).await;
+                self.log_sync_event("delete_local", &path, "success", Some(outcome)).await?;
+            }
+
+            SyncAction::ArchiveLocal { path } => {
+                let local_full_path = self.config.sync_folder.join(&path);
+                let recorded = path.clone();
+                let record: Option<(String, u64, Option<String>)> = self
+                    .store
+                    .call(move |db| {
+                        Ok(db
+                            .query_row(
+                                "SELECT hash, size, onedrive_id FROM files WHERE path = ?1",
+                                params![recorded],
+                                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                            )
+                            .optional()?)
+                    })
+                    .await?;
+                // Checked again in case it was written to since it was verified
+                // against OneDrive
+                let (hash, _) = self.calculate_file_hashes(&local_full_path).await?;
+                let Some((_, size, onedrive_id)) = record.filter(|(recorded_hash, _, _)| *recorded_hash == hash) else {
+                    info!("Not freeing {}; it changed since the sync started", path);
+                    return Ok(());
+                };
+
+                info!("Freeing archived file from this computer: {}", path);
+                fs::remove_file(&local_full_path).await?;
+
+                let (archived, now) = (path.clone(), SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs());
+                self.store
+                    .call(move |db| {
+                        let tx = db.transaction()?;
+                        tx.execute("DELETE FROM files WHERE path = ?1", params![archived])?;
+                        tx.execute(
+                            "INSERT OR REPLACE INTO archived_files (path, onedrive_id, size, archived) VALUES (?1, ?2, ?3, ?4)",
+                            params![archived, onedrive_id, size, now],
+                        )?;
+                        tx.commit()?;
+                        Ok(())
+                    })
+                    .await?;
+                self.mark_file(&path, None).await?;
+
+                self.log_sync_event("archive_local", &path, "success", Some(&format!("Freed {}; kept on OneDrive", format::size(size)))).await?;
+            }
+
+            SyncAction::CreatePlaceholder { remote_item, local_path } => {
+                let placeholder_file = self.config.sync_folder.join(placeholder_path(&local_path));
+                if let Some(parent) = placeholder_file.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                let contents = serde_json::json!({
+                    "name": remote_item.name,
+                    "size": remote_item.size,
+                    "modified": remote_item.last_modified,
+                    "onedrive_id": remote_item.id,
+                    "note": "This file is online-only. Choose Download now in the Files tab, or run: onedrive-ubuntu download-now <this file>",
+                });
+                fs::write(&placeholder_file, serde_json::to_vec_pretty(&contents)?).await?;
+                if let Some(modified) = parse_iso_datetime(&remote_item.last_modified) {
+                    let modified = filetime::FileTime::from_unix_time(modified as i64, 0);
+                    if let Err(e) = filetime::set_file_mtime(&placeholder_file, modified) {
+                        debug!("Could not date the placeholder of {}: {}", local_path, e);
+                    }
+                }
+
+                let (path, onedrive_id, size, modified) =
+                    (local_path.clone(), remote_item.id.clone(), remote_item.size.unwrap_or(0), remote_item.last_modified.clone());
+                self.store
+                    .call(move |db| {
+                        db.execute(
+                            "INSERT OR REPLACE INTO placeholders (path, onedrive_id, size, modified, requested) VALUES (?1, ?2, ?3, ?4, 0)",
+                            params![path, onedrive_id, size, modified],
+                        )?;
+                        Ok(())
+                    })
+                    .await?;
+
+                self.log_sync_event("placeholder", &local_path, "success", Some("Online-only")).await?;
+            }
+
+            SyncAction::ResolveConflict { remote_item, local_path } => {
+                // Keep both: the local edit moves aside to a conflicted copy, the remote
+                // version takes the original name, and the copy is uploaded as a new file
+                let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+                let mut copy_path = conflict_copy_path(&local_path, &today, 1);
+                let mut attempt = 1;
+                while self.config.sync_folder.join(&copy_path).exists() {
+                    attempt += 1;
+                    copy_path = conflict_copy_path(&local_path, &today, attempt);
+                }
+
+                warn!("Resolving conflict: {} -> {}", local_path, copy_path);
+                fs::rename(
+                    self.config.sync_folder.join(&local_path),
+                    self.config.sync_folder.join(&copy_path),
+                ).await?;
+
+                self.download_and_record(&remote_item, &local_path, cancel).await?;
+                self.upload_and_record(&copy_path, &copy_path, cancel).await?;
+
+                self.update_status(|status| {
+                    status.files_conflicted += 1;
+                    status.files_downloaded += 1;
+                    status.files_uploaded += 1;
+                }).await;
+                self.log_sync_event(
+                    "conflict",
+                    &local_path,
+                    "conflict",
+                    Some(&format!("Modified locally and remotely; local version kept as {}", copy_path)),
+                ).await?;
+                self.webhooks.conflict(self.run_id.as_deref().unwrap_or_default(), &local_path, &copy_path);
+                self.notifier.notify_conflict(&local_path, &copy_path, self.run_id.as_deref().unwrap_or_default());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn upload_and_record(&self, local_path: &str, remote_path: &str, cancel: &CancellationToken) -> Result<()> {
+        let local_full_path = self.config.sync_folder.join(local_path);
+        
+        info!("Uploading: {}", local_path);
+        let size = fs::metadata(&local_full_path).await?.len();
+        self.start_transfer_progress(local_path, 0, size).await;
+        let remote_item = if size < SIMPLE_UPLOAD_LIMIT {
+            self.api.upload_file(&local_full_path, remote_path).await
+        } else {
+            self.upload_with_session(local_path, remote_path, size, cancel).await
+        };
+        if remote_item.is_ok() {
+            self.report_transfer_progress(local_path, size);
+        }
+        self.finish_transfer_progress(local_path).await;
+        let remote_item = remote_item?;
+        
+        // Update database
+        let (hash, quick_xor_hash) = self.calculate_file_hashes(&local_full_path).await?;
+        let metadata = fs::metadata(&local_full_path).await?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        // Record the hash OneDrive now holds; if the file changed during the
+        // upload, the local hash still differs and it is uploaded again next run
+        let remote_hash = remote_item.quick_xor_hash().map(str::to_string).unwrap_or(quick_xor_hash);
+        let (path, id) = (local_path.to_string(), remote_item.id.clone());
+        let mime_type = remote_item.mime_type().map(str::to_string);
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "INSERT OR REPLACE INTO files (path, hash, size, modified, onedrive_id, last_synced, quick_xor_hash, mime_type) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![path, hash, size, modified, id, now, remote_hash, mime_type],
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        self.mark_file(local_path, Some(FileState::InSync)).await
+    }
+
+    /// Uploads a large file through an upload session saved in the database, so
+    /// an upload interrupted by a crash or restart continues from the last
+    /// confirmed chunk instead of from zero.
+    async fn upload_with_session(&self, local_path: &str, remote_path: &str, size: u64, cancel: &CancellationToken) -> Result<DriveItem> {
+        let local_full_path = self.config.sync_folder.join(local_path);
+        let modified = fs::metadata(&local_full_path)
+            .await?
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+
+        let path = local_path.to_string();
+        let saved_url: Option<String> = self
+            .store
+            .call(move |db| {
+                Ok(db
+                    .query_row(
+                        "SELECT upload_url FROM upload_sessions WHERE path = ?1 AND size = ?2 AND modified = ?3",
+                        params![path, size, modified],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        // A saved session is only reused while the file is unchanged and Graph
+        // still knows the session; the offset Graph reports is authoritative, the
+        // saved one only records how far the last attempt got
+        let resumed = match saved_url {
+            Some(upload_url) => match self.api.upload_session_offset(&upload_url).await {
+                Ok(Some(offset)) => Some((upload_url, offset)),
+                Ok(None) => None,
+                Err(e) => {
+                    warn!("Could not query saved upload session for {}: {}", local_path, e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let (upload_url, start_offset) = match resumed {
+            Some((upload_url, offset)) => {
+                info!("Resuming upload of {} at {} of {} bytes", local_path, offset, size);
+                (upload_url, offset)
+            }
+            None => {
+                let session = self.api.create_upload_session(&local_full_path, remote_path).await?;
+                let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+                let (path, upload_url) = (local_path.to_string(), session.upload_url.clone());
+                self.store
+                    .call(move |db| {
+                        db.execute(
+                            "INSERT OR REPLACE INTO upload_sessions (path, upload_url, size, modified, offset, created) VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+                            params![path, upload_url, size, modified, now],
+                        )?;
+                        Ok(())
+                    })
+                    .await?;
+                (session.upload_url, 0)
+            }
+        };
+
+        let confirmed = std::sync::atomic::AtomicU64::new(start_offset);
+        self.report_transfer_progress(local_path, start_offset);
+        let result = self
+            .api
+            .upload_to_session(&upload_url, &local_full_path, start_offset, size, cancel, &|offset| {
+                confirmed.store(offset, std::sync::atomic::Ordering::Relaxed);
+                self.report_transfer_progress(local_path, offset);
+            })
+            .await;
+
+        let path = local_path.to_string();
+        let finished = result.is_ok();
+        let confirmed = confirmed.load(std::sync::atomic::Ordering::Relaxed);
+        self.store
+            .call(move |db| {
+                if finished {
+                    db.execute("DELETE FROM upload_sessions WHERE path = ?1", params![path])?;
+                } else {
+                    db.execute("UPDATE upload_sessions SET offset = ?1 WHERE path = ?2", params![confirmed, path])?;
+                }
+                Ok(())
+            })
+            .await?;
+
+        result
+    }
+
+    async fn download_and_record(&self, remote_item: &DriveItem, local_path: &str, cancel: &CancellationToken) -> Result<()> {
+        let local_full_path = self.config.sync_folder.join(local_path);
+        
+        // Create parent directories if needed
+        if let Some(parent) = local_full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        
+        // Graph has no writable attribute for items, so the local mode bits are
+        // the source of truth and must survive a download that replaces the file
+        let existing_permissions = fs::metadata(&local_full_path).await.ok().map(|m| m.permissions());
+        if let Some(ref permissions) = existing_permissions {
+            if permissions.readonly() {
+                debug!("Temporarily making read-only file writable: {}", local_path);
+                let writable = std::fs::Permissions::from_mode(permissions.mode() | 0o200);
+                fs::set_permissions(&local_full_path, writable).await?;
+            }
+        }
+
+        let offset = self.partial_download_offset(remote_item, local_path).await?;
+        if offset > 0 {
+            info!("Downloading: {} (resuming at {} of {} bytes)", local_path, offset, remote_item.size.unwrap_or(0));
+        } else {
+            info!("Downloading: {}", local_path);
+        }
+        self.start_transfer_progress(local_path, offset, remote_item.size.unwrap_or(0)).await;
+        let download_result = self
+            .api
+            .download_file_from(remote_item, &local_full_path, offset, cancel, &|written| {
+                self.report_transfer_progress(local_path, written);
+            })
+            .await;
+        if download_result.is_ok() {
+            self.report_transfer_progress(local_path, remote_item.size.unwrap_or(0));
+        }
+        self.finish_transfer_progress(local_path).await;
+
+        if let Some(permissions) = existing_permissions {
+            if let Err(e) = fs::set_permissions(&local_full_path, permissions).await {
+                warn!("Failed to restore permissions on {}: {}", local_path, e);
+            }
+        }
+        if let Err(e) = download_result {
+            // The corrupt partial file is gone, so there is nothing to resume
+            if e.is::<HashMismatch>() {
+                let path = local_path.to_string();
+                self.store
+                    .call(move |db| {
+                        db.execute("DELETE FROM partial_downloads WHERE path = ?1", params![path])?;
+                        Ok(())
+                    })
+                    .await?;
+            }
+            return Err(e);
+        }
+        
+        // Update database
+        let (hash, quick_xor_hash) = self.calculate_file_hashes(&local_full_path).await?;
+        let size = remote_item.size.unwrap_or(0);
+        // What the disk reports, so the offline change check sees an unchanged file
+        let modified = fs::metadata(&local_full_path)
+            .await?
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        let (path, id) = (local_path.to_string(), remote_item.id.clone());
+        let mime_type = remote_item.mime_type().map(str::to_string);
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "INSERT OR REPLACE INTO files (path, hash, size, modified, onedrive_id, last_synced, quick_xor_hash, mime_type) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![path, hash, size, modified, id, now, quick_xor_hash, mime_type],
+                )?;
+                db.execute("DELETE FROM partial_downloads WHERE path = ?1", params![path])?;
+                Ok(())
+            })
+            .await?;
+
+        self.mark_file(local_path, Some(FileState::InSync)).await
+    }
+
+    /// Makes `path` the transfer whose bytes `SyncStatus` reports, with `done`
+    /// of its `total` bytes already transferred.
+    async fn start_transfer_progress(&self, path: &str, done: u64, total: u64) {
+        self.throughput.lock().unwrap().begin(path, done);
+        let path = path.to_string();
+        self.update_status(|status| {
+            status.current_file = Some(path);
+            status.current_file_bytes_done = done;
+            status.current_file_bytes_total = total;
+        }).await;
+    }
+
+    /// Records how far the transfer of `path` has got, and the speed and time
+    /// left that follow. Called from inside the chunk loops, so it never waits
+    /// for the status lock; a missed update is replaced by the next chunk's.
+    fn report_transfer_progress(&self, path: &str, done: u64) {
+        let (speed, remaining) = {
+            let mut throughput = self.throughput.lock().unwrap();
+            throughput.progress(path, done);
+            (throughput.bytes_per_second(), throughput.remaining())
+        };
+        if let Ok(mut status) = self.status.try_lock() {
+            status.current_speed_bps = speed;
+            status.estimated_remaining = remaining;
+            if status.current_file.as_deref() == Some(path) {
+                status.current_file_bytes_done = done;
+            }
+        }
+    }
+
+    /// Stops reporting bytes for `path` once its transfer has ended, unless a
+    /// later transfer has taken the status over since.
+    async fn finish_transfer_progress(&self, path: &str) {
+        self.throughput.lock().unwrap().end(path);
+        self.update_status(|status| {
+            if status.current_file.as_deref() == Some(path) {
+                status.current_file = None;
+                status.current_file_bytes_done = 0;
+                status.current_file_bytes_total = 0;
+            }
+        }).await;
+    }
+
+    /// How many bytes of an earlier, interrupted download of this exact remote
+    /// version are already in the `.partial` file. Anything else starts over.
+    async fn partial_download_offset(&self, remote_item: &DriveItem, local_path: &str) -> Result<u64> {
+        let partial_path = partial_download_path(&self.config.sync_folder.join(local_path));
+        let size = remote_item.size.unwrap_or(0);
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        let path = local_path.to_string();
+        let recorded: Option<(String, String)> = self
+            .store
+            .call(move |db| {
+                Ok(db
+                    .query_row(
+                        "SELECT onedrive_id, last_modified FROM partial_downloads WHERE path = ?1",
+                        params![path],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        let same_version = recorded == Some((remote_item.id.clone(), remote_item.last_modified.clone()));
+        let partial_len = fs::metadata(&partial_path).await.map(|m| m.len()).unwrap_or(0);
+        let offset = if same_version && partial_len < size { partial_len } else { 0 };
+
+        if offset == 0 {
+            let (path, id, last_modified) = (local_path.to_string(), remote_item.id.clone(), remote_item.last_modified.clone());
+            self.store
+                .call(move |db| {
+                    db.execute(
+                        "INSERT OR REPLACE INTO partial_downloads (path, onedrive_id, last_modified, size, started) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![path, id, last_modified, size, now],
+                    )?;
+                    Ok(())
+                })
+                .await?;
+        }
+
+        Ok(offset)
+    }
+
+    async fn record_folder(&self, path: &str, onedrive_id: &str) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+
+        let (path, onedrive_id) = (path.to_string(), onedrive_id.to_string());
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "INSERT OR REPLACE INTO folders (path, onedrive_id, last_synced) VALUES (?1, ?2, ?3)",
+                    params![path, onedrive_id, now],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Stops tracking a file that is gone from both sides.
+    async fn forget_file(&self, path: &str) -> Result<()> {
+        let forgotten = path.to_string();
+        self.store
+            .call(move |db| {
+                db.execute("DELETE FROM files WHERE path = ?1", params![forgotten])?;
+                Ok(())
+            })
+            .await?;
+
+        self.mark_file(path, None).await
+    }
+
+    /// Moves a tracked file's record after it was moved on either side.
+    async fn record_file_move(&self, from: &str, to: &str) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let (from, to) = (from.to_string(), to.to_string());
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "UPDATE files SET path = ?1, last_synced = ?2 WHERE path = ?3",
+                    params![to, now, from],
+                )?;
+                db.execute(
+                    "UPDATE OR REPLACE file_states SET path = ?1, updated = ?2 WHERE path = ?3",
+                    params![to, now, from],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// SHA-256 (hex, for local change tracking) and QuickXorHash (base64, to
+    /// compare against OneDrive) of a file, computed in a single read.
+    async fn calculate_file_hashes(&self, path: &Path) -> Result<(String, String)> {
+        // Hash in fixed-size reads so large files are never held in memory
+        let mut file = fs::File::open(path).await?;
+        let mut hasher = Sha256::new();
+        let mut quick_xor = QuickXorHash::new();
+        let mut buffer = vec![0u8; 1024 * 1024];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            quick_xor.update(&buffer[..read]);
+        }
+        Ok((hex::encode(hasher.finalize()), quick_xor.finalize()))
+    }
+
+    /// Opens the `sync_runs` row of the run starting now.
+    async fn start_run_record(&self, run_id: &str) -> Result<RunStart> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let run_id = run_id.to_string();
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "INSERT OR REPLACE INTO sync_runs (run_id, started) VALUES (?1, ?2)",
+                    params![run_id, now],
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        let status = self.status.lock().await;
+        Ok(RunStart {
+            uploaded: status.files_uploaded,
+            downloaded: status.files_downloaded,
+            deleted: status.files_deleted,
+        })
+    }
+
+    /// Fills in the `sync_runs` row of a run that has ended with `outcome`.
+    async fn finish_run_record(&self, run_id: &str, outcome: &str, start: RunStart) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let bytes_transferred = self.throughput.lock().unwrap().done();
+        let (uploaded, downloaded, deleted, skipped, errors) = {
+            let status = self.status.lock().await;
+            (
+                status.files_uploaded.saturating_sub(start.uploaded),
+                status.files_downloaded.saturating_sub(start.downloaded),
+                status.files_deleted.saturating_sub(start.deleted),
+                status.skipped_files.len() as u64,
+                status.sync_errors.len() as u64,
+            )
+        };
+
+        let (run_id, outcome) = (run_id.to_string(), outcome.to_string());
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "UPDATE sync_runs SET finished = ?2, outcome = ?3, uploaded = ?4, downloaded = ?5, deleted = ?6,
+                         skipped = ?7, bytes_transferred = ?8, errors = ?9
+                     WHERE run_id = ?1",
+                    params![run_id, now, outcome, uploaded, downloaded, deleted, skipped, bytes_transferred, errors],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Deletes sync log entries and run records past `Config::log_retention_days`,
+    /// then the oldest log entries beyond `Config::log_max_rows`.
+    async fn prune_logs(&self) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let cutoff = match self.config.log_retention_days {
+            0 => 0,
+            days => now.saturating_sub(days * 24 * 60 * 60),
+        };
+        let max_rows = self.config.log_max_rows;
+
+        let pruned = self
+            .store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                let mut pruned = tx.execute("DELETE FROM sync_log WHERE timestamp < ?1", params![cutoff])?;
+                tx.execute("DELETE FROM sync_runs WHERE started < ?1", params![cutoff])?;
+                if max_rows > 0 {
+                    pruned += tx.execute(
+                        "DELETE FROM sync_log WHERE id <= (SELECT id FROM sync_log ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+                        params![max_rows],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(pruned)
+            })
+            .await?;
+        if pruned > 0 {
+            info!("Pruned {} old sync log entries", pruned);
+        }
+        Ok(())
+    }
+
+    /// Deletes the whole sync log and every finished run record.
+    pub async fn clear_logs(&self) -> Result<()> {
+        self.store
+            .call(|db| {
+                let tx = db.transaction()?;
+                tx.execute("DELETE FROM sync_log", [])?;
+                tx.execute("DELETE FROM sync_runs WHERE finished IS NOT NULL", [])?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await?;
+        info!("Sync log cleared");
+        Ok(())
+    }
+
+    /// The most recent sync runs, newest first, as kept in the database
+    /// across restarts.
+    pub async fn get_sync_runs(&self, limit: usize) -> Result<Vec<SyncRun>> {
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT run_id, started, finished, outcome, uploaded, downloaded, deleted, skipped, bytes_transferred, errors
+                     FROM sync_runs ORDER BY started DESC, rowid DESC LIMIT ?1"
+                )?;
+                let runs = stmt
+                    .query_map(params![limit as i64], |row| {
+                        Ok(SyncRun {
+                            run_id: row.get(0)?,
+                            started: row.get(1)?,
+                            finished: row.get(2)?,
+                            outcome: row.get(3)?,
+                            uploaded: row.get(4)?,
+                            downloaded: row.get(5)?,
+                            deleted: row.get(6)?,
+                            skipped: row.get(7)?,
+                            bytes_transferred: row.get(8)?,
+                            errors: row.get(9)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(runs)
+            })
+            .await
+    }
+
+    async fn log_sync_event(&self, action: &str, file_path: &str, status: &str, error: Option<&str>) -> Result<()> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        
+        let (action, file_path, status) = (action.to_string(), file_path.to_string(), status.to_string());
+        let error = error.map(str::to_string);
+        let run_id = self.run_id.clone();
+        self.store
+            .call(move |db| {
+                db.execute(
+                    "INSERT INTO sync_log (timestamp, action, file_path, status, error, run_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![now, action, file_path, status, error, run_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// The newest `limit` log entries fuzzily matching `filter` (see
+    /// `fuzzy_like_pattern`) on their action, path, status, message or run ID.
+    pub async fn get_sync_history(&self, limit: usize, filter: &str) -> Result<Vec<SyncLogEntry>> {
+        let pattern = fuzzy_like_pattern(filter);
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT sync_log.timestamp, sync_log.action, sync_log.file_path, sync_log.status, sync_log.error, files.mime_type, sync_log.run_id
+                     FROM sync_log LEFT JOIN files ON files.path = sync_log.file_path
+                     WHERE ?2 = '%'
+                        OR sync_log.action || ' ' || sync_log.file_path || ' ' || sync_log.status || ' '
+                           || IFNULL(sync_log.error, '') || ' ' || IFNULL(sync_log.run_id, '') LIKE ?2 ESCAPE '\\'
+                     ORDER BY sync_log.timestamp DESC LIMIT ?1"
+                )?;
+
+                let entries = stmt.query_map(params![limit, pattern], |row| {
+                    Ok(SyncLogEntry {
+                        timestamp: row.get(0)?,
+                        action: row.get(1)?,
+                        file_path: row.get(2)?,
+                        status: row.get(3)?,
+                        error: row.get(4)?,
+                        mime_type: row.get(5)?,
+                        run_id: row.get(6)?,
+                    })
+                })?;
+
+                let mut result = Vec::new();
+                for entry in entries {
+                    result.push(entry?);
+                }
+
+                Ok(result)
+            })
+            .await
+    }
+
+    /// Brings the full-text index in line with the synced files: indexes new
+    /// and changed documents and drops files no longer tracked. With
+    /// `Config::index_content` off it is emptied instead.
+    async fn update_content_index(&self) -> Result<()> {
+        if !self.config.index_content {
+            return self
+                .store
+                .call(|db| {
+                    db.execute("DELETE FROM content_index", [])?;
+                    Ok(())
+                })
+                .await;
+        }
+
+        let (tracked, indexed) = self
+            .store
+            .call(|db| {
+                let tracked = db
+                    .prepare("SELECT path, hash FROM files")?
+                    .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let indexed = db
+                    .prepare("SELECT path, hash FROM content_index")?
+                    .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+                Ok((tracked, indexed))
+            })
+            .await?;
+
+        let tracked_paths: HashSet<&str> = tracked.iter().map(|(path, _)| path.as_str()).collect();
+        let removed: Vec<String> = indexed
+            .keys()
+            .filter(|path| !tracked_paths.contains(path.as_str()))
+            .cloned()
+            .collect();
+
+        let mut extracted = Vec::new();
+        for (path, hash) in &tracked {
+            if !index::is_indexable(path) || indexed.get(path) == Some(hash) {
+                continue;
+            }
+            // Files kept online-only have nothing to read yet
+            let full_path = self.config.sync_folder.join(path);
+            if !full_path.is_file() {
+                continue;
+            }
+            match tokio::task::spawn_blocking(move || index::extract_text(&full_path)).await? {
+                Ok(text) => extracted.push((path.clone(), hash.clone(), text)),
+                Err(e) => warn!("Not indexing {}: {}", path, e),
+            }
+        }
+
+        if removed.is_empty() && extracted.is_empty() {
+            return Ok(());
+        }
+        info!("Indexing {} documents, dropping {} from the content index", extracted.len(), removed.len());
+        self.store
+            .call(move |db| {
+                let tx = db.transaction()?;
+                for path in removed {
+                    tx.execute("DELETE FROM content_index WHERE path = ?1", params![path])?;
+                }
+                for (path, hash, text) in extracted {
+                    tx.execute("DELETE FROM content_index WHERE path = ?1", params![path])?;
+                    tx.execute(
+                        "INSERT INTO content_index (path, hash, body) VALUES (?1, ?2, ?3)",
+                        params![path, hash, text],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Indexed documents containing every word of `query`, best match first.
+    /// Only reads the local index, so it works offline.
+    pub async fn search_content(&self, query: &str, limit: usize) -> Result<Vec<ContentMatch>> {
+        let Some(query) = index::match_query(query) else {
+            return Ok(Vec::new());
+        };
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT path, snippet(content_index, 2, '[', ']', '…', 12) FROM content_index
+                     WHERE content_index MATCH ?1
+                     ORDER BY rank LIMIT ?2"
+                )?;
+                let matches = stmt
+                    .query_map(params![query, limit as i64], |row| {
+                        Ok(ContentMatch {
+                            path: row.get(0)?,
+                            snippet: row.get(1)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(matches)
+            })
+            .await
+    }
+
+    /// Everything logged about one file, oldest first: its uploads, downloads,
+    /// conflicts and failures across every sync run.
+    pub async fn get_file_history(&self, path: &str) -> Result<Vec<SyncLogEntry>> {
+        let path = path.to_string();
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT sync_log.timestamp, sync_log.action, sync_log.file_path, sync_log.status, sync_log.error, files.mime_type, sync_log.run_id
+                     FROM sync_log LEFT JOIN files ON files.path = sync_log.file_path
+                     WHERE sync_log.file_path = ?1
+                     ORDER BY sync_log.timestamp, sync_log.id"
+                )?;
+
+                let entries = stmt.query_map(params![path], |row| {
+                    Ok(SyncLogEntry {
+                        timestamp: row.get(0)?,
+                        action: row.get(1)?,
+                        file_path: row.get(2)?,
+                        status: row.get(3)?,
+                        error: row.get(4)?,
+                        mime_type: row.get(5)?,
+                        run_id: row.get(6)?,
+                    })
+                })?;
+
+                let mut result = Vec::new();
+                for entry in entries {
+                    result.push(entry?);
+                }
+
+                Ok(result)
+            })
+            .await
+    }
+
+    /// The first `limit` tracked files, in path order, whose path or content
+    /// type fuzzily matches `filter`.
+    pub async fn get_synced_files(&self, limit: usize, filter: &str) -> Result<Vec<SyncedFile>> {
+        let pattern = fuzzy_like_pattern(filter);
+        self.store
+            .call(move |db| {
+                let mut stmt = db.prepare(
+                    "SELECT files.path, files.size, files.mime_type, files.last_synced, file_states.state
+                     FROM files LEFT JOIN file_states ON file_states.path = files.path
+                     WHERE ?2 = '%' OR files.path LIKE ?2 ESCAPE '\\' OR files.mime_type LIKE ?2 ESCAPE '\\'
+                     ORDER BY files.path LIMIT ?1"
+                )?;
+
+                let files = stmt.query_map(params![limit, pattern], |row| {
+                    let state: Option<String> = row.get(4)?;
+                    Ok(SyncedFile {
+                        path: row.get(0)?,
+                        state: state.as_deref().and_then(FileState::parse).unwrap_or(FileState::InSync),
+                        size: row.get(1)?,
+                        mime_type: row.get(2)?,
+                        last_synced: row.get(3)?,
+                    })
+                })?;
+
+                let mut result = Vec::new();
+                for file in files {
+                    result.push(file?);
                 }
-            }
-        }
 
-        info!("=== SYNC PROCESS COMPLETE ===");
-        Ok(())
+                Ok(result)
+            })
+            .await
     }
+}
 
-    async fn scan_local_files(&self) -> Result<HashMap<String, FileRecord>> {
-        let mut files = HashMap::new();
-        
-        if !self.config.sync_folder.exists() {
-            info!("Creating sync folder: {}", self.config.sync_folder.display());
-            fs::create_dir_all(&self.config.sync_folder).await?;
-            return Ok(files);
+/// Turns a filter typed by the user into a `LIKE` pattern matching text that
+/// contains its characters in order, not necessarily adjacent, so "rptpdf"
+/// finds "report.pdf". Whitespace is ignored; an empty filter matches everything.
+fn fuzzy_like_pattern(filter: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in filter.chars().filter(|c| !c.is_whitespace()) {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
         }
+        pattern.push(c);
+        pattern.push('%');
+    }
+    pattern
+}
 
-        info!("Scanning local files in: {}", self.config.sync_folder.display());
-        
-        for entry in WalkDir::new(&self.config.sync_folder)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() {
-                let path = entry.path();
-                let relative_path = path.strip_prefix(&self.config.sync_folder)?;
-                let relative_path_str = relative_path.to_string_lossy().replace('\\', "/");
-
-                // Skip hidden files and system files
-                if relative_path_str.starts_with('.') {
-                    continue;
-                }
+/// A short ID for a sync run, unique enough to find the run in a user's logs.
+fn new_run_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{:08x}", (nanos as u32) ^ std::process::id().rotate_left(16))
+}
 
-                if let Ok(metadata) = entry.metadata() {
-                    let size = metadata.len();
-                    let modified = metadata
-                        .modified()
-                        .unwrap_or(SystemTime::UNIX_EPOCH)
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs();
+fn parse_iso_datetime(datetime_str: &str) -> Option<u64> {
+    chrono::DateTime::parse_from_rfc3339(datetime_str)
+        .ok()
+        .map(|datetime| datetime.timestamp().max(0) as u64)
+}
 
-                    let hash = self.calculate_file_hash(path).await.unwrap_or_else(|e| {
-                        warn!("Failed to calculate hash for {}: {}", path.display(), e);
-                        String::new()
-                    });
+/// Pairs tracked files that vanished locally with untracked local files of the same
+/// content, giving `(from, to, onedrive_id)` for each rename or move. Duplicated
+/// content is paired at most once per side, so nothing is moved twice.
+fn detect_local_moves(
+    local_files: &HashMap<String, FileRecord>,
+    remote_files: &HashMap<String, DriveItem>,
+    stored_files: &HashMap<String, FileRecord>,
+) -> Vec<(String, String, String)> {
+    let mut missing_by_hash: HashMap<(&str, u64), Vec<&FileRecord>> = HashMap::new();
+    for (path, stored_file) in stored_files {
+        if !local_files.contains_key(path)
+            && remote_files.contains_key(path)
+            && stored_file.onedrive_id.is_some()
+            && !stored_file.hash.is_empty()
+        {
+            missing_by_hash
+                .entry((stored_file.hash.as_str(), stored_file.size))
+                .or_default()
+                .push(stored_file);
+        }
+    }
 
-                    info!("Found local file: {} (size: {}, hash: {})", relative_path_str, size, &hash[..8]);
+    let mut new_files: Vec<&FileRecord> = local_files
+        .values()
+        .filter(|file| !stored_files.contains_key(&file.path) && !remote_files.contains_key(&file.path))
+        .collect();
+    new_files.sort_by(|a, b| a.path.cmp(&b.path));
 
-                    files.insert(relative_path_str.clone(), FileRecord {
-                        path: relative_path_str,
-                        hash,
-                        size,
-                        modified,
-                        onedrive_id: None,
-                        last_synced: 0,
-                    });
+    let mut moves = Vec::new();
+    for new_file in new_files {
+        if let Some(candidates) = missing_by_hash.get_mut(&(new_file.hash.as_str(), new_file.size)) {
+            if let Some(stored_file) = candidates.pop() {
+                if let Some(ref onedrive_id) = stored_file.onedrive_id {
+                    moves.push((stored_file.path.clone(), new_file.path.clone(), onedrive_id.clone()));
                 }
             }
         }
-
-        info!("Scanned {} local files", files.len());
-        Ok(files)
     }
 
-    async fn scan_remote_files(&self) -> Result<HashMap<String, DriveItem>> {
-        let mut files = HashMap::new();
-        
-        info!("Scanning remote OneDrive files...");
-        
-        match self.scan_remote_folder(&mut files, "/").await {
-            Ok(_) => {
-                info!("Scanned {} remote files", files.len());
-                Ok(files)
+    moves
+}
+
+/// Restores the status saved by `save_status_snapshot`, so the GUI and tray show
+/// the real last sync time straight after a restart.
+fn load_status_snapshot(db: &Connection) -> Result<SyncStatus> {
+    let mut status = SyncStatus::default();
+    let mut stmt = db.prepare("SELECT key, value FROM sync_state WHERE key LIKE 'status_%'")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+    for row in rows {
+        let (key, value) = row?;
+        match key.as_str() {
+            "status_last_sync" => {
+                status.last_sync = value
+                    .parse::<u64>()
+                    .ok()
+                    .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
             }
-            Err(e) => {
-                error!("Failed to scan remote files: {}", e);
-                // Return empty map instead of failing completely
-                Ok(HashMap::new())
+            "status_last_error" if !value.is_empty() => status.sync_errors.push(value),
+            "status_pending_actions" => status.pending_actions = value.parse().unwrap_or(0),
+            "status_run_id" if !value.is_empty() => status.run_id = Some(value),
+            "status_mass_change" if !value.is_empty() => status.uploads_paused_for_mass_change = Some(value),
+            "status_paused" => {
+                status.paused = value == "1";
+                if status.paused {
+                    status.current_operation = "Paused".to_string();
+                }
             }
+            _ => {}
         }
     }
 
-    fn scan_remote_folder<'a>(&'a self, files: &'a mut HashMap<String, DriveItem>, folder_path: &'a str) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
-        Box::pin(async move {
-            let items = self.api.list_items(folder_path).await?;
-            
-            for item in items {
-                let item_path = if folder_path == "/" {
-                    item.name.clone()
-                } else {
-                    format!("{}/{}", folder_path.trim_start_matches('/'), item.name)
-                };
-
-                if item.file.is_some() {
-                    files.insert(item_path, item);
-                } else if item.folder.is_some() {
-                    // Recursively scan subfolders
-                    self.scan_remote_folder(files, &format!("/{}", item_path)).await?;
-                }
-            }
+    Ok(status)
+}
 
-            Ok(())
+fn load_sync_issues(db: &Connection) -> Result<Vec<SyncIssue>> {
+    let mut stmt = db.prepare(
+        "SELECT path, IFNULL(detail, ''), updated FROM file_states WHERE state = 'quarantined' ORDER BY updated DESC",
+    )?;
+    let issues = stmt.query_map([], |row| {
+        Ok(SyncIssue {
+            path: row.get(0)?,
+            reason: row.get(1)?,
+            created: row.get(2)?,
         })
+    })?;
+    Ok(issues.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+fn load_file_state_counts(db: &Connection) -> Result<BTreeMap<FileState, u64>> {
+    let mut stmt = db.prepare("SELECT state, COUNT(*) FROM file_states GROUP BY state")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?;
+
+    let mut counts = BTreeMap::new();
+    for row in rows {
+        let (state, count) = row?;
+        if let Some(state) = FileState::parse(&state) {
+            counts.insert(state, count);
+        }
     }
+    Ok(counts)
+}
 
-    async fn get_stored_files(&self) -> Result<HashMap<String, FileRecord>> {
-        let db = self.db.lock().await;
-        let mut files = HashMap::new();
-        
-        let mut stmt = db.prepare(
-            "SELECT path, hash, size, modified, onedrive_id, last_synced FROM files"
-        )?;
-
-            let file_iter = stmt.query_map([], |row| {
-                Ok(FileRecord {
-                    path: row.get(0)?,
-                    hash: row.get(1)?,
-                    size: row.get(2)?,
-                    modified: row.get(3)?,
-                    onedrive_id: row.get(4)?,
-                    last_synced: row.get(5)?,
-                })
-            })?;
+/// Moves `path` to `state`, or forgets it when `None`, and returns the states
+/// it was and is now in. Quarantined files keep their state until the user
+/// dismisses the issue or the file is gone.
+fn update_file_state(
+    db: &Connection,
+    path: &str,
+    state: Option<FileState>,
+    now: u64,
+) -> Result<(Option<FileState>, Option<FileState>)> {
+    let read = |db: &Connection| -> Result<Option<FileState>> {
+        let state: Option<String> = db
+            .query_row("SELECT state FROM file_states WHERE path = ?1", params![path], |row| row.get(0))
+            .optional()?;
+        Ok(state.as_deref().and_then(FileState::parse))
+    };
 
-            for file in file_iter {
-                let file = file?;
-                files.insert(file.path.clone(), file);
-            }
+    let before = read(db)?;
+    match state {
+        Some(state) => {
+            db.execute(
+                "INSERT INTO file_states (path, state, detail, updated) VALUES (?1, ?2, NULL, ?3)
+                 ON CONFLICT(path) DO UPDATE SET state = excluded.state, detail = NULL, updated = excluded.updated
+                 WHERE file_states.state != 'quarantined'",
+                params![path, state.as_str(), now],
+            )?;
+        }
+        None => {
+            db.execute("DELETE FROM file_states WHERE path = ?1", params![path])?;
+        }
+    }
+    Ok((before, read(db)?))
+}
 
-            Ok(files)
+/// `(folder_name, parent_path)` of the OneDrive folder at `path`, as
+/// `RemoteStore::create_folder` takes them.
+fn remote_name_and_parent(path: &str) -> (String, String) {
+    match path.rsplit_once('/') {
+        Some((parent, name)) => (name.to_string(), format!("/{}", parent)),
+        None => (path.to_string(), "/".to_string()),
     }
+}
 
-    fn determine_sync_actions(
-        &self,
-        local_files: &HashMap<String, FileRecord>,
-        remote_files: &HashMap<String, DriveItem>,
-        stored_files: &HashMap<String, FileRecord>,
-    ) -> Result<Vec<SyncAction>> {
-        let mut actions = Vec::new();
+fn describe_action(action: &SyncAction) -> String {
+    match action {
+        SyncAction::Upload { local_path, .. } => format!("Uploading {}", local_path),
+        SyncAction::Download { local_path, .. } => format!("Downloading {}", local_path),
+        SyncAction::RemoveFromDatabase { path } => format!("Cleaning up {}", path),
+        SyncAction::CreateRemoteFolder { path } => format!("Creating remote folder {}", path),
+        SyncAction::CreateLocalFolder { path, .. } => format!("Creating local folder {}", path),
+        SyncAction::RemoveFolderFromDatabase { path } => format!("Cleaning up folder {}", path),
+        SyncAction::ResolveConflict { local_path, .. } => format!("Resolving conflict {}", local_path),
+        SyncAction::MoveRemote { from, to, .. } => format!("Moving {} to {}", from, to),
+        SyncAction::MoveLocal { from, to } => format!("Moving local {} to {}", from, to),
+        SyncAction::MoveLocalFolder { from, to } => format!("Moving local folder {} to {}", from, to),
+        SyncAction::DeleteLocal { path } => format!("Removing {} (deleted on OneDrive)", path),
+        SyncAction::ArchiveLocal { path } => format!("Freeing up {} (kept on OneDrive)", path),
+        SyncAction::CreatePlaceholder { local_path, .. } => format!("Adding online-only placeholder for {}", local_path),
+    }
+}
 
-        info!("Determining sync actions...");
-        info!("Local files: {}, Remote files: {}, Stored files: {}", 
-              local_files.len(), remote_files.len(), stored_files.len());
+/// Finds tracked folders whose OneDrive item now lives at a different path, giving
+/// `(from, to)` for the outermost folder of each remote rename or move. A folder
+/// holding local edits is left to the per-file handling so nothing is overwritten.
+fn detect_remote_folder_moves(
+    local_folders: &HashSet<String>,
+    remote_folders: &HashMap<String, DriveItem>,
+    stored_folders: &HashMap<String, FolderRecord>,
+    local_files: &HashMap<String, FileRecord>,
+    stored_files: &HashMap<String, FileRecord>,
+) -> Vec<(String, String)> {
+    let remote_paths_by_id: HashMap<&str, &String> = remote_folders
+        .iter()
+        .map(|(path, item)| (item.id.as_str(), path))
+        .collect();
 
-        // Check for uploads (local files not in remote or modified locally)
-        for (path, local_file) in local_files {
-            info!("Checking local file: {}", path);
-            
-            if let Some(stored_file) = stored_files.get(path) {
-                if local_file.hash != stored_file.hash {
-                    // File modified locally
-                    info!("Local file modified: {} (hash changed)", path);
-                    actions.push(SyncAction::Upload {
-                        local_path: path.clone(),
-                        remote_path: path.clone(),
-                    });
-                } else {
-                    info!("Local file unchanged: {}", path);
-                }
-            } else if !remote_files.contains_key(path) {
-                // New local file
-                info!("New local file found: {}", path);
-                actions.push(SyncAction::Upload {
-                    local_path: path.clone(),
-                    remote_path: path.clone(),
-                });
-            } else {
-                info!("Local file exists remotely but not in database: {}", path);
-                // File exists remotely but not in our database - treat as already synced
-                // This can happen if database was cleared
-            }
-        }
+    let mut candidates: Vec<(&String, &String)> = stored_folders
+        .iter()
+        .filter(|(path, _)| local_folders.contains(*path) && !remote_folders.contains_key(*path))
+        .filter_map(|(path, folder)| {
+            let new_path = remote_paths_by_id.get(folder.onedrive_id.as_deref()?)?;
+            (!local_folders.contains(*new_path)).then_some((path, *new_path))
+        })
+        .collect();
+    // Parents first, so a moved folder's subfolders ride along with it
+    candidates.sort();
 
-        // Check for downloads (remote files not in local or modified remotely)
-        for (path, remote_file) in remote_files {
-            info!("Checking remote file: {}", path);
-            
-            if !local_files.contains_key(path) {
-                // New remote file
-                info!("New remote file found: {}", path);
-                actions.push(SyncAction::Download {
-                    remote_item: remote_file.clone(),
-                    local_path: path.clone(),
-                });
-            } else if let Some(stored_file) = stored_files.get(path) {
-                // Check if remote file is newer (simplified comparison)
-                let remote_modified = parse_iso_datetime(&remote_file.last_modified).unwrap_or(0);
-                if remote_modified > stored_file.last_synced {
-                    info!("Remote file newer than local: {}", path);
-                    actions.push(SyncAction::Download {
-                        remote_item: remote_file.clone(),
-                        local_path: path.clone(),
-                    });
-                } else {
-                    info!("Remote file up to date: {}", path);
-                }
-            } else {
-                info!("Remote file exists locally but not in database: {}", path);
-            }
+    let mut moves: Vec<(String, String)> = Vec::new();
+    for (from, to) in candidates {
+        if moves.iter().any(|(moved, _)| is_same_or_inside(from, moved)) {
+            continue;
         }
 
-        // Check for deletions (files in stored but not in local or remote)
-        for (path, _) in stored_files {
-            if !local_files.contains_key(path) && !remote_files.contains_key(path) {
-                info!("File deleted both locally and remotely: {}", path);
-                actions.push(SyncAction::RemoveFromDatabase {
-                    path: path.clone(),
-                });
-            }
+        let has_local_edits = local_files.iter().any(|(path, local_file)| {
+            is_same_or_inside(path, from)
+                && stored_files.get(path).is_none_or(|stored_file| stored_file.hash != local_file.hash)
+        });
+        if has_local_edits {
+            info!("Not moving folder {} as a whole: it contains local changes", from);
+            continue;
         }
 
-        info!("Determined {} sync actions", actions.len());
-        for action in &actions {
-            match action {
-                SyncAction::Upload { local_path, .. } => info!("Action: Upload {}", local_path),
-                SyncAction::Download { local_path, .. } => info!("Action: Download {}", local_path),
-                SyncAction::RemoveFromDatabase { path } => info!("Action: Cleanup {}", path),
-            }
-        }
+        moves.push((from.clone(), to.clone()));
+    }
 
-        Ok(actions)
+    moves
+}
+
+/// Groups of two or more distinct paths that are equal ignoring letter case,
+/// each sorted. Only the colliding paths themselves are listed, not their
+/// contents.
+fn find_case_conflicts<'a>(paths: impl Iterator<Item = &'a String>) -> Vec<Vec<String>> {
+    let mut by_lowercase: BTreeMap<String, BTreeSet<&String>> = BTreeMap::new();
+    for path in paths {
+        by_lowercase.entry(path.to_lowercase()).or_default().insert(path);
     }
+    by_lowercase
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|paths| paths.into_iter().cloned().collect())
+        .collect()
+}
 
-    async fn execute_sync_action(&mut self, action: SyncAction) -> Result<()> {
-        match action {
-            SyncAction::Upload { local_path, remote_path } => {
-                let local_full_path = self.config.sync_folder.join(&local_path);
-                
-                info!("Uploading: {}", local_path);
-                let remote_item = self.api.upload_file(&local_full_path, &remote_path).await?;
-                
-                // Update database
-                let hash = self.calculate_file_hash(&local_full_path).await?;
-                let metadata = fs::metadata(&local_full_path).await?;
-                let size = metadata.len();
-                let modified = metadata
-                    .modified()?
-                    .duration_since(SystemTime::UNIX_EPOCH)?
-                    .as_secs();
-                let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+/// Where the placeholder of the online-only file at `path` is written.
+pub fn placeholder_path(path: &str) -> String {
+    format!("{}{}", path, PLACEHOLDER_SUFFIX)
+}
 
-                let db = self.db.lock().await;
-                db.execute(
-                    "INSERT OR REPLACE INTO files (path, hash, size, modified, onedrive_id, last_synced) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                    params![local_path, hash, size, modified, remote_item.id, now],
-                )?;
-                drop(db);
+/// Whether `path` is `folder` itself or somewhere beneath it.
+fn is_same_or_inside(path: &str, folder: &str) -> bool {
+    path == folder || path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/'))
+}
 
-                self.update_status(|status| {
-                    status.files_uploaded += 1;
-                }).await;
-                self.log_sync_event("upload", &local_path, "success", None).await?;
-            }
+/// Whether a file move is just a consequence of its folder being moved.
+fn moved_with_folder(from: &str, to: &str, folder_from: &str, folder_to: &str) -> bool {
+    match (from.strip_prefix(folder_from), to.strip_prefix(folder_to)) {
+        (Some(from_rest), Some(to_rest)) => from_rest.starts_with('/') && from_rest == to_rest,
+        _ => false,
+    }
+}
 
-            SyncAction::Download { remote_item, local_path } => {
-                let local_full_path = self.config.sync_folder.join(&local_path);
-                
-                // Create parent directories if needed
-                if let Some(parent) = local_full_path.parent() {
-                    fs::create_dir_all(parent).await?;
-                }
-                
-                info!("Downloading: {}", local_path);
-                self.api.download_file(&remote_item, &local_full_path).await?;
-                
-                // Update database
-                let hash = self.calculate_file_hash(&local_full_path).await?;
-                let size = remote_item.size.unwrap_or(0);
-                let modified = parse_iso_datetime(&remote_item.last_modified).unwrap_or(0);
-                let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+/// Finds tracked files whose OneDrive item now lives at a different path, giving
+/// `(from, to)` for each remote rename or move that can be replayed locally.
+fn detect_remote_moves(
+    local_files: &HashMap<String, FileRecord>,
+    remote_files: &HashMap<String, DriveItem>,
+    stored_files: &HashMap<String, FileRecord>,
+) -> Vec<(String, String)> {
+    let remote_paths_by_id: HashMap<&str, &String> = remote_files
+        .iter()
+        .map(|(path, item)| (item.id.as_str(), path))
+        .collect();
 
-                let db = self.db.lock().await;
-                db.execute(
-                    "INSERT OR REPLACE INTO files (path, hash, size, modified, onedrive_id, last_synced) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                    params![local_path, hash, size, modified, remote_item.id, now],
-                )?;
-                drop(db);
+    let mut moves = Vec::new();
+    for (path, stored_file) in stored_files {
+        if remote_files.contains_key(path) || !local_files.contains_key(path) {
+            continue;
+        }
 
-                self.update_status(|status| {
-                    status.files_downloaded += 1;
-                }).await;
-                self.log_sync_event("download", &local_path, "success", None).await?;
-            }
+        let Some(onedrive_id) = stored_file.onedrive_id.as_deref() else {
+            continue;
+        };
 
-            SyncAction::RemoveFromDatabase { path } => {
-                let db = self.db.lock().await;
-                db.execute("DELETE FROM files WHERE path = ?1", params![path])?;
-                drop(db);
-                
-                self.update_status(|status| {
-                    status.files_deleted += 1;
-                }).await;
-                self.log_sync_event("remove_from_db", &path, "success", None).await?;
+        if let Some(new_path) = remote_paths_by_id.get(onedrive_id) {
+            // Only move when the target is free and the local copy is not mid-edit
+            if !local_files.contains_key(*new_path)
+                && !stored_files.contains_key(*new_path)
+                && local_files[path].hash == stored_file.hash
+            {
+                moves.push((path.clone(), (*new_path).clone()));
             }
         }
+    }
 
-        Ok(())
+    moves.sort();
+    moves
+}
+
+/// Why `path` is left out of the sync by folder name or another sync client's
+/// marker file, if it is. Touches the disk, so keep it off the async runtime.
+fn excluded_folder_reason(config: &Config, path: &Path) -> Option<String> {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    if config.excluded_folder_names.iter().any(|excluded| *excluded == name) {
+        return Some("excluded folder".to_string());
     }
 
-    async fn calculate_file_hash(&self, path: &Path) -> Result<String> {
-        let content = fs::read(path).await?;
-        let mut hasher = Sha256::new();
-        hasher.update(&content);
-        Ok(hex::encode(hasher.finalize()))
+    if config.auto_exclude_sync_folders {
+        if let Some(marker) = FOREIGN_SYNC_MARKERS.iter().find(|marker| path.join(marker).exists()) {
+            return Some(format!("folder managed by another sync client ({} found)", marker));
+        }
     }
 
-    async fn log_sync_event(&self, action: &str, file_path: &str, status: &str, error: Option<&str>) -> Result<()> {
-        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
-        
-        let db = self.db.lock().await;
-        db.execute(
-            "INSERT INTO sync_log (timestamp, action, file_path, status, error) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![now, action, file_path, status, error],
-        )?;
-        drop(db);
+    None
+}
 
-        Ok(())
+/// `path` and each folder above it, nearest to the root first.
+fn path_and_ancestors(path: &str) -> impl Iterator<Item = &str> {
+    path.match_indices('/').map(move |(end, _)| &path[..end]).chain(std::iter::once(path))
+}
+
+/// Whether a sync failed because the user has to sign in again.
+fn is_auth_error(error: &anyhow::Error) -> bool {
+    error.is::<NotAuthenticated>() || error.downcast_ref::<GraphError>().is_some_and(|graph_error| graph_error.status == 401)
+}
+
+/// Whether OneDrive rejected a delta link and wants the drive listed again.
+fn is_resync_required(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<GraphError>().is_some_and(|graph_error| graph_error.status == 410)
+}
+
+/// Whether OneDrive refused a change because the drive is full.
+fn is_quota_error(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<GraphError>()
+        .is_some_and(|graph_error| graph_error.status == 507 || graph_error.code == "quotaLimitReached")
+}
+
+/// Whether OneDrive refused a change because this user may not make it, as
+/// in a folder shared with view access only. Other refusals, such as a
+/// blocked file type, fail like any other error.
+fn is_access_denied(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<GraphError>()
+        .is_some_and(|graph_error| graph_error.code == "accessDenied")
+}
+
+/// The paths on OneDrive a queued action is checked against before it resumes.
+fn rechecked_remote_paths(action: &SyncAction) -> Vec<&str> {
+    match action {
+        SyncAction::Upload { local_path, .. }
+        | SyncAction::Download { local_path, .. }
+        | SyncAction::CreatePlaceholder { local_path, .. }
+        | SyncAction::ResolveConflict { local_path, .. } => vec![local_path],
+        SyncAction::DeleteLocal { path }
+        | SyncAction::ArchiveLocal { path }
+        | SyncAction::RemoveFromDatabase { path }
+        | SyncAction::RemoveFolderFromDatabase { path }
+        | SyncAction::CreateLocalFolder { path, .. } => vec![path],
+        SyncAction::MoveRemote { from, to, .. } => vec![from, to],
+        SyncAction::CreateRemoteFolder { .. } | SyncAction::MoveLocal { .. } | SyncAction::MoveLocalFolder { .. } => Vec::new(),
     }
+}
 
-    pub async fn get_sync_history(&self, limit: usize) -> Result<Vec<SyncLogEntry>> {
-        let db = self.db.lock().await;
-        let mut stmt = db.prepare(
-            "SELECT timestamp, action, file_path, status, error FROM sync_log ORDER BY timestamp DESC LIMIT ?1"
-        )?;
+/// The path an action changes on OneDrive, relative to the sync folder.
+fn changed_remote_path(action: &SyncAction) -> Option<&str> {
+    match action {
+        SyncAction::Upload { local_path, .. } | SyncAction::ResolveConflict { local_path, .. } => Some(local_path),
+        SyncAction::CreateRemoteFolder { path } => Some(path),
+        SyncAction::MoveRemote { to, .. } => Some(to),
+        _ => None,
+    }
+}
 
-        let entries = stmt.query_map(params![limit], |row| {
-            Ok(SyncLogEntry {
-                timestamp: row.get(0)?,
-                action: row.get(1)?,
-                file_path: row.get(2)?,
-                status: row.get(3)?,
-                error: row.get(4)?,
-            })
-        })?;
+/// Whether an action stopped because the sync was cancelled.
+fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.is::<Cancelled>()
+}
+
+/// The SHA-256 Graph reports for a file, when the drive provides one.
+fn remote_sha256(item: &DriveItem) -> Option<&str> {
+    item.file.as_ref()?.hashes.as_ref()?.sha256_hash.as_deref()
+}
 
-        let mut result = Vec::new();
-        for entry in entries {
-            result.push(entry?);
+/// Orders downloads newest first by their OneDrive modified time
+/// (`Config::download_newest_first`), so the files someone is working on now
+/// arrive before old archives. Other actions keep their place ahead of them.
+fn schedule_newest_first(actions: &mut [SyncAction]) {
+    actions.sort_by_key(|action| match action {
+        SyncAction::Download { remote_item, .. } => {
+            (1, std::cmp::Reverse(parse_iso_datetime(&remote_item.last_modified).unwrap_or(0)))
         }
+        _ => (0, std::cmp::Reverse(0)),
+    });
+}
 
-        Ok(result)
+/// Whether the remote content differs from what was last synced. Compares
+/// QuickXorHashes when both are known, since timestamps also move on
+/// metadata-only changes; older records fall back to the modified time.
+fn is_remote_newer(remote_file: &DriveItem, stored_file: &FileRecord) -> bool {
+    match (remote_file.quick_xor_hash(), stored_file.quick_xor_hash.as_deref()) {
+        (Some(remote_hash), Some(stored_hash)) => remote_hash != stored_hash,
+        _ => {
+            // Remote times come from the server's clock, last_synced from ours
+            let remote_modified = parse_iso_datetime(&remote_file.last_modified).unwrap_or(0);
+            clock::to_local_secs(remote_modified) > stored_file.last_synced
+        }
     }
 }
 
-fn parse_iso_datetime(_datetime_str: &str) -> Option<u64> {
-    // Simplified ISO datetime parsing
-    // In a real implementation, use a proper datetime parsing library
-    // For now, return current timestamp as placeholder
-    Some(
-        SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs()
-    )
+/// Builds `dir/name (conflicted copy 2024-05-01).ext`, numbering further copies
+/// made on the same day.
+fn conflict_copy_path(path: &str, date: &str, attempt: u32) -> String {
+    let (dir, file_name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, path),
+    };
+
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+        _ => (file_name, None),
+    };
+
+    let suffix = if attempt > 1 {
+        format!(" (conflicted copy {} {})", date, attempt)
+    } else {
+        format!(" (conflicted copy {})", date)
+    };
+
+    let copy_name = match extension {
+        Some(ext) => format!("{}{}.{}", stem, suffix, ext),
+        None => format!("{}{}", stem, suffix),
+    };
+
+    match dir {
+        Some(dir) => format!("{}/{}", dir, copy_name),
+        None => copy_name,
+    }
 }
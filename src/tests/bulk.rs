@@ -0,0 +1,73 @@
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use super::{content, TestEnv};
+use crate::api::DriveItem;
+use crate::bulk::{self, BulkOperation, BulkProgress};
+
+#[tokio::test]
+async fn bulk_operations_download_move_and_delete_browser_selections() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Photos/2024/beach.jpg", &content("beach", 0, 1024));
+    env.graph.put_file("Photos/cat.jpg", &content("cat", 0, 1024));
+    env.graph.put_file("Photos/dog.jpg", &content("dog", 0, 1024));
+    env.graph.put_file("Archive/old.txt", &content("old", 0, 1024));
+    let api = env.api();
+    let selected = |names: &[&str], items: &[DriveItem]| -> Vec<(String, DriveItem)> {
+        items
+            .iter()
+            .filter(|item| names.contains(&item.name.as_str()))
+            .map(|item| (format!("Photos/{}", item.name), item.clone()))
+            .collect()
+    };
+
+    let destination = env.local_path("Downloads");
+    let items = api.list_items("/Photos").await.unwrap();
+    let progress = Mutex::new(BulkProgress::default());
+    let download = BulkOperation::Download { destination: destination.clone() };
+    bulk::run(&api, &download, &selected(&["2024", "cat.jpg"], &items), &progress, &CancellationToken::new()).await;
+    let progress = progress.into_inner().unwrap();
+    assert!(progress.finished && progress.errors.is_empty(), "{:?}", progress);
+    assert_eq!(progress.done, 2);
+    assert_eq!(std::fs::read(destination.join("2024/beach.jpg")).unwrap(), content("beach", 0, 1024));
+    assert_eq!(std::fs::read(destination.join("cat.jpg")).unwrap(), content("cat", 0, 1024));
+    assert!(!destination.join("dog.jpg").exists());
+
+    let progress = Mutex::new(BulkProgress::default());
+    let move_to_archive = BulkOperation::Move { folder: "Archive".to_string() };
+    bulk::run(&api, &move_to_archive, &selected(&["2024", "dog.jpg"], &items), &progress, &CancellationToken::new()).await;
+    assert!(progress.into_inner().unwrap().errors.is_empty());
+
+    let items = api.list_items("/Photos").await.unwrap();
+    let progress = Mutex::new(BulkProgress::default());
+    bulk::run(&api, &BulkOperation::Delete, &selected(&["cat.jpg"], &items), &progress, &CancellationToken::new()).await;
+    assert!(progress.into_inner().unwrap().errors.is_empty());
+
+    let remote: Vec<String> = env.graph.files().into_keys().collect();
+    assert_eq!(remote, vec!["Archive/2024/beach.jpg", "Archive/dog.jpg", "Archive/old.txt"]);
+}
+
+#[tokio::test]
+async fn copies_are_made_on_onedrive_without_downloading() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Photos/2024/beach.jpg", &content("beach", 0, 1024));
+    env.graph.put_file("Archive/old.txt", &content("old", 0, 1024));
+    let api = env.api();
+
+    let folder = api.get_item("/Photos/2024").await.unwrap();
+    let copy = api.copy_item(&folder.id, "/", "2024 copy", &CancellationToken::new()).await.unwrap();
+    assert_eq!(copy.name, "2024 copy");
+    assert_ne!(copy.id, folder.id);
+
+    let items: Vec<(String, DriveItem)> = vec![("Photos/2024".to_string(), folder)];
+    let progress = Mutex::new(BulkProgress::default());
+    let copy_to_archive = BulkOperation::Copy { folder: "Archive".to_string() };
+    bulk::run(&api, &copy_to_archive, &items, &progress, &CancellationToken::new()).await;
+    assert!(progress.into_inner().unwrap().errors.is_empty());
+
+    let remote: Vec<String> = env.graph.files().into_keys().collect();
+    assert_eq!(
+        remote,
+        vec!["2024 copy/beach.jpg", "Archive/2024/beach.jpg", "Archive/old.txt", "Photos/2024/beach.jpg"]
+    );
+}
@@ -0,0 +1,42 @@
+use tempfile::TempDir;
+
+use crate::config::Config;
+
+#[test]
+fn saving_keeps_the_administrators_settings_out_of_the_user_config() {
+    let dir = TempDir::new().expect("temporary directory");
+    let config_dir = dir.path().join("config");
+    let defaults = Config {
+        config_file: config_dir.join("config.toml"),
+        token_file: config_dir.join("tokens.json"),
+        db_file: config_dir.join("sync.db"),
+        config_dir,
+        ..Config::default()
+    };
+
+    let user = Config {
+        sync_interval_minutes: 10,
+        trash_remote_deletions: false,
+        ..defaults.clone()
+    };
+    user.save().expect("save user config");
+    let user_file = std::fs::read_to_string(&user.config_file).unwrap();
+
+    let system_file = dir.path().join("system.toml");
+    std::fs::write(
+        &system_file,
+        "sync_interval_minutes = 60\nmax_upload_size_mb = 100\nlocked_settings = [\"sync_interval_minutes\"]\n",
+    )
+    .unwrap();
+    let mut config = Config::load(defaults, &system_file).expect("load config");
+    assert_eq!(config.sync_interval_minutes, 60);
+    assert_eq!(config.max_upload_size_mb, 100);
+    assert!(!config.trash_remote_deletions);
+
+    config.set_minimize_to_tray(false).expect("change a setting");
+
+    let saved: toml::Table = toml::from_str(&std::fs::read_to_string(&config.config_file).unwrap()).unwrap();
+    let mut expected: toml::Table = toml::from_str(&user_file).unwrap();
+    expected.insert("minimize_to_tray".to_string(), toml::Value::Boolean(false));
+    assert_eq!(saved, expected);
+}
@@ -0,0 +1,786 @@
+use chrono::TimeZone;
+use std::sync::Arc;
+
+use super::{content, TestEnv};
+use crate::api::{MAX_FILE_SIZE, PARTIAL_DOWNLOAD_SUFFIX};
+use crate::format;
+use crate::sync::SkipKind;
+
+const SMALL_FILE: usize = 2 * 1024;
+
+/// Syncs `count` files that start out on OneDrive and returns their contents.
+async fn synced_drive(env: &TestEnv, count: usize) -> Vec<(String, Vec<u8>)> {
+    let files: Vec<(String, Vec<u8>)> = (0..count)
+        .map(|index| (format!("Documents/file-{:02}.txt", index), content("original", index, SMALL_FILE)))
+        .collect();
+    for (path, bytes) in &files {
+        env.graph.put_file(path, bytes);
+    }
+
+    env.manager().sync().await.expect("initial sync");
+    assert_eq!(env.local_files(), env.graph.files());
+    files
+}
+
+#[tokio::test]
+async fn mass_remote_deletion_is_refused() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 12).await;
+
+    env.graph.delete("Documents");
+    let result = env.manager().sync().await;
+
+    assert!(result.is_err(), "a sync deleting every file must be refused");
+    for (path, bytes) in &files {
+        assert_eq!(std::fs::read(env.local_path(path)).ok().as_ref(), Some(bytes), "{} was lost", path);
+    }
+}
+
+#[tokio::test]
+async fn failed_remote_listing_deletes_nothing() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 12).await;
+
+    env.graph.fail_listings(true);
+    let _ = env.manager().sync().await;
+    env.graph.fail_listings(false);
+
+    for (path, bytes) in &files {
+        assert_eq!(std::fs::read(env.local_path(path)).ok().as_ref(), Some(bytes), "{} was lost", path);
+    }
+    assert_eq!(env.graph.files().len(), files.len());
+}
+
+#[tokio::test]
+async fn emptying_a_small_drive_is_refused() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 4).await;
+
+    env.graph.delete("Documents");
+    assert!(env.manager().sync().await.is_err(), "a sync deleting every file must be refused");
+
+    for (path, bytes) in &files {
+        assert_eq!(std::fs::read(env.local_path(path)).ok().as_ref(), Some(bytes), "{} was lost", path);
+    }
+}
+
+#[tokio::test]
+async fn small_remote_deletion_still_applies() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 12).await;
+
+    env.graph.delete(&files[0].0);
+    env.manager().sync().await.expect("sync");
+
+    assert!(!env.local_path(&files[0].0).exists());
+    for (path, bytes) in &files[1..] {
+        assert_eq!(std::fs::read(env.local_path(path)).ok().as_ref(), Some(bytes), "{} was lost", path);
+    }
+}
+
+#[tokio::test]
+async fn unmounted_sync_folder_leaves_onedrive_untouched() {
+    let env = TestEnv::new().await;
+    synced_drive(&env, 12).await;
+    let remote_before = env.graph.files();
+
+    // An unmounted drive leaves an empty mount point behind
+    std::fs::remove_dir_all(&env.config.sync_folder).unwrap();
+    std::fs::create_dir(&env.config.sync_folder).unwrap();
+    env.manager().sync().await.expect("sync");
+
+    assert_eq!(env.graph.files(), remote_before);
+    assert_eq!(env.local_files(), remote_before);
+}
+
+#[tokio::test]
+async fn missing_sync_folder_leaves_onedrive_untouched() {
+    let env = TestEnv::new().await;
+    synced_drive(&env, 12).await;
+    let remote_before = env.graph.files();
+
+    std::fs::remove_dir_all(&env.config.sync_folder).unwrap();
+    env.manager().sync().await.expect("sync");
+
+    assert_eq!(env.graph.files(), remote_before);
+    assert_eq!(env.local_files(), remote_before);
+}
+
+#[tokio::test]
+async fn filtered_file_is_never_overwritten() {
+    let env = TestEnv::with_config(|config| {
+        config.excluded_extensions = vec!["vdi".to_string()];
+        config.max_upload_size_mb = 1;
+    })
+    .await;
+    let disk = content("local disk", 0, SMALL_FILE);
+    let video = content("local video", 0, 2 * 1024 * 1024);
+    env.write_local("VMs/ubuntu.vdi", &disk);
+    env.write_local("Videos/raw.mp4", &video);
+    env.graph.put_file("VMs/ubuntu.vdi", &content("remote disk", 0, SMALL_FILE));
+    env.graph.put_file("Videos/raw.mp4", &content("remote video", 0, SMALL_FILE));
+    let remote_before = env.graph.files();
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert_eq!(std::fs::read(env.local_path("VMs/ubuntu.vdi")).unwrap(), disk);
+    assert_eq!(std::fs::read(env.local_path("Videos/raw.mp4")).unwrap(), video);
+    assert_eq!(env.graph.files(), remote_before);
+    let mut skipped: Vec<String> = manager.get_status().await.skipped_files.into_iter().map(|file| file.path).collect();
+    skipped.sort();
+    assert_eq!(skipped, ["VMs/ubuntu.vdi", "Videos/raw.mp4"]);
+}
+
+#[tokio::test]
+async fn excluded_paths_are_listed_as_skipped() {
+    let env = TestEnv::with_config(|config| {
+        config.exclude_patterns = vec!["*.iso".to_string()];
+        config.max_upload_size_mb = 1;
+    })
+    .await;
+    env.write_local("Downloads/ubuntu.iso", &content("iso", 0, SMALL_FILE));
+    env.write_local("Videos/raw.mp4", &content("video", 0, 2 * 1024 * 1024));
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let skipped: Vec<(SkipKind, String)> = manager
+        .get_status()
+        .await
+        .skipped_files
+        .into_iter()
+        .map(|file| (file.kind, file.path))
+        .collect();
+    assert_eq!(
+        skipped,
+        [
+            (SkipKind::Excluded, "Downloads/ubuntu.iso".to_string()),
+            (SkipKind::Filtered, "Videos/raw.mp4".to_string()),
+        ]
+    );
+    assert_eq!(env.graph.files().keys().collect::<Vec<_>>(), ["Documents/notes.txt"]);
+}
+
+#[tokio::test]
+async fn conflict_storm_keeps_both_versions() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 20).await;
+
+    let mut expected = Vec::new();
+    for (index, (path, _)) in files.iter().enumerate() {
+        let local = content("local edit", index, SMALL_FILE + 10);
+        let remote = content("remote edit", index, SMALL_FILE + 20);
+        env.write_local(path, &local);
+        env.graph.put_file(path, &remote);
+        expected.push((path.clone(), local, remote));
+    }
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let local_files = env.local_files();
+    let remote_files = env.graph.files();
+    for (path, local, remote) in &expected {
+        assert_eq!(local_files.get(path), Some(remote), "{} lost the OneDrive edit", path);
+        assert!(
+            local_files.values().any(|bytes| bytes == local),
+            "the local edit of {} was lost",
+            path
+        );
+    }
+    assert_eq!(local_files, remote_files);
+
+    // Both sides agree, so nothing is left to do
+    let pending = manager.preview_sync().await.expect("preview");
+    assert!(pending.is_empty(), "sync did not settle: {:?}", pending);
+}
+
+#[tokio::test]
+async fn offline_edits_also_made_on_onedrive_keep_both_versions() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 2).await;
+
+    // Edited here while the client was not running, and on OneDrive meanwhile
+    let local = content("offline edit", 0, SMALL_FILE + 10);
+    let remote = content("remote edit", 0, SMALL_FILE + 20);
+    env.write_local(&files[0].0, &local);
+    env.graph.put_file(&files[0].0, &remote);
+    let offline = content("offline edit", 1, SMALL_FILE + 10);
+    env.write_local(&files[1].0, &offline);
+
+    env.manager().recover_offline_changes().await.expect("recovery");
+
+    assert_eq!(env.graph.file(&files[1].0), Some(offline));
+    assert!(env.graph.files().values().any(|bytes| *bytes == remote), "the OneDrive edit was overwritten");
+    assert!(env.local_files().values().any(|bytes| *bytes == local), "the offline edit was lost");
+}
+
+#[tokio::test]
+async fn interrupted_downloads_resume_without_corruption() {
+    let env = TestEnv::new().await;
+    for index in 0..6 {
+        env.graph.put_file(&format!("Photos/photo-{}.jpg", index), &content("photo", index, 256 * 1024));
+    }
+
+    env.graph.fail_downloads(3);
+    let _ = env.manager().sync().await;
+
+    // The client crashed and restarted with partial downloads on disk
+    env.manager().sync().await.expect("sync after restart");
+
+    let local_files = env.local_files();
+    assert!(local_files.keys().all(|path| !path.ends_with(PARTIAL_DOWNLOAD_SUFFIX)));
+    assert_eq!(local_files, env.graph.files());
+}
+
+#[tokio::test]
+async fn downloads_without_a_link_fetch_the_content_once() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Videos/clip.mp4", &content("clip", 0, 256 * 1024));
+    let mut item = env.api().get_item("/Videos/clip.mp4").await.expect("item");
+    // As when the link from a listing has expired
+    item.download_url = None;
+
+    let local_path = env.local_path("clip.mp4");
+    env.api().download_file(&item, &local_path).await.expect("download");
+
+    assert_eq!(std::fs::read(&local_path).unwrap(), content("clip", 0, 256 * 1024));
+    assert_eq!(env.graph.downloads_served(), 1);
+}
+
+#[tokio::test]
+async fn corrupt_downloads_never_replace_local_files() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 4096));
+    let mut manager = env.manager();
+    manager.sync().await.expect("initial sync");
+
+    env.graph.put_file("Documents/report.txt", &content("report", 1, 4096));
+    env.graph.put_file("Documents/new.txt", &content("new", 0, 4096));
+    env.graph.corrupt_downloads(2);
+    let _ = manager.sync().await;
+
+    // The old content stays, nothing half-written is left, and the next sync
+    // downloads both files again
+    let local_files = env.local_files();
+    assert_eq!(local_files["Documents/report.txt"], content("report", 0, 4096));
+    assert!(!local_files.contains_key("Documents/new.txt"));
+    assert!(local_files.keys().all(|path| !path.ends_with(PARTIAL_DOWNLOAD_SUFFIX)));
+
+    manager.sync().await.expect("sync after corrupt downloads");
+    assert_eq!(env.local_files(), env.graph.files());
+}
+
+#[tokio::test]
+async fn interrupted_upload_session_resumes() {
+    let env = TestEnv::new().await;
+    let video = content("video", 0, 6 * 1024 * 1024);
+    env.write_local("Videos/holiday.mp4", &video);
+
+    env.graph.fail_upload_chunk_after(4);
+    let _ = env.manager().sync().await;
+    assert_eq!(env.graph.file("Videos/holiday.mp4"), None);
+
+    env.manager().sync().await.expect("sync after restart");
+
+    assert_eq!(env.graph.file("Videos/holiday.mp4"), Some(video.clone()));
+    assert_eq!(std::fs::read(env.local_path("Videos/holiday.mp4")).unwrap(), video);
+    assert_eq!(env.graph.upload_sessions_created(), 1, "the upload restarted instead of resuming");
+}
+
+#[tokio::test]
+async fn oversized_file_is_held_instead_of_retried() {
+    let env = TestEnv::new().await;
+    env.write_local("Backups/disk.img", b"");
+    // Sparse, so it takes no real space
+    std::fs::File::options()
+        .write(true)
+        .open(env.local_path("Backups/disk.img"))
+        .unwrap()
+        .set_len(MAX_FILE_SIZE + 1)
+        .unwrap();
+
+    for _ in 0..2 {
+        let mut manager = env.manager();
+        manager.sync().await.expect("sync");
+        let issues = manager.get_status().await.issues;
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "Backups/disk.img");
+    }
+
+    assert_eq!(env.graph.upload_sessions_created(), 0);
+    assert!(env.graph.files().is_empty());
+    assert_eq!(std::fs::metadata(env.local_path("Backups/disk.img")).unwrap().len(), MAX_FILE_SIZE + 1);
+}
+
+#[tokio::test]
+async fn downloads_keep_the_onedrive_modified_time() {
+    let env = TestEnv::new().await;
+    let edited = chrono::Utc.with_ymd_and_hms(2021, 3, 14, 15, 9, 26).unwrap();
+    env.graph.put_file("Documents/report.odt", &content("report", 0, SMALL_FILE));
+    env.graph.set_modified("Documents/report.odt", edited);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let modified = std::fs::metadata(env.local_path("Documents/report.odt")).unwrap().modified().unwrap();
+    assert_eq!(modified, std::time::SystemTime::from(edited));
+    let pending = manager.preview_sync().await.expect("preview");
+    assert!(pending.is_empty(), "sync did not settle: {:?}", pending);
+}
+
+#[tokio::test]
+async fn uploads_send_the_local_modified_time() {
+    let env = TestEnv::new().await;
+    let edited = chrono::Utc.with_ymd_and_hms(2021, 3, 14, 15, 9, 26).unwrap();
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+    env.write_local("Videos/holiday.mp4", &content("video", 0, 6 * 1024 * 1024));
+    for path in ["Documents/notes.txt", "Videos/holiday.mp4"] {
+        filetime::set_file_mtime(env.local_path(path), filetime::FileTime::from_unix_time(edited.timestamp(), 0)).unwrap();
+    }
+
+    env.manager().sync().await.expect("sync");
+
+    let expected = edited.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    assert_eq!(env.graph.file_system_modified("Documents/notes.txt").as_ref(), Some(&expected));
+    assert_eq!(env.graph.file_system_modified("Videos/holiday.mp4").as_ref(), Some(&expected));
+}
+
+#[tokio::test]
+async fn unchanged_drive_reuses_the_last_listing() {
+    let env = TestEnv::new().await;
+    let mut manager = env.manager();
+    env.graph.put_file("Documents/report.txt", &content("report", 0, SMALL_FILE));
+    manager.sync().await.expect("initial sync");
+
+    // With nothing changed, no listing is needed to see that
+    env.graph.fail_listings(true);
+    let pending = manager.preview_sync().await.expect("preview");
+    assert!(pending.is_empty(), "{:?}", pending);
+    env.graph.fail_listings(false);
+
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+    manager.sync().await.expect("sync");
+    assert_eq!(env.local_files(), env.graph.files());
+}
+
+#[tokio::test]
+async fn expired_delta_link_falls_back_to_a_full_scan() {
+    let env = TestEnv::new().await;
+    let mut manager = env.manager();
+    let files: Vec<(String, Vec<u8>)> = (0..12)
+        .map(|index| (format!("Documents/file-{:02}.txt", index), content("original", index, SMALL_FILE)))
+        .collect();
+    for (path, bytes) in &files {
+        env.graph.put_file(path, bytes);
+    }
+    manager.sync().await.expect("initial sync");
+
+    env.graph.put_file("Documents/new.txt", &content("new", 0, SMALL_FILE));
+    env.graph.expire_delta_links();
+    manager.sync().await.expect("sync after the delta link expired");
+
+    assert_eq!(env.local_files(), env.graph.files());
+    let history = manager.get_sync_history(100, "").await.unwrap();
+    assert_eq!(history.iter().filter(|entry| entry.action == "delta_resync").count(), 1);
+    assert_eq!(history.iter().filter(|entry| entry.action == "download").count(), files.len() + 1);
+
+    let pending = manager.preview_sync().await.expect("preview");
+    assert!(pending.is_empty(), "sync did not settle: {:?}", pending);
+}
+
+#[tokio::test]
+async fn uploads_wait_when_onedrive_is_full() {
+    let env = TestEnv::new().await;
+    env.write_local("Videos/raw.mov", b"");
+    // Sparse, so it takes no real space
+    std::fs::File::options()
+        .write(true)
+        .open(env.local_path("Videos/raw.mov"))
+        .unwrap()
+        .set_len(200 * 1024 * 1024)
+        .unwrap();
+    env.graph.put_file("Documents/report.txt", &content("report", 0, SMALL_FILE));
+    env.graph.set_quota_remaining(50 * 1024 * 1024);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let status = manager.get_status().await;
+    let reason = status.uploads_held_for_quota.expect("uploads held");
+    let expected = format!("need {}, have {}", format::size(200 * 1024 * 1024), format::size(50 * 1024 * 1024));
+    assert!(reason.contains(&expected), "{}", reason);
+    assert_eq!(env.graph.upload_sessions_created(), 0);
+    assert_eq!(env.graph.file("Videos/raw.mov"), None);
+    // Downloads are not held up by the full drive
+    assert_eq!(env.graph.file("Documents/report.txt"), env.local_files().get("Documents/report.txt").cloned());
+}
+
+#[tokio::test]
+async fn files_emptied_en_masse_are_not_uploaded() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 8).await;
+
+    for (path, _) in &files[..6] {
+        env.write_local(path, b"");
+    }
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    // OneDrive keeps every good copy
+    for (path, bytes) in &files {
+        assert_eq!(env.graph.file(path).as_ref(), Some(bytes));
+    }
+    let status = manager.get_status().await;
+    assert_eq!(status.issues.len(), 6);
+    assert!(status.sync_errors.iter().any(|error| error.contains("emptied")), "{:?}", status.sync_errors);
+
+    // Dismissing an issue lets that one file upload as it is
+    manager.control().dismiss_issue(&files[0].0).await.expect("dismiss");
+    manager.sync().await.expect("sync after dismissing");
+    assert_eq!(env.graph.file(&files[0].0), Some(Vec::new()));
+    assert_eq!(env.graph.file(&files[1].0).as_ref(), Some(&files[1].1));
+}
+
+#[tokio::test]
+async fn mass_changes_pause_uploads_until_confirmed() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 30).await;
+
+    let encrypted: Vec<(String, Vec<u8>)> = files[..24]
+        .iter()
+        .enumerate()
+        .map(|(index, (path, _))| (path.clone(), content("encrypted", index, SMALL_FILE + 16)))
+        .collect();
+    for (path, bytes) in &encrypted {
+        env.write_local(path, bytes);
+    }
+    env.graph.put_file("Documents/shared.txt", &content("shared", 0, SMALL_FILE));
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    // OneDrive keeps every original, and downloads still arrive
+    for (path, bytes) in &files {
+        assert_eq!(env.graph.file(path).as_ref(), Some(bytes));
+    }
+    assert_eq!(env.local_files()["Documents/shared.txt"], content("shared", 0, SMALL_FILE));
+    let reason = manager.get_status().await.uploads_paused_for_mass_change.expect("uploads paused");
+    assert!(reason.contains("24 of your 30"), "{}", reason);
+
+    // The breaker holds across restarts until the changes are confirmed
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync after restarting");
+    assert_eq!(env.graph.file(&encrypted[0].0).as_ref(), Some(&files[0].1));
+
+    manager.control().confirm_mass_change().await.expect("confirm");
+    manager.sync().await.expect("sync after confirming");
+    for (path, bytes) in &encrypted {
+        assert_eq!(env.graph.file(path).as_ref(), Some(bytes));
+    }
+    assert!(manager.get_status().await.uploads_paused_for_mass_change.is_none());
+}
+
+#[tokio::test]
+async fn mass_changes_made_offline_are_not_uploaded_at_startup() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 30).await;
+
+    for (index, (path, _)) in files[..24].iter().enumerate() {
+        env.write_local(path, &content("encrypted", index, SMALL_FILE + 16));
+    }
+    env.manager().recover_offline_changes().await.expect("recovery");
+
+    for (path, bytes) in &files {
+        assert_eq!(env.graph.file(path).as_ref(), Some(bytes));
+    }
+}
+
+#[tokio::test]
+async fn a_single_emptied_file_is_uploaded() {
+    let env = TestEnv::new().await;
+    let files = synced_drive(&env, 8).await;
+
+    env.write_local(&files[0].0, b"");
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert_eq!(env.graph.file(&files[0].0), Some(Vec::new()));
+    assert!(manager.get_status().await.issues.is_empty());
+}
+
+#[tokio::test]
+async fn uploads_that_fit_go_ahead_when_onedrive_is_nearly_full() {
+    let env = TestEnv::new().await;
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+    env.write_local("Videos/raw.mov", b"");
+    std::fs::File::options()
+        .write(true)
+        .open(env.local_path("Videos/raw.mov"))
+        .unwrap()
+        .set_len(200 * 1024 * 1024)
+        .unwrap();
+    env.graph.set_quota_remaining(50 * 1024 * 1024);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let reason = manager.get_status().await.uploads_held_for_quota.expect("uploads held");
+    assert!(reason.contains("1 of 2 uploads"), "{}", reason);
+    assert_eq!(env.graph.file("Documents/notes.txt"), Some(content("notes", 0, SMALL_FILE)));
+    assert_eq!(env.graph.file("Videos/raw.mov"), None);
+}
+
+#[tokio::test]
+async fn uploads_stop_when_onedrive_fills_up_during_a_sync() {
+    let env = TestEnv::with_config(|config| config.max_concurrent_transfers = 1).await;
+    for index in 0..3 {
+        env.write_local(&format!("Documents/file-{}.txt", index), &content("local", index, SMALL_FILE));
+    }
+    // Too little for any of them, but below the size that is checked up front
+    env.graph.set_quota_remaining(1024);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let status = manager.get_status().await;
+    assert!(status.uploads_held_for_quota.is_some());
+    // Only the first upload was tried; the others wait for space
+    assert_eq!(status.sync_errors.len(), 1, "{:?}", status.sync_errors);
+    assert!(env.graph.files().is_empty());
+
+    env.graph.set_quota_remaining(1024 * 1024);
+    manager.sync().await.expect("sync with space");
+    assert_eq!(env.graph.files().len(), 3);
+    assert!(manager.get_status().await.uploads_held_for_quota.is_none());
+}
+
+#[tokio::test]
+async fn files_still_being_written_are_not_uploaded() {
+    let env = TestEnv::with_config(|config| config.upload_settle_seconds = 3600).await;
+    env.write_local("Videos/recording.mkv", &content("recording", 0, SMALL_FILE));
+    env.write_local("Documents/old.txt", &content("old", 0, SMALL_FILE));
+    let written_long_ago = filetime::FileTime::from_unix_time(chrono::Utc::now().timestamp() - 7200, 0);
+    filetime::set_file_mtime(env.local_path("Documents/old.txt"), written_long_ago).unwrap();
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert_eq!(env.graph.file("Videos/recording.mkv"), None);
+    assert!(env.graph.file("Documents/old.txt").is_some());
+    assert_eq!(manager.get_status().await.files_being_written, ["Videos/recording.mkv"]);
+
+    // Once it stops changing it goes up like any other file
+    filetime::set_file_mtime(env.local_path("Videos/recording.mkv"), written_long_ago).unwrap();
+    manager.sync().await.expect("sync");
+    assert_eq!(env.graph.file("Videos/recording.mkv"), Some(content("recording", 0, SMALL_FILE)));
+    assert!(manager.get_status().await.files_being_written.is_empty());
+}
+
+#[tokio::test]
+async fn names_differing_only_in_case_are_not_synced() {
+    let env = TestEnv::new().await;
+    env.write_local("Documents/Report.txt", &content("upper", 0, SMALL_FILE));
+    env.write_local("Documents/report.txt", &content("lower", 0, SMALL_FILE));
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert_eq!(env.graph.files().keys().collect::<Vec<_>>(), ["Documents/notes.txt"]);
+    let status = manager.get_status().await;
+    assert!(
+        status.sync_errors.iter().any(|error| error.contains("Documents/Report.txt and Documents/report.txt")),
+        "{:?}",
+        status.sync_errors
+    );
+    let mut skipped: Vec<&str> = status.skipped_files.iter().map(|file| file.path.as_str()).collect();
+    skipped.sort();
+    assert_eq!(skipped, ["Documents/Report.txt", "Documents/report.txt"]);
+
+    // Both local copies are untouched
+    assert_eq!(std::fs::read(env.local_path("Documents/Report.txt")).unwrap(), content("upper", 0, SMALL_FILE));
+    assert_eq!(std::fs::read(env.local_path("Documents/report.txt")).unwrap(), content("lower", 0, SMALL_FILE));
+}
+
+#[tokio::test]
+async fn names_onedrive_refuses_are_skipped_or_renamed() {
+    let mut env = TestEnv::new().await;
+    env.write_local("Documents/notes: draft?.txt", &content("draft", 0, SMALL_FILE));
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert_eq!(env.graph.files().keys().collect::<Vec<_>>(), ["Documents/notes.txt"]);
+    let status = manager.get_status().await;
+    let skipped: Vec<&str> = status.skipped_files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(skipped, ["Documents/notes: draft?.txt"]);
+    assert!(status.skipped_files[0].reason.contains("character :"), "{}", status.skipped_files[0].reason);
+
+    // The user turns on renaming
+    let mut config = (*env.config).clone();
+    config.rename_illegal_names = true;
+    env.config = Arc::new(config);
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync with renaming");
+
+    assert!(!env.local_path("Documents/notes: draft?.txt").exists());
+    assert_eq!(std::fs::read(env.local_path("Documents/notes_ draft_.txt")).unwrap(), content("draft", 0, SMALL_FILE));
+    assert!(env.graph.files().contains_key("Documents/notes_ draft_.txt"));
+    assert!(manager.get_status().await.skipped_files.is_empty());
+}
+
+#[tokio::test]
+async fn startup_removes_partial_downloads_that_cannot_resume() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Photos/photo-0.jpg", &content("photo", 0, 256 * 1024));
+    env.graph.put_file("Photos/photo-1.jpg", &content("photo", 1, 256 * 1024));
+    env.graph.fail_downloads(1);
+    let _ = env.manager().sync().await;
+
+    // Started too long ago to be worth resuming
+    let db = rusqlite::Connection::open(&env.config.db_file).unwrap();
+    let interrupted: String = db.query_row("SELECT path FROM partial_downloads", [], |row| row.get(0)).unwrap();
+    db.execute("UPDATE partial_downloads SET started = 0", []).unwrap();
+    drop(db);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync after restart");
+
+    let recovery = manager.get_status().await.recovery.expect("recovery report");
+    assert_eq!(recovery.removed_partials, [interrupted]);
+    assert_eq!(recovery.resumable_downloads, 0);
+    assert_eq!(env.local_files(), env.graph.files());
+}
+
+#[tokio::test]
+async fn files_of_the_users_ending_in_partial_sync_like_any_other() {
+    let env = TestEnv::new().await;
+    let own = format!("Documents/old.odt{}", PARTIAL_DOWNLOAD_SUFFIX);
+    env.write_local(&own, b"half a document");
+    env.graph.put_file(&format!("Photos/raw{}", PARTIAL_DOWNLOAD_SUFFIX), b"from a camera");
+    env.manager().sync().await.expect("sync");
+
+    // Nor does a restart take them for leftovers
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync after restart");
+
+    assert_eq!(env.graph.file(&own), Some(b"half a document".to_vec()));
+    assert_eq!(env.local_files(), env.graph.files());
+    assert!(manager.get_status().await.recovery.map_or(true, |recovery| recovery.removed_partials.is_empty()));
+    assert!(manager.preview_sync().await.expect("preview").is_empty());
+}
+
+#[tokio::test]
+async fn files_are_only_rehashed_when_size_or_mtime_change() {
+    let env = TestEnv::new().await;
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+    let written_long_ago = filetime::FileTime::from_unix_time(chrono::Utc::now().timestamp() - 7200, 0);
+    filetime::set_file_mtime(env.local_path("Documents/notes.txt"), written_long_ago).unwrap();
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("first sync");
+    assert_eq!(env.graph.file("Documents/notes.txt"), Some(content("notes", 0, SMALL_FILE)));
+
+    // Same size and mtime: the recorded hash is trusted and the file not read
+    env.write_local("Documents/notes.txt", &content("notes", 1, SMALL_FILE));
+    filetime::set_file_mtime(env.local_path("Documents/notes.txt"), written_long_ago).unwrap();
+    manager.sync().await.expect("second sync");
+    assert_eq!(env.graph.file("Documents/notes.txt"), Some(content("notes", 0, SMALL_FILE)));
+
+    // A new mtime gets it hashed, and the edit uploaded
+    let edited = filetime::FileTime::from_unix_time(chrono::Utc::now().timestamp() - 3600, 0);
+    filetime::set_file_mtime(env.local_path("Documents/notes.txt"), edited).unwrap();
+    manager.sync().await.expect("third sync");
+    assert_eq!(env.graph.file("Documents/notes.txt"), Some(content("notes", 1, SMALL_FILE)));
+}
+
+#[tokio::test]
+async fn unused_files_in_the_archive_folder_are_freed_but_kept_on_onedrive() {
+    let env = TestEnv::with_config(|config| {
+        config.archive_folder = "Archive".to_string();
+        config.archive_after_days = 30;
+    })
+    .await;
+    env.graph.put_file("Archive/2019/taxes.pdf", &content("taxes", 0, SMALL_FILE));
+    env.graph.put_file("Archive/recent.txt", &content("recent", 0, SMALL_FILE));
+    env.graph.put_file("Documents/old.txt", &content("old", 0, SMALL_FILE));
+    let mut manager = env.manager();
+    manager.sync().await.expect("initial sync");
+
+    let long_ago = filetime::FileTime::from_unix_time(chrono::Utc::now().timestamp() - 60 * 24 * 60 * 60, 0);
+    for path in ["Archive/2019/taxes.pdf", "Documents/old.txt"] {
+        filetime::set_file_times(env.local_path(path), long_ago, long_ago).unwrap();
+    }
+    manager.sync().await.expect("sync");
+
+    // Only the unused file inside the archive folder is freed
+    assert!(!env.local_path("Archive/2019/taxes.pdf").exists());
+    assert!(env.local_path("Archive/recent.txt").exists());
+    assert!(env.local_path("Documents/old.txt").exists());
+    assert_eq!(env.graph.file("Archive/2019/taxes.pdf"), Some(content("taxes", 0, SMALL_FILE)));
+
+    // ...and is not downloaded again
+    manager.sync().await.expect("sync");
+    assert!(!env.local_path("Archive/2019/taxes.pdf").exists());
+    assert_eq!(env.graph.files().len(), 3);
+}
+
+#[tokio::test]
+async fn changes_in_a_read_only_shared_folder_are_held_instead_of_retried() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Shared/plan.txt", &content("plan", 0, SMALL_FILE));
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+    let mut manager = env.manager();
+    manager.sync().await.expect("first sync");
+
+    env.graph.make_read_only("Shared");
+    env.write_local("Shared/plan.txt", &content("my plan", 0, SMALL_FILE + 16));
+    env.write_local("Documents/notes.txt", &content("my notes", 0, SMALL_FILE + 16));
+    let _ = manager.sync().await;
+
+    // The edit stays on this computer, and the rest of the drive syncs
+    assert_eq!(env.graph.file("Shared/plan.txt"), Some(content("plan", 0, SMALL_FILE)));
+    assert_eq!(env.local_files()["Shared/plan.txt"], content("my plan", 0, SMALL_FILE + 16));
+    assert_eq!(env.graph.file("Documents/notes.txt"), Some(content("my notes", 0, SMALL_FILE + 16)));
+    let issues = manager.get_status().await.issues;
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].path, "Shared/plan.txt");
+    assert!(issues[0].reason.contains("write access"), "{}", issues[0].reason);
+
+    // Later changes beneath the folder are held without asking OneDrive again
+    let refused = env.graph.writes_refused();
+    env.write_local("Shared/ideas.txt", &content("ideas", 0, SMALL_FILE));
+    manager.sync().await.expect("sync after the folder turned read-only");
+    manager.sync().await.expect("another sync");
+    assert_eq!(env.graph.writes_refused(), refused);
+    assert_eq!(env.graph.file("Shared/ideas.txt"), None);
+    let mut held: Vec<String> = manager.get_status().await.issues.into_iter().map(|issue| issue.path).collect();
+    held.sort();
+    assert_eq!(held, ["Shared/ideas.txt", "Shared/plan.txt"]);
+}
+
+#[tokio::test]
+async fn a_refused_change_outside_a_shared_folder_holds_only_that_file() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/locked.txt", &content("locked", 0, SMALL_FILE));
+    let mut manager = env.manager();
+    manager.sync().await.expect("first sync");
+
+    env.graph.make_read_only("Documents/locked.txt");
+    env.write_local("Documents/locked.txt", &content("my edit", 0, SMALL_FILE + 16));
+    let _ = manager.sync().await;
+    let issues = manager.get_status().await.issues;
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].path, "Documents/locked.txt");
+
+    // The rest of the folder still syncs
+    env.write_local("Documents/notes.txt", &content("notes", 0, SMALL_FILE));
+    manager.sync().await.expect("sync after the refusal");
+    assert_eq!(env.graph.file("Documents/notes.txt"), Some(content("notes", 0, SMALL_FILE)));
+    let db = rusqlite::Connection::open(&env.config.db_file).unwrap();
+    let read_only: i64 = db.query_row("SELECT COUNT(*) FROM read_only_folders", [], |row| row.get(0)).unwrap();
+    assert_eq!(read_only, 0);
+}
@@ -0,0 +1,40 @@
+use super::{content, TestEnv};
+
+#[tokio::test]
+async fn a_chosen_drive_is_synced_by_its_id() {
+    let env = TestEnv::with_config(|config| config.drive_id = "fake-drive".to_string()).await;
+    let drives = env.api().list_drives().await.expect("drive list");
+    assert_eq!(drives.iter().map(|drive| drive.id.as_str()).collect::<Vec<_>>(), ["fake-drive"]);
+
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.write_local("notes.txt", &content("notes", 0, 1024));
+    env.manager().sync().await.expect("sync");
+
+    assert_eq!(env.local_files()["Documents/report.txt"], content("report", 0, 1024));
+    assert_eq!(env.graph.file("notes.txt"), Some(content("notes", 0, 1024)));
+}
+
+#[tokio::test]
+async fn a_drive_that_does_not_exist_syncs_nothing() {
+    let env = TestEnv::with_config(|config| config.drive_id = "other-drive".to_string()).await;
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.write_local("notes.txt", &content("notes", 0, 1024));
+
+    assert!(env.manager().sync().await.is_err());
+    assert!(env.graph.file("notes.txt").is_none());
+    assert!(!env.local_files().contains_key("Documents/report.txt"));
+}
+
+#[tokio::test]
+async fn moves_on_a_chosen_drive_name_the_parent_by_that_drive() {
+    let env = TestEnv::with_config(|config| config.drive_id = "fake-drive".to_string()).await;
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.graph.put_file("Archive/old.txt", &content("old", 0, 1024));
+    let api = env.api();
+
+    let report = api.get_item("/Documents/report.txt").await.unwrap();
+    api.move_item(&report.id, "/Archive", "report 2024.txt").await.expect("move");
+
+    assert!(env.graph.file("Documents/report.txt").is_none());
+    assert_eq!(env.graph.file("Archive/report 2024.txt"), Some(content("report", 0, 1024)));
+}
@@ -0,0 +1,18 @@
+use super::{content, TestEnv};
+
+#[tokio::test]
+async fn a_folder_another_sync_client_claims_is_left_out_both_ways() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Shared/from-onedrive.txt", &content("onedrive", 0, 1024));
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.write_local("Shared/.stfolder/marker", b"");
+    env.write_local("Shared/local.txt", &content("local", 0, 1024));
+
+    env.manager().sync().await.expect("sync");
+
+    let local_files = env.local_files();
+    assert!(!local_files.contains_key("Shared/from-onedrive.txt"));
+    assert_eq!(local_files["Documents/report.txt"], content("report", 0, 1024));
+    assert_eq!(env.graph.file("Shared/local.txt"), None);
+    assert_eq!(env.graph.file("Shared/from-onedrive.txt"), Some(content("onedrive", 0, 1024)));
+}
@@ -0,0 +1,682 @@
+use hyper::body::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use super::memory_drive::{join, normalize, now, parent_of, Item, MemoryDrive};
+use crate::quickxor::QuickXorHash;
+
+/// An in-memory OneDrive served over HTTP with the parts of the Graph API the
+/// sync engine uses: listing, item lookup, simple and session uploads, ranged
+/// downloads, moves, copies, deletes, folder creation, delta links and JSON batches. Tests change the drive
+/// directly to play the part of OneDrive's other clients.
+pub struct FakeGraph {
+    graph: Arc<Mutex<Graph>>,
+    base_url: String,
+}
+
+/// The server's side: the drive it serves, and how it misbehaves when told to.
+#[derive(Default)]
+struct Graph {
+    origin: String,
+    drive: MemoryDrive,
+    sessions: HashMap<String, Session>,
+    sessions_created: usize,
+    /// Downloads still to be cut off halfway through
+    failing_downloads: usize,
+    downloads_served: usize,
+    /// Downloads still to be sent with their first byte flipped
+    corrupt_downloads: usize,
+    /// Session chunks accepted before the next one fails
+    chunks_before_failure: Option<usize>,
+    /// Answer listings with a server error
+    failing_listings: bool,
+    /// API requests still to be answered with HTTP 429
+    throttled_requests: usize,
+    /// Requests inside JSON batches still to be answered with HTTP 429
+    throttled_batched_requests: usize,
+    batches_received: usize,
+    /// Delta links from before this version are rejected with HTTP 410
+    oldest_delta_version: u64,
+    /// Space reported as left on the drive; plenty when unset
+    quota_remaining: Option<u64>,
+    /// Folders this user may read but not change, as in a share with view access
+    read_only_folders: Vec<String>,
+    /// Changes turned away by `read_only_folders`
+    writes_refused: usize,
+}
+
+struct Session {
+    path: String,
+    data: Vec<u8>,
+    file_system_modified: Option<String>,
+}
+
+impl FakeGraph {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind fake Graph listener");
+        let origin = format!("http://{}", listener.local_addr().expect("listener address"));
+        let graph = Arc::new(Mutex::new(Graph {
+            origin: origin.clone(),
+            ..Graph::default()
+        }));
+
+        let shared = graph.clone();
+        let make_service = make_service_fn(move |_| {
+            let graph = shared.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(graph.clone(), request))) }
+        });
+        let server = Server::from_tcp(listener).expect("fake Graph server").serve(make_service);
+        tokio::spawn(server);
+
+        Self {
+            graph,
+            base_url: format!("{}/v1.0", origin),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn put_file(&self, path: &str, content: &[u8]) {
+        self.graph.lock().unwrap().drive.put_file(path, content.to_vec());
+    }
+
+    /// Backdates an item's `lastModifiedDateTime`, as for a file edited long ago.
+    pub fn set_modified(&self, path: &str, modified: chrono::DateTime<chrono::Utc>) {
+        self.graph.lock().unwrap().drive.change(path, |item| {
+            item.modified = modified.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        });
+    }
+
+    /// The modified time the uploading client reported for a file.
+    pub fn file_system_modified(&self, path: &str) -> Option<String> {
+        self.graph.lock().unwrap().drive.items.get(path)?.file_system_modified.clone()
+    }
+
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        self.graph.lock().unwrap().drive.file(path)
+    }
+
+    pub fn files(&self) -> BTreeMap<String, Vec<u8>> {
+        self.graph.lock().unwrap().drive.files()
+    }
+
+    pub fn delete(&self, path: &str) {
+        self.graph.lock().unwrap().drive.delete(path);
+    }
+
+    /// Cuts off the next `count` downloads halfway through their content.
+    pub fn fail_downloads(&self, count: usize) {
+        self.graph.lock().unwrap().failing_downloads = count;
+    }
+
+    /// Flips the first byte of the next `count` downloads, as a broken proxy might.
+    pub fn corrupt_downloads(&self, count: usize) {
+        self.graph.lock().unwrap().corrupt_downloads = count;
+    }
+
+    /// Fails the upload session chunk after the next `accepted` ones.
+    pub fn fail_upload_chunk_after(&self, accepted: usize) {
+        self.graph.lock().unwrap().chunks_before_failure = Some(accepted);
+    }
+
+    /// Makes folder listings fail with a server error, or work again.
+    pub fn fail_listings(&self, failing: bool) {
+        self.graph.lock().unwrap().failing_listings = failing;
+    }
+
+    /// Answers the next `count` API requests with HTTP 429, asking for an
+    /// immediate retry.
+    pub fn throttle(&self, count: usize) {
+        self.graph.lock().unwrap().throttled_requests = count;
+    }
+
+    /// Rejects every delta link handed out so far, as OneDrive does when its
+    /// change history no longer reaches back to them.
+    pub fn expire_delta_links(&self) {
+        let mut graph = self.graph.lock().unwrap();
+        graph.oldest_delta_version = graph.drive.version + 1;
+    }
+
+    /// Reports only `bytes` left on the drive from now on.
+    pub fn set_quota_remaining(&self, bytes: u64) {
+        self.graph.lock().unwrap().quota_remaining = Some(bytes);
+    }
+
+    /// Answers the next `count` requests that arrive inside JSON batches with
+    /// HTTP 429, leaving the batch itself alone.
+    pub fn throttle_batched(&self, count: usize) {
+        self.graph.lock().unwrap().throttled_batched_requests = count;
+    }
+
+    /// Refuses every change to `folder` or beneath it with HTTP 403 from now
+    /// on, as OneDrive does in a folder shared with view access only, and
+    /// lists what is there as shared.
+    pub fn make_read_only(&self, folder: &str) {
+        self.graph.lock().unwrap().read_only_folders.push(folder.to_string());
+    }
+
+    pub fn writes_refused(&self) -> usize {
+        self.graph.lock().unwrap().writes_refused
+    }
+
+    /// File contents sent so far, whole or in part.
+    pub fn downloads_served(&self) -> usize {
+        self.graph.lock().unwrap().downloads_served
+    }
+
+    pub fn batches_received(&self) -> usize {
+        self.graph.lock().unwrap().batches_received
+    }
+
+    pub fn upload_sessions_created(&self) -> usize {
+        self.graph.lock().unwrap().sessions_created
+    }
+}
+
+async fn handle(graph: Arc<Mutex<Graph>>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = urlencoding::decode(request.uri().path())
+        .map(|path| path.into_owned())
+        .unwrap_or_default();
+    let query = request.uri().query().unwrap_or_default().to_string();
+    let range_start = header(&request, "Range")
+        .and_then(|range| range.strip_prefix("bytes=")?.trim_end_matches('-').parse().ok());
+    let content_range = header(&request, "Content-Range").and_then(|range| parse_content_range(&range));
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default().to_vec();
+
+    let response = graph.lock().unwrap().route(&method, &path, &query, range_start, content_range, body);
+    Ok(response)
+}
+
+fn header(request: &Request<Body>, name: &str) -> Option<String> {
+    request.headers().get(name)?.to_str().ok().map(str::to_string)
+}
+
+/// `bytes start-end/total` as `(start, end, total)`
+fn parse_content_range(range: &str) -> Option<(usize, usize, usize)> {
+    let (span, total) = range.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = span.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?, total.parse().ok()?))
+}
+
+impl Graph {
+    fn route(
+        &mut self,
+        method: &Method,
+        path: &str,
+        query: &str,
+        range_start: Option<usize>,
+        content_range: Option<(usize, usize, usize)>,
+        body: Vec<u8>,
+    ) -> Response<Body> {
+        if let Some(id) = path.strip_prefix("/download/") {
+            return self.download(id, range_start.unwrap_or(0));
+        }
+        if let Some(session_id) = path.strip_prefix("/upload/") {
+            return self.upload_chunk(method, session_id, content_range, body);
+        }
+        if let Some(id) = path.strip_prefix("/monitor/") {
+            // Copies finish at once, so their monitor only ever reports success
+            return json_response(
+                StatusCode::OK,
+                json!({ "status": "completed", "percentageComplete": 100.0, "resourceId": id }),
+            );
+        }
+
+        let Some(api_path) = path.strip_prefix("/v1.0") else {
+            return not_found();
+        };
+        if self.throttled_requests > 0 {
+            self.throttled_requests -= 1;
+            let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "activityLimitReached");
+            response.headers_mut().insert("Retry-After", "0".parse().unwrap());
+            return response;
+        }
+
+        // The drive also answers by its ID, as `/drives/{id}`, and then expects
+        // moves to name their new parent the same way
+        let (api_path, root_reference) = match api_path.strip_prefix("/drives/fake-drive") {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => (format!("/me/drive{}", rest), "/drives/fake-drive/root:"),
+            _ => (api_path.to_string(), "/drive/root:"),
+        };
+        match (method, api_path.as_str()) {
+            (&Method::GET, "/me/drive") => json_response(StatusCode::OK, self.drive_json()),
+            (&Method::GET, "/me/drives") => json_response(StatusCode::OK, json!({ "value": [self.drive_json()] })),
+            (&Method::GET, "/me/drive/root") => json_response(
+                StatusCode::OK,
+                json!({ "id": "root", "name": "root", "lastModifiedDateTime": now(), "folder": { "childCount": 0 } }),
+            ),
+            (&Method::GET, "/me/drive/root/children") => self.list(""),
+            (&Method::GET, "/me/drive/root/delta") => self.delta(query.strip_prefix("token=").unwrap_or_default()),
+            (&Method::POST, "/me/drive/root/children") => self.create_folder("", &body),
+            (&Method::POST, "/$batch") => self.batch(&body),
+            _ => {
+                if let Some(rest) = api_path.strip_prefix("/me/drive/root:") {
+                    return self.route_path(method, rest, body);
+                }
+                if let Some(rest) = api_path.strip_prefix("/me/drive/items/") {
+                    return self.route_item(method, rest, body, root_reference);
+                }
+                not_found()
+            }
+        }
+    }
+
+    fn drive_json(&self) -> Value {
+        json!({
+            "id": "fake-drive",
+            "name": "OneDrive",
+            "driveType": "personal",
+            "quota": {
+                "total": 1u64 << 40,
+                "used": 0,
+                "remaining": self.quota_remaining.unwrap_or(1u64 << 40)
+            }
+        })
+    }
+
+    /// Requests addressed by path, `/me/drive/root:/{path}[:/{action}]`
+    fn route_path(&mut self, method: &Method, rest: &str, body: Vec<u8>) -> Response<Body> {
+        if *method != Method::GET {
+            if let Some(refused) = self.refuse_write(&normalize(rest.split(":/").next().unwrap_or(rest))) {
+                return refused;
+            }
+        }
+        if let Some(folder) = rest.strip_suffix(":/children") {
+            let folder = normalize(folder);
+            return match *method {
+                Method::GET => self.list(&folder),
+                Method::POST => self.create_folder(&folder, &body),
+                _ => not_found(),
+            };
+        }
+        if let Some(file) = rest.strip_suffix(":/content") {
+            if *method == Method::PUT {
+                if self.over_quota(body.len()) {
+                    return error_response(StatusCode::INSUFFICIENT_STORAGE, "quotaLimitReached");
+                }
+                let file = normalize(file);
+                self.drive.put_file(&file, body);
+                return self.item_response(StatusCode::CREATED, &file);
+            }
+            return not_found();
+        }
+        if let Some(file) = rest.strip_suffix(":/createUploadSession") {
+            let request: Value = serde_json::from_slice(&body).unwrap_or_default();
+            self.sessions_created += 1;
+            let session_id = format!("session-{}", self.sessions_created);
+            self.sessions.insert(
+                session_id.clone(),
+                Session {
+                    path: normalize(file),
+                    data: Vec::new(),
+                    file_system_modified: request["item"]["fileSystemInfo"]["lastModifiedDateTime"]
+                        .as_str()
+                        .map(str::to_string),
+                },
+            );
+            return json_response(
+                StatusCode::OK,
+                json!({ "uploadUrl": format!("{}/upload/{}", self.origin, session_id) }),
+            );
+        }
+        if *method == Method::GET {
+            return self.item_response(StatusCode::OK, &normalize(rest));
+        }
+        not_found()
+    }
+
+    /// Requests addressed by ID, `/me/drive/items/{id}[/content]`. Moves name
+    /// their new parent by a path under `root_reference`, the drive's root.
+    fn route_item(&mut self, method: &Method, rest: &str, body: Vec<u8>, root_reference: &str) -> Response<Body> {
+        if let Some(id) = rest.strip_suffix("/content") {
+            // Graph answers with a redirect to a pre-authenticated download URL
+            return Response::builder()
+                .status(StatusCode::FOUND)
+                .header("Location", format!("{}/download/{}", self.origin, id))
+                .body(Body::empty())
+                .unwrap();
+        }
+
+        if let Some(id) = rest.strip_suffix("/copy") {
+            return self.copy(id, &body);
+        }
+
+        let Some(path) = self.drive.path_of(rest) else {
+            return not_found();
+        };
+        match *method {
+            Method::DELETE => {
+                self.drive.delete(&path);
+                Response::builder().status(StatusCode::NO_CONTENT).body(Body::empty()).unwrap()
+            }
+            Method::PATCH => {
+                let request: Value = serde_json::from_slice(&body).unwrap_or_default();
+                if let Some(modified) = request["fileSystemInfo"]["lastModifiedDateTime"].as_str() {
+                    self.drive.change(&path, |item| item.file_system_modified = Some(modified.to_string()));
+                    return self.item_response(StatusCode::OK, &path);
+                }
+                let parent = match request["parentReference"]["path"].as_str() {
+                    Some(parent) => match parent.strip_prefix(root_reference) {
+                        Some(parent) => parent,
+                        None => return error_response(StatusCode::BAD_REQUEST, "invalidRequest"),
+                    },
+                    None => parent_of(&path),
+                };
+                let name = request["name"].as_str().unwrap_or_default();
+                let to = join(&normalize(parent), name);
+                self.drive.rename(&path, &to);
+                self.item_response(StatusCode::OK, &to)
+            }
+            _ => not_found(),
+        }
+    }
+
+    fn list(&self, folder: &str) -> Response<Body> {
+        if self.failing_listings {
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "generalException");
+        }
+        if !folder.is_empty() && !self.drive.items.contains_key(folder) {
+            return not_found();
+        }
+        let children: Vec<Value> = self
+            .drive
+            .items
+            .iter()
+            .filter(|(path, _)| parent_of(path) == folder)
+            .map(|(path, item)| self.item_json(path, item))
+            .collect();
+        json_response(StatusCode::OK, json!({ "value": children }))
+    }
+
+    /// The items changed, moved or deleted after the delta link's version.
+    /// Like Graph, deleted items carry only their ID and parent's ID.
+    fn delta(&self, token: &str) -> Response<Body> {
+        let since = match token {
+            "latest" => Some(self.drive.version),
+            _ => token.strip_prefix('v').and_then(|version| version.parse().ok()),
+        };
+        let Some(since) = since else {
+            return error_response(StatusCode::BAD_REQUEST, "invalidRequest");
+        };
+        if since < self.oldest_delta_version {
+            return error_response(StatusCode::GONE, "resyncRequired");
+        }
+
+        let mut changes: Vec<Value> = self
+            .drive
+            .items
+            .iter()
+            .filter(|(_, item)| item.version > since)
+            .map(|(path, item)| self.item_json(path, item))
+            .collect();
+        for (_, id, folder) in self.drive.deleted.iter().filter(|(version, _, _)| *version > since) {
+            let mut value = json!({ "id": id, "deleted": { "state": "deleted" }, "parentReference": { "id": "root" } });
+            if *folder {
+                value["folder"] = json!({ "childCount": 0 });
+            }
+            changes.push(value);
+        }
+        json_response(
+            StatusCode::OK,
+            json!({
+                "value": changes,
+                "@odata.deltaLink": format!("{}/v1.0/me/drive/root/delta?token=v{}", self.origin, self.drive.version),
+            }),
+        )
+    }
+
+    /// Answers each request of a JSON batch as if it had come on its own.
+    fn batch(&mut self, body: &[u8]) -> Response<Body> {
+        self.batches_received += 1;
+        let batch: Value = serde_json::from_slice(body).unwrap_or_default();
+        let mut responses = Vec::new();
+        for request in batch["requests"].as_array().cloned().unwrap_or_default() {
+            let response = if self.throttled_batched_requests > 0 {
+                self.throttled_batched_requests -= 1;
+                let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "activityLimitReached");
+                response.headers_mut().insert("Retry-After", "0".parse().unwrap());
+                response
+            } else {
+                let method = Method::from_bytes(request["method"].as_str().unwrap_or("GET").as_bytes()).unwrap_or(Method::GET);
+                let url = request["url"].as_str().unwrap_or_default();
+                let (path, query) = url.split_once('?').unwrap_or((url, ""));
+                let path = urlencoding::decode(path).map(|path| path.into_owned()).unwrap_or_default();
+                let body = match &request["body"] {
+                    Value::Null => Vec::new(),
+                    body => body.to_string().into_bytes(),
+                };
+                self.route(&method, &format!("/v1.0{}", path), query, None, None, body)
+            };
+
+            let headers: serde_json::Map<String, Value> = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), Value::from(value.to_str().ok()?))))
+                .collect();
+            let status = response.status().as_u16();
+            let bytes = futures::executor::block_on(hyper::body::to_bytes(response.into_body())).unwrap_or_default();
+            responses.push(json!({
+                "id": request["id"],
+                "status": status,
+                "headers": headers,
+                "body": serde_json::from_slice::<Value>(&bytes).unwrap_or(Value::Null),
+            }));
+        }
+        json_response(StatusCode::OK, json!({ "responses": responses }))
+    }
+
+    fn create_folder(&mut self, parent: &str, body: &[u8]) -> Response<Body> {
+        let request: Value = serde_json::from_slice(body).unwrap_or_default();
+        let path = join(parent, request["name"].as_str().unwrap_or_default());
+        self.drive.ensure_folder(&path);
+        self.item_response(StatusCode::CREATED, &path)
+    }
+
+    fn download(&mut self, id: &str, start: usize) -> Response<Body> {
+        let Some(content) = self
+            .drive
+            .path_of(id)
+            .and_then(|path| self.drive.items.get(&path))
+            .and_then(|item| item.content.clone())
+        else {
+            return not_found();
+        };
+
+        self.downloads_served += 1;
+        let status = if start > 0 { StatusCode::PARTIAL_CONTENT } else { StatusCode::OK };
+        let remaining = content[start.min(content.len())..].to_vec();
+        let builder = Response::builder()
+            .status(status)
+            .header("Content-Length", remaining.len().to_string());
+
+        if self.failing_downloads > 0 {
+            // Send half the body, then drop the connection
+            self.failing_downloads -= 1;
+            let half = Bytes::from(remaining[..remaining.len() / 2].to_vec());
+            let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+                Ok(half),
+                Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection lost")),
+            ];
+            return builder.body(Body::wrap_stream(futures::stream::iter(chunks))).unwrap();
+        }
+
+        if self.corrupt_downloads > 0 && !remaining.is_empty() {
+            self.corrupt_downloads -= 1;
+            let mut corrupt = remaining;
+            corrupt[0] ^= 0xff;
+            return builder.body(Body::from(corrupt)).unwrap();
+        }
+
+        builder.body(Body::from(remaining)).unwrap()
+    }
+
+    fn upload_chunk(
+        &mut self,
+        method: &Method,
+        session_id: &str,
+        content_range: Option<(usize, usize, usize)>,
+        body: Vec<u8>,
+    ) -> Response<Body> {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return not_found();
+        };
+
+        if *method == Method::GET {
+            return json_response(
+                StatusCode::OK,
+                json!({ "nextExpectedRanges": [format!("{}-", session.data.len())] }),
+            );
+        }
+
+        let Some((start, end, total)) = content_range else {
+            return error_response(StatusCode::BAD_REQUEST, "invalidRange");
+        };
+        if start != session.data.len() || end + 1 - start != body.len() {
+            return error_response(StatusCode::RANGE_NOT_SATISFIABLE, "invalidRange");
+        }
+        match &mut self.chunks_before_failure {
+            Some(0) => {
+                self.chunks_before_failure = None;
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, "generalException");
+            }
+            Some(accepted) => *accepted -= 1,
+            None => {}
+        }
+
+        if end + 1 == total && self.over_quota(total) {
+            return error_response(StatusCode::INSUFFICIENT_STORAGE, "quotaLimitReached");
+        }
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return not_found();
+        };
+        session.data.extend_from_slice(&body);
+        if end + 1 < total {
+            return json_response(
+                StatusCode::ACCEPTED,
+                json!({ "nextExpectedRanges": [format!("{}-", end + 1)] }),
+            );
+        }
+
+        let session = self.sessions.remove(session_id).unwrap();
+        let path = normalize(&session.path);
+        self.drive.put_file(&path, session.data);
+        if let Some(item) = self.drive.items.get_mut(&path) {
+            item.file_system_modified = session.file_system_modified;
+        }
+        self.item_response(StatusCode::CREATED, &session.path)
+    }
+
+    /// HTTP 403 for a change to `path` inside a read-only folder.
+    fn refuse_write(&mut self, path: &str) -> Option<Response<Body>> {
+        if !self.is_read_only(path) {
+            return None;
+        }
+        self.writes_refused += 1;
+        Some(error_response(StatusCode::FORBIDDEN, "accessDenied"))
+    }
+
+    fn is_read_only(&self, path: &str) -> bool {
+        self.read_only_folders
+            .iter()
+            .any(|folder| path == folder || path.strip_prefix(folder.as_str()).is_some_and(|rest| rest.starts_with('/')))
+    }
+
+    /// Whether an upload of `bytes` is more than the space left on the drive.
+    fn over_quota(&self, bytes: usize) -> bool {
+        self.quota_remaining.is_some_and(|remaining| bytes as u64 > remaining)
+    }
+
+    /// Copies item `id` to the `parentReference` and `name` in `body`, then
+    /// answers like Graph: accepted, with a URL to monitor the copy.
+    fn copy(&mut self, id: &str, body: &[u8]) -> Response<Body> {
+        let request: Value = serde_json::from_slice(body).unwrap_or_default();
+        let parent = match request["parentReference"]["id"].as_str() {
+            Some("root") => Some(String::new()),
+            Some(parent_id) => self.drive.path_of(parent_id),
+            None => None,
+        };
+        let (Some(from), Some(parent)) = (self.drive.path_of(id), parent) else {
+            return not_found();
+        };
+        let name = request["name"].as_str().unwrap_or_else(|| from.rsplit('/').next().unwrap_or_default());
+        let to = join(&parent, name);
+        if self.drive.items.contains_key(&to) {
+            return error_response(StatusCode::CONFLICT, "nameAlreadyExists");
+        }
+
+        self.drive.copy(&from, &to);
+
+        Response::builder()
+            .status(StatusCode::ACCEPTED)
+            .header("Location", format!("{}/monitor/{}", self.origin, self.drive.items[&to].id))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn item_response(&self, status: StatusCode, path: &str) -> Response<Body> {
+        match self.drive.items.get(path) {
+            Some(item) => json_response(status, self.item_json(path, item)),
+            None => not_found(),
+        }
+    }
+
+    fn item_json(&self, path: &str, item: &Item) -> Value {
+        let name = path.rsplit('/').next().unwrap_or_default();
+        let mut value = json!({
+            "id": item.id,
+            "name": name,
+            "lastModifiedDateTime": item.modified,
+            "parentReference": {
+                "id": self.drive.parent_id(path),
+                "path": format!("/drive/root:{}", if parent_of(path).is_empty() { String::new() } else { format!("/{}", parent_of(path)) }),
+            },
+        });
+        if let Some(modified) = &item.file_system_modified {
+            value["fileSystemInfo"] = json!({ "lastModifiedDateTime": modified });
+        }
+        if self.is_read_only(path) {
+            value["shared"] = json!({ "scope": "users" });
+        }
+        match &item.content {
+            Some(content) => {
+                let mut hash = QuickXorHash::new();
+                hash.update(content);
+                value["size"] = json!(content.len());
+                value["file"] = json!({
+                    "hashes": { "quickXorHash": hash.finalize() },
+                    "mimeType": "application/octet-stream"
+                });
+                value["@microsoft.graph.downloadUrl"] = json!(format!("{}/download/{}", self.origin, item.id));
+            }
+            None => {
+                value["size"] = json!(0);
+                value["folder"] = json!({ "childCount": 0 });
+            }
+        }
+        value
+    }
+}
+
+fn json_response(status: StatusCode, value: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(value.to_string()))
+        .unwrap()
+}
+
+fn error_response(status: StatusCode, code: &str) -> Response<Body> {
+    json_response(status, json!({ "error": { "code": code, "message": code } }))
+}
+
+fn not_found() -> Response<Body> {
+    error_response(StatusCode::NOT_FOUND, "itemNotFound")
+}
@@ -0,0 +1,61 @@
+use super::{content, TestEnv};
+
+#[tokio::test]
+async fn file_history_lists_every_change_oldest_first() {
+    let env = TestEnv::new().await;
+    let mut manager = env.manager();
+    env.write_local("Documents/notes.txt", &content("notes", 0, 1024));
+    env.graph.put_file("Documents/other.txt", &content("other", 0, 1024));
+    manager.sync().await.expect("initial sync");
+
+    env.graph.put_file("Documents/notes.txt", &content("notes", 1, 2048));
+    manager.sync().await.expect("sync");
+
+    let history = manager.get_file_history("Documents/notes.txt").await.unwrap();
+    let actions: Vec<&str> = history.iter().map(|entry| entry.action.as_str()).collect();
+    assert_eq!(actions, ["upload", "download"]);
+    assert!(history.iter().all(|entry| entry.file_path == "Documents/notes.txt"));
+    assert!(history[0].run_id.is_some() && history[0].run_id != history[1].run_id);
+}
+
+#[tokio::test]
+async fn sync_runs_are_recorded_across_restarts() {
+    let env = TestEnv::new().await;
+    env.write_local("Documents/a.txt", &content("a", 0, 1024));
+    env.write_local("Documents/b.txt", &content("b", 0, 1024));
+    env.graph.put_file("Photos/c.jpg", &content("c", 0, 4096));
+    env.manager().sync().await.expect("first sync");
+
+    env.write_local("Documents/a.txt", &content("a", 1, 2048));
+    env.manager().sync().await.expect("second sync");
+
+    // A fresh manager, as after a restart, still sees both runs
+    let runs = env.manager().get_sync_runs(10).await.unwrap();
+    assert_eq!(runs.len(), 2);
+    let (latest, first) = (&runs[0], &runs[1]);
+    assert_eq!((first.uploaded, first.downloaded, first.errors), (2, 1, 0));
+    assert_eq!(first.bytes_transferred, 1024 + 1024 + 4096);
+    assert_eq!((latest.uploaded, latest.downloaded), (1, 0));
+    assert_eq!(latest.bytes_transferred, 2048);
+    assert!(runs.iter().all(|run| run.outcome.as_deref() == Some("success") && run.finished >= Some(run.started)));
+    assert_ne!(latest.run_id, first.run_id);
+}
+
+#[tokio::test]
+async fn sync_log_is_pruned_to_the_configured_size_and_can_be_cleared() {
+    let env = TestEnv::with_config(|config| config.log_max_rows = 5).await;
+    let mut manager = env.manager();
+    for index in 0..8 {
+        env.write_local(&format!("Documents/file-{}.txt", index), &content("file", index, 1024));
+    }
+    manager.sync().await.expect("sync");
+
+    // Pruning runs before the run's own completion entry is logged
+    let history = manager.get_sync_history(100, "").await.unwrap();
+    assert_eq!(history.len(), 6);
+    assert_eq!(history.iter().filter(|entry| entry.action == "sync_complete").count(), 1);
+
+    manager.clear_logs().await.unwrap();
+    assert!(manager.get_sync_history(100, "").await.unwrap().is_empty());
+    assert!(manager.get_sync_runs(10).await.unwrap().is_empty());
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+/// A OneDrive kept in memory: its items by path, their IDs, and the versions
+/// delta queries are answered from. The fake Graph server serves one over
+/// HTTP and `MemoryStore` behind the `RemoteStore` trait; tests change it
+/// directly to play the part of OneDrive's other clients.
+#[derive(Default)]
+pub struct MemoryDrive {
+    /// Items by path relative to the drive root, without a leading slash
+    pub items: BTreeMap<String, Item>,
+    next_id: u64,
+    /// Bumped by every change; delta links name the version they start from
+    pub version: u64,
+    /// Items deleted so far: the version deleted at, the ID, and whether a folder
+    pub deleted: Vec<(u64, String, bool)>,
+}
+
+pub struct Item {
+    pub id: String,
+    /// `None` for folders
+    pub content: Option<Vec<u8>>,
+    pub modified: String,
+    /// `fileSystemInfo.lastModifiedDateTime` as the uploading client set it
+    pub file_system_modified: Option<String>,
+    /// The drive's version when the item last changed or moved
+    pub version: u64,
+}
+
+impl MemoryDrive {
+    pub fn new_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("item-{}", self.next_id)
+    }
+
+    /// Creates or replaces a file, creating its parent folders as needed.
+    pub fn put_file(&mut self, path: &str, content: Vec<u8>) {
+        let path = normalize(path);
+        self.ensure_folder(parent_of(&path));
+        self.version += 1;
+        let id = match self.items.get(&path) {
+            Some(item) => item.id.clone(),
+            None => self.new_id(),
+        };
+        let version = self.version;
+        self.items.insert(
+            path,
+            Item {
+                id,
+                content: Some(content),
+                modified: now(),
+                file_system_modified: None,
+                version,
+            },
+        );
+    }
+
+    pub fn ensure_folder(&mut self, path: &str) {
+        if path.is_empty() || self.items.contains_key(path) {
+            return;
+        }
+        self.ensure_folder(parent_of(path));
+        self.version += 1;
+        let id = self.new_id();
+        let version = self.version;
+        self.items.insert(
+            path.to_string(),
+            Item {
+                id,
+                content: None,
+                modified: now(),
+                file_system_modified: None,
+                version,
+            },
+        );
+    }
+
+    /// Applies `change` to the item at `path` as a new version of it.
+    pub fn change(&mut self, path: &str, change: impl FnOnce(&mut Item)) {
+        self.version += 1;
+        let version = self.version;
+        if let Some(item) = self.items.get_mut(path) {
+            change(item);
+            item.version = version;
+        }
+    }
+
+    /// Deletes an item and everything under it.
+    pub fn delete(&mut self, path: &str) {
+        self.version += 1;
+        let version = self.version;
+        let deleted = &mut self.deleted;
+        self.items.retain(|item_path, item| {
+            let kept = !is_within(item_path, path);
+            if !kept {
+                deleted.push((version, item.id.clone(), item.content.is_none()));
+            }
+            kept
+        });
+    }
+
+    /// Moves an item, and everything under it for a folder, to `to`.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        self.ensure_folder(parent_of(to));
+        self.version += 1;
+        let moved: Vec<String> = self.items.keys().filter(|path| is_within(path, from)).cloned().collect();
+        for path in moved {
+            let mut item = self.items.remove(&path).unwrap();
+            item.version = self.version;
+            self.items.insert(format!("{}{}", to, &path[from.len()..]), item);
+        }
+    }
+
+    /// Copies an item, and everything under it for a folder, to `to` under new IDs.
+    pub fn copy(&mut self, from: &str, to: &str) {
+        self.ensure_folder(parent_of(to));
+        self.version += 1;
+        let copied: Vec<(String, Option<Vec<u8>>, Option<String>)> = self
+            .items
+            .iter()
+            .filter(|(path, _)| is_within(path, from))
+            .map(|(path, item)| (format!("{}{}", to, &path[from.len()..]), item.content.clone(), item.file_system_modified.clone()))
+            .collect();
+        for (path, content, file_system_modified) in copied {
+            let id = self.new_id();
+            let version = self.version;
+            self.items.insert(path, Item { id, content, modified: now(), file_system_modified, version });
+        }
+    }
+
+    pub fn path_of(&self, id: &str) -> Option<String> {
+        self.items.iter().find(|(_, item)| item.id == id).map(|(path, _)| path.clone())
+    }
+
+    /// The ID of the folder holding `path`; `"root"` for the drive's root.
+    pub fn parent_id(&self, path: &str) -> &str {
+        self.items.get(parent_of(path)).map_or("root", |parent| parent.id.as_str())
+    }
+
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        self.items.get(path).and_then(|item| item.content.clone())
+    }
+
+    /// Every file on the drive and its content.
+    pub fn files(&self) -> BTreeMap<String, Vec<u8>> {
+        self.items
+            .iter()
+            .filter_map(|(path, item)| Some((path.clone(), item.content.clone()?)))
+            .collect()
+    }
+}
+
+/// Whether `path` is `folder` or lies beneath it.
+fn is_within(path: &str, folder: &str) -> bool {
+    path == folder || path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/'))
+}
+
+pub fn normalize(path: &str) -> String {
+    path.trim_matches('/').to_string()
+}
+
+pub fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+pub fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+pub fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
@@ -0,0 +1,308 @@
+use anyhow::{anyhow, Result};
+use filetime::FileTime;
+use futures::future::BoxFuture;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::fs;
+use tokio_util::sync::CancellationToken;
+
+use super::memory_drive::{join, normalize, now, parent_of, MemoryDrive};
+use crate::api::{partial_download_path, Cancelled, DriveInfo, DriveItem, FileFacet, FileHashes, FileSystemInfo, GraphError, ItemReference, UploadSession};
+use crate::quickxor::QuickXorHash;
+use crate::remote::RemoteStore;
+
+/// A drive kept in memory behind the `RemoteStore` trait, with no HTTP in
+/// between: the sync engine's remote side reduced to what reconciliation
+/// needs. Tests change the drive directly to play the part of other clients.
+#[derive(Default)]
+pub struct MemoryStore {
+    drive: Mutex<MemoryDrive>,
+    /// Paths of the open upload sessions, by upload URL
+    sessions: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn put_file(&self, path: &str, content: &[u8]) {
+        self.drive.lock().unwrap().put_file(path, content.to_vec());
+    }
+
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        self.drive.lock().unwrap().file(path)
+    }
+
+    pub fn files(&self) -> BTreeMap<String, Vec<u8>> {
+        self.drive.lock().unwrap().files()
+    }
+}
+
+impl RemoteStore for MemoryStore {
+    fn get_drive_info(&self) -> BoxFuture<'_, Result<DriveInfo>> {
+        Box::pin(async {
+            Ok(DriveInfo {
+                id: "memory".to_string(),
+                name: Some("OneDrive".to_string()),
+                drive_type: "personal".to_string(),
+                quota: None,
+            })
+        })
+    }
+
+    fn list_items<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<DriveItem>>> {
+        Box::pin(async move {
+            let drive = self.drive.lock().unwrap();
+            let folder = normalize(path);
+            if !folder.is_empty() && !drive.items.get(&folder).is_some_and(|item| item.content.is_none()) {
+                return Err(not_found(&folder));
+            }
+            drive
+                .items
+                .keys()
+                .filter(|child| *child != &folder && parent_of(child) == folder)
+                .map(|child| item(&drive, child))
+                .collect()
+        })
+    }
+
+    fn get_item<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(async move { item(&self.drive.lock().unwrap(), &normalize(path)) })
+    }
+
+    fn get_items<'a>(&'a self, paths: &'a [String]) -> BoxFuture<'a, Result<Vec<Result<DriveItem>>>> {
+        Box::pin(async move {
+            let drive = self.drive.lock().unwrap();
+            Ok(paths.iter().map(|path| item(&drive, &normalize(path))).collect())
+        })
+    }
+
+    fn latest_delta_link(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move { Ok(format!("memory:{}", self.drive.lock().unwrap().version)) })
+    }
+
+    fn delta_changes<'a>(&'a self, delta_link: &'a str) -> BoxFuture<'a, Result<(Vec<DriveItem>, String)>> {
+        Box::pin(async move {
+            let since: u64 = delta_link
+                .strip_prefix("memory:")
+                .and_then(|version| version.parse().ok())
+                .ok_or_else(|| anyhow!("Not a delta link of this drive: {}", delta_link))?;
+            let drive = self.drive.lock().unwrap();
+            let changed = drive
+                .items
+                .iter()
+                .filter(|(_, item)| item.version > since)
+                .map(|(path, _)| item(&drive, path))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((changed, format!("memory:{}", drive.version)))
+        })
+    }
+
+    fn download_file_from<'a>(
+        &'a self,
+        item: &'a DriveItem,
+        local_path: &'a Path,
+        _offset: u64,
+        cancel: &'a CancellationToken,
+        on_progress: &'a (dyn Fn(u64) + Send + Sync),
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            let content = {
+                let drive = self.drive.lock().unwrap();
+                drive
+                    .items
+                    .values()
+                    .find(|stored| stored.id == item.id)
+                    .and_then(|stored| stored.content.clone())
+                    .ok_or_else(|| not_found(&item.name))?
+            };
+
+            // Always from the start, as a server that ignores `Range` would
+            if let Some(parent) = local_path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let partial_path = partial_download_path(local_path);
+            fs::write(&partial_path, &content).await?;
+            on_progress(content.len() as u64);
+            let modified = chrono::DateTime::parse_from_rfc3339(item.modified_time())?;
+            filetime::set_file_mtime(
+                &partial_path,
+                FileTime::from_unix_time(modified.timestamp(), modified.timestamp_subsec_nanos()),
+            )?;
+            fs::rename(&partial_path, local_path).await?;
+            Ok(())
+        })
+    }
+
+    fn upload_file<'a>(&'a self, local_path: &'a Path, remote_name: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(async move {
+            let content = fs::read(local_path).await?;
+            let modified = local_modified_time(local_path).await?;
+            let mut drive = self.drive.lock().unwrap();
+            let path = normalize(remote_name);
+            upload(&mut drive, &path, content, modified);
+            item(&drive, &path)
+        })
+    }
+
+    fn create_upload_session<'a>(&'a self, _local_path: &'a Path, remote_name: &'a str) -> BoxFuture<'a, Result<UploadSession>> {
+        Box::pin(async move {
+            let upload_url = format!("memory://upload/{}", self.drive.lock().unwrap().new_id());
+            self.sessions.lock().unwrap().insert(upload_url.clone(), normalize(remote_name));
+            Ok(UploadSession { upload_url })
+        })
+    }
+
+    fn upload_session_offset<'a>(&'a self, upload_url: &'a str) -> BoxFuture<'a, Result<Option<u64>>> {
+        // Nothing arrives until the whole file does
+        Box::pin(async move { Ok(self.sessions.lock().unwrap().contains_key(upload_url).then_some(0)) })
+    }
+
+    fn upload_to_session<'a>(
+        &'a self,
+        upload_url: &'a str,
+        local_path: &'a Path,
+        _offset: u64,
+        total_size: u64,
+        cancel: &'a CancellationToken,
+        on_progress: &'a (dyn Fn(u64) + Send + Sync),
+    ) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(async move {
+            if cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            let content = fs::read(local_path).await?;
+            let modified = local_modified_time(local_path).await?;
+            let path = self
+                .sessions
+                .lock()
+                .unwrap()
+                .remove(upload_url)
+                .ok_or_else(|| anyhow!("No upload session at {}", upload_url))?;
+            let mut drive = self.drive.lock().unwrap();
+            upload(&mut drive, &path, content, modified);
+            on_progress(total_size);
+            item(&drive, &path)
+        })
+    }
+
+    fn move_item<'a>(&'a self, item_id: &'a str, new_parent_path: &'a str, new_name: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(async move {
+            let mut drive = self.drive.lock().unwrap();
+            let from = drive.path_of(item_id).ok_or_else(|| not_found(item_id))?;
+            let to = join(&normalize(new_parent_path), new_name);
+            drive.rename(&from, &to);
+            item(&drive, &to)
+        })
+    }
+
+    fn create_folder<'a>(&'a self, folder_name: &'a str, parent_path: &'a str) -> BoxFuture<'a, Result<DriveItem>> {
+        Box::pin(async move {
+            let mut drive = self.drive.lock().unwrap();
+            let path = join(&normalize(parent_path), folder_name);
+            drive.ensure_folder(&path);
+            item(&drive, &path)
+        })
+    }
+
+    fn create_folders<'a>(&'a self, folders: &'a [(String, String)]) -> BoxFuture<'a, Result<Vec<Result<DriveItem>>>> {
+        Box::pin(async move {
+            let mut drive = self.drive.lock().unwrap();
+            Ok(folders
+                .iter()
+                .map(|(folder_name, parent_path)| {
+                    let path = join(&normalize(parent_path), folder_name);
+                    drive.ensure_folder(&path);
+                    item(&drive, &path)
+                })
+                .collect())
+        })
+    }
+}
+
+/// Stores an uploaded file with the local modified time its upload carried.
+fn upload(drive: &mut MemoryDrive, path: &str, content: Vec<u8>, modified: String) {
+    drive.put_file(path, content);
+    if let Some(item) = drive.items.get_mut(path) {
+        item.file_system_modified = Some(modified);
+    }
+}
+
+/// The item at `path` as Graph would describe it; `""` is the root.
+fn item(drive: &MemoryDrive, path: &str) -> Result<DriveItem> {
+    if path.is_empty() {
+        return Ok(folder_item("root", "root", now()));
+    }
+    let item = drive.items.get(path).ok_or_else(|| not_found(path))?;
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let parent = parent_of(path);
+    let parent_reference = Some(ItemReference {
+        id: Some(drive.parent_id(path).to_string()),
+        path: Some(format!("/drive/root:{}", if parent.is_empty() { String::new() } else { format!("/{}", parent) })),
+    });
+    let Some(content) = &item.content else {
+        return Ok(DriveItem { parent_reference, ..folder_item(&item.id, name, item.modified.clone()) });
+    };
+    let mut hash = QuickXorHash::new();
+    hash.update(content);
+    Ok(DriveItem {
+        id: item.id.clone(),
+        name: name.to_string(),
+        last_modified: item.modified.clone(),
+        size: Some(content.len() as u64),
+        file: Some(FileFacet {
+            hashes: Some(FileHashes { quick_xor_hash: Some(hash.finalize()), ..FileHashes::default() }),
+            mime_type: Some("application/octet-stream".to_string()),
+        }),
+        folder: None,
+        download_url: None,
+        file_system_info: item
+            .file_system_modified
+            .clone()
+            .map(|modified| FileSystemInfo { last_modified: Some(modified) }),
+        shared: None,
+        last_modified_by: None,
+        parent_reference,
+        deleted: None,
+    })
+}
+
+fn folder_item(id: &str, name: &str, modified: String) -> DriveItem {
+    DriveItem {
+        id: id.to_string(),
+        name: name.to_string(),
+        last_modified: modified,
+        size: Some(0),
+        file: None,
+        folder: Some(serde_json::json!({ "childCount": 0 })),
+        download_url: None,
+        file_system_info: None,
+        shared: None,
+        last_modified_by: None,
+        parent_reference: None,
+        deleted: None,
+    }
+}
+
+fn not_found(path: &str) -> anyhow::Error {
+    GraphError {
+        context: format!("Failed to find {}", path),
+        status: 404,
+        code: "itemNotFound".to_string(),
+        message: "The resource could not be found.".to_string(),
+        request_id: None,
+        user_message: "The item no longer exists on OneDrive.".to_string(),
+    }
+    .into()
+}
+
+/// The file's modified time as an upload carries it to OneDrive.
+async fn local_modified_time(local_path: &Path) -> Result<String> {
+    let modified = fs::metadata(local_path).await?.modified()?;
+    Ok(chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
@@ -0,0 +1,39 @@
+//! Known QuickXorHash values, worked out with Microsoft's reference
+//! implementation.
+
+use crate::quickxor::QuickXorHash;
+
+fn hash(chunks: &[&[u8]]) -> String {
+    let mut hasher = QuickXorHash::new();
+    for chunk in chunks {
+        hasher.update(chunk);
+    }
+    hasher.finalize()
+}
+
+/// 1000 bytes, more than six 160-byte blocks
+fn multi_block() -> Vec<u8> {
+    (0..1000u32).map(|index| ((index * 7 + 3) % 256) as u8).collect()
+}
+
+#[test]
+fn empty_input() {
+    assert_eq!(hash(&[]), "AAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+}
+
+#[test]
+fn single_byte() {
+    assert_eq!(hash(&[b"J".as_slice()]), "SgAAAAAAAAAAAAAAAQAAAAAAAAA=");
+}
+
+#[test]
+fn multi_block_input() {
+    assert_eq!(hash(&[multi_block().as_slice()]), "dgD8j0n8sM0aPE5CUJ8tqmilX/E=");
+    assert_eq!(hash(&[b"The quick brown fox jumps over the lazy dog".as_slice()]), "bMSlbysmxJL6S75XwfMcQZOpcr4=");
+}
+
+#[test]
+fn input_split_across_updates() {
+    let data = multi_block();
+    assert_eq!(hash(&[&data[..1], &data[1..161], &data[161..500], &data[500..]]), "dgD8j0n8sM0aPE5CUJ8tqmilX/E=");
+}
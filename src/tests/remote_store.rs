@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use super::memory_store::MemoryStore;
+use super::{content, TestEnv};
+
+#[tokio::test]
+async fn the_engine_syncs_against_an_in_memory_store() {
+    let env = TestEnv::new().await;
+    let store = Arc::new(MemoryStore::new());
+    store.put_file("Documents/report.txt", &content("report", 0, 1024));
+    store.put_file("Pictures/cat.jpg", &content("cat", 0, 4096));
+    let mut manager = env.manager_with(store.clone());
+    manager.sync().await.expect("first sync");
+
+    assert_eq!(env.local_files()["Documents/report.txt"], content("report", 0, 1024));
+    assert_eq!(env.local_files()["Pictures/cat.jpg"], content("cat", 0, 4096));
+
+    // A new local file goes up and an edit made elsewhere comes down
+    env.write_local("Documents/notes.txt", &content("notes", 0, 512));
+    store.put_file("Documents/report.txt", &content("report v2", 0, 1024));
+    manager.sync().await.expect("second sync");
+
+    assert_eq!(store.file("Documents/notes.txt"), Some(content("notes", 0, 512)));
+    assert_eq!(env.local_files()["Documents/report.txt"], content("report v2", 0, 1024));
+
+    // Nothing is left to do
+    manager.sync().await.expect("third sync");
+    assert_eq!(store.files().len(), 3);
+    assert_eq!(env.local_files().len(), 3);
+}
@@ -0,0 +1,34 @@
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use super::{content, TestEnv};
+use crate::restore::{restore_folders, RestoreProgress};
+
+#[tokio::test]
+async fn restore_downloads_chosen_folders_without_overwriting() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.graph.put_file("Documents/Taxes/2025.txt", &content("taxes", 0, 1024));
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    env.graph.put_file("Pictures/cat.jpg", &content("cat", 0, 1024));
+
+    let destination = env.local_path("Restored");
+    std::fs::create_dir_all(destination.join("Documents")).unwrap();
+    std::fs::write(destination.join("Documents/notes.txt"), b"edited here").unwrap();
+
+    let progress = Mutex::new(RestoreProgress::default());
+    restore_folders(&env.api(), &["/Documents".to_string()], &destination, &progress, &CancellationToken::new()).await;
+
+    let progress = progress.into_inner().unwrap();
+    assert!(progress.finished && progress.errors.is_empty(), "{:?}", progress);
+    assert_eq!(progress.downloaded_files, 2);
+    assert_eq!(progress.kept_files, vec!["Documents/notes.txt".to_string()]);
+    assert_eq!(std::fs::read(destination.join("Documents/notes.txt")).unwrap(), b"edited here");
+    assert_eq!(
+        std::fs::read(destination.join("Documents/Taxes/2025.txt")).unwrap(),
+        content("taxes", 0, 1024)
+    );
+    assert!(!destination.join("Pictures").exists());
+    // Nothing is uploaded
+    assert_eq!(env.graph.files().len(), 4);
+}
@@ -0,0 +1,214 @@
+use chrono::TimeZone;
+
+use super::{content, TestEnv};
+use crate::sync::SyncAction;
+
+/// Local paths of the planned downloads, in the order they would run.
+fn download_order(actions: &[SyncAction]) -> Vec<&str> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            SyncAction::Download { local_path, .. } => Some(local_path.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn recently_modified_files_are_downloaded_first() {
+    let env = TestEnv::new().await;
+    let mut manager = env.manager();
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    manager.sync().await.expect("initial sync");
+
+    for (path, year) in [("Archive/2015.zip", 2015), ("Documents/draft.odt", 2024), ("Photos/2019.jpg", 2019)] {
+        env.graph.put_file(path, &content(path, 0, 1024));
+        env.graph.set_modified(path, chrono::Utc.with_ymd_and_hms(year, 6, 1, 12, 0, 0).unwrap());
+    }
+
+    let actions = manager.preview_sync().await.expect("preview");
+    assert_eq!(download_order(&actions), ["Documents/draft.odt", "Photos/2019.jpg", "Archive/2015.zip"]);
+}
+
+#[tokio::test]
+async fn pulling_remote_changes_only_downloads() {
+    let env = TestEnv::new().await;
+    let mut manager = env.manager();
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    env.graph.put_file("Documents/old.txt", &content("old", 0, 1024));
+    manager.sync().await.expect("initial sync");
+    assert_eq!(manager.pull_remote_changes().await.expect("pull with nothing changed"), 0);
+
+    env.graph.put_file("Documents/notes.txt", &content("notes", 1, 2048));
+    env.graph.put_file("Photos/new.jpg", &content("new", 0, 4096));
+    env.graph.delete("Documents/old.txt");
+    env.write_local("Documents/local.txt", &content("local", 0, 512));
+
+    manager.pull_remote_changes().await.expect("pull");
+    let local = env.local_files();
+    assert_eq!(local["Documents/notes.txt"], content("notes", 1, 2048));
+    assert_eq!(local["Photos/new.jpg"], content("new", 0, 4096));
+    // Deletions and local changes wait for the next full sync
+    assert!(local.contains_key("Documents/old.txt"));
+    assert!(env.graph.file("Documents/local.txt").is_none());
+
+    manager.sync().await.expect("full sync");
+    assert!(!env.local_files().contains_key("Documents/old.txt"));
+    assert!(env.graph.file("Documents/local.txt").is_some());
+}
+
+#[tokio::test]
+async fn pulling_remote_changes_lists_no_folders() {
+    let env = TestEnv::new().await;
+    let mut manager = env.manager();
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    manager.sync().await.expect("initial sync");
+
+    // Only the delta query answers; listing any folder would fail the pull
+    env.graph.fail_listings(true);
+    env.graph.put_file("Documents/notes.txt", &content("notes", 1, 2048));
+    env.graph.put_file("Photos/new.jpg", &content("new", 0, 4096));
+    assert_eq!(manager.pull_remote_changes().await.expect("pull"), 3);
+    assert_eq!(env.local_files()["Photos/new.jpg"], content("new", 0, 4096));
+
+    env.graph.put_file("Photos/later.jpg", &content("later", 0, 4096));
+    assert_eq!(manager.pull_remote_changes().await.expect("second pull"), 1);
+
+    // The kept listing took in the pulled changes, so the full sync needs no
+    // listing either and finds nothing left to do
+    assert!(manager.preview_sync().await.expect("preview").is_empty());
+    manager.sync().await.expect("full sync");
+    let local = env.local_files();
+    assert_eq!(local["Documents/notes.txt"], content("notes", 1, 2048));
+    assert_eq!(local["Photos/later.jpg"], content("later", 0, 4096));
+}
+
+#[tokio::test]
+async fn the_sync_queue_records_how_each_action_ended() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    env.write_local("Documents/draft.txt", &content("draft", 0, 1024));
+    env.write_local("Videos/raw.mov", b"");
+    std::fs::File::options()
+        .write(true)
+        .open(env.local_path("Videos/raw.mov"))
+        .unwrap()
+        .set_len(200 * 1024 * 1024)
+        .unwrap();
+    env.graph.set_quota_remaining(50 * 1024 * 1024);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    let control = manager.control();
+    let counts = control.get_queue_counts().await.expect("queue counts");
+    assert_eq!(counts.get("queued"), None);
+    assert_eq!(counts.get("in_progress"), None);
+    assert_eq!(counts.get("failed"), Some(&1));
+
+    let queue = control.get_queue(100).await.expect("queue");
+    let status_of = |description: &str| {
+        queue
+            .iter()
+            .find(|action| action.description == description)
+            .map(|action| action.status.as_str())
+    };
+    assert_eq!(status_of("Downloading Documents/notes.txt"), Some("done"));
+    assert_eq!(status_of("Uploading Documents/draft.txt"), Some("done"));
+    let held = queue.iter().find(|action| action.description == "Uploading Videos/raw.mov").expect("held upload");
+    assert_eq!(held.status, "failed");
+    assert!(held.error.as_deref().is_some_and(|error| error.contains("Not enough OneDrive space")));
+}
+
+#[tokio::test]
+async fn online_only_files_download_when_asked() {
+    let env = TestEnv::with_config(|config| config.online_only = true).await;
+    env.graph.put_file("Photos/2020/beach.jpg", &content("beach", 0, 64 * 1024));
+    env.graph.put_file("Photos/2020/hike.jpg", &content("hike", 0, 64 * 1024));
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    // Only placeholders arrive, and they are never uploaded
+    assert!(!env.local_path("Photos/2020/beach.jpg").exists());
+    assert!(env.local_path("Photos/2020/beach.jpg.onedrive").exists());
+    manager.sync().await.expect("sync");
+    assert_eq!(env.graph.files().len(), 2);
+
+    // A file of the user's with the same ending is not a placeholder
+    env.write_local("Notes/todo.onedrive", b"call back");
+    manager.sync().await.expect("sync");
+    assert_eq!(env.graph.file("Notes/todo.onedrive"), Some(b"call back".to_vec()));
+
+    let control = manager.control();
+    assert_eq!(control.download_now(vec!["Photos/2020/beach.jpg".to_string()]).await.expect("request"), 1);
+    manager.sync().await.expect("sync");
+
+    assert_eq!(std::fs::read(env.local_path("Photos/2020/beach.jpg")).unwrap(), content("beach", 0, 64 * 1024));
+    assert!(!env.local_path("Photos/2020/beach.jpg.onedrive").exists());
+    assert!(env.local_path("Photos/2020/hike.jpg.onedrive").exists());
+    let remaining: Vec<String> = control.get_placeholders(10, "").await.unwrap().into_iter().map(|file| file.path).collect();
+    assert_eq!(remaining, ["Photos/2020/hike.jpg"]);
+}
+
+#[tokio::test]
+async fn actions_left_queued_resume_without_planning_again() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    // Queued by a run that quit before getting to it
+    env.graph.put_file("report.pdf", &content("report", 0, 2048));
+    let remote_item = env.api().get_item("/report.pdf").await.expect("remote item");
+    let db = rusqlite::Connection::open(&env.config.db_file).unwrap();
+    let action = serde_json::to_string(&SyncAction::Download { remote_item, local_path: "report.pdf".to_string() }).unwrap();
+    db.execute("INSERT INTO pending_actions (action_json, status) VALUES (?1, 'queued')", [action]).unwrap();
+    drop(db);
+
+    // Planning again would list OneDrive, which fails now
+    env.graph.fail_listings(true);
+    env.manager().sync().await.expect("sync after restart");
+
+    assert_eq!(env.local_files()["report.pdf"], content("report", 0, 2048));
+}
+
+#[tokio::test]
+async fn queued_deletions_of_files_already_gone_succeed() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    // Queued when the file went from OneDrive; the user deleted it here too
+    env.graph.delete("Documents/notes.txt");
+    std::fs::remove_file(env.local_path("Documents/notes.txt")).unwrap();
+    let db = rusqlite::Connection::open(&env.config.db_file).unwrap();
+    let action = serde_json::to_string(&SyncAction::DeleteLocal { path: "Documents/notes.txt".to_string() }).unwrap();
+    db.execute("INSERT INTO pending_actions (action_json, status) VALUES (?1, 'queued')", [action]).unwrap();
+    drop(db);
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync after restart");
+
+    assert!(manager.get_status().await.sync_errors.is_empty());
+    assert!(!env.local_files().contains_key("Documents/notes.txt"));
+}
+
+#[tokio::test]
+async fn actions_left_queued_that_no_longer_apply_are_planned_again() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/notes.txt", &content("notes", 0, 1024));
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    // Queued when the file was gone from OneDrive, which has it again by the
+    // time syncing resumes
+    let db = rusqlite::Connection::open(&env.config.db_file).unwrap();
+    let action = serde_json::to_string(&SyncAction::DeleteLocal { path: "Documents/notes.txt".to_string() }).unwrap();
+    db.execute("INSERT INTO pending_actions (action_json, status) VALUES (?1, 'queued')", [action]).unwrap();
+    drop(db);
+
+    env.manager().sync().await.expect("sync after restart");
+
+    assert_eq!(env.local_files()["Documents/notes.txt"], content("notes", 0, 1024));
+}
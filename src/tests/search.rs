@@ -0,0 +1,31 @@
+use super::TestEnv;
+
+#[tokio::test]
+async fn content_search_finds_synced_documents_by_their_text() {
+    let env = TestEnv::with_config(|config| config.index_content = true).await;
+    let mut manager = env.manager();
+    env.write_local("Documents/plan.md", b"# Plan\nThe quarterly budget review moves to Thursday.\n");
+    env.write_local("Documents/notes.txt", b"Groceries: apples, bread\n");
+    env.graph.put_file("Documents/minutes.txt", b"Budget approved by the board\n");
+    env.write_local("Pictures/budget.jpg", b"not text");
+    manager.sync().await.expect("initial sync");
+
+    let mut found: Vec<String> = manager.search_content("budget", 10).await.unwrap().into_iter().map(|m| m.path).collect();
+    found.sort();
+    assert_eq!(found, ["Documents/minutes.txt", "Documents/plan.md"]);
+
+    let matches = manager.search_content("quarterly budg", 10).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0].snippet.contains("[quarterly]"), "{}", matches[0].snippet);
+
+    // Edited and deleted files are reindexed on the next sync
+    env.write_local("Documents/plan.md", b"# Plan\nNothing planned yet.\n");
+    std::fs::remove_file(env.local_path("Documents/notes.txt")).unwrap();
+    manager.sync().await.expect("sync");
+
+    let found: Vec<String> = manager.search_content("budget", 10).await.unwrap().into_iter().map(|m| m.path).collect();
+    assert_eq!(found, ["Documents/minutes.txt"]);
+    assert!(manager.search_content("apples", 10).await.unwrap().is_empty());
+    // Query syntax in the search box is taken literally
+    assert!(manager.search_content("\"budget OR (", 10).await.is_ok());
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use super::{content, TestEnv};
+use crate::config::SummaryFrequency;
+
+#[tokio::test]
+async fn summary_command_receives_the_sync_summary() {
+    let summary_file = tempfile::NamedTempFile::new().unwrap();
+    let summary_path = summary_file.path().to_path_buf();
+    let env = TestEnv::with_config(|config| {
+        config.summary_command = format!("cat > '{}'", summary_path.display());
+        config.summary_frequency = SummaryFrequency::EverySync;
+    })
+    .await;
+    env.write_local("Documents/notes.txt", &content("notes", 0, 1024));
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+
+    env.manager().sync().await.expect("sync");
+
+    // The command runs in the background
+    let mut summary = String::new();
+    for _ in 0..50 {
+        summary = std::fs::read_to_string(&summary_path).unwrap_or_default();
+        if summary.contains("Storage:") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(summary.contains("all good"), "{}", summary);
+    assert!(summary.contains("Syncs: 1 (0 failed)"), "{}", summary);
+    assert!(summary.lines().any(|line| line.contains("Uploaded:") && line.ends_with('1')), "{}", summary);
+    assert!(summary.lines().any(|line| line.contains("Downloaded:") && line.ends_with('1')), "{}", summary);
+}
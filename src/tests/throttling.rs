@@ -0,0 +1,46 @@
+use super::{content, TestEnv};
+
+#[tokio::test]
+async fn throttled_requests_are_retried() {
+    let env = TestEnv::new().await;
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.write_local("Documents/notes.txt", &content("notes", 0, 1024));
+
+    env.graph.throttle(3);
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert!(manager.get_status().await.sync_errors.is_empty());
+    assert_eq!(env.local_files(), env.graph.files());
+}
+
+#[tokio::test]
+async fn new_folders_are_created_in_batches() {
+    let env = TestEnv::new().await;
+    for index in 0..30 {
+        env.write_local(&format!("Projects/project-{:02}/notes.txt", index), &content("notes", index, 1024));
+    }
+
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert!(manager.get_status().await.sync_errors.is_empty());
+    assert_eq!(env.local_files(), env.graph.files());
+    // "Projects" first, then its 30 subfolders in two batches of up to 20
+    assert_eq!(env.graph.batches_received(), 3);
+}
+
+#[tokio::test]
+async fn throttled_batched_requests_are_retried() {
+    let env = TestEnv::new().await;
+    for index in 0..5 {
+        env.write_local(&format!("Projects/project-{}/notes.txt", index), &content("notes", index, 1024));
+    }
+
+    env.graph.throttle_batched(3);
+    let mut manager = env.manager();
+    manager.sync().await.expect("sync");
+
+    assert!(manager.get_status().await.sync_errors.is_empty());
+    assert_eq!(env.local_files(), env.graph.files());
+}
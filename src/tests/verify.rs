@@ -0,0 +1,35 @@
+use super::{content, TestEnv};
+use crate::verify::Mismatch;
+
+#[tokio::test]
+async fn verify_reports_mismatches_without_transferring() {
+    let env = TestEnv::new().await;
+    for index in 0..6 {
+        env.graph.put_file(&format!("Documents/file-{}.txt", index), &content("original", index, 1024));
+    }
+    let mut manager = env.manager();
+    manager.sync().await.expect("initial sync");
+
+    std::fs::remove_file(env.local_path("Documents/file-0.txt")).unwrap();
+    env.write_local("Documents/file-1.txt", &content("corrupted", 1, 1024));
+    env.graph.delete("Documents/file-2.txt");
+    env.graph.put_file("Documents/file-3.txt", &content("edited online", 3, 1024));
+    let (local_before, remote_before) = (env.local_files(), env.graph.files());
+
+    let report = manager.verify().await.expect("verify");
+
+    assert_eq!(report.checked_files, 6);
+    let mut mismatches = report.mismatches.clone();
+    mismatches.sort();
+    assert_eq!(
+        mismatches,
+        vec![
+            ("Documents/file-0.txt".to_string(), Mismatch::MissingLocally),
+            ("Documents/file-1.txt".to_string(), Mismatch::LocalContentChanged),
+            ("Documents/file-2.txt".to_string(), Mismatch::MissingRemotely),
+            ("Documents/file-3.txt".to_string(), Mismatch::RemoteContentChanged),
+        ]
+    );
+    assert_eq!(env.local_files(), local_before);
+    assert_eq!(env.graph.files(), remote_before);
+}
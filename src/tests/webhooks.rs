@@ -0,0 +1,70 @@
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server};
+use serde_json::Value;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{content, TestEnv};
+
+/// Starts a webhook endpoint and returns its URL and the payloads it receives.
+fn webhook_receiver() -> (String, Arc<Mutex<Vec<Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let shared = received.clone();
+    let make_service = make_service_fn(move |_| {
+        let received = shared.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                let received = received.clone();
+                async move {
+                    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+                    if let Ok(payload) = serde_json::from_slice(&body) {
+                        received.lock().unwrap().push(payload);
+                    }
+                    Ok::<_, Infallible>(Response::new(Body::empty()))
+                }
+            }))
+        }
+    });
+    tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_service));
+    (url, received)
+}
+
+/// Waits for the payloads sent in the background to arrive.
+async fn wait_for(received: &Mutex<Vec<Value>>, count: usize) -> Vec<Value> {
+    for _ in 0..50 {
+        if received.lock().unwrap().len() >= count {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    received.lock().unwrap().clone()
+}
+
+#[tokio::test]
+async fn webhooks_receive_completions_and_conflicts() {
+    let (url, received) = webhook_receiver();
+    let env = TestEnv::with_config(|config| config.webhook_urls = vec![url]).await;
+    env.graph.put_file("Documents/report.txt", &content("report", 0, 1024));
+    env.manager().sync().await.expect("initial sync");
+
+    let payloads = wait_for(&received, 1).await;
+    assert_eq!(payloads.len(), 1, "{:?}", payloads);
+    assert_eq!(payloads[0]["event"], "sync_completed");
+    assert_eq!(payloads[0]["changes"]["download"], 1);
+    assert!(payloads[0]["run_id"].as_str().is_some_and(|run_id| !run_id.is_empty()));
+
+    env.write_local("Documents/report.txt", &content("local edit", 0, 1030));
+    env.graph.put_file("Documents/report.txt", &content("remote edit", 0, 1040));
+    env.manager().sync().await.expect("sync");
+
+    let payloads = wait_for(&received, 3).await;
+    let conflict = payloads.iter().find(|payload| payload["event"] == "conflict").expect("conflict webhook");
+    assert_eq!(conflict["path"], "Documents/report.txt");
+    assert!(conflict["text"].as_str().unwrap().contains("Documents/report.txt"));
+    assert_eq!(payloads.iter().filter(|payload| payload["event"] == "sync_completed").count(), 2);
+}
@@ -0,0 +1,89 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Throughput is averaged over this much of the recent past, so the speed
+/// follows changes in the connection without jumping with every chunk
+const SPEED_WINDOW: Duration = Duration::from_secs(10);
+
+/// No speed is reported until transfers have run for this long
+const MIN_SPEED_SAMPLE: Duration = Duration::from_secs(1);
+
+/// Bytes moved by the transfers of one sync, for its speed and time left.
+/// Parallel transfers report their own progress; the speed is their sum.
+#[derive(Debug, Default)]
+pub struct Throughput {
+    /// Bytes the sync's transfers move in total
+    total: u64,
+    /// Bytes moved so far by this sync
+    done: u64,
+    /// Bytes each running transfer had reached at its last report
+    running: HashMap<String, u64>,
+    /// `done` at recent moments, oldest first
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl Throughput {
+    /// Starts measuring a new sync.
+    pub fn start(&mut self) {
+        *self = Self::default();
+        self.samples.push_back((Instant::now(), 0));
+    }
+
+    /// The sync is about to transfer `bytes` more, on top of what it has moved.
+    pub fn expect(&mut self, bytes: u64) {
+        self.total = self.done + bytes;
+        // Time spent planning is not time spent transferring
+        self.samples.clear();
+        self.samples.push_back((Instant::now(), self.done));
+    }
+
+    /// A transfer of `path` begins with `offset` bytes already on the other
+    /// side, as when it resumes.
+    pub fn begin(&mut self, path: &str, offset: u64) {
+        self.running.insert(path.to_string(), offset);
+    }
+
+    /// The transfer of `path` has reached `done` bytes.
+    pub fn progress(&mut self, path: &str, done: u64) {
+        let previous = self.running.insert(path.to_string(), done).unwrap_or(0);
+        self.done += done.saturating_sub(previous);
+
+        let now = Instant::now();
+        self.samples.push_back((now, self.done));
+        // Keep one sample older than the window as the baseline
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) > SPEED_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The transfer of `path` has ended, finished or not.
+    pub fn end(&mut self, path: &str) {
+        self.running.remove(path);
+    }
+
+    /// Bytes moved so far by this sync.
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    /// Recent bytes per second, or 0 until there is enough to go on.
+    pub fn bytes_per_second(&self) -> u64 {
+        let (Some((first_time, first_done)), Some((last_time, last_done))) = (self.samples.front(), self.samples.back()) else {
+            return 0;
+        };
+        let elapsed = last_time.duration_since(*first_time);
+        if elapsed < MIN_SPEED_SAMPLE {
+            return 0;
+        }
+        ((last_done - first_done) as f64 / elapsed.as_secs_f64()) as u64
+    }
+
+    /// Time the rest of the sync's transfers take at the recent speed.
+    pub fn remaining(&self) -> Option<Duration> {
+        let speed = self.bytes_per_second();
+        if speed == 0 {
+            return None;
+        }
+        Some(Duration::from_secs(self.total.saturating_sub(self.done) / speed))
+    }
+}
@@ -6,17 +6,32 @@ use tray_icon::{TrayIcon, TrayIconBuilder, menu::{Menu, MenuItem, MenuEvent}};
 use image::ImageBuffer;
 use std::time::Duration;
 
+use crate::api::OneDriveAPI;
 use crate::auth::AuthManager;
 use crate::config::Config;
-use crate::sync::SyncManager;
+use crate::format;
+use crate::mirror;
+use crate::push;
+use crate::shutdown;
+use crate::sync::{FileState, SyncControl, SyncHealth, SyncManager};
 
 pub struct TrayManager {
     config: Arc<Config>,
     auth: Arc<Mutex<AuthManager>>,
     sync_manager: Arc<Mutex<SyncManager>>,
     tray_icon: Option<TrayIcon>,
+    /// Syncing flag and health the current icon was drawn for
+    shown_state: Option<(bool, SyncHealth)>,
+    /// Pauses syncing without waiting for the auto-sync loop's lock
+    sync_control: Option<SyncControl>,
+    pause_item: Option<MenuItem>,
+    cancel_item: Option<MenuItem>,
 }
 
+const PAUSE_MENU_ID: &str = "pause";
+const CANCEL_MENU_ID: &str = "cancel";
+const QUIT_MENU_ID: &str = "quit";
+
 impl TrayManager {
     pub fn new(
         config: Arc<Config>,
@@ -28,6 +43,10 @@ impl TrayManager {
             auth,
             sync_manager,
             tray_icon: None,
+            shown_state: None,
+            sync_control: None,
+            pause_item: None,
+            cancel_item: None,
         })
     }
 
@@ -56,6 +75,20 @@ impl TrayManager {
             }
         }
 
+        // Start upload-only folder mirrors
+        let api = Arc::new(
+            OneDriveAPI::new(self.auth.clone())
+                .with_upload_chunk_size(self.config.upload_chunk_size_kib * 1024)
+                .with_call_upload_limit(self.config.call_upload_limit())
+                .with_drive(self.config.selected_drive()),
+        );
+        mirror::start_upload_mirrors(self.config.clone(), api.clone());
+
+        let sync_control = self.sync_manager.lock().await.control();
+        push::start_push_notifications(&self.config, api, sync_control.clone());
+        self.sync_control = Some(sync_control);
+        self.update_pause_item();
+
         // Start auto-sync in background
         let sync_manager_clone = self.sync_manager.clone();
         tokio::spawn(async move {
@@ -106,14 +139,18 @@ impl TrayManager {
         
         let open_item = MenuItem::new("Open OneDrive", true, None);
         let sync_item = MenuItem::new("Sync Now", true, None);
+        let pause_item = MenuItem::with_id(PAUSE_MENU_ID, "Pause Syncing", true, None);
+        let cancel_item = MenuItem::with_id(CANCEL_MENU_ID, "Cancel Sync", false, None);
         let status_item = MenuItem::new("Status: Ready", false, None);
         let settings_item = MenuItem::new("Settings", true, None);
-        let quit_item = MenuItem::new("Quit", true, None);
+        let quit_item = MenuItem::with_id(QUIT_MENU_ID, "Quit", true, None);
         
         tray_menu.append_items(&[
             &status_item,
             &open_item,
             &sync_item,
+            &pause_item,
+            &cancel_item,
             &settings_item,
             &quit_item,
         ])?;
@@ -125,12 +162,48 @@ impl TrayManager {
             .build()?;
 
         self.tray_icon = Some(tray_icon);
+        self.pause_item = Some(pause_item);
+        self.cancel_item = Some(cancel_item);
+        Ok(())
+    }
+
+    async fn toggle_pause(&self) -> Result<()> {
+        if let Some(control) = &self.sync_control {
+            if control.is_paused() {
+                control.resume().await?;
+            } else {
+                control.pause().await?;
+            }
+        }
+        self.update_pause_item();
         Ok(())
     }
 
+    fn update_pause_item(&self) {
+        if let (Some(item), Some(control)) = (&self.pause_item, &self.sync_control) {
+            item.set_text(if control.is_paused() { "Resume Syncing" } else { "Pause Syncing" });
+        }
+    }
+
     async fn handle_menu_event(&mut self, event: MenuEvent) -> Result<()> {
         info!("Menu event received: {:?}", event.id);
         
+        if event.id == PAUSE_MENU_ID {
+            return self.toggle_pause().await;
+        }
+        if event.id == CANCEL_MENU_ID {
+            if let Some(control) = &self.sync_control {
+                control.cancel();
+            }
+            return Ok(());
+        }
+        if event.id == QUIT_MENU_ID {
+            info!("Quitting from the tray");
+            shutdown::stop_sync().await;
+            shutdown::release_instance_lock();
+            std::process::exit(0);
+        }
+        
         // Simple approach using menu text to identify actions
         // This is not ideal but avoids the complex ID matching issues
         
@@ -149,15 +222,52 @@ impl TrayManager {
                 let sync_guard = self.sync_manager.lock().await;
                 sync_guard.get_status().await
             };
+            let health = status.health();
+            let waiting: u64 = [FileState::PendingUpload, FileState::PendingDownload]
+                .iter()
+                .filter_map(|state| status.file_states.get(state))
+                .sum();
             
-            let tooltip = if status.is_syncing {
+            let tooltip = if status.relink_required {
+                "OneDrive - Drive changed, open the app to re-link".to_string()
+            } else if status.auth_failed {
+                "OneDrive - Signed out, open the app to sign in again".to_string()
+            } else if status.is_syncing && status.sleep_inhibited {
+                format!("OneDrive - {} (keeping the computer awake)", status.current_operation)
+            } else if status.is_syncing && status.current_speed_bps > 0 {
+                let remaining = status
+                    .estimated_remaining
+                    .map(|remaining| format!(", about {} left", format::duration(remaining)))
+                    .unwrap_or_default();
+                format!("OneDrive - {} ({}{})", status.current_operation, format::rate(status.current_speed_bps), remaining)
+            } else if status.is_syncing {
                 format!("OneDrive - {}", status.current_operation)
+            } else if let Some(app) = &status.paused_by {
+                format!("OneDrive - Paused while {} is running", app)
+            } else if status.outside_sync_window {
+                "OneDrive - Outside scheduled sync hours".to_string()
+            } else if status.request_budget_low {
+                "OneDrive - Waiting for the request budget".to_string()
+            } else if status.uploads_paused_for_mass_change.is_some() {
+                "OneDrive - Uploads paused, many files changed".to_string()
+            } else if status.uploads_held_for_quota.is_some() {
+                "OneDrive - Storage full, uploads waiting".to_string()
+            } else if waiting > 0 {
+                format!("OneDrive - {} files waiting to sync", format::count(waiting))
             } else if let Some(last_sync) = status.last_sync {
                 let elapsed = std::time::SystemTime::now()
                     .duration_since(last_sync)
                     .unwrap_or_default()
                     .as_secs();
-                format!("OneDrive - Last sync: {}s ago", elapsed)
+                if status.skipped_files.is_empty() {
+                    format!("OneDrive - Last sync: {}s ago", elapsed)
+                } else {
+                    format!(
+                        "OneDrive - Last sync: {}s ago, {} skipped",
+                        elapsed,
+                        format::count(status.skipped_files.len() as u64)
+                    )
+                }
             } else {
                 "OneDrive - Ready".to_string()
             };
@@ -166,6 +276,24 @@ impl TrayManager {
             if let Err(e) = tray_icon.set_tooltip(Some(&tooltip)) {
                 warn!("Failed to update tray tooltip: {}", e);
             }
+            
+            // Only redraw the icon when what it shows has changed
+            let state = (status.is_syncing, health);
+            if self.shown_state != Some(state) {
+                if let Some(item) = &self.cancel_item {
+                    item.set_enabled(status.is_syncing);
+                }
+                let icon = match state {
+                    (_, SyncHealth::Failing) => self.create_error_icon(),
+                    (true, _) => self.create_syncing_icon(),
+                    (false, SyncHealth::Degraded) => self.create_degraded_icon(),
+                    (false, SyncHealth::Healthy) => self.create_icon(),
+                };
+                if let Err(e) = tray_icon.set_icon(Some(icon)) {
+                    warn!("Failed to update tray icon: {}", e);
+                }
+                self.shown_state = Some(state);
+            }
         }
     }
 
@@ -273,6 +401,34 @@ impl TrayManager {
             .expect("Failed to create syncing icon")
     }
 
+    fn create_degraded_icon(&self) -> tray_icon::Icon {
+        // Create an amber icon to indicate recent errors
+        let size = 32;
+        let mut image_data = ImageBuffer::new(size, size);
+        
+        // Fill with amber background
+        for pixel in image_data.pixels_mut() {
+            *pixel = image::Rgba([230, 160, 0, 255]);
+        }
+        
+        // Add white exclamation mark (simplified)
+        for y in 8..19 {
+            for x in 15..17 {
+                image_data.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+            }
+        }
+        for y in 21..24 {
+            for x in 15..17 {
+                image_data.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+            }
+        }
+
+        let rgba_data = image_data.into_raw();
+        
+        tray_icon::Icon::from_rgba(rgba_data, size, size)
+            .expect("Failed to create degraded icon")
+    }
+
     fn create_error_icon(&self) -> tray_icon::Icon {
         // Create a red icon to indicate errors
         let size = 32;
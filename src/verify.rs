@@ -0,0 +1,69 @@
+/// How a tracked file no longer matches what was recorded at its last sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mismatch {
+    /// The local copy is gone
+    MissingLocally,
+    /// OneDrive no longer has the file
+    MissingRemotely,
+    /// The local content differs from the last synced version
+    LocalContentChanged,
+    /// OneDrive's content differs from the last synced version
+    RemoteContentChanged,
+}
+
+impl Mismatch {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Mismatch::MissingLocally => "Missing on this computer",
+            Mismatch::MissingRemotely => "Missing on OneDrive",
+            Mismatch::LocalContentChanged => "Changed on this computer",
+            Mismatch::RemoteContentChanged => "Changed on OneDrive",
+        }
+    }
+}
+
+/// Result of `SyncManager::verify`: every tracked file checked against the
+/// hashes recorded when it was last synced, without transferring anything.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub checked_files: usize,
+    /// Files that could not be read to hash them, with the error
+    pub unreadable: Vec<(String, String)>,
+    pub mismatches: Vec<(String, Mismatch)>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty() && self.unreadable.is_empty()
+    }
+
+    /// Plain text listing the problems found, grouped by kind.
+    pub fn render(&self) -> String {
+        let mut text = format!("Checked {} synced files\n", self.checked_files);
+        if self.is_clean() {
+            text.push_str("Every file matches OneDrive and the last sync\n");
+            return text;
+        }
+
+        let mut mismatches = self.mismatches.clone();
+        mismatches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        let mut current = None;
+        for (path, mismatch) in &mismatches {
+            if current != Some(*mismatch) {
+                let count = mismatches.iter().filter(|(_, other)| other == mismatch).count();
+                text.push_str(&format!("\n{} ({})\n", mismatch.label(), count));
+                current = Some(*mismatch);
+            }
+            text.push_str(&format!("  {}\n", path));
+        }
+
+        if !self.unreadable.is_empty() {
+            text.push_str(&format!("\nCould not be read ({})\n", self.unreadable.len()));
+            for (path, error) in &self.unreadable {
+                text.push_str(&format!("  {}: {}\n", path, error));
+            }
+        }
+
+        text
+    }
+}
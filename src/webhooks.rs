@@ -0,0 +1,101 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::report;
+
+/// A webhook that has not answered by then is given up on
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts sync events as JSON to `Config::webhook_urls`, for Home Assistant,
+/// Slack, ntfy and the like. Every payload has `event`, `run_id`, `host`,
+/// `time` and a readable `text` line (the field Slack shows), plus details of
+/// the event. Best effort: requests run in the background and failures are
+/// only logged.
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    urls: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            urls: config
+                .webhook_urls
+                .iter()
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    /// A sync run finished; `changes` counts its `sync_log` entries by action.
+    pub fn sync_completed(&self, run_id: &str, changes: &BTreeMap<String, u64>) {
+        let changed: u64 = changes.values().sum();
+        self.post(
+            "sync_completed",
+            run_id,
+            format!("OneDrive sync finished, {} changes", changed),
+            json!({ "changes": changes }),
+        );
+    }
+
+    pub fn sync_failed(&self, run_id: &str, error: &str) {
+        self.post(
+            "sync_failed",
+            run_id,
+            format!("OneDrive sync failed: {}", error),
+            json!({ "error": error }),
+        );
+    }
+
+    /// Both sides changed `path`; the local version was kept as `kept_as`.
+    pub fn conflict(&self, run_id: &str, path: &str, kept_as: &str) {
+        self.post(
+            "conflict",
+            run_id,
+            format!("OneDrive conflict in {}; local version kept as {}", path, kept_as),
+            json!({ "path": path, "kept_as": kept_as }),
+        );
+    }
+
+    fn post(&self, event: &str, run_id: &str, text: String, details: Value) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut payload = json!({
+            "event": event,
+            "run_id": run_id,
+            "host": report::hostname(),
+            "time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "text": text,
+        });
+        if let (Some(payload), Value::Object(details)) = (payload.as_object_mut(), details) {
+            payload.extend(details);
+        }
+
+        for url in &self.urls {
+            let request = self.client.post(url).json(&payload);
+            let (url, event) = (url.clone(), event.to_string());
+            tokio::spawn(async move {
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => debug!("Sent {} webhook to {}", event, url),
+                    Ok(response) => warn!("Webhook {} answered HTTP {}", url, response.status().as_u16()),
+                    Err(e) => warn!("Failed to send webhook to {}: {}", url, e),
+                }
+            });
+        }
+    }
+}